@@ -0,0 +1,144 @@
+//! Load, update and quote timings on a fund laid out like a mainnet one: a full token list,
+//! ten composition tokens with curves, and Pyth oracles. Set `SYMMETRY_BENCH_FIXTURE` to an
+//! `AmmFixture` file to time a recorded fund instead.
+//!
+//! `cargo bench -p jupiter-core --bench quote --features test-fixtures`
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use jupiter_amm_interface::{AccountMap, Amm, KeyedAccount, QuoteParams};
+use jupiter_core::amms::account_builders::{CurveDataBuilder, FundStateBuilder, OracleAccountBuilder, TokenListBuilder};
+use jupiter_core::amms::accounts::{
+    CurveData, FundState, TokenList, TokenPriceData, TokenSettings, MAX_TOKENS_IN_ASSET_POOL,
+    NUM_OF_POINTS_IN_CURVE_DATA, USE_CURVE_DATA, WEIGHT_MULTIPLIER,
+};
+use jupiter_core::amms::symmetry_config::SymmetryConfig;
+use jupiter_core::amms::symmetry_token_swap::SymmetryTokenSwap;
+use jupiter_core::fixture::AmmFixture;
+use solana_sdk::clock::Clock;
+use solana_sdk::pubkey::Pubkey;
+use std::path::Path;
+
+const FUND_TOKENS: u64 = 10;
+const SLOT: u64 = 1_000;
+
+/// Fund accounts and the pair quoted in the benchmarks.
+struct Setup {
+    fund: Pubkey,
+    account_map: AccountMap,
+    clock: Clock,
+    input_mint: Pubkey,
+    output_mint: Pubkey,
+    in_amount: u64,
+}
+
+fn curve(price: u64, step: u64, rising: bool) -> TokenPriceData {
+    let mut curve = TokenPriceData { amount: [0; NUM_OF_POINTS_IN_CURVE_DATA], price: [0; NUM_OF_POINTS_IN_CURVE_DATA] };
+    for j in 0..NUM_OF_POINTS_IN_CURVE_DATA {
+        let offset = price / 1_000 * j as u64;
+        curve.amount[j] = step;
+        curve.price[j] = if rising { price + offset } else { price - offset };
+    }
+    curve
+}
+
+/// Every listed token has 6 decimals and is priced at $1 to $10 by its own Pyth account;
+/// the first `FUND_TOKENS` are held in equal weights with curves.
+fn built_setup() -> Setup {
+    let mut token_list = TokenListBuilder::new().with_fee_split(20, 10, 30);
+    let mut fund_state = FundStateBuilder::new().with_weight_band(1_000, 5_000);
+    let mut curve_data = CurveDataBuilder::new();
+    let mut account_map = AccountMap::new();
+    let mut mints = Vec::new();
+    for token_id in 0..MAX_TOKENS_IN_ASSET_POOL as u64 {
+        let mint = Pubkey::new_unique();
+        let oracle = Pubkey::new_unique();
+        let dollars = 1 + token_id % 10;
+        token_list = token_list.with_token(TokenSettings {
+            token_mint: mint,
+            decimals: 6,
+            oracle_account: oracle,
+            oracle_confidence_pct: 10,
+            token_swap_fee_before_tw_bps: 10,
+            token_swap_fee_after_tw_bps: 30,
+            is_live: 1,
+            lp_on: 1,
+            use_curve_data: USE_CURVE_DATA,
+            ..TokenSettings::empty()
+        });
+        account_map.insert(oracle, OracleAccountBuilder::pyth(dollars as i64 * 100_000_000, 10_000, -8, SLOT).build_account());
+        if token_id < FUND_TOKENS {
+            // $100,000 of each token.
+            fund_state = fund_state.with_token(token_id, 100_000_000_000 / dollars, WEIGHT_MULTIPLIER / FUND_TOKENS);
+            let price = dollars * 1_000_000_000_000;
+            curve_data = curve_data
+                .with_buy_curve(token_id as usize, curve(price, 1_000_000_000 / dollars, true))
+                .with_sell_curve(token_id as usize, curve(price, 1_000_000_000 / dollars, false));
+        }
+        mints.push(mint);
+    }
+    let fund = Pubkey::new_unique();
+    account_map.insert(fund, fund_state.build_account());
+    account_map.insert(SymmetryTokenSwap::TOKEN_LIST_ADDRESS, token_list.build_account());
+    account_map.insert(SymmetryConfig::MAINNET.curve_data, curve_data.build_account());
+    Setup {
+        fund,
+        account_map,
+        clock: Clock { slot: SLOT, ..Clock::default() },
+        input_mint: mints[0],
+        output_mint: mints[1],
+        in_amount: 1_000_000_000,
+    }
+}
+
+/// The recorded fund, quoting 1,000 raw units of its first token into its second.
+fn fixture_setup(path: &Path) -> Setup {
+    let fixture = AmmFixture::load(path).expect("fixture");
+    let amm = fixture.amm().expect("fixture fund");
+    let mints = amm.get_reserve_mints();
+    Setup {
+        fund: fixture.fund().unwrap(),
+        account_map: fixture.account_map().unwrap(),
+        clock: fixture.clock(),
+        input_mint: mints[0],
+        output_mint: mints[1],
+        in_amount: 1_000,
+    }
+}
+
+fn setup() -> Setup {
+    match std::env::var("SYMMETRY_BENCH_FIXTURE") {
+        Ok(path) => fixture_setup(Path::new(&path)),
+        Err(_) => built_setup(),
+    }
+}
+
+fn amm(setup: &Setup) -> SymmetryTokenSwap {
+    let keyed_account = |key: Pubkey| KeyedAccount { key, account: setup.account_map[&key].clone(), params: None };
+    SymmetryTokenSwap::from_keyed_account(&keyed_account(setup.fund), &keyed_account(SymmetryTokenSwap::TOKEN_LIST_ADDRESS)).unwrap()
+}
+
+fn bench_loads(c: &mut Criterion) {
+    let setup = setup();
+    let fund_state = &setup.account_map[&setup.fund].data;
+    let token_list = &setup.account_map[&SymmetryTokenSwap::TOKEN_LIST_ADDRESS].data;
+    let curve_data = &setup.account_map[&SymmetryConfig::MAINNET.curve_data].data;
+    c.bench_function("FundState::load", |b| b.iter(|| FundState::load(black_box(fund_state)).unwrap()));
+    c.bench_function("TokenList::load", |b| b.iter(|| TokenList::load(black_box(token_list)).unwrap()));
+    c.bench_function("CurveData::load", |b| b.iter(|| CurveData::load(black_box(curve_data)).unwrap()));
+}
+
+fn bench_update_and_quote(c: &mut Criterion) {
+    let setup = setup();
+    let mut amm = amm(&setup);
+    c.bench_function("update", |b| b.iter(|| amm.update_with_clock(black_box(&setup.account_map), setup.clock.clone()).unwrap()));
+
+    let quote_params = QuoteParams {
+        in_amount: setup.in_amount,
+        input_mint: setup.input_mint,
+        output_mint: setup.output_mint,
+    };
+    c.bench_function("quote", |b| b.iter(|| amm.quote(black_box(&quote_params)).unwrap()));
+}
+
+criterion_group!(benches, bench_loads, bench_update_and_quote);
+criterion_main!(benches);
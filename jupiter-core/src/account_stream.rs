@@ -0,0 +1,199 @@
+//! Keeps an AMM current from a stream of account writes the caller has already decoded,
+//! from whatever source: a Geyser plugin, a websocket or a replay. There is no transport
+//! here; subscribe to `account_filter(&amm)` with your own client, convert each write into
+//! an `AccountUpdate` and pass the stream to `AccountStreamUpdater::spawn`.
+
+use futures::{FutureExt, Stream, StreamExt};
+use jupiter_amm_interface::{AccountMap, Amm};
+use solana_sdk::{account::Account, pubkey::Pubkey};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use tokio::task::JoinHandle;
+
+/// One account write from the stream.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AccountUpdate {
+    pub pubkey: Pubkey,
+    pub slot: u64,
+    pub account: Account,
+}
+
+/// Accounts the stream must carry writes for.
+pub fn account_filter(amm: &dyn Amm) -> Vec<Pubkey> {
+    amm.get_accounts_to_update()
+}
+
+/// The AMM as of the last applied update and the highest slot applied.
+#[derive(Clone)]
+pub struct QuotableState<T> {
+    pub amm: T,
+    pub slot: u64,
+}
+
+/// Account writes collected from the stream, keeping the newest slot per account.
+struct StreamedAccounts {
+    keys: HashMap<Pubkey, Option<u64>>,
+    account_map: AccountMap,
+}
+
+impl StreamedAccounts {
+    fn new(keys: Vec<Pubkey>, account_map: AccountMap) -> Self {
+        Self { keys: keys.into_iter().map(|key| (key, None)).collect(), account_map }
+    }
+
+    /// Stores `update` unless the account isn't followed or a later write was already seen.
+    fn apply(&mut self, update: AccountUpdate) -> bool {
+        match self.keys.get_mut(&update.pubkey) {
+            Some(Some(slot)) if *slot > update.slot => false,
+            Some(slot) => {
+                *slot = Some(update.slot);
+                self.account_map.insert(update.pubkey, update.account);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// An AMM shared with a background task applying an account stream. Writes arriving
+/// together are applied with one `update()`, so within a slot the AMM reflects every write
+/// received so far.
+pub struct AccountStreamUpdater<T> {
+    state: Arc<RwLock<QuotableState<T>>>,
+    failed_updates: Arc<AtomicU64>,
+    task: JoinHandle<()>,
+}
+
+impl<T: Amm + Send + Sync + 'static> AccountStreamUpdater<T> {
+    /// Applies `updates` to `amm` on a tokio task. `account_map` holds the accounts already
+    /// applied, if any; until every account in `account_filter` has been seen, `update()`
+    /// fails and the AMM keeps its state.
+    pub fn spawn<S>(amm: T, account_map: AccountMap, updates: S) -> Self
+    where
+        S: Stream<Item = AccountUpdate> + Send + Unpin + 'static,
+    {
+        let accounts = StreamedAccounts::new(amm.get_accounts_to_update(), account_map);
+        let state = Arc::new(RwLock::new(QuotableState { amm, slot: 0 }));
+        let failed_updates = Arc::new(AtomicU64::new(0));
+        let task = tokio::spawn(apply_updates(accounts, updates, state.clone(), failed_updates.clone()));
+        Self { state, failed_updates, task }
+    }
+}
+
+impl<T> AccountStreamUpdater<T> {
+    pub fn state(&self) -> Arc<RwLock<QuotableState<T>>> {
+        self.state.clone()
+    }
+
+    /// Copy of the latest quotable state.
+    pub fn snapshot(&self) -> QuotableState<T>
+    where
+        T: Clone,
+    {
+        self.state.read().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+    }
+
+    /// Batches whose `update()` failed; the AMM keeps its previous state for those.
+    pub fn failed_updates(&self) -> u64 {
+        self.failed_updates.load(Ordering::Relaxed)
+    }
+
+    /// Stops applying the stream. The AMM keeps its last state.
+    pub fn stop(&self) {
+        self.task.abort();
+    }
+}
+
+impl<T> Drop for AccountStreamUpdater<T> {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn apply_updates<T: Amm, S: Stream<Item = AccountUpdate> + Unpin>(
+    mut accounts: StreamedAccounts,
+    mut updates: S,
+    state: Arc<RwLock<QuotableState<T>>>,
+    failed_updates: Arc<AtomicU64>,
+) {
+    while let Some(update) = updates.next().await {
+        let mut slot = None;
+        let mut apply = |update: AccountUpdate| {
+            let update_slot = update.slot;
+            if accounts.apply(update) {
+                slot = slot.max(Some(update_slot));
+            }
+        };
+        apply(update);
+        while let Some(Some(update)) = updates.next().now_or_never() {
+            apply(update);
+        }
+        // Only writes that were applied advance the slot.
+        let Some(slot) = slot else {
+            continue;
+        };
+        let mut state = state.write().unwrap_or_else(|poisoned| poisoned.into_inner());
+        match state.amm.update(&accounts.account_map) {
+            Ok(()) => state.slot = state.slot.max(slot),
+            Err(_) => {
+                failed_updates.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_streamed_accounts_keep_newest_write() {
+    let key = Pubkey::new_unique();
+    let update = |slot: u64, lamports: u64| AccountUpdate {
+        pubkey: key,
+        slot,
+        account: Account { lamports, ..Account::default() },
+    };
+    let mut accounts = StreamedAccounts::new(vec![key], AccountMap::new());
+    assert!(accounts.apply(update(10, 1)));
+    assert!(!accounts.apply(update(9, 2)));
+    assert!(accounts.apply(update(10, 3)));
+    assert_eq!(accounts.account_map[&key].lamports, 3);
+    assert!(!accounts.apply(AccountUpdate { pubkey: Pubkey::new_unique(), ..update(11, 4) }));
+}
+
+#[test]
+fn test_apply_updates_skips_stale_and_unfollowed_writes() {
+    use crate::amms::account_builders::{fixture_accounts, quotable_fund, FixtureToken, FundStateBuilder};
+    use crate::amms::accounts::{ONE_USD, WEIGHT_MULTIPLIER};
+
+    let tokens = [FixtureToken::new(6, 100_000_000), FixtureToken::new(9, 10_000_000_000)];
+    let fund_state = |sol_amount: u64| FundStateBuilder::new()
+        .with_token(0, 10_000_000_000, WEIGHT_MULTIPLIER / 2)
+        .with_token(1, sol_amount, WEIGHT_MULTIPLIER / 2);
+    let key = Pubkey::new_unique();
+    let fund = quotable_fund(key, &fund_state(100_000_000_000), &tokens);
+    let write = |pubkey: Pubkey, slot: u64, sol_amount: u64| AccountUpdate {
+        pubkey,
+        slot,
+        account: fund_state(sol_amount).build_account(),
+    };
+    let run = |state: &Arc<RwLock<QuotableState<_>>>, updates: Vec<AccountUpdate>| {
+        let accounts = StreamedAccounts::new(fund.get_accounts_to_update(), fixture_accounts(key, &fund_state(100_000_000_000), &tokens));
+        let failed_updates = Arc::new(AtomicU64::new(0));
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        runtime.block_on(apply_updates(accounts, futures::stream::iter(updates), state.clone(), failed_updates.clone()));
+        assert_eq!(failed_updates.load(Ordering::Relaxed), 0);
+    };
+
+    // The write from slot 15 arrives after the one from slot 20 and is dropped, and a key
+    // outside the filter neither applies nor advances the slot.
+    let state = Arc::new(RwLock::new(QuotableState { amm: fund.clone(), slot: 0 }));
+    run(&state, vec![write(key, 20, 200_000_000_000), write(key, 15, 150_000_000_000), write(Pubkey::new_unique(), 30, 0)]);
+    let state = state.read().unwrap();
+    assert_eq!(state.slot, 20);
+    assert_eq!(state.amm.fund_worth().unwrap(), 30_000 * ONE_USD);
+
+    let state = Arc::new(RwLock::new(QuotableState { amm: fund.clone(), slot: 0 }));
+    run(&state, vec![write(Pubkey::new_unique(), 30, 200_000_000_000)]);
+    let state = state.read().unwrap();
+    assert_eq!(state.slot, 0);
+    assert_eq!(state.amm.fund_worth().unwrap(), 20_000 * ONE_USD);
+}
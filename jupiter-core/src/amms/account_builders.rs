@@ -0,0 +1,450 @@
+//! Byte blobs laid out like the on-chain accounts, for offline tests of quoting edge cases.
+//! Each builder starts from a zeroed account with the right size and discriminator.
+
+use solana_sdk::{account::Account, clock::Clock, pubkey::Pubkey};
+
+use crate::amms::account_views::{
+    BUY_CURVES_OFFSET, SELL_CURVES_OFFSET, TOKEN_PRICE_DATA_SIZE, TOKEN_SETTINGS_OFFSET, TOKEN_SETTINGS_SIZE,
+};
+use crate::amms::accounts::{
+    TokenPriceData, TokenSettings, CURVE_DATA_ACCOUNT_SIZE, CURVE_DATA_DISCRIMINATOR, FUND_STATE_ACCOUNT_SIZE,
+    FUND_STATE_DISCRIMINATOR, MAX_TOKENS_IN_ASSET_POOL, NUM_OF_POINTS_IN_CURVE_DATA, NUM_TOKENS_IN_FUND,
+    ORACLE_ACCOUNT_SIZE, TOKEN_LIST_ACCOUNT_SIZE, TOKEN_LIST_DISCRIMINATOR,
+};
+use crate::amms::pyth_pull::{price_update_v2_data, VerificationLevel, PYTH_RECEIVER_PROGRAM_ID};
+use crate::amms::switchboard::{pull_feed_data, SWITCHBOARD_ON_DEMAND_PROGRAM_ID};
+use crate::amms::account_owners::PYTH_ORACLE_PROGRAM_ID;
+use crate::amms::symmetry_token_swap::SymmetryTokenSwap;
+
+fn put(data: &mut [u8], offset: usize, bytes: &[u8]) {
+    data[offset..offset + bytes.len()].copy_from_slice(bytes);
+}
+
+fn account(data: Vec<u8>, owner: Pubkey) -> Account {
+    Account { lamports: 1_000_000_000, data, owner, ..Account::default() }
+}
+
+/// FundState account data. Tokens are added to the composition in order.
+#[derive(Clone)]
+pub struct FundStateBuilder {
+    data: Vec<u8>,
+    num_of_tokens: usize,
+    weight_sum: u64,
+}
+
+impl Default for FundStateBuilder {
+    fn default() -> Self {
+        let mut data = vec![0u8; FUND_STATE_ACCOUNT_SIZE];
+        put(&mut data, 0, &FUND_STATE_DISCRIMINATOR);
+        Self { data, num_of_tokens: 0, weight_sum: 0 }
+    }
+}
+
+impl FundStateBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_manager(mut self, manager: Pubkey) -> Self {
+        put(&mut self.data, 16, manager.as_ref());
+        self
+    }
+
+    pub fn with_fund_token(mut self, fund_token: Pubkey) -> Self {
+        put(&mut self.data, 48, fund_token.as_ref());
+        self
+    }
+
+    pub fn with_supply_outstanding(mut self, supply_outstanding: u64) -> Self {
+        put(&mut self.data, 88, &supply_outstanding.to_le_bytes());
+        self
+    }
+
+    /// Sets `active_buy_states`, `sell_state` and `rebalance_sell_state`.
+    pub fn with_rebalance_states(mut self, active_buy_states: u64, sell_state: u64, rebalance_sell_state: u64) -> Self {
+        put(&mut self.data, 104, &active_buy_states.to_le_bytes());
+        put(&mut self.data, 112, &sell_state.to_le_bytes());
+        put(&mut self.data, 120, &rebalance_sell_state.to_le_bytes());
+        self
+    }
+
+    /// Manager and host deposit fees, in bps of the minted fund tokens.
+    pub fn with_deposit_fees(mut self, manager_fee: u64, host_fee: u64) -> Self {
+        put(&mut self.data, 80, &manager_fee.to_le_bytes());
+        put(&mut self.data, 160, &host_fee.to_le_bytes());
+        self
+    }
+
+    pub fn with_host(mut self, host: Pubkey) -> Self {
+        put(&mut self.data, 128, host.as_ref());
+        self
+    }
+
+    /// Adds token list entry `token_id` to the composition, holding `amount` with
+    /// `target_weight` counted towards `weight_sum`.
+    pub fn with_token(mut self, token_id: u64, amount: u64, target_weight: u64) -> Self {
+        assert!(self.num_of_tokens < NUM_TOKENS_IN_FUND, "a fund holds at most {NUM_TOKENS_IN_FUND} tokens");
+        let i = self.num_of_tokens;
+        put(&mut self.data, 176 + i * 8, &token_id.to_le_bytes());
+        put(&mut self.data, 336 + i * 8, &amount.to_le_bytes());
+        put(&mut self.data, 656 + i * 8, &target_weight.to_le_bytes());
+        self.num_of_tokens += 1;
+        self.weight_sum += target_weight;
+        self
+    }
+
+    /// Overrides the sum of the target weights added with `with_token`.
+    pub fn with_weight_sum(mut self, weight_sum: u64) -> Self {
+        self.weight_sum = weight_sum;
+        self
+    }
+
+    /// Fund worth as of `last_update_time`, in `ONE_USD` precision.
+    pub fn with_fund_worth(mut self, fund_worth: u64, last_update_time: u64) -> Self {
+        put(&mut self.data, 984, &fund_worth.to_le_bytes());
+        put(&mut self.data, 992, &last_update_time.to_le_bytes());
+        self
+    }
+
+    /// Swaps may move a token's weight by `rebalance_threshold * lp_offset_threshold` parts in
+    /// 10^8 of its target weight; see `allowed_weight_band`.
+    pub fn with_weight_band(mut self, rebalance_threshold: u64, lp_offset_threshold: u64) -> Self {
+        put(&mut self.data, 1024, &rebalance_threshold.to_le_bytes());
+        put(&mut self.data, 1040, &lp_offset_threshold.to_le_bytes());
+        self
+    }
+
+    pub fn with_lp_disabled(mut self, lp_disabled: u64) -> Self {
+        put(&mut self.data, 9432, &lp_disabled.to_le_bytes());
+        self
+    }
+
+    pub fn build(&self) -> Vec<u8> {
+        let mut data = self.data.clone();
+        put(&mut data, 168, &(self.num_of_tokens as u64).to_le_bytes());
+        put(&mut data, 816, &self.weight_sum.to_le_bytes());
+        data
+    }
+
+    /// The data in an account owned by the Symmetry program.
+    pub fn build_account(&self) -> Account {
+        account(self.build(), SymmetryTokenSwap::SYMMETRY_PROGRAM_ADDRESS)
+    }
+}
+
+/// TokenList account data. Tokens are listed in order; entry 0 carries the fee split.
+#[derive(Clone, Default)]
+pub struct TokenListBuilder {
+    tokens: Vec<TokenSettings>,
+    fee_split_pct: Option<[u8; 3]>,
+}
+
+impl TokenListBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Lists `settings` at the next token id. Its `oracle_price` isn't part of the account.
+    pub fn with_token(mut self, settings: TokenSettings) -> Self {
+        assert!(self.tokens.len() < MAX_TOKENS_IN_ASSET_POOL, "the token list holds at most {MAX_TOKENS_IN_ASSET_POOL} tokens");
+        self.tokens.push(settings);
+        self
+    }
+
+    /// Symmetry, host and manager shares of swap fees, in percent, written to entry 0.
+    pub fn with_fee_split(mut self, symmetry_fee_pct: u8, host_fee_pct: u8, manager_fee_pct: u8) -> Self {
+        self.fee_split_pct = Some([symmetry_fee_pct, host_fee_pct, manager_fee_pct]);
+        self
+    }
+
+    pub fn build(&self) -> Vec<u8> {
+        let mut data = vec![0u8; TOKEN_LIST_ACCOUNT_SIZE];
+        put(&mut data, 0, &TOKEN_LIST_DISCRIMINATOR);
+        put(&mut data, 8, &(self.tokens.len() as u64).to_le_bytes());
+        for (token_id, settings) in self.tokens.iter().enumerate() {
+            let entry = TOKEN_SETTINGS_OFFSET + token_id * TOKEN_SETTINGS_SIZE;
+            put(&mut data, entry, settings.token_mint.as_ref());
+            data[entry + 32] = settings.decimals;
+            put(&mut data, entry + 33, &settings.coingecko_id);
+            put(&mut data, entry + 63, settings.pda_token_account.as_ref());
+            data[entry + 95] = settings.oracle_type;
+            put(&mut data, entry + 96, settings.oracle_account.as_ref());
+            put(&mut data, entry + 128, &[
+                settings.oracle_index,
+                settings.oracle_confidence_pct,
+                settings.fixed_confidence_bps,
+                settings.token_swap_fee_after_tw_bps,
+                settings.token_swap_fee_before_tw_bps,
+                settings.is_live,
+                settings.lp_on,
+                settings.use_curve_data,
+            ]);
+            put(&mut data, entry + 136, &settings.additional_data);
+        }
+        if let Some(fee_split_pct) = self.fee_split_pct {
+            put(&mut data, TOKEN_SETTINGS_OFFSET + 136 + 60, &fee_split_pct);
+        }
+        data
+    }
+
+    pub fn build_account(&self) -> Account {
+        account(self.build(), SymmetryTokenSwap::SYMMETRY_PROGRAM_ADDRESS)
+    }
+}
+
+/// CurveData account data. Curves not set are all zero, i.e. unused.
+#[derive(Clone)]
+pub struct CurveDataBuilder {
+    data: Vec<u8>,
+}
+
+impl Default for CurveDataBuilder {
+    fn default() -> Self {
+        let mut data = vec![0u8; CURVE_DATA_ACCOUNT_SIZE];
+        put(&mut data, 0, &CURVE_DATA_DISCRIMINATOR);
+        Self { data }
+    }
+}
+
+impl CurveDataBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn with_curve(mut self, offset: usize, token_id: usize, curve: TokenPriceData) -> Self {
+        let offset = offset + token_id * TOKEN_PRICE_DATA_SIZE;
+        for j in 0..NUM_OF_POINTS_IN_CURVE_DATA {
+            put(&mut self.data, offset + j * 8, &curve.amount[j].to_le_bytes());
+            put(&mut self.data, offset + 80 + j * 8, &curve.price[j].to_le_bytes());
+        }
+        self
+    }
+
+    pub fn with_buy_curve(self, token_id: usize, curve: TokenPriceData) -> Self {
+        self.with_curve(BUY_CURVES_OFFSET, token_id, curve)
+    }
+
+    pub fn with_sell_curve(self, token_id: usize, curve: TokenPriceData) -> Self {
+        self.with_curve(SELL_CURVES_OFFSET, token_id, curve)
+    }
+
+    pub fn build(&self) -> Vec<u8> {
+        self.data.clone()
+    }
+
+    pub fn build_account(&self) -> Account {
+        account(self.build(), SymmetryTokenSwap::SYMMETRY_PROGRAM_ADDRESS)
+    }
+}
+
+/// Oracle account data for each supported `oracle_type`.
+#[derive(Clone)]
+pub struct OracleAccountBuilder {
+    data: Vec<u8>,
+    owner: Pubkey,
+}
+
+impl OracleAccountBuilder {
+    /// Legacy Pyth price account (type 0) with trading status, `price * 10^expo` published
+    /// at `valid_slot`, and an EMA price equal to `price`.
+    pub fn pyth(price: i64, conf: u64, expo: i32, valid_slot: u64) -> Self {
+        let mut data = vec![0u8; ORACLE_ACCOUNT_SIZE[0]];
+        put(&mut data, 20, &expo.to_le_bytes());
+        put(&mut data, 40, &valid_slot.to_le_bytes());
+        put(&mut data, 48, &price.to_le_bytes());
+        put(&mut data, 208, &price.to_le_bytes());
+        put(&mut data, 216, &conf.to_le_bytes());
+        put(&mut data, 224, &1u32.to_le_bytes());
+        Self { data, owner: PYTH_ORACLE_PROGRAM_ID }
+    }
+
+    /// Sets the EMA price of a legacy Pyth or fully verified Pyth pull oracle, in the units
+    /// of its spot price.
+    pub fn with_ema_price(mut self, ema_price: i64) -> Self {
+        // The pull message starts after a fully verified account's 41-byte header.
+        let offset = match self.owner {
+            PYTH_ORACLE_PROGRAM_ID => 48,
+            PYTH_RECEIVER_PROGRAM_ID => 41 + 68,
+            _ => panic!("with_ema_price only applies to Pyth oracles"),
+        };
+        put(&mut self.data, offset, &ema_price.to_le_bytes());
+        self
+    }
+
+    /// Internal oracle account (type 1) with every slot empty; fill them with `with_price`.
+    pub fn internal() -> Self {
+        Self { data: vec![0u8; ORACLE_ACCOUNT_SIZE[1]], owner: SymmetryTokenSwap::SYMMETRY_PROGRAM_ADDRESS }
+    }
+
+    /// Sets internal oracle slot `oracle_index` to `price`, in `ONE_USD` precision, written
+    /// at unix time `write_timestamp`.
+    pub fn with_price(mut self, oracle_index: u8, price: u64, write_timestamp: u64) -> Self {
+        assert_eq!(self.data.len(), ORACLE_ACCOUNT_SIZE[1], "with_price only applies to internal oracles");
+        let index = oracle_index as usize;
+        put(&mut self.data, 9 + index * 8, &price.to_le_bytes());
+        put(&mut self.data, 409 + index * 8, &write_timestamp.to_le_bytes());
+        self
+    }
+
+    /// Fully verified Pyth `PriceUpdateV2` (type 2) of `price * 10^exponent`.
+    pub fn pyth_pull(price: i64, conf: u64, exponent: i32, publish_time: i64) -> Self {
+        Self {
+            data: price_update_v2_data(VerificationLevel::Full, price, conf, exponent, publish_time),
+            owner: PYTH_RECEIVER_PROGRAM_ID,
+        }
+    }
+
+    /// Switchboard On-Demand pull feed (type 3) whose 18-decimal result was computed at `slot`.
+    pub fn switchboard(value: i128, std_dev: i128, num_samples: u8, slot: u64) -> Self {
+        Self {
+            data: pull_feed_data(value, std_dev, num_samples, slot, 0),
+            owner: SWITCHBOARD_ON_DEMAND_PROGRAM_ID,
+        }
+    }
+
+    pub fn build(&self) -> Vec<u8> {
+        self.data.clone()
+    }
+
+    /// The data in an account owned by the oracle's program.
+    pub fn build_account(&self) -> Account {
+        account(self.build(), self.owner)
+    }
+}
+
+/// The clock sysvar account holding `clock`, which `update` reads oracle staleness against.
+pub fn clock_account(clock: &Clock) -> Account {
+    Account { data: bincode::serialize(clock).unwrap(), ..Account::default() }
+}
+
+/// A token of a fund built by `quotable_fund`, priced by its own Pyth oracle account.
+#[derive(Clone, Copy, Debug)]
+pub struct FixtureToken {
+    pub mint: Pubkey,
+    pub decimals: u8,
+    /// USD price with 8 decimals.
+    pub price: i64,
+    pub oracle: Pubkey,
+}
+
+impl FixtureToken {
+    pub fn new(decimals: u8, price: i64) -> Self {
+        Self { mint: Pubkey::new_unique(), decimals, price, oracle: Pubkey::new_unique() }
+    }
+
+    /// Token list entry charging 10 bps before and 30 bps after the target weight.
+    pub fn settings(&self) -> TokenSettings {
+        TokenSettings {
+            token_mint: self.mint,
+            decimals: self.decimals,
+            oracle_account: self.oracle,
+            oracle_confidence_pct: 10,
+            token_swap_fee_after_tw_bps: 30,
+            token_swap_fee_before_tw_bps: 10,
+            is_live: 1,
+            lp_on: 1,
+            ..TokenSettings::empty()
+        }
+    }
+
+    /// Its Pyth oracle at `price`, published at `valid_slot`.
+    pub fn oracle_account(&self, valid_slot: u64) -> Account {
+        OracleAccountBuilder::pyth(self.price, 0, -8, valid_slot).build_account()
+    }
+}
+
+/// Token list of `tokens` in order, fees split 20/10/30% to Symmetry, the host and the manager.
+pub fn fixture_token_list(tokens: &[FixtureToken]) -> TokenListBuilder {
+    tokens.iter().fold(TokenListBuilder::new().with_fee_split(20, 10, 30), |token_list, token| {
+        token_list.with_token(token.settings())
+    })
+}
+
+/// Accounts `update()` reads for `fund_state` keyed `key`: no curves, the clock at slot
+/// 1,010 and each token's oracle published at slot 1,000. Insert over them to vary one.
+pub fn fixture_accounts(key: Pubkey, fund_state: &FundStateBuilder, tokens: &[FixtureToken]) -> jupiter_amm_interface::AccountMap {
+    let mut account_map: jupiter_amm_interface::AccountMap = [
+        (key, fund_state.build_account()),
+        (SymmetryTokenSwap::CURVE_DATA_ADDRESS, CurveDataBuilder::new().build_account()),
+        (solana_sdk::sysvar::clock::ID, clock_account(&Clock { slot: 1_010, ..Clock::default() })),
+    ].into();
+    for token in tokens {
+        account_map.insert(token.oracle, token.oracle_account(1_000));
+    }
+    account_map
+}
+
+/// Loads `fund_state` as a fund keyed `key` whose token ids index `tokens`, listed by
+/// `fixture_token_list` and updated from `fixture_accounts`.
+pub fn quotable_fund(key: Pubkey, fund_state: &FundStateBuilder, tokens: &[FixtureToken]) -> SymmetryTokenSwap {
+    use jupiter_amm_interface::{Amm, KeyedAccount};
+
+    let mut fund = SymmetryTokenSwap::from_keyed_account(
+        &KeyedAccount { key, account: fund_state.build_account(), params: None },
+        &KeyedAccount { key: SymmetryTokenSwap::TOKEN_LIST_ADDRESS, account: fixture_token_list(tokens).build_account(), params: None },
+    ).unwrap();
+    fund.update(&fixture_accounts(key, fund_state, tokens)).unwrap();
+    fund
+}
+
+#[test]
+fn test_builders_produce_quotable_fund() {
+    use jupiter_amm_interface::{AccountMap, Amm, KeyedAccount, QuoteParams};
+
+    use crate::amms::accounts::{ONE_USD, WEIGHT_MULTIPLIER};
+    use crate::amms::amm_config::AmmConfig;
+
+    let usdc = Pubkey::new_unique();
+    let sol = Pubkey::new_unique();
+    let usdc_oracle = Pubkey::new_unique();
+    let sol_oracle = Pubkey::new_unique();
+    let token = |mint: Pubkey, decimals: u8, oracle_account: Pubkey| TokenSettings {
+        token_mint: mint,
+        decimals,
+        oracle_account,
+        oracle_confidence_pct: 10,
+        token_swap_fee_after_tw_bps: 30,
+        token_swap_fee_before_tw_bps: 10,
+        is_live: 1,
+        lp_on: 1,
+        ..TokenSettings::empty()
+    };
+    let token_list = TokenListBuilder::new()
+        .with_token(token(usdc, 6, usdc_oracle))
+        .with_token(token(sol, 9, sol_oracle))
+        .with_fee_split(20, 10, 30);
+    // $10,000 of USDC and 100 SOL at $100, targeting half each within 5% of the target.
+    let fund_state = FundStateBuilder::new()
+        .with_token(0, 10_000_000_000, WEIGHT_MULTIPLIER / 2)
+        .with_token(1, 100_000_000_000, WEIGHT_MULTIPLIER / 2)
+        .with_weight_band(1_000, 5_000)
+        .with_supply_outstanding(20_000_000_000);
+
+    let fund = Pubkey::new_unique();
+    let mut amm = SymmetryTokenSwap::from_keyed_account(
+        &KeyedAccount { key: fund, account: fund_state.build_account(), params: None },
+        &KeyedAccount { key: SymmetryTokenSwap::TOKEN_LIST_ADDRESS, account: token_list.build_account(), params: None },
+    ).unwrap();
+
+    let mut account_map = AccountMap::new();
+    account_map.insert(fund, fund_state.build_account());
+    account_map.insert(SymmetryTokenSwap::CURVE_DATA_ADDRESS, CurveDataBuilder::new().build_account());
+    account_map.insert(usdc_oracle, OracleAccountBuilder::pyth(100_000_000, 10_000, -8, 1_000).build_account());
+    account_map.insert(sol_oracle, OracleAccountBuilder::pyth(10_000_000_000, 1_000_000, -8, 1_000).build_account());
+    amm.update_with_clock(&account_map, Clock { slot: 1_010, ..Clock::default() }).unwrap();
+    assert_eq!(amm.fund_worth().unwrap(), 20_000 * ONE_USD);
+    assert_eq!(amm.oracle_price(&sol).unwrap().avg_price, 100 * ONE_USD);
+    assert_eq!(amm.all_prices().iter().map(|(mint, _)| *mint).collect::<Vec<_>>(), vec![usdc, sol]);
+
+    let quote = amm.quote(&QuoteParams { in_amount: 100_000_000, input_mint: usdc, output_mint: sol }).unwrap();
+    assert!(quote.out_amount > 990_000_000 && quote.out_amount < 1_000_000_000);
+    assert_eq!(quote.fee_mint, sol);
+
+    // SOL's EMA at $90 values the fund's 100 SOL at $9,000; the sell price stays at spot.
+    account_map.insert(sol_oracle, OracleAccountBuilder::pyth(10_000_000_000, 1_000_000, -8, 1_000).with_ema_price(9_000_000_000).build_account());
+    let mut amm = amm.with_config(AmmConfig { use_ema_price: true, ..AmmConfig::default() });
+    amm.update_with_clock(&account_map, Clock { slot: 1_010, ..Clock::default() }).unwrap();
+    assert_eq!(amm.fund_worth().unwrap(), 19_000 * ONE_USD);
+    assert_eq!(amm.oracle_price(&sol).unwrap().avg_price, 90 * ONE_USD);
+    assert!(amm.oracle_price(&sol).unwrap().sell_price > 99 * ONE_USD);
+}
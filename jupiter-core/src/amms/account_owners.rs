@@ -0,0 +1,104 @@
+use std::convert::TryFrom;
+
+use anyhow::Result;
+use jupiter_amm_interface::{AccountMap, Amm, KeyedAccount};
+use solana_sdk::{account::Account, pubkey, pubkey::Pubkey};
+
+use crate::amms::accounts::MAX_TOKENS_IN_ASSET_POOL;
+use crate::amms::oracle_type::OracleType;
+use crate::amms::symmetry_token_swap::SymmetryTokenSwap;
+use crate::error::SymmetryError;
+
+/// Legacy Pyth oracle program owning the price accounts of `oracle_type` 0 tokens.
+pub const PYTH_ORACLE_PROGRAM_ID: Pubkey = pubkey!("FsJ3A3u2vn5cTVofAjvy6y5kwABJAqYWpe4975bi2epH");
+
+/// Program expected to own the oracle account of a token with `oracle_type`, if known.
+/// Internal oracles (type 1) are written by the Symmetry program, Pyth pull oracles (type 2)
+/// by the Pyth receiver and Switchboard feeds (type 3) by Switchboard On-Demand.
+pub fn expected_oracle_owner(oracle_type: u8) -> Option<Pubkey> {
+    OracleType::try_from(oracle_type).ok().map(OracleType::expected_owner)
+}
+
+/// Fails with `SymmetryError::AccountOwnerMismatch` unless `account` is owned by `expected_owner`.
+pub fn check_account_owner(key: &Pubkey, account: &Account, expected_owner: &Pubkey) -> Result<()> {
+    if account.owner != *expected_owner {
+        return Err(SymmetryError::AccountOwnerMismatch {
+            account: *key,
+            expected_owner: *expected_owner,
+            actual_owner: account.owner,
+        }.into());
+    }
+    Ok(())
+}
+
+fn check_owner_in_map(account_map: &AccountMap, key: &Pubkey, expected_owner: &Pubkey) -> Result<()> {
+    let account = account_map.get(key)
+        .ok_or(SymmetryError::MissingAccount { key: *key })?;
+    check_account_owner(key, account, expected_owner)
+}
+
+impl SymmetryTokenSwap {
+    /// `from_keyed_account` after checking that both accounts are owned by the Symmetry
+    /// program, for accounts fetched from RPC rather than raw bytes.
+    pub fn from_keyed_account_checked(fund_state_account: &KeyedAccount, token_list_account: &KeyedAccount) -> Result<Self> {
+        for keyed_account in [fund_state_account, token_list_account] {
+            check_account_owner(&keyed_account.key, &keyed_account.account, &SymmetryTokenSwap::SYMMETRY_PROGRAM_ADDRESS)?;
+        }
+        SymmetryTokenSwap::from_keyed_account(fund_state_account, token_list_account)
+    }
+
+    /// Checks the owners of the accounts `update()` reads from `account_map`: the fund state
+    /// and curve data must belong to the Symmetry program, oracles to their oracle program and
+    /// the fund token mint and fallback oracles, when present, to the token program and their
+    /// oracle program. The token list is only read at construction; check it with
+    /// `check_account_owner`.
+    pub fn verify_account_owners(&self, account_map: &AccountMap) -> Result<()> {
+        let program_id = self.symmetry_config().program_id;
+        check_owner_in_map(account_map, &self.key(), &program_id)?;
+        check_owner_in_map(account_map, &self.symmetry_config().curve_data, &program_id)?;
+        let fund_token = self.fund_state().fund_token;
+        if let Some(account) = account_map.get(&fund_token) {
+            check_account_owner(&fund_token, account, &SymmetryTokenSwap::SPL_TOKEN_PROGRAM_ADDRESS)?;
+        }
+        for token_settings in &self.token_list().list[..MAX_TOKENS_IN_ASSET_POOL] {
+            if token_settings.oracle_account == Pubkey::default() {
+                continue;
+            }
+            // Internal oracles belong to the configured deployment's program.
+            let owner = match token_settings.oracle_type() {
+                Ok(OracleType::SymmetryCustom) => Some(program_id),
+                oracle_type => oracle_type.ok().map(OracleType::expected_owner),
+            };
+            if let Some(owner) = owner {
+                check_owner_in_map(account_map, &token_settings.oracle_account, &owner)?;
+            }
+        }
+        for fallback in self.fallback_oracles().values() {
+            let owner = match fallback.oracle_type {
+                OracleType::SymmetryCustom => program_id,
+                oracle_type => oracle_type.expected_owner(),
+            };
+            if let Some(account) = account_map.get(&fallback.oracle_account) {
+                check_account_owner(&fallback.oracle_account, account, &owner)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_check_account_owner() {
+    let key = Pubkey::new_unique();
+    let account = Account { owner: SymmetryTokenSwap::SYMMETRY_PROGRAM_ADDRESS, ..Account::default() };
+    assert!(check_account_owner(&key, &account, &SymmetryTokenSwap::SYMMETRY_PROGRAM_ADDRESS).is_ok());
+
+    let error = check_account_owner(&key, &account, &PYTH_ORACLE_PROGRAM_ID).unwrap_err();
+    assert_eq!(
+        error.downcast_ref::<SymmetryError>(),
+        Some(&SymmetryError::AccountOwnerMismatch {
+            account: key,
+            expected_owner: PYTH_ORACLE_PROGRAM_ID,
+            actual_owner: SymmetryTokenSwap::SYMMETRY_PROGRAM_ADDRESS,
+        })
+    );
+}
@@ -0,0 +1,194 @@
+//! Views reading the token list and curve data accounts in place, so callers that need a
+//! few entries don't copy the whole 40KB and 64KB accounts.
+
+use anyhow::Result;
+use solana_sdk::pubkey::Pubkey;
+use std::convert::TryInto;
+
+use crate::amms::accounts::{
+    check_discriminator, CurveData, OraclePrice, TokenList, TokenPriceData, TokenSettings,
+    CURVE_DATA_ACCOUNT_SIZE, CURVE_DATA_DISCRIMINATOR, MAX_TOKENS_IN_ASSET_POOL,
+    NUM_OF_POINTS_IN_CURVE_DATA, TOKEN_LIST_ACCOUNT_SIZE, TOKEN_LIST_DISCRIMINATOR,
+};
+use crate::error::SymmetryError;
+
+pub(crate) const TOKEN_SETTINGS_OFFSET: usize = 16;
+pub(crate) const TOKEN_SETTINGS_SIZE: usize = 199;
+pub(crate) const BUY_CURVES_OFFSET: usize = 8;
+pub(crate) const SELL_CURVES_OFFSET: usize = 32008;
+pub(crate) const TOKEN_PRICE_DATA_SIZE: usize = 160;
+
+fn u64_at(account_data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(account_data[offset..offset + 8].try_into().unwrap())
+}
+
+fn pubkey_at(account_data: &[u8], offset: usize) -> Pubkey {
+    Pubkey::new_from_array(account_data[offset..offset + 32].try_into().unwrap())
+}
+
+/// The token list account, read in place.
+#[derive(Clone, Copy)]
+pub struct TokenListView<'a> {
+    account_data: &'a [u8],
+}
+
+impl<'a> TokenListView<'a> {
+    pub fn load(account_data: &'a [u8]) -> Result<Self> {
+        if account_data.len() != TOKEN_LIST_ACCOUNT_SIZE {
+            return Err(SymmetryError::AccountSizeMismatch {
+                account: "TokenList",
+                expected: TOKEN_LIST_ACCOUNT_SIZE,
+                got: account_data.len(),
+            }.into());
+        }
+        check_discriminator("TokenList", account_data, TOKEN_LIST_DISCRIMINATOR)?;
+        Ok(Self { account_data })
+    }
+
+    /// Number of listed tokens, capped at `MAX_TOKENS_IN_ASSET_POOL`.
+    pub fn num_tokens(&self) -> usize {
+        (u64_at(self.account_data, 8) as usize).min(MAX_TOKENS_IN_ASSET_POOL)
+    }
+
+    fn entry(&self, token_id: usize) -> Option<&'a [u8]> {
+        if token_id >= self.num_tokens() {
+            return None;
+        }
+        let offset = TOKEN_SETTINGS_OFFSET + token_id * TOKEN_SETTINGS_SIZE;
+        Some(&self.account_data[offset..offset + TOKEN_SETTINGS_SIZE])
+    }
+
+    pub fn token_mint(&self, token_id: usize) -> Option<Pubkey> {
+        self.entry(token_id).map(|entry| pubkey_at(entry, 0))
+    }
+
+    /// Settings of token `token_id`, with a zero `oracle_price`.
+    pub fn token_settings(&self, token_id: usize) -> Option<TokenSettings> {
+        let entry = self.entry(token_id)?;
+        Some(TokenSettings {
+            token_mint: pubkey_at(entry, 0),
+            decimals: entry[32],
+            coingecko_id: [0; 30],
+            pda_token_account: pubkey_at(entry, 63),
+            oracle_type: entry[95],
+            oracle_account: pubkey_at(entry, 96),
+            oracle_index: entry[128],
+            oracle_confidence_pct: entry[129],
+            fixed_confidence_bps: entry[130],
+            token_swap_fee_after_tw_bps: entry[131],
+            token_swap_fee_before_tw_bps: entry[132],
+            is_live: entry[133],
+            lp_on: entry[134],
+            use_curve_data: entry[135],
+            additional_data: entry[136..199].try_into().unwrap(),
+            oracle_price: OraclePrice { sell_price: 0, avg_price: 0, buy_price: 0, oracle_live: 0 },
+        })
+    }
+
+    /// Token list index of `mint`.
+    pub fn position(&self, mint: &Pubkey) -> Option<usize> {
+        (0..self.num_tokens()).find(|&token_id| self.token_mint(token_id) == Some(*mint))
+    }
+
+    /// Overwrites the listed entries of `token_list`, leaving the others untouched. The
+    /// token count is clamped to the list's capacity, like `num_tokens()`.
+    pub fn copy_into(&self, token_list: &mut TokenList) {
+        token_list.num_tokens = self.num_tokens() as u64;
+        for token_id in 0..self.num_tokens() {
+            token_list.list[token_id] = self.token_settings(token_id).unwrap();
+        }
+    }
+}
+
+/// The curve data account, read in place.
+#[derive(Clone, Copy)]
+pub struct CurveDataView<'a> {
+    account_data: &'a [u8],
+}
+
+impl<'a> CurveDataView<'a> {
+    pub fn load(account_data: &'a [u8]) -> Result<Self> {
+        if account_data.len() != CURVE_DATA_ACCOUNT_SIZE {
+            return Err(SymmetryError::AccountSizeMismatch {
+                account: "CurveData",
+                expected: CURVE_DATA_ACCOUNT_SIZE,
+                got: account_data.len(),
+            }.into());
+        }
+        check_discriminator("CurveData", account_data, CURVE_DATA_DISCRIMINATOR)?;
+        Ok(Self { account_data })
+    }
+
+    fn token_price_data(&self, offset: usize, token_id: usize) -> Option<TokenPriceData> {
+        if token_id >= MAX_TOKENS_IN_ASSET_POOL {
+            return None;
+        }
+        let offset = offset + token_id * TOKEN_PRICE_DATA_SIZE;
+        let mut data = TokenPriceData {
+            amount: [0; NUM_OF_POINTS_IN_CURVE_DATA],
+            price: [0; NUM_OF_POINTS_IN_CURVE_DATA],
+        };
+        for j in 0..NUM_OF_POINTS_IN_CURVE_DATA {
+            data.amount[j] = u64_at(self.account_data, offset + j * 8);
+            data.price[j] = u64_at(self.account_data, offset + 80 + j * 8);
+        }
+        Some(data)
+    }
+
+    /// Curve the fund buys token `token_id` along.
+    pub fn buy(&self, token_id: usize) -> Option<TokenPriceData> {
+        self.token_price_data(BUY_CURVES_OFFSET, token_id)
+    }
+
+    /// Curve the fund sells token `token_id` along.
+    pub fn sell(&self, token_id: usize) -> Option<TokenPriceData> {
+        self.token_price_data(SELL_CURVES_OFFSET, token_id)
+    }
+
+    /// Overwrites the curves of `token_ids` in `curve_data`, leaving the others untouched.
+    pub fn copy_into(&self, token_ids: impl IntoIterator<Item = u64>, curve_data: &mut CurveData) {
+        for token_id in token_ids {
+            let token_id = token_id as usize;
+            if let (Some(buy), Some(sell)) = (self.buy(token_id), self.sell(token_id)) {
+                curve_data.buy[token_id] = buy;
+                curve_data.sell[token_id] = sell;
+            }
+        }
+    }
+}
+
+#[test]
+fn test_account_views() {
+    let mut token_list_data = vec![0u8; TOKEN_LIST_ACCOUNT_SIZE];
+    token_list_data[..8].copy_from_slice(&TOKEN_LIST_DISCRIMINATOR);
+    token_list_data[8..16].copy_from_slice(&2u64.to_le_bytes());
+    let mint = Pubkey::new_unique();
+    let entry = TOKEN_SETTINGS_OFFSET + TOKEN_SETTINGS_SIZE;
+    token_list_data[entry..entry + 32].copy_from_slice(mint.as_ref());
+    token_list_data[entry + 32] = 9;
+    token_list_data[entry + 134] = 1;
+    let view = TokenListView::load(&token_list_data).unwrap();
+    assert_eq!(view.num_tokens(), 2);
+    assert_eq!(view.position(&mint), Some(1));
+    assert_eq!(view.token_settings(1).unwrap().decimals, 9);
+    assert!(view.token_settings(2).is_none());
+    let token_list = TokenList::load(&token_list_data).unwrap();
+    assert_eq!(token_list.list[1].token_mint, mint);
+    assert_eq!(token_list.list[1].lp_on, 1);
+
+    let mut curve_data_bytes = vec![0u8; CURVE_DATA_ACCOUNT_SIZE];
+    curve_data_bytes[..8].copy_from_slice(&CURVE_DATA_DISCRIMINATOR);
+    let sell = SELL_CURVES_OFFSET + 3 * TOKEN_PRICE_DATA_SIZE;
+    curve_data_bytes[sell + 8..sell + 16].copy_from_slice(&500u64.to_le_bytes());
+    curve_data_bytes[sell + 88..sell + 96].copy_from_slice(&7u64.to_le_bytes());
+    let view = CurveDataView::load(&curve_data_bytes).unwrap();
+    assert_eq!(view.sell(3).unwrap().amount[1], 500);
+    assert_eq!(view.sell(3).unwrap().price[1], 7);
+    assert!(view.buy(MAX_TOKENS_IN_ASSET_POOL).is_none());
+    let mut curve_data = CurveData::empty();
+    view.copy_into([3], &mut curve_data);
+    assert_eq!(curve_data.sell[3], view.sell(3).unwrap());
+    assert!(CurveDataView::load(&curve_data_bytes[1..]).is_err());
+    curve_data_bytes[..8].copy_from_slice(&TOKEN_LIST_DISCRIMINATOR);
+    assert!(CurveDataView::load(&curve_data_bytes).is_err());
+}
@@ -1,16 +1,36 @@
 use anchor_lang::prelude::*;
-use std::convert::TryInto;
-use anyhow::{Result, Error};
+use std::convert::{TryFrom, TryInto};
+use anyhow::Result;
+
+use crate::amms::account_views::{CurveDataView, TokenListView};
+use crate::amms::oracle_type::OracleType;
+use crate::amms::pyth_pull::{PriceUpdateV2, PRICE_UPDATE_V2_SIZE};
+use crate::amms::switchboard::{PullFeedResult, PULL_FEED_ACCOUNT_SIZE};
+use crate::error::SymmetryError;
 
 pub const FUND_STATE_ACCOUNT_SIZE: usize = 10208;
+/// Anchor discriminator of `FundState`, the first 8 bytes of `sha256("account:FundState")`.
+pub const FUND_STATE_DISCRIMINATOR: [u8; 8] = [3, 254, 145, 43, 146, 96, 162, 104];
+/// Leading bytes of a FundState covering everything `FundStateHeader` reads.
+pub const FUND_STATE_HEADER_SIZE: usize = 1000;
+pub const FUND_STATE_MANAGER_OFFSET: usize = 16;
+pub const FUND_STATE_HOST_OFFSET: usize = 128;
 pub const TOKEN_LIST_ACCOUNT_SIZE: usize = 39816;
+/// Anchor discriminator of `TokenList`, the first 8 bytes of `sha256("account:TokenList")`.
+pub const TOKEN_LIST_DISCRIMINATOR: [u8; 8] = [145, 167, 153, 173, 5, 187, 157, 150];
 pub const CURVE_DATA_ACCOUNT_SIZE: usize = 64008;
-pub const ORACLE_ACCOUNT_SIZE: [usize; 2] = [3312, 809];
+/// Anchor discriminator of `CurveData`, the first 8 bytes of `sha256("account:CurveData")`.
+pub const CURVE_DATA_DISCRIMINATOR: [u8; 8] = [68, 250, 194, 234, 191, 224, 106, 12];
+/// Oracle account size by `TokenSettings::oracle_type`: legacy Pyth, internal, Pyth pull,
+/// Switchboard On-Demand.
+pub const ORACLE_ACCOUNT_SIZE: [usize; 4] = [3312, 809, PRICE_UPDATE_V2_SIZE, PULL_FEED_ACCOUNT_SIZE];
 
 pub const MAX_TOKENS_IN_ASSET_POOL: usize = 100;
 pub const NUM_TOKENS_IN_FUND: usize = 20;
 pub const NUM_OF_POINTS_IN_CURVE_DATA: usize = 10;
 pub const ONE_USD: u64 = 1_000_000_000_000;
+/// Decimals of `ONE_USD`.
+pub const ONE_USD_DECIMALS: u32 = 12;
 pub const USE_CURVE_DATA: u8 = 1;
 pub const BPS_DIVIDER: u64 = 10000;
 pub const WEIGHT_MULTIPLIER: u64 = 10000;
@@ -25,33 +45,124 @@ pub fn mul_div(a: u64, b: u64, c: u64) -> u64 {
     }
 }
 
+/// Direction integer division rounds in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rounding {
+    /// Toward zero, as `mul_div` does.
+    Floor,
+    /// Away from zero whenever there is a remainder.
+    Ceil,
+}
+
+/// `mul_div` rounding in the given direction. Like `mul_div`, returns 0 when `c` is 0 or
+/// the result doesn't fit in a u64.
+pub fn mul_div_rounded(a: u64, b: u64, c: u64, rounding: Rounding) -> u64 {
+    if c == 0 {
+        return 0;
+    }
+    let product = (a as u128) * (b as u128);
+    let quotient = match rounding {
+        Rounding::Floor => product / c as u128,
+        Rounding::Ceil => product.div_ceil(c as u128),
+    };
+    quotient.try_into().unwrap_or_default()
+}
+
+/// Fails with `SymmetryError::DiscriminatorMismatch` unless `account_data` starts with
+/// `expected`, so an account of the right size but the wrong type isn't parsed as `account`.
+pub(crate) fn check_discriminator(account: &'static str, account_data: &[u8], expected: [u8; 8]) -> Result<()> {
+    let got: [u8; 8] = account_data.get(..8).and_then(|bytes| bytes.try_into().ok()).unwrap_or_default();
+    if got != expected {
+        return Err(SymmetryError::DiscriminatorMismatch { account, expected, got }.into());
+    }
+    Ok(())
+}
+
+/// A fund account, decoded up to `last_reweight_time` plus `lp_disabled`. The rule and
+/// rebalance bookkeeping in between isn't decoded. The fund's name and symbol aren't stored
+/// here but in its share token's Metaplex metadata; see `FundMetadata`.
 #[derive(Clone, Copy)]
+#[non_exhaustive]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FundState {
     pub manager: Pubkey,
+    /// Mint of the fund's share token.
+    pub fund_token: Pubkey,
+    /// Manager fee charged on deposits, in bps.
+    pub manager_fee: u64,
+    /// Fund tokens in circulation.
+    pub supply_outstanding: u64,
+    /// Nonzero when the manager rebalances the fund rather than following rules only.
+    pub actively_managed: u64,
+    /// Number of buy states of the current rebalance still to be executed.
+    pub active_buy_states: u64,
+    /// Nonzero while the sell leg of a deposit/withdraw flow is in progress.
+    pub sell_state: u64,
+    /// Nonzero while the sell leg of a rebalance is in progress.
+    pub rebalance_sell_state: u64,
     pub host_pubkey: Pubkey,
+    /// Host platform fee charged on deposits, in bps.
+    pub host_fee: u64,
     pub num_of_tokens: u64,
     pub current_comp_token: [u64; NUM_TOKENS_IN_FUND],
     pub current_comp_amount: [u64; NUM_TOKENS_IN_FUND],
+    /// Unix time each token was last rebalanced.
+    pub last_rebalance_time: [u64; NUM_TOKENS_IN_FUND],
     pub target_weight: [u64; NUM_TOKENS_IN_FUND],
     pub weight_sum: u64,
+    /// Weights as of the last on-chain fund worth update, in `WEIGHT_MULTIPLIER` units.
+    pub current_weight: [u64; NUM_TOKENS_IN_FUND],
+    /// Fund worth as of `last_update_time`, in `ONE_USD` precision.
+    pub fund_worth: u64,
+    pub last_update_time: u64,
+    /// Seconds between rule refilters.
+    pub refilter_interval: u64,
+    /// Seconds between rule reweights.
+    pub reweight_interval: u64,
+    /// Seconds between rebalances.
+    pub rebalance_interval: u64,
     pub rebalance_threshold: u64,
+    pub rebalance_slippage: u64,
     pub lp_offset_threshold: u64,
+    pub last_refilter_time: u64,
+    pub last_reweight_time: u64,
     pub lp_disabled: u64,
 }
 
 impl FundState {
+    /// Whether a rebalance or a deposit/withdraw flow is part-way through its buy or sell
+    /// legs, during which the composition arrays may not add up to the fund's holdings.
+    pub fn rebalance_in_progress(&self) -> bool {
+        self.active_buy_states != 0 || self.sell_state != 0 || self.rebalance_sell_state != 0
+    }
+
+    /// Parses only the `FundStateHeader` fields, for indexers that see every fund account.
     #[inline]
-    pub fn load<'a>(account_data: &[u8]) -> Result<FundState> {
+    pub fn load_header(account_data: &[u8]) -> Result<FundStateHeader> {
+        FundStateHeader::load(account_data)
+    }
+
+    #[inline]
+    pub fn load(account_data: &[u8]) -> Result<FundState> {
         if account_data.len() != FUND_STATE_ACCOUNT_SIZE {
-            return Err(Error::msg("Wrong account size for FundState"));
+            return Err(SymmetryError::AccountSizeMismatch {
+                account: "FundState",
+                expected: FUND_STATE_ACCOUNT_SIZE,
+                got: account_data.len(),
+            }.into());
         }
-        let mut current_comp_token: [u64; NUM_TOKENS_IN_FUND] = [0 as u64; NUM_TOKENS_IN_FUND];
-        let mut current_comp_amount: [u64; NUM_TOKENS_IN_FUND] = [0 as u64; NUM_TOKENS_IN_FUND];
-        let mut target_weight: [u64; NUM_TOKENS_IN_FUND] = [0 as u64; NUM_TOKENS_IN_FUND];
+        check_discriminator("FundState", account_data, FUND_STATE_DISCRIMINATOR)?;
+        let mut current_comp_token: [u64; NUM_TOKENS_IN_FUND] = [0; NUM_TOKENS_IN_FUND];
+        let mut current_comp_amount: [u64; NUM_TOKENS_IN_FUND] = [0; NUM_TOKENS_IN_FUND];
+        let mut last_rebalance_time: [u64; NUM_TOKENS_IN_FUND] = [0; NUM_TOKENS_IN_FUND];
+        let mut target_weight: [u64; NUM_TOKENS_IN_FUND] = [0; NUM_TOKENS_IN_FUND];
+        let mut current_weight: [u64; NUM_TOKENS_IN_FUND] = [0; NUM_TOKENS_IN_FUND];
         for i in 0..NUM_TOKENS_IN_FUND {
             current_comp_token[i] = u64::from_le_bytes(account_data[(176 + i*8)..(184 + i*8)].try_into().unwrap_or_default());
             current_comp_amount[i] = u64::from_le_bytes(account_data[(336 + i*8)..(344 + i*8)].try_into().unwrap_or_default());
+            last_rebalance_time[i] = u64::from_le_bytes(account_data[(496 + i*8)..(504 + i*8)].try_into().unwrap_or_default());
             target_weight[i] = u64::from_le_bytes(account_data[(656 + i*8)..(664 + i*8)].try_into().unwrap_or_default());
+            current_weight[i] = u64::from_le_bytes(account_data[(824 + i*8)..(832 + i*8)].try_into().unwrap_or_default());
         }
         let num_of_tokens = u64::from_le_bytes(account_data[168..176].try_into().unwrap_or_default());
         let weight_sum = u64::from_le_bytes(account_data[816..824].try_into().unwrap_or_default());
@@ -60,20 +171,107 @@ impl FundState {
         let lp_disabled = u64::from_le_bytes(account_data[9432..9440].try_into().unwrap_or_default());
         Ok(FundState {
             manager: Pubkey::new_from_array(account_data[16..48].try_into().unwrap_or_default()),
+            fund_token: Pubkey::new_from_array(account_data[48..80].try_into().unwrap_or_default()),
+            manager_fee: u64::from_le_bytes(account_data[80..88].try_into().unwrap_or_default()),
+            supply_outstanding: u64::from_le_bytes(account_data[88..96].try_into().unwrap_or_default()),
+            actively_managed: u64::from_le_bytes(account_data[96..104].try_into().unwrap_or_default()),
+            active_buy_states: u64::from_le_bytes(account_data[104..112].try_into().unwrap_or_default()),
+            sell_state: u64::from_le_bytes(account_data[112..120].try_into().unwrap_or_default()),
+            rebalance_sell_state: u64::from_le_bytes(account_data[120..128].try_into().unwrap_or_default()),
             host_pubkey: Pubkey::new_from_array(account_data[128..160].try_into().unwrap_or_default()),
+            host_fee: u64::from_le_bytes(account_data[160..168].try_into().unwrap_or_default()),
             num_of_tokens,
             current_comp_token,
             current_comp_amount,
+            last_rebalance_time,
             target_weight,
             weight_sum,
+            current_weight,
+            fund_worth: u64::from_le_bytes(account_data[984..992].try_into().unwrap_or_default()),
+            last_update_time: u64::from_le_bytes(account_data[992..1000].try_into().unwrap_or_default()),
+            refilter_interval: u64::from_le_bytes(account_data[1000..1008].try_into().unwrap_or_default()),
+            reweight_interval: u64::from_le_bytes(account_data[1008..1016].try_into().unwrap_or_default()),
+            rebalance_interval: u64::from_le_bytes(account_data[1016..1024].try_into().unwrap_or_default()),
             rebalance_threshold,
+            rebalance_slippage: u64::from_le_bytes(account_data[1032..1040].try_into().unwrap_or_default()),
             lp_offset_threshold,
+            last_refilter_time: u64::from_le_bytes(account_data[1048..1056].try_into().unwrap_or_default()),
+            last_reweight_time: u64::from_le_bytes(account_data[1056..1064].try_into().unwrap_or_default()),
             lp_disabled,
         })
     }
+
+    /// Number of active tokens, capped at `NUM_TOKENS_IN_FUND` so a corrupt count can't index
+    /// past the composition arrays.
+    pub fn num_tokens(&self) -> usize {
+        (self.num_of_tokens as usize).min(NUM_TOKENS_IN_FUND)
+    }
+
+    /// Token list indices of the fund's active tokens.
+    pub fn token_ids(&self) -> &[u64] {
+        &self.current_comp_token[..self.num_tokens()]
+    }
 }
 
+/// Identifying and composition fields from the first `FUND_STATE_HEADER_SIZE` bytes of a FundState,
+/// so a data-sliced account can be inspected without downloading the full 10KB.
 #[derive(Clone, Copy)]
+#[non_exhaustive]
+pub struct FundStateHeader {
+    pub manager: Pubkey,
+    pub supply_outstanding: u64,
+    pub host_pubkey: Pubkey,
+    pub num_of_tokens: u64,
+    pub current_comp_token: [u64; NUM_TOKENS_IN_FUND],
+    pub current_comp_amount: [u64; NUM_TOKENS_IN_FUND],
+    pub target_weight: [u64; NUM_TOKENS_IN_FUND],
+    pub weight_sum: u64,
+    /// Fund worth as of `last_update_time`, in `ONE_USD` precision.
+    pub fund_worth: u64,
+    pub last_update_time: u64,
+}
+
+impl FundStateHeader {
+    #[inline]
+    pub fn load(account_data: &[u8]) -> Result<FundStateHeader> {
+        if account_data.len() < FUND_STATE_HEADER_SIZE {
+            return Err(SymmetryError::AccountTooShort {
+                account: "FundState header",
+                min: FUND_STATE_HEADER_SIZE,
+                got: account_data.len(),
+            }.into());
+        }
+        check_discriminator("FundState", account_data, FUND_STATE_DISCRIMINATOR)?;
+        let mut current_comp_token: [u64; NUM_TOKENS_IN_FUND] = [0; NUM_TOKENS_IN_FUND];
+        let mut current_comp_amount: [u64; NUM_TOKENS_IN_FUND] = [0; NUM_TOKENS_IN_FUND];
+        let mut target_weight: [u64; NUM_TOKENS_IN_FUND] = [0; NUM_TOKENS_IN_FUND];
+        for i in 0..NUM_TOKENS_IN_FUND {
+            current_comp_token[i] = u64::from_le_bytes(account_data[(176 + i*8)..(184 + i*8)].try_into().unwrap_or_default());
+            current_comp_amount[i] = u64::from_le_bytes(account_data[(336 + i*8)..(344 + i*8)].try_into().unwrap_or_default());
+            target_weight[i] = u64::from_le_bytes(account_data[(656 + i*8)..(664 + i*8)].try_into().unwrap_or_default());
+        }
+        Ok(FundStateHeader {
+            manager: Pubkey::new_from_array(account_data[16..48].try_into().unwrap_or_default()),
+            supply_outstanding: u64::from_le_bytes(account_data[88..96].try_into().unwrap_or_default()),
+            host_pubkey: Pubkey::new_from_array(account_data[128..160].try_into().unwrap_or_default()),
+            num_of_tokens: u64::from_le_bytes(account_data[168..176].try_into().unwrap_or_default()),
+            current_comp_token,
+            current_comp_amount,
+            target_weight,
+            weight_sum: u64::from_le_bytes(account_data[816..824].try_into().unwrap_or_default()),
+            fund_worth: u64::from_le_bytes(account_data[984..992].try_into().unwrap_or_default()),
+            last_update_time: u64::from_le_bytes(account_data[992..1000].try_into().unwrap_or_default()),
+        })
+    }
+
+    /// Token list indices of the fund's active tokens.
+    pub fn token_ids(&self) -> &[u64] {
+        &self.current_comp_token[..(self.num_of_tokens as usize).min(NUM_TOKENS_IN_FUND)]
+    }
+}
+
+#[derive(Clone, Copy, bytemuck::Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TokenSettings {                                      // 199 bytes
     pub token_mint: Pubkey,                                     // 32 bytes
     pub decimals: u8,                                           // 1 byte
@@ -89,111 +287,121 @@ pub struct TokenSettings {                                      // 199 bytes
     pub is_live: u8,                                            // 1 byte
     pub lp_on: u8,                                              // 1 byte
     pub use_curve_data: u8,                                     // 1 byte
+    #[cfg_attr(feature = "serde", serde(with = "serde_big_array::BigArray"))]
     pub additional_data: [u8; 63],                              // 64 bytes
     pub oracle_price: OraclePrice,
 }
 
-#[derive(Clone, Copy)]
+/// Typed view of `TokenSettings::additional_data`. Only token list entry 0 carries the fee
+/// split; the other bytes are unused by the program and kept as `reserved`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AdditionalData {
+    pub reserved: [u8; 60],
+    /// Symmetry's share of swap fees, in percent.
+    pub symmetry_fee_pct: u8,
+    /// Host platform's share of swap fees, in percent.
+    pub host_fee_pct: u8,
+    /// Manager's share of swap fees, in percent.
+    pub manager_fee_pct: u8,
+}
+
+impl AdditionalData {
+    pub fn parse(additional_data: &[u8; 63]) -> AdditionalData {
+        AdditionalData {
+            reserved: additional_data[..60].try_into().unwrap(),
+            symmetry_fee_pct: additional_data[60],
+            host_fee_pct: additional_data[61],
+            manager_fee_pct: additional_data[62],
+        }
+    }
+}
+
+impl TokenSettings {
+    pub fn additional_data(&self) -> AdditionalData {
+        AdditionalData::parse(&self.additional_data)
+    }
+
+    /// Typed `oracle_type`. Fails with `SymmetryError::UnsupportedOracleType` on types this
+    /// crate can't price.
+    pub fn oracle_type(&self) -> std::result::Result<OracleType, SymmetryError> {
+        OracleType::try_from(self.oracle_type)
+    }
+
+    pub fn empty() -> TokenSettings {
+        TokenSettings {
+            token_mint: Pubkey::default(),
+            decimals: 0,
+            coingecko_id: [0; 30],
+            pda_token_account: Pubkey::default(),
+            oracle_type: 0,
+            oracle_account: Pubkey::default(),
+            oracle_index: 0,
+            oracle_confidence_pct: 0,
+            fixed_confidence_bps: 0,
+            token_swap_fee_after_tw_bps: 0,
+            token_swap_fee_before_tw_bps: 0,
+            is_live: 0,
+            lp_on: 0,
+            use_curve_data: 0,
+            additional_data: [0; 63],
+            oracle_price: OraclePrice { sell_price: 0, avg_price: 0, buy_price: 0, oracle_live: 0}
+        }
+    }
+}
+
+#[derive(Clone, Copy, bytemuck::Zeroable)]
+#[non_exhaustive]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TokenList {                                          // 39808 bytes
     pub num_tokens: u64,                                        // 8 bytes
+    #[cfg_attr(feature = "serde", serde(with = "serde_big_array::BigArray"))]
     pub list: [TokenSettings; MAX_TOKENS_IN_ASSET_POOL],        // 39800 bytes
 }
 
 impl TokenList {
+    /// Copies the listed tokens out of the account; `TokenListView` reads them in place.
     #[inline]
-    pub fn load<'a>(account_data: &Vec<u8>) -> Result<TokenList> {
-        if account_data.len() != TOKEN_LIST_ACCOUNT_SIZE {
-            return Err(Error::msg("Wrong account size for TokenList"));
-        }
-        let num_tokens = u64::from_le_bytes(account_data[8..16].try_into().unwrap_or_default());
-        let mut list: [TokenSettings; MAX_TOKENS_IN_ASSET_POOL] = [
-            TokenSettings {
-                token_mint: Pubkey::default(),
-                decimals: 0,
-                coingecko_id: [0; 30],
-                pda_token_account: Pubkey::default(),
-                oracle_type: 0,
-                oracle_account: Pubkey::default(),
-                oracle_index: 0,
-                oracle_confidence_pct: 0,
-                fixed_confidence_bps: 0,
-                token_swap_fee_after_tw_bps: 0,
-                token_swap_fee_before_tw_bps: 0,
-                is_live: 0,
-                lp_on: 0,
-                use_curve_data: 0,
-                additional_data: [0; 63],
-                oracle_price: OraclePrice { sell_price: 0, avg_price: 0, buy_price: 0, oracle_live: 0}
-            };
-            MAX_TOKENS_IN_ASSET_POOL
-        ];
-        for i in 0..num_tokens as usize {
-            let slice: [u8; 199] = account_data[16 + i*199..16 + (i+1)*199].try_into().unwrap();
-            list[i].token_mint = Pubkey::new_from_array(slice[0..32].try_into().unwrap_or_default());
-            list[i].decimals = slice[32];
-            list[i].pda_token_account = Pubkey::new_from_array(slice[63..95].try_into().unwrap_or_default());
-            list[i].oracle_type = slice[95];
-            list[i].oracle_account = Pubkey::new_from_array(slice[96..128].try_into().unwrap_or_default());
-            list[i].oracle_index = slice[128];
-            list[i].oracle_confidence_pct = slice[129];
-            list[i].fixed_confidence_bps = slice[130];
-            list[i].token_swap_fee_after_tw_bps = slice[131];
-            list[i].token_swap_fee_before_tw_bps = slice[132];
-            list[i].is_live = slice[133];
-            list[i].lp_on = slice[134];
-            list[i].use_curve_data = slice[135];
-            list[i].additional_data = slice[136..199].try_into().unwrap();
+    pub fn load(account_data: &[u8]) -> Result<TokenList> {
+        let mut token_list = TokenList::empty();
+        TokenListView::load(account_data)?.copy_into(&mut token_list);
+        Ok(token_list)
+    }
+
+    pub fn empty() -> TokenList {
+        TokenList {
+            num_tokens: 0,
+            list: [TokenSettings::empty(); MAX_TOKENS_IN_ASSET_POOL],
         }
-        Ok(TokenList { num_tokens, list, })
     }
 }
 
 
-#[derive(PartialEq, Debug, Copy, Clone)]
+#[derive(PartialEq, Debug, Copy, Clone, bytemuck::Zeroable)]
 #[repr(C)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TokenPriceData {
     pub amount: [u64; NUM_OF_POINTS_IN_CURVE_DATA],
     pub price: [u64; NUM_OF_POINTS_IN_CURVE_DATA],
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, bytemuck::Zeroable)]
+#[non_exhaustive]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CurveData {
+    #[cfg_attr(feature = "serde", serde(with = "serde_big_array::BigArray"))]
     pub buy: [TokenPriceData; MAX_TOKENS_IN_ASSET_POOL],
+    #[cfg_attr(feature = "serde", serde(with = "serde_big_array::BigArray"))]
     pub sell: [TokenPriceData; MAX_TOKENS_IN_ASSET_POOL],
 }
 
 impl CurveData {
+    /// Copies every curve out of the account; `CurveDataView` reads them in place.
     #[inline]
-    pub fn load<'a>(account_data: &[u8]) -> Result<CurveData> {
-        if account_data.len() != CURVE_DATA_ACCOUNT_SIZE {
-            return Err(Error::msg("Wrong account size for CurveData"));
-        }
-        let mut buy_vec: Vec<TokenPriceData> = Vec::new();
-        let mut sell_vec: Vec<TokenPriceData> = Vec::new();
-        for _ in 0..MAX_TOKENS_IN_ASSET_POOL {
-            buy_vec.push(TokenPriceData {
-                amount: [0; NUM_OF_POINTS_IN_CURVE_DATA],
-                price: [0; NUM_OF_POINTS_IN_CURVE_DATA],
-            });
-            sell_vec.push(TokenPriceData {
-                amount: [0; NUM_OF_POINTS_IN_CURVE_DATA],
-                price: [0; NUM_OF_POINTS_IN_CURVE_DATA],
-            });
-        }
-        let mut buy: [TokenPriceData; MAX_TOKENS_IN_ASSET_POOL] = buy_vec.try_into().unwrap();
-        let mut sell: [TokenPriceData; MAX_TOKENS_IN_ASSET_POOL] = sell_vec.try_into().unwrap();
-        for i in 0..MAX_TOKENS_IN_ASSET_POOL {
-            for j in 0..NUM_OF_POINTS_IN_CURVE_DATA {
-                buy[i].amount[j] = u64::from_le_bytes(account_data[(8 + i*160 + j*8)..(16 + i*160 + j*8)].try_into().unwrap_or_default());
-                buy[i].price[j] = u64::from_le_bytes(account_data[(88 + i*160 + j*8)..(96 + i*160 + j*8)].try_into().unwrap_or_default());
-                sell[i].amount[j] = u64::from_le_bytes(account_data[(32008 + i*160 + j*8)..(32016 + i*160 + j*8)].try_into().unwrap_or_default());
-                sell[i].price[j] = u64::from_le_bytes(account_data[(32088 + i*160 + j*8)..(32096 + i*160 + j*8)].try_into().unwrap_or_default());
-            }
-        }
-        Ok(CurveData {
-            buy,
-            sell,
-        })
+    pub fn load(account_data: &[u8]) -> Result<CurveData> {
+        let view = CurveDataView::load(account_data)?;
+        let mut curve_data = CurveData::empty();
+        view.copy_into(0..MAX_TOKENS_IN_ASSET_POOL as u64, &mut curve_data);
+        Ok(curve_data)
     }
 
     pub fn empty() -> CurveData {
@@ -218,7 +426,18 @@ impl CurveData {
     }
 }
 
-#[derive(Clone, Copy)]
+/// `value * 10^exponent` in `ONE_USD` precision, saturating.
+fn scale_to_usd(value: u64, exponent: i32) -> u64 {
+    if exponent <= 0 {
+        mul_div(value, ONE_USD, 10u64.saturating_pow(exponent.unsigned_abs()))
+    } else {
+        value.saturating_mul(10u64.saturating_pow(exponent as u32)).saturating_mul(ONE_USD)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, bytemuck::Zeroable)]
+#[non_exhaustive]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct OraclePrice {
     pub sell_price: u64,
     pub avg_price: u64,
@@ -226,14 +445,90 @@ pub struct OraclePrice {
     pub oracle_live: u8,
 }
 
+pub const INTERNAL_ORACLE_NUM_SLOTS: usize = 50;
+
+/// Layout of the 809-byte Symmetry internal oracle (oracle_type 1). One account holds the
+/// prices of up to 50 tokens, selected by `TokenSettings::oracle_index`.
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct InternalOracle {
+    pub discriminator: [u8; 8],
+    pub header: u8,
+    prices: [[u8; 8]; INTERNAL_ORACLE_NUM_SLOTS],
+    write_timestamps: [[u8; 8]; INTERNAL_ORACLE_NUM_SLOTS],
+}
+
+impl InternalOracle {
+    /// Casts the account data in place.
+    pub fn load(account_data: &[u8]) -> Result<&InternalOracle> {
+        if account_data.len() != ORACLE_ACCOUNT_SIZE[1] {
+            return Err(SymmetryError::AccountSizeMismatch {
+                account: "internal oracle",
+                expected: ORACLE_ACCOUNT_SIZE[1],
+                got: account_data.len(),
+            }.into());
+        }
+        bytemuck::try_from_bytes(account_data).map_err(|_| SymmetryError::InvalidAccountData { account: "internal oracle" }.into())
+    }
+
+    /// Price mantissa in slot `index`, in `ONE_USD` precision.
+    pub fn price(&self, index: usize) -> Option<u64> {
+        self.prices.get(index).map(|price| u64::from_le_bytes(*price))
+    }
+
+    /// Unix time slot `index` was last written.
+    pub fn write_timestamp(&self, index: usize) -> Option<u64> {
+        self.write_timestamps.get(index).map(|t| u64::from_le_bytes(*t))
+    }
+
+    pub fn prices(&self) -> impl Iterator<Item = u64> + '_ {
+        self.prices.iter().map(|price| u64::from_le_bytes(*price))
+    }
+
+    pub fn write_timestamps(&self) -> impl Iterator<Item = u64> + '_ {
+        self.write_timestamps.iter().map(|t| u64::from_le_bytes(*t))
+    }
+}
+
+/// An internal oracle `oracle_index` whose price or timestamp slot lies outside the oracle account.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct OracleIndexOutOfBounds {
+    pub oracle_index: u8,
+    pub account_len: usize,
+}
+
+impl std::fmt::Display for OracleIndexOutOfBounds {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Oracle index {} is out of bounds for a {} byte oracle account", self.oracle_index, self.account_len)
+    }
+}
+
+impl std::error::Error for OracleIndexOutOfBounds {}
+
 impl OraclePrice {
+    /// `load_with_clock` at `Clock::get()`, which only succeeds on-chain: off-chain every
+    /// oracle is judged at slot 0 and unix time 0, so none looks stale.
     #[inline]
-    pub fn load<'a>(account_data: &[u8], token_settings: TokenSettings) -> Result<OraclePrice> {
-        if account_data.len() != ORACLE_ACCOUNT_SIZE[token_settings.oracle_type as usize] {
-            return Err(Error::msg("Wrong account size for oracle"));
+    pub fn load(account_data: &[u8], token_settings: TokenSettings) -> Result<OraclePrice> {
+        OraclePrice::load_with_clock(account_data, token_settings, &Clock::get().unwrap_or_default())
+    }
+
+    /// Parses the oracle, judging staleness against `clock`: its slot for legacy Pyth and
+    /// Switchboard oracles, its unix timestamp for internal and Pyth pull oracles.
+    #[inline]
+    pub fn load_with_clock(account_data: &[u8], token_settings: TokenSettings, clock: &Clock) -> Result<OraclePrice> {
+        let oracle_type = token_settings.oracle_type()?;
+        let expected = oracle_type.account_size();
+        if account_data.len() != expected {
+            return Err(SymmetryError::AccountSizeMismatch {
+                account: "oracle",
+                expected,
+                got: account_data.len(),
+            }.into());
         }
-        let (price, coinfidence, oracle_live) = match token_settings.oracle_type {
-            0 => {
+        let (price, coinfidence, oracle_live) = match oracle_type {
+            OracleType::PythPush => {
                 let valid_slot: u64 =  u64::from_le_bytes(account_data[40..48].try_into().unwrap_or_default());
                 let expo: i32 = i32::from_le_bytes(account_data[20..24].try_into().unwrap_or_default());
                 let price: i64 =  i64::from_le_bytes(account_data[208..216].try_into().unwrap_or_default());
@@ -241,7 +536,7 @@ impl OraclePrice {
                 let status: u32 = u32::from_le_bytes(account_data[224..228].try_into().unwrap_or_default());
                 let mut oracle_live = 1;
         
-                if Clock::get().unwrap_or_default().slot >= 25 + valid_slot {
+                if clock.slot >= 25 + valid_slot {
                     oracle_live = 0;
                 }
                 if status != 1 {
@@ -266,30 +561,30 @@ impl OraclePrice {
                 
                 (avg_price, base_confidene, oracle_live)
             },
-            1 => {
+            OracleType::SymmetryCustom => {
                 
-                let price_start = (token_settings.oracle_index as usize) * 8 + 9;
-                let price_end = price_start + 8;
-                let price: [u8; 8] = account_data[price_start..price_end].try_into().unwrap_or_default();
-                let mantissa: u64 = u64::from_le_bytes(price);
-    
-                let timestamp_start = price_start + 400;
-                let timetamp_end = price_end + 400;
-                let t: [u8; 8] = account_data[timestamp_start..timetamp_end].try_into().unwrap_or_default();
-                let write_timestamp: u64 = u64::from_le_bytes(t);
+                let oracle = InternalOracle::load(account_data)?;
+                let index = token_settings.oracle_index as usize;
+                let out_of_bounds = || OracleIndexOutOfBounds {
+                    oracle_index: token_settings.oracle_index,
+                    account_len: account_data.len(),
+                };
+                let mantissa = oracle.price(index).ok_or_else(out_of_bounds)?;
+                let write_timestamp = oracle.write_timestamp(index).ok_or_else(out_of_bounds)?;
                 let mut oracle_live: u8 = 0; // Disable lp for vaults containing switchboard tokens
                 
-                let current_time = Clock::get().unwrap_or_default().unix_timestamp as u64;
+                let current_time = clock.unix_timestamp as u64;
                 if current_time > write_timestamp + 40 {
                     oracle_live = 0;
                 }
             
-                let time_based_confidence_bps =
-                    if current_time > write_timestamp + 30
-                        { 9900 } else
-                    if current_time > write_timestamp + 10
-                        { token_settings.oracle_confidence_pct as u64 + (current_time - write_timestamp - 10) * 2 } else
-                        { token_settings.oracle_confidence_pct as u64 };
+                let time_based_confidence_bps = if current_time > write_timestamp + 30 {
+                    9900
+                } else if current_time > write_timestamp + 10 {
+                    token_settings.oracle_confidence_pct as u64 + (current_time - write_timestamp - 10) * 2
+                } else {
+                    token_settings.oracle_confidence_pct as u64
+                };
             
 
                 let avg_price = mul_div(
@@ -304,9 +599,33 @@ impl OraclePrice {
                     10000
                 );
                 
+                (avg_price, base_confidence, oracle_live)
+            },
+            OracleType::PythPull => {
+                let update = PriceUpdateV2::parse(account_data)?;
+                let oracle_live = update.is_live(clock.unix_timestamp) as u8;
+                let avg_price = scale_to_usd(update.price.max(0) as u64, update.exponent);
+                let confidence = scale_to_usd(update.conf, update.exponent);
+                let base_confidence = mul_div(
+                    confidence,
+                    token_settings.oracle_confidence_pct as u64,
+                    100
+                ).min(avg_price);
+
+                (avg_price, base_confidence, oracle_live)
+            },
+            OracleType::SwitchboardOnDemand => {
+                let result = PullFeedResult::parse(account_data)?;
+                let oracle_live = result.is_live(clock.slot) as u8;
+                let avg_price = result.value_with_decimals(ONE_USD_DECIMALS);
+                let base_confidence = mul_div(
+                    result.std_dev_with_decimals(ONE_USD_DECIMALS),
+                    token_settings.oracle_confidence_pct as u64,
+                    100
+                ).min(avg_price);
+
                 (avg_price, base_confidence, oracle_live)
             }
-            _ => (0, 0, 0)
         };
     
         let additional_confidence = mul_div(
@@ -315,11 +634,213 @@ impl OraclePrice {
             10000
         );
     
+        // Pull oracles clamp the base confidence to the price, but the fixed confidence
+        // on top can still take the spread past it.
+        let spread = coinfidence
+            .checked_add(additional_confidence)
+            .ok_or(SymmetryError::Arithmetic("Oracle confidence overflows u64"))?;
         Ok(OraclePrice {
-            sell_price: price - coinfidence - additional_confidence,
+            sell_price: price
+                .checked_sub(spread)
+                .ok_or(SymmetryError::Arithmetic("Oracle confidence exceeds the price"))?,
             avg_price: price,
-            buy_price: price + coinfidence + additional_confidence,
-            oracle_live: oracle_live,
+            buy_price: price
+                .checked_add(spread)
+                .ok_or(SymmetryError::Arithmetic("Oracle buy price overflows u64"))?,
+            oracle_live,
         })
     }
+
+    /// The oracle's EMA price in `ONE_USD` precision, for Pyth push and pull oracles. `None`
+    /// for oracle types that don't publish one.
+    pub fn load_ema_price(account_data: &[u8], token_settings: TokenSettings) -> Result<Option<u64>> {
+        match token_settings.oracle_type()? {
+            OracleType::PythPush => {
+                let field = |range: std::ops::Range<usize>| account_data.get(range.clone())
+                    .ok_or(SymmetryError::AccountTooShort { account: "Pyth price", min: range.end, got: account_data.len() });
+                let expo = i32::from_le_bytes(field(20..24)?.try_into()?);
+                let ema_price = i64::from_le_bytes(field(48..56)?.try_into()?);
+                Ok(Some(scale_to_usd(ema_price.max(0) as u64, expo)))
+            },
+            OracleType::PythPull => {
+                let update = PriceUpdateV2::parse(account_data)?;
+                Ok(Some(scale_to_usd(update.ema_price.max(0) as u64, update.exponent)))
+            },
+            OracleType::SymmetryCustom | OracleType::SwitchboardOnDemand => Ok(None),
+        }
+    }
+}
+
+#[test]
+fn test_internal_oracle_index_bounds() {
+    let account_data = vec![0u8; ORACLE_ACCOUNT_SIZE[1]];
+    let mut token_settings = TokenSettings::empty();
+    token_settings.oracle_type = 1;
+
+    token_settings.oracle_index = 49;
+    assert!(OraclePrice::load(&account_data, token_settings).is_ok());
+
+    token_settings.oracle_index = 50;
+    let err = OraclePrice::load(&account_data, token_settings).err().unwrap();
+    assert_eq!(
+        err.downcast_ref::<OracleIndexOutOfBounds>(),
+        Some(&OracleIndexOutOfBounds { oracle_index: 50, account_len: 809 })
+    );
+}
+
+#[test]
+fn test_pyth_staleness_uses_given_clock() {
+    let mut account_data = vec![0u8; ORACLE_ACCOUNT_SIZE[0]];
+    account_data[20..24].copy_from_slice(&(-8i32).to_le_bytes());
+    account_data[40..48].copy_from_slice(&1_000u64.to_le_bytes());
+    account_data[208..216].copy_from_slice(&2_000_000_000i64.to_le_bytes());
+    account_data[216..224].copy_from_slice(&1_000_000u64.to_le_bytes());
+    account_data[224..228].copy_from_slice(&1u32.to_le_bytes());
+    let token_settings = TokenSettings::empty();
+
+    let fresh = Clock { slot: 1_010, ..Clock::default() };
+    assert_eq!(OraclePrice::load_with_clock(&account_data, token_settings, &fresh).unwrap().oracle_live, 1);
+    let stale = Clock { slot: 1_025, ..Clock::default() };
+    assert_eq!(OraclePrice::load_with_clock(&account_data, token_settings, &stale).unwrap().oracle_live, 0);
+}
+
+#[test]
+fn test_pyth_pull_oracle_price() {
+    use crate::amms::pyth_pull::{price_update_v2_data, VerificationLevel};
+
+    let account_data = price_update_v2_data(VerificationLevel::Full, 15_000_000_000, 5_000_000, -8, 1_000);
+    let mut token_settings = TokenSettings::empty();
+    token_settings.oracle_type = OracleType::PythPull.into();
+    token_settings.oracle_confidence_pct = 100;
+
+    let clock = Clock { unix_timestamp: 1_010, ..Clock::default() };
+    let price = OraclePrice::load_with_clock(&account_data, token_settings, &clock).unwrap();
+    assert_eq!(price.avg_price, 150 * ONE_USD);
+    assert_eq!(price.buy_price - price.avg_price, ONE_USD / 20);
+    assert_eq!(price.oracle_live, 1);
+
+    let clock = Clock { unix_timestamp: 1_100, ..Clock::default() };
+    assert_eq!(OraclePrice::load_with_clock(&account_data, token_settings, &clock).unwrap().oracle_live, 0);
+}
+
+#[test]
+fn test_pyth_pull_wide_confidence() {
+    use crate::amms::pyth_pull::{price_update_v2_data, VerificationLevel};
+
+    // A 150 USD price whose confidence interval is wider than the price itself.
+    let account_data = price_update_v2_data(VerificationLevel::Full, 15_000_000_000, 20_000_000_000, -8, 1_000);
+    let mut token_settings = TokenSettings::empty();
+    token_settings.oracle_type = OracleType::PythPull.into();
+    token_settings.oracle_confidence_pct = 100;
+    let clock = Clock { unix_timestamp: 1_010, ..Clock::default() };
+
+    // The base confidence is clamped to the price, so without a fixed spread the sell
+    // price bottoms out at zero.
+    let price = OraclePrice::load_with_clock(&account_data, token_settings, &clock).unwrap();
+    assert_eq!(price.sell_price, 0);
+    assert_eq!(price.buy_price, 300 * ONE_USD);
+    assert_eq!(price.oracle_live, 0);
+
+    token_settings.fixed_confidence_bps = 50;
+    let error = OraclePrice::load_with_clock(&account_data, token_settings, &clock).unwrap_err();
+    assert_eq!(
+        error.downcast_ref::<SymmetryError>(),
+        Some(&SymmetryError::Arithmetic("Oracle confidence exceeds the price"))
+    );
+}
+
+#[test]
+fn test_switchboard_oracle_price() {
+    use crate::amms::switchboard::pull_feed_data;
+
+    // 150 USD with a standard deviation of 0.05 USD, 18 decimals.
+    let account_data = pull_feed_data(150_000_000_000_000_000_000, 50_000_000_000_000_000, 3, 1_000, 0);
+    let mut token_settings = TokenSettings::empty();
+    token_settings.oracle_type = OracleType::SwitchboardOnDemand.into();
+    token_settings.oracle_confidence_pct = 200;
+
+    let clock = Clock { slot: 1_020, ..Clock::default() };
+    let price = OraclePrice::load_with_clock(&account_data, token_settings, &clock).unwrap();
+    assert_eq!(price.avg_price, 150 * ONE_USD);
+    assert_eq!(price.buy_price - price.avg_price, ONE_USD / 10);
+    assert_eq!(price.oracle_live, 1);
+
+    let clock = Clock { slot: 1_026, ..Clock::default() };
+    assert_eq!(OraclePrice::load_with_clock(&account_data, token_settings, &clock).unwrap().oracle_live, 0);
+}
+
+#[test]
+fn test_switchboard_wide_std_dev() {
+    use crate::amms::switchboard::pull_feed_data;
+
+    // 150 USD with a standard deviation of 200 USD, 18 decimals.
+    let account_data = pull_feed_data(150_000_000_000_000_000_000, 200_000_000_000_000_000_000, 3, 1_000, 0);
+    let mut token_settings = TokenSettings::empty();
+    token_settings.oracle_type = OracleType::SwitchboardOnDemand.into();
+    token_settings.oracle_confidence_pct = 100;
+    let clock = Clock { slot: 1_020, ..Clock::default() };
+
+    let price = OraclePrice::load_with_clock(&account_data, token_settings, &clock).unwrap();
+    assert_eq!(price.sell_price, 0);
+    assert_eq!(price.buy_price, 300 * ONE_USD);
+    assert_eq!(price.oracle_live, 0);
+
+    token_settings.fixed_confidence_bps = 50;
+    let error = OraclePrice::load_with_clock(&account_data, token_settings, &clock).unwrap_err();
+    assert_eq!(
+        error.downcast_ref::<SymmetryError>(),
+        Some(&SymmetryError::Arithmetic("Oracle confidence exceeds the price"))
+    );
+}
+
+#[test]
+fn test_fund_state_load_header_matches_load() {
+    let mut account_data = vec![0u8; FUND_STATE_ACCOUNT_SIZE];
+    account_data[..8].copy_from_slice(&FUND_STATE_DISCRIMINATOR);
+    account_data[88..96].copy_from_slice(&1_000u64.to_le_bytes());
+    account_data[168..176].copy_from_slice(&2u64.to_le_bytes());
+    account_data[664..672].copy_from_slice(&4_000u64.to_le_bytes());
+    account_data[984..992].copy_from_slice(&(5 * ONE_USD).to_le_bytes());
+
+    let fund_state = FundState::load(&account_data).unwrap();
+    let header = FundState::load_header(&account_data[..FUND_STATE_HEADER_SIZE]).unwrap();
+    assert_eq!(header.supply_outstanding, fund_state.supply_outstanding);
+    assert_eq!(header.num_of_tokens, fund_state.num_of_tokens);
+    assert_eq!(header.target_weight, fund_state.target_weight);
+    assert_eq!(header.fund_worth, fund_state.fund_worth);
+    assert!(FundState::load_header(&account_data[..FUND_STATE_HEADER_SIZE - 1]).is_err());
+
+    account_data[..8].copy_from_slice(&TOKEN_LIST_DISCRIMINATOR);
+    let error = FundState::load(&account_data).err().unwrap();
+    assert!(matches!(error.downcast_ref::<SymmetryError>(), Some(SymmetryError::DiscriminatorMismatch { .. })));
+    assert!(FundState::load_header(&account_data).is_err());
+}
+
+#[test]
+fn test_mul_div_rounded() {
+    assert_eq!(mul_div_rounded(10, 10, 3, Rounding::Floor), 33);
+    assert_eq!(mul_div_rounded(10, 10, 3, Rounding::Ceil), 34);
+    assert_eq!(mul_div_rounded(10, 9, 3, Rounding::Ceil), 30);
+    assert_eq!(mul_div_rounded(10, 10, 3, Rounding::Floor), mul_div(10, 10, 3));
+    assert_eq!(mul_div_rounded(10, 10, 0, Rounding::Ceil), 0);
+    assert_eq!(mul_div_rounded(u64::MAX, 2, 1, Rounding::Floor), 0);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_account_structs_serde_roundtrip() {
+    let mut token_list = TokenList::empty();
+    token_list.num_tokens = 1;
+    token_list.list[99].additional_data[62] = 30;
+    token_list.list[99].oracle_price.avg_price = 1_000;
+    let json = serde_json::to_string(&token_list).unwrap();
+    let decoded: TokenList = serde_json::from_str(&json).unwrap();
+    assert_eq!(decoded.num_tokens, 1);
+    assert_eq!(decoded.list[99].additional_data, token_list.list[99].additional_data);
+    assert_eq!(decoded.list[99].oracle_price.avg_price, 1_000);
+
+    let mut curve_data = bytemuck::allocation::zeroed_box::<CurveData>();
+    curve_data.sell[42].price[9] = 7;
+    let decoded: Box<CurveData> = serde_json::from_slice(&serde_json::to_vec(&curve_data).unwrap()).unwrap();
+    assert_eq!(decoded.sell[42], curve_data.sell[42]);
 }
@@ -0,0 +1,112 @@
+use anyhow::Result;
+
+use crate::amms::accounts::BPS_DIVIDER;
+use crate::error::SymmetryError;
+
+/// Tunables for how `quote()` checks a swap against the fund's weight limits, and how the
+/// fund presents itself.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AmmConfig {
+    /// Margin added to both swap legs when projecting post-swap weights, in bps. Wider
+    /// margins reject swaps earlier, leaving room for the fund to move before execution.
+    pub weight_check_safety_bps: u64,
+    /// Append the fund token's symbol to `label()` ("Symmetry: <symbol>") once fund
+    /// metadata is set, so route plans through several funds tell them apart.
+    pub label_with_fund_symbol: bool,
+    /// Make `update()` check that the accounts it reads are owned by the programs expected
+    /// to write them (`verify_account_owners`) before parsing them. Worth enabling when the
+    /// account map comes from a shared cache rather than straight from RPC.
+    pub verify_account_owners: bool,
+    /// Pay the host fee of a swap into a mint listed in `SwapParams::quote_mint_to_referrer`
+    /// to that referrer token account instead of the fund host's ATA, so integrators
+    /// collecting referral fees through Jupiter collect them on Symmetry legs too. Only
+    /// enable it for funds whose program accepts a host fee account other than the host's
+    /// ATA; otherwise the swap fails.
+    pub route_host_fee_to_referrer: bool,
+    /// Value tokens at their Pyth EMA price instead of the spot price when computing fund
+    /// worth and target amounts, smoothing quotes through oracle spikes. Sell and buy prices
+    /// stay at spot, and tokens priced by other oracles keep their spot price.
+    pub use_ema_price: bool,
+    /// Quote pairs whose own oracles are live while other fund tokens' oracles are offline,
+    /// valuing those tokens in fund worth at the last price their oracle holds. Quotes list
+    /// them in `QuoteDetails::stale_oracles`. The program may still reject such swaps.
+    pub allow_stale_oracles: bool,
+}
+
+impl Default for AmmConfig {
+    fn default() -> Self {
+        Self {
+            weight_check_safety_bps: 100,
+            label_with_fund_symbol: true,
+            verify_account_owners: false,
+            route_host_fee_to_referrer: false,
+            use_ema_price: false,
+            allow_stale_oracles: false,
+        }
+    }
+}
+
+impl AmmConfig {
+    /// `amount` scaled up by the safety margin. Fails rather than wrapping when the scaled
+    /// amount leaves the range of u64, so an overflow can't pass the weight check.
+    pub fn with_safety_margin(&self, amount: u64) -> Result<u64> {
+        BPS_DIVIDER
+            .checked_add(self.weight_check_safety_bps)
+            .map(|scale| amount as u128 * scale as u128 / BPS_DIVIDER as u128)
+            .and_then(|scaled| u64::try_from(scaled).ok())
+            .ok_or_else(|| SymmetryError::Arithmetic("Safety margin overflows u64").into())
+    }
+}
+
+#[test]
+fn test_with_safety_margin() {
+    let config = AmmConfig::default();
+    assert_eq!(config.with_safety_margin(1_000_000).unwrap(), 1_010_000);
+    assert_eq!(config.with_safety_margin(0).unwrap(), 0);
+
+    let overflow = Some(&SymmetryError::Arithmetic("Safety margin overflows u64"));
+    assert_eq!(config.with_safety_margin(u64::MAX).unwrap_err().downcast_ref::<SymmetryError>(), overflow);
+    let wide = AmmConfig { weight_check_safety_bps: u64::MAX, ..config };
+    assert_eq!(wide.with_safety_margin(1).unwrap_err().downcast_ref::<SymmetryError>(), overflow);
+}
+
+#[test]
+fn test_route_host_fee_to_referrer() {
+    use jupiter_amm_interface::{KeyedAccount, QuoteMintToReferrer, SwapParams};
+    use solana_sdk::pubkey::Pubkey;
+
+    use crate::amms::account_builders::{FundStateBuilder, TokenListBuilder};
+    use crate::amms::accounts::{TokenSettings, WEIGHT_MULTIPLIER};
+    use crate::amms::symmetry_token_swap::SymmetryTokenSwap;
+
+    let usdc = Pubkey::new_unique();
+    let sol = Pubkey::new_unique();
+    let token = |mint: Pubkey| TokenSettings { token_mint: mint, is_live: 1, lp_on: 1, ..TokenSettings::empty() };
+    let fund_state = FundStateBuilder::new()
+        .with_token(0, 1, WEIGHT_MULTIPLIER / 2)
+        .with_token(1, 1, WEIGHT_MULTIPLIER / 2);
+    let token_list = TokenListBuilder::new().with_token(token(usdc)).with_token(token(sol));
+    let amm = SymmetryTokenSwap::from_keyed_account(
+        &KeyedAccount { key: Pubkey::new_unique(), account: fund_state.build_account(), params: None },
+        &KeyedAccount { key: SymmetryTokenSwap::TOKEN_LIST_ADDRESS, account: token_list.build_account(), params: None },
+    ).unwrap();
+
+    let referrer = Pubkey::new_unique();
+    let referrers = QuoteMintToReferrer::from([(sol, referrer)]);
+    let user = Pubkey::new_unique();
+    let host_fee_account = |amm: &SymmetryTokenSwap| amm.build_swap_instruction(&SwapParams {
+        in_amount: 1,
+        source_mint: usdc,
+        destination_mint: sol,
+        source_token_account: Pubkey::new_unique(),
+        destination_token_account: Pubkey::new_unique(),
+        token_transfer_authority: user,
+        open_order_address: None,
+        quote_mint_to_referrer: Some(&referrers),
+        jupiter_program_id: &SymmetryTokenSwap::SYMMETRY_PROGRAM_ADDRESS,
+    }, 0).unwrap().accounts[8].pubkey;
+
+    assert_eq!(host_fee_account(&amm), amm.swap_fee_accounts(&sol).host_fee);
+    let amm = amm.with_config(AmmConfig { route_host_fee_to_referrer: true, ..AmmConfig::default() });
+    assert_eq!(host_fee_account(&amm), referrer);
+}
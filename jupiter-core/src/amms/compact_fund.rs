@@ -0,0 +1,206 @@
+use anyhow::Result;
+use jupiter_amm_interface::{Amm, Quote, QuoteParams};
+use bytemuck::{allocation::zeroed_box, Zeroable};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::amms::accounts::{CurveData, FundState, TokenList, TokenPriceData, TokenSettings};
+use crate::amms::amm_config::AmmConfig;
+use crate::amms::consistency::RebalancePolicy;
+use crate::amms::fee_model::{FeeSplit, OnChainFeeModel};
+use crate::amms::liquidity::LiquidityPolicy;
+use crate::amms::quote_source::QuoteSource;
+use crate::amms::symmetry_config::SymmetryConfig;
+use crate::amms::symmetry_token_swap::{pair_setup_for, quote_pair_for, PostSwapWeights, SymmetryTokenSwap};
+
+/// One of the fund's tokens together with everything quoting needs about it.
+#[derive(Clone, Copy)]
+pub struct CompactToken {
+    /// Index of the token in the global token list.
+    pub token_id: u64,
+    pub settings: TokenSettings,
+    pub buy_curve: TokenPriceData,
+    pub sell_curve: TokenPriceData,
+}
+
+/// A fund reduced to its own tokens: a few KB instead of the full 100-slot
+/// token list and curve arrays held by `SymmetryTokenSwap`. It quotes like the fund it was
+/// built from, except that swaps are split with `OnChainFeeModel`, weight limits are never
+/// partially filled, and mints outside the fund are `TokenNotSupported`.
+#[derive(Clone)]
+pub struct CompactFund {
+    pub key: Pubkey,
+    pub label: String,
+    pub symmetry_config: SymmetryConfig,
+    pub fund_state: FundState,
+    /// Token list entry 0, whose `additional_data` carries the fee split used by every swap.
+    pub fee_settings: TokenSettings,
+    pub tokens: Vec<CompactToken>,
+    pub config: AmmConfig,
+    pub rebalance_policy: RebalancePolicy,
+    pub liquidity_policy: LiquidityPolicy,
+}
+
+impl CompactFund {
+    pub fn token(&self, mint: &Pubkey) -> Option<&CompactToken> {
+        self.tokens.iter().find(|token| token.settings.token_mint == *mint)
+    }
+
+    /// Same as `Amm::quote` on the full fund, computed from the compact state.
+    pub fn quote(&self, quote_params: &QuoteParams) -> Result<Quote> {
+        self.quote_with_weights(quote_params).map(|(quote, _)| quote)
+    }
+
+    pub fn quote_with_weights(&self, quote_params: &QuoteParams) -> Result<(Quote, PostSwapWeights)> {
+        let setup = pair_setup_for(self, &quote_params.input_mint, &quote_params.output_mint)?;
+        quote_pair_for(self, &setup, quote_params.in_amount)
+    }
+
+    fn token_by_id(&self, token_id: usize) -> Option<&CompactToken> {
+        self.tokens.iter().find(|token| token.token_id as usize == token_id)
+    }
+
+    /// Rebuilds a full `SymmetryTokenSwap`, e.g. to build a swap instruction. Token list
+    /// slots that are not part of the fund are left empty.
+    pub fn expand(&self) -> SymmetryTokenSwap {
+        let mut token_list = zeroed_box::<TokenList>();
+        let mut curve_data = zeroed_box::<CurveData>();
+        token_list.list[0] = self.fee_settings;
+        for token in &self.tokens {
+            let id = token.token_id as usize;
+            token_list.list[id] = token.settings;
+            curve_data.buy[id] = token.buy_curve;
+            curve_data.sell[id] = token.sell_curve;
+            token_list.num_tokens = token_list.num_tokens.max(token.token_id + 1);
+        }
+        SymmetryTokenSwap::from_parts(
+            self.key,
+            self.label.clone(),
+            self.fund_state,
+            token_list,
+            curve_data,
+            self.symmetry_config,
+        )
+        .with_config(self.config)
+        .with_rebalance_policy(self.rebalance_policy)
+        .with_liquidity_policy(self.liquidity_policy)
+    }
+}
+
+impl QuoteSource for CompactFund {
+    fn fund_key(&self) -> Pubkey {
+        self.key
+    }
+
+    fn fund_state(&self) -> &FundState {
+        &self.fund_state
+    }
+
+    fn token_id(&self, mint: &Pubkey) -> Option<usize> {
+        self.token(mint).map(|token| token.token_id as usize)
+    }
+
+    fn token_settings(&self, token_id: usize) -> Option<&TokenSettings> {
+        match self.token_by_id(token_id) {
+            Some(token) => Some(&token.settings),
+            None if token_id == 0 => Some(&self.fee_settings),
+            None => None,
+        }
+    }
+
+    fn sell_curve(&self, token_id: usize) -> TokenPriceData {
+        self.token_by_id(token_id).map_or_else(TokenPriceData::zeroed, |token| token.sell_curve)
+    }
+
+    fn buy_curve(&self, token_id: usize) -> TokenPriceData {
+        self.token_by_id(token_id).map_or_else(TokenPriceData::zeroed, |token| token.buy_curve)
+    }
+
+    fn quote_config(&self) -> &AmmConfig {
+        &self.config
+    }
+
+    fn rebalance_policy(&self) -> RebalancePolicy {
+        self.rebalance_policy
+    }
+
+    fn liquidity_policy(&self) -> LiquidityPolicy {
+        self.liquidity_policy
+    }
+
+    fn split_fees(&self, total_fees: u64) -> Result<FeeSplit> {
+        OnChainFeeModel::split_with_settings(total_fees, &self.fee_settings)
+    }
+}
+
+impl From<&SymmetryTokenSwap> for CompactFund {
+    fn from(fund: &SymmetryTokenSwap) -> Self {
+        let fund_state = *fund.fund_state();
+        let token_list = fund.token_list();
+        let curve_data = fund.curve_data();
+        let tokens = fund_state.token_ids()
+            .iter()
+            .filter_map(|&token_id| Some(CompactToken {
+                token_id,
+                settings: *token_list.list.get(token_id as usize)?,
+                buy_curve: curve_data.buy[token_id as usize],
+                sell_curve: curve_data.sell[token_id as usize],
+            }))
+            .collect();
+        CompactFund {
+            key: fund.key(),
+            label: fund.label(),
+            symmetry_config: *fund.symmetry_config(),
+            fund_state,
+            fee_settings: token_list.list[0],
+            tokens,
+            config: *fund.config(),
+            rebalance_policy: QuoteSource::rebalance_policy(fund),
+            liquidity_policy: QuoteSource::liquidity_policy(fund),
+        }
+    }
+}
+
+#[test]
+fn test_compact_fund_quotes_like_full_fund() {
+    use crate::amms::account_builders::{quotable_fund, FixtureToken, FundStateBuilder};
+    use crate::amms::accounts::WEIGHT_MULTIPLIER;
+    use crate::error::SymmetryError;
+
+    // Token 1 is listed but not held, so the compact token ids are sparse.
+    let tokens = [FixtureToken::new(6, 100_000_000), FixtureToken::new(9, 2_000_000_000), FixtureToken::new(9, 10_000_000_000)];
+    let fund_state = FundStateBuilder::new()
+        .with_token(0, 10_000_000_000, WEIGHT_MULTIPLIER / 2)
+        .with_token(2, 100_000_000_000, WEIGHT_MULTIPLIER / 2)
+        .with_weight_band(1_000, 5_000);
+    let full = quotable_fund(Pubkey::new_unique(), &fund_state, &tokens);
+    let compact = CompactFund::from(&full);
+    assert_eq!(compact.tokens.iter().map(|token| token.token_id).collect::<Vec<_>>(), vec![0, 2]);
+
+    let (usdc, sol) = (tokens[0].mint, tokens[2].mint);
+    for (in_amount, input_mint, output_mint) in [
+        (100_000_000, usdc, sol),
+        (1_000_000_000, sol, usdc),
+        (250_000_000_000, sol, usdc),
+    ] {
+        let quote_params = QuoteParams { in_amount, input_mint, output_mint };
+        let full_quote = full.quote_with_weights(&quote_params);
+        let compact_quote = compact.quote_with_weights(&quote_params);
+        match (full_quote, compact_quote) {
+            (Ok(full_quote), Ok(compact_quote)) => assert_eq!(format!("{full_quote:?}"), format!("{compact_quote:?}")),
+            (Err(full_error), Err(compact_error)) => assert_eq!(
+                full_error.downcast_ref::<SymmetryError>(),
+                compact_error.downcast_ref::<SymmetryError>()
+            ),
+            (full_quote, compact_quote) => panic!("full {full_quote:?} but compact {compact_quote:?}"),
+        }
+    }
+    assert!(full.quote(&QuoteParams { in_amount: 250_000_000_000, input_mint: sol, output_mint: usdc }).is_err());
+    let expanded = compact.expand().quote(&QuoteParams { in_amount: 100_000_000, input_mint: usdc, output_mint: sol }).unwrap();
+    assert_eq!(expanded.out_amount, compact.quote(&QuoteParams { in_amount: 100_000_000, input_mint: usdc, output_mint: sol }).unwrap().out_amount);
+
+    let unheld = QuoteParams { in_amount: 1_000_000, input_mint: tokens[1].mint, output_mint: usdc };
+    assert_eq!(
+        compact.quote(&unheld).unwrap_err().downcast_ref::<SymmetryError>(),
+        Some(&SymmetryError::TokenNotSupported { mint: tokens[1].mint })
+    );
+}
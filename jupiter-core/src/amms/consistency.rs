@@ -0,0 +1,150 @@
+use anyhow::Result;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+
+use crate::error::SymmetryError;
+
+/// What to do when the accounts handed to an update were read at slots too far apart.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SlotSpreadPolicy {
+    /// Apply the update and report the spread to the caller.
+    Warn,
+    /// Refuse the update and keep the previous state.
+    Reject,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SlotConsistency {
+    pub max_slot_spread: u64,
+    pub policy: SlotSpreadPolicy,
+}
+
+impl Default for SlotConsistency {
+    fn default() -> Self {
+        Self {
+            max_slot_spread: 2,
+            policy: SlotSpreadPolicy::Warn,
+        }
+    }
+}
+
+/// Slot range covered by the accounts of one update.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct SlotSpread {
+    pub min_slot: u64,
+    pub max_slot: u64,
+    /// Set when the spread is above `SlotConsistency::max_slot_spread`.
+    pub exceeded: bool,
+}
+
+impl SlotSpread {
+    pub fn spread(&self) -> u64 {
+        self.max_slot - self.min_slot
+    }
+}
+
+impl SlotConsistency {
+    /// Computes the spread over the `accounts` present in `slots` and applies the policy.
+    /// Accounts without slot metadata are ignored.
+    pub fn check(&self, accounts: &[Pubkey], slots: &HashMap<Pubkey, u64>) -> Result<SlotSpread> {
+        let mut known_slots = accounts.iter().filter_map(|key| slots.get(key).copied());
+        let first = match known_slots.next() {
+            Some(slot) => slot,
+            None => return Ok(SlotSpread::default()),
+        };
+        let (min_slot, max_slot) = known_slots.fold((first, first), |(min, max), slot| (min.min(slot), max.max(slot)));
+        let exceeded = max_slot - min_slot > self.max_slot_spread;
+        if exceeded && self.policy == SlotSpreadPolicy::Reject {
+            return Err(SymmetryError::SlotSpreadExceeded {
+                min_slot,
+                max_slot,
+                max_slot_spread: self.max_slot_spread,
+            }.into());
+        }
+        Ok(SlotSpread { min_slot, max_slot, exceeded })
+    }
+}
+
+/// What `quote()` does while the fund is part-way through a rebalance or refilter.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RebalancePolicy {
+    /// Quote as usual; callers can check `SymmetryTokenSwap::rebalance_in_progress()`.
+    #[default]
+    Flag,
+    /// Fail quotes with `SymmetryError::RebalanceInProgress`.
+    Reject,
+}
+
+#[test]
+fn test_slot_spread_policies() {
+    use jupiter_amm_interface::Amm;
+
+    use crate::amms::account_builders::{fixture_accounts, quotable_fund, FixtureToken, FundStateBuilder};
+    use crate::amms::accounts::{ONE_USD, WEIGHT_MULTIPLIER};
+
+    let accounts = [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+    let slots: HashMap<Pubkey, u64> = [(accounts[0], 100), (accounts[1], 105)].into();
+    let warn = SlotConsistency { max_slot_spread: 2, policy: SlotSpreadPolicy::Warn };
+    let reject = SlotConsistency { policy: SlotSpreadPolicy::Reject, ..warn };
+    assert_eq!(warn.check(&accounts, &slots).unwrap(), SlotSpread { min_slot: 100, max_slot: 105, exceeded: true });
+    assert!(reject.check(&accounts, &slots).is_err());
+    assert_eq!(SlotConsistency { max_slot_spread: 5, ..reject }.check(&accounts, &slots).unwrap().spread(), 5);
+    // Accounts without a slot are ignored.
+    assert_eq!(reject.check(&accounts[2..], &slots).unwrap(), SlotSpread::default());
+
+    let tokens = [FixtureToken::new(6, 100_000_000), FixtureToken::new(9, 10_000_000_000)];
+    let fund_state = |sol_amount: u64| FundStateBuilder::new()
+        .with_token(0, 10_000_000_000, WEIGHT_MULTIPLIER / 2)
+        .with_token(1, sol_amount, WEIGHT_MULTIPLIER / 2);
+    let key = Pubkey::new_unique();
+    let mut fund = quotable_fund(key, &fund_state(100_000_000_000), &tokens).with_slot_consistency(reject);
+    // The fund state moved on to 200 SOL, but the oracles were read 10 slots earlier.
+    let account_map = fixture_accounts(key, &fund_state(200_000_000_000), &tokens);
+    let mut slots: HashMap<Pubkey, u64> = fund.get_accounts_to_update().into_iter().map(|account| (account, 1_000)).collect();
+    slots.insert(key, 1_010);
+
+    assert_eq!(
+        fund.update_with_slots(&account_map, &slots).unwrap_err().downcast_ref::<SymmetryError>(),
+        Some(&SymmetryError::SlotSpreadExceeded { min_slot: 1_000, max_slot: 1_010, max_slot_spread: 2 })
+    );
+    assert_eq!(fund.fund_worth().unwrap(), 20_000 * ONE_USD);
+    assert_eq!(fund.last_update_slot(), None);
+
+    let mut fund = fund.with_slot_consistency(warn);
+    assert!(fund.update_with_slots(&account_map, &slots).unwrap().exceeded);
+    assert_eq!(fund.fund_worth().unwrap(), 30_000 * ONE_USD);
+    assert_eq!(fund.last_update_slot(), Some(1_010));
+}
+
+#[test]
+fn test_rebalance_policy() {
+    use jupiter_amm_interface::{Amm, QuoteParams};
+
+    use crate::amms::account_builders::{quotable_fund, FixtureToken, FundStateBuilder};
+    use crate::amms::accounts::WEIGHT_MULTIPLIER;
+    use crate::rejections::RejectionReason;
+
+    let tokens = [FixtureToken::new(6, 100_000_000), FixtureToken::new(9, 10_000_000_000)];
+    let fund_state = FundStateBuilder::new()
+        .with_token(0, 10_000_000_000, WEIGHT_MULTIPLIER / 2)
+        .with_token(1, 100_000_000_000, WEIGHT_MULTIPLIER / 2)
+        .with_weight_band(1_000, 5_000);
+    let quote_params = QuoteParams { in_amount: 100_000_000, input_mint: tokens[0].mint, output_mint: tokens[1].mint };
+    let idle = quotable_fund(Pubkey::new_unique(), &fund_state, &tokens).with_rebalance_policy(RebalancePolicy::Reject);
+    assert!(!idle.rebalance_in_progress());
+    let expected = idle.quote(&quote_params).unwrap().out_amount;
+
+    // Two buy states of a rebalance still to run.
+    let key = Pubkey::new_unique();
+    let rebalancing = quotable_fund(key, &fund_state.clone().with_rebalance_states(2, 0, 0), &tokens);
+    assert!(rebalancing.rebalance_in_progress());
+    assert_eq!(rebalancing.quote(&quote_params).unwrap().out_amount, expected);
+
+    let error = rebalancing.with_rebalance_policy(RebalancePolicy::Reject).quote(&quote_params).unwrap_err();
+    assert_eq!(
+        error.downcast_ref::<SymmetryError>(),
+        Some(&SymmetryError::RebalanceInProgress { fund: key, active_buy_states: 2, sell_state: 0, rebalance_sell_state: 0 })
+    );
+    assert_eq!(RejectionReason::classify(&error), RejectionReason::RebalanceInProgress);
+}
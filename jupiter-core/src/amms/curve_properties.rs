@@ -0,0 +1,125 @@
+//! Property tests of the curve stepping math in `compute_value_of_sold_token` and
+//! `compute_amount_of_bought_token`.
+//!
+//! Panics are checked over the whole input space. The other invariants are checked where
+//! every USD value fits in a `u64`, as `mul_div_rounded` returns zero on overflow.
+
+use proptest::prelude::*;
+
+use crate::amms::accounts::{OraclePrice, TokenPriceData, TokenSettings, ONE_USD, USE_CURVE_DATA};
+use crate::amms::symmetry_token_swap::SymmetryTokenSwap;
+
+/// Decimals, fee before and after the target weight in bps, and whether curves are used.
+type TokenParams = (u8, u8, u8, bool);
+
+fn token_params() -> impl Strategy<Value = TokenParams> {
+    (0u8..=12, any::<u8>(), any::<u8>(), any::<bool>())
+}
+
+fn token_settings((decimals, before_tw_bps, after_tw_bps, use_curve_data): TokenParams) -> TokenSettings {
+    TokenSettings {
+        decimals,
+        token_swap_fee_before_tw_bps: before_tw_bps,
+        token_swap_fee_after_tw_bps: after_tw_bps,
+        use_curve_data: if use_curve_data { USE_CURVE_DATA } else { 0 },
+        ..TokenSettings::empty()
+    }
+}
+
+/// Oracle price with the lower of the two prices as sell and the higher as buy price.
+fn oracle_price((a, b): (u64, u64)) -> OraclePrice {
+    OraclePrice {
+        sell_price: a.min(b),
+        avg_price: a / 2 + b / 2,
+        buy_price: a.max(b),
+        oracle_live: 1,
+    }
+}
+
+/// Whether `value` buys less than a quarter of `u64::MAX` of a token with `decimals` at
+/// `price`, so the last step of `compute_amount_of_bought_token` doesn't overflow.
+fn amount_fits(value: u64, decimals: u8, price: u64) -> bool {
+    (value as u128) * 10u128.pow(decimals as u32) < (price as u128) * (u64::MAX / 4) as u128
+}
+
+/// Token params with a start amount, target amount, curve step amounts and two ordered trade
+/// amounts of up to 1,000 whole tokens each, so at up to $1,000 per token no interval is
+/// worth more than `u64::MAX`.
+fn holdings() -> impl Strategy<Value = (TokenParams, u64, u64, [u64; 10], (u64, u64))> {
+    token_params().prop_flat_map(|token| {
+        let amounts = 0..1_000 * 10u64.pow(token.0 as u32);
+        (Just(token), amounts.clone(), amounts.clone(), prop::array::uniform10(amounts.clone()), ordered(amounts))
+    })
+}
+
+/// Up to $1,000 per whole token.
+fn prices() -> impl Strategy<Value = u64> + Clone {
+    1u64..1_000 * ONE_USD
+}
+
+fn ordered(values: impl Strategy<Value = u64> + Clone) -> impl Strategy<Value = (u64, u64)> {
+    (values.clone(), values).prop_map(|(a, b)| (a.min(b), a.max(b)))
+}
+
+proptest! {
+    #[test]
+    fn test_curve_math_never_panics(
+        amount in any::<u64>(),
+        token in token_params(),
+        price in any::<(u64, u64)>(),
+        start_amount in any::<u64>(),
+        target_amount in any::<u64>(),
+        curve_amounts in any::<[u64; 10]>(),
+        curve_prices in any::<[u64; 10]>(),
+    ) {
+        let token_settings = token_settings(token);
+        let price = oracle_price(price);
+        let curve_data = TokenPriceData { amount: curve_amounts, price: curve_prices };
+        SymmetryTokenSwap::compute_value_of_sold_token_with_split(amount, token_settings, price, start_amount, target_amount, curve_data);
+        SymmetryTokenSwap::compute_amount_of_bought_token_with_split(amount, token_settings, price, start_amount, target_amount, curve_data);
+    }
+
+    #[test]
+    fn test_sold_value_invariants(
+        (token, start_amount, target_amount, curve_amounts, (smaller, larger)) in holdings(),
+        price in (prices(), prices()),
+        curve_prices in prop::array::uniform10(prices()),
+    ) {
+        let token_settings = token_settings(token);
+        let price = oracle_price(price);
+        let curve_data = TokenPriceData { amount: curve_amounts, price: curve_prices };
+
+        let sell = |amount| SymmetryTokenSwap::compute_value_of_sold_token_with_split(
+            amount, token_settings, price, start_amount, target_amount, curve_data,
+        );
+        let (smaller_value, _) = sell(smaller);
+        let (larger_value, split) = sell(larger);
+        prop_assert!(smaller_value <= larger_value);
+        prop_assert!(larger_value <= SymmetryTokenSwap::amount_to_usd_value(larger, token_settings.decimals, price.sell_price));
+        prop_assert!(split.total_fees() <= split.value_before_tw + split.value_after_tw);
+        prop_assert_eq!(larger_value, split.value_before_tw + split.value_after_tw - split.total_fees());
+    }
+
+    #[test]
+    fn test_bought_amount_invariants(
+        (token, start_amount, target_amount, curve_amounts, _) in holdings(),
+        (smaller, larger) in ordered(0u64..1_000_000 * ONE_USD),
+        price in (prices(), prices()),
+        curve_prices in prop::array::uniform10(prices()),
+    ) {
+        let token_settings = token_settings(token);
+        let price = oracle_price(price);
+        let curve_data = TokenPriceData { amount: curve_amounts, price: curve_prices };
+        prop_assume!(amount_fits(larger.saturating_mul(2), token_settings.decimals, price.buy_price));
+
+        let buy = |value| SymmetryTokenSwap::compute_amount_of_bought_token_with_split(
+            value, token_settings, price, start_amount, target_amount, curve_data,
+        );
+        let (smaller_amount, _) = buy(smaller);
+        let (larger_amount, split) = buy(larger);
+        prop_assert!(smaller_amount <= larger_amount);
+        prop_assert!(larger_amount <= SymmetryTokenSwap::usd_value_to_amount(larger, token_settings.decimals, price.buy_price));
+        prop_assert!(split.total_fees() <= larger);
+        prop_assert!(split.value_before_tw + split.value_after_tw <= larger);
+    }
+}
@@ -0,0 +1,92 @@
+use anyhow::Result;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::amms::symmetry_token_swap::SymmetryTokenSwap;
+use crate::error::SymmetryError;
+
+/// Currency fund valuations are expressed in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Denomination {
+    /// USD with `ONE_USD` precision.
+    #[default]
+    Usd,
+    /// Raw amount of a token-list token, converted at its oracle avg price (e.g. wSOL for
+    /// SOL-denominated baskets). The token doesn't need to be in the fund.
+    Token(Pubkey),
+}
+
+impl SymmetryTokenSwap {
+    /// Converts a `ONE_USD` precision value into `denomination`.
+    pub fn usd_value_in(&self, usd_value: u64, denomination: Denomination) -> Result<u64> {
+        match denomination {
+            Denomination::Usd => Ok(usd_value),
+            Denomination::Token(mint) => {
+                let settings = self.token_id(&mint)
+                    .map(|token_id| &self.token_list().list[token_id])
+                    .ok_or(SymmetryError::TokenNotSupported { mint })?;
+                if settings.oracle_price.oracle_live == 0 || settings.oracle_price.avg_price == 0 {
+                    return Err(SymmetryError::OracleOffline { mint }.into());
+                }
+                Ok(SymmetryTokenSwap::usd_value_to_amount(usd_value, settings.decimals, settings.oracle_price.avg_price))
+            }
+        }
+    }
+
+    pub fn fund_worth_in(&self, denomination: Denomination) -> Result<u64> {
+        self.usd_value_in(self.fund_worth()?, denomination)
+    }
+
+    /// Value of `fund_tokens` of the fund's share token in `denomination`.
+    pub fn nav_in(&self, fund_tokens: u64, denomination: Denomination) -> Result<u64> {
+        let supply = self.fund_state().supply_outstanding;
+        if supply == 0 {
+            return Err(SymmetryError::NoFundTokensOutstanding.into());
+        }
+        let usd_value = SymmetryTokenSwap::mul_div(fund_tokens, self.fund_worth()?, supply);
+        self.usd_value_in(usd_value, denomination)
+    }
+}
+
+#[test]
+fn test_nav_in_denomination() {
+    use jupiter_amm_interface::Amm;
+
+    use crate::amms::account_builders::{fixture_accounts, quotable_fund, FixtureToken, FundStateBuilder};
+    use crate::amms::accounts::{ONE_USD, WEIGHT_MULTIPLIER};
+
+    // $10,000 of USDC and 100 SOL at $100 backing 20,000 fund tokens of 6 decimals. BONK is
+    // listed but not held.
+    let tokens = [FixtureToken::new(6, 100_000_000), FixtureToken::new(9, 10_000_000_000), FixtureToken::new(5, 2_000)];
+    let (usdc, sol, bonk) = (tokens[0].mint, tokens[1].mint, tokens[2].mint);
+    let fund_state = FundStateBuilder::new()
+        .with_token(0, 10_000_000_000, WEIGHT_MULTIPLIER / 2)
+        .with_token(1, 100_000_000_000, WEIGHT_MULTIPLIER / 2)
+        .with_weight_band(1_000, 5_000)
+        .with_supply_outstanding(20_000_000_000);
+    let key = Pubkey::new_unique();
+    let mut fund = quotable_fund(key, &fund_state, &tokens);
+
+    assert_eq!(fund.nav_in(1_000_000, Denomination::Usd).unwrap(), ONE_USD);
+    assert_eq!(fund.nav_in(1_000_000, Denomination::Token(usdc)).unwrap(), 1_000_000);
+    assert_eq!(fund.nav_in(1_000_000, Denomination::Token(sol)).unwrap(), 10_000_000);
+    assert_eq!(fund.fund_worth_in(Denomination::Token(sol)).unwrap(), 200_000_000_000);
+    // Any listed token can denominate, held or not.
+    assert_eq!(fund.nav_in(1_000_000, Denomination::Token(bonk)).unwrap(), 5_000_000_000);
+
+    let error = |result: Result<u64>| result.unwrap_err().downcast::<SymmetryError>().ok();
+    let unlisted = Pubkey::new_unique();
+    assert_eq!(
+        error(fund.nav_in(1_000_000, Denomination::Token(unlisted))),
+        Some(SymmetryError::TokenNotSupported { mint: unlisted })
+    );
+
+    // BONK's oracle last published 100 slots before the clock.
+    let mut account_map = fixture_accounts(key, &fund_state, &tokens);
+    account_map.insert(tokens[2].oracle, tokens[2].oracle_account(910));
+    fund.update(&account_map).unwrap();
+    assert_eq!(error(fund.nav_in(1_000_000, Denomination::Token(bonk))), Some(SymmetryError::OracleOffline { mint: bonk }));
+    assert_eq!(fund.nav_in(1_000_000, Denomination::Token(sol)).unwrap(), 10_000_000);
+
+    let unissued = quotable_fund(Pubkey::new_unique(), &fund_state.clone().with_supply_outstanding(0), &tokens);
+    assert_eq!(error(unissued.nav_in(1_000_000, Denomination::Usd)), Some(SymmetryError::NoFundTokensOutstanding));
+}
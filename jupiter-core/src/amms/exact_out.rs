@@ -0,0 +1,127 @@
+use anyhow::Result;
+use jupiter_amm_interface::{Quote, QuoteParams};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::amms::quoter::Quoter;
+use crate::amms::symmetry_token_swap::SymmetryTokenSwap;
+use crate::error::SymmetryError;
+use crate::math::symmetry_math::smallest_input_reaching;
+
+/// Which side of a swap the quoted amount fixes. jupiter-amm-interface 0.2.1 only has
+/// exact-in quotes: its `QuoteParams` carries no swap mode and `Amm` no way to report
+/// exact-out support, so this is the SDK's own.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SwapMode {
+    #[default]
+    ExactIn,
+    /// The amount is the output wanted; the quote finds the input to sell.
+    ExactOut,
+}
+
+impl Quoter<'_> {
+    /// Quote selling the least input that returns at least `out_amount`, found by bisecting
+    /// exact-in quotes. `Quote::out_amount` is what that input returns, which can exceed
+    /// `out_amount` by the rounding of one input unit. Fails if the fund's holding of the
+    /// output token or the weight limits can't deliver `out_amount`.
+    pub fn quote_exact_out(&self, out_amount: u64) -> Result<Quote> {
+        if out_amount == 0 {
+            return Err(SymmetryError::ZeroAmount.into());
+        }
+        // The output before fees is capped at the fund's holding, so no input gets more.
+        if out_amount > self.fund().fund_state().current_comp_amount[self.setup().to_token_index] {
+            return Err(SymmetryError::OutAmountUnreachable { out_amount }.into());
+        }
+        let from = &self.setup().from_token_settings;
+        let to = &self.setup().to_token_settings;
+        let estimate = SymmetryTokenSwap::usd_value_to_amount(
+            SymmetryTokenSwap::amount_to_usd_value(out_amount, to.decimals, to.oracle_price.buy_price),
+            from.decimals,
+            from.oracle_price.sell_price,
+        );
+        let out_amount_for = |in_amount: u64| -> Result<u64> {
+            let quote = self.quote_amount(in_amount)?;
+            // Capped quotes return the holding, not what the input buys.
+            Ok(if quote.not_enough_liquidity { 0 } else { quote.out_amount })
+        };
+        let in_amount = smallest_input_reaching(out_amount, estimate.saturating_mul(2), out_amount_for)?
+            .ok_or(SymmetryError::OutAmountUnreachable { out_amount })?;
+        self.quote_amount(in_amount)
+    }
+}
+
+impl SymmetryTokenSwap {
+    /// Whether `quote_exact_out` and `quote_with_swap_mode` can answer ExactOut quotes,
+    /// which they do by searching exact-in quotes. `Amm::quote` itself stays exact-in.
+    pub fn supports_exact_out(&self) -> bool {
+        true
+    }
+
+    /// Quote receiving at least `out_amount` of `output_mint` for the least `input_mint`.
+    /// `Amm::quote` can't be asked for this: `QuoteParams` has no swap mode.
+    pub fn quote_exact_out(&self, input_mint: &Pubkey, output_mint: &Pubkey, out_amount: u64) -> Result<Quote> {
+        Quoter::for_pair(self, input_mint, output_mint)?.quote_exact_out(out_amount)
+    }
+
+    /// `quote()` when `swap_mode` is `ExactIn`. Under `ExactOut`, `quote_params.in_amount` is
+    /// the wanted output.
+    pub fn quote_with_swap_mode(&self, quote_params: &QuoteParams, swap_mode: SwapMode) -> Result<Quote> {
+        match swap_mode {
+            SwapMode::ExactIn => self.quote_with_weights(quote_params).map(|(quote, _)| quote),
+            SwapMode::ExactOut => self.quote_exact_out(&quote_params.input_mint, &quote_params.output_mint, quote_params.in_amount),
+        }
+    }
+}
+
+#[test]
+fn test_quote_exact_out() {
+    use jupiter_amm_interface::Amm;
+
+    use crate::amms::account_builders::{quotable_fund, FixtureToken, FundStateBuilder};
+    use crate::amms::accounts::WEIGHT_MULTIPLIER;
+    use crate::error::SwapSide;
+
+    let tokens = [FixtureToken::new(6, 100_000_000), FixtureToken::new(9, 10_000_000_000)];
+    let fund_state = FundStateBuilder::new()
+        .with_token(0, 10_000_000_000, WEIGHT_MULTIPLIER / 2)
+        .with_token(1, 100_000_000_000, WEIGHT_MULTIPLIER / 2)
+        .with_weight_band(1_000, 5_000);
+    let fund = quotable_fund(Pubkey::new_unique(), &fund_state, &tokens);
+    assert!(fund.supports_exact_out());
+    let (usdc, sol) = (tokens[0].mint, tokens[1].mint);
+    let exact_in = |in_amount: u64| fund.quote(&QuoteParams { in_amount, input_mint: usdc, output_mint: sol });
+
+    // The weight band caps USDC -> SOL well below the fund's 100 SOL.
+    let max_in = fund.max_in_amount(&usdc, &sol).unwrap();
+    let edge_out = exact_in(max_in).unwrap().out_amount;
+    assert!(max_in > 0 && edge_out < 100_000_000_000);
+
+    for out_amount in [1, 1_000, 990_000_000, edge_out] {
+        let quote = fund.quote_exact_out(&usdc, &sol, out_amount).unwrap();
+        assert!(quote.out_amount >= out_amount);
+        assert_eq!(quote.out_amount, exact_in(quote.in_amount).unwrap().out_amount);
+        assert!(exact_in(quote.in_amount - 1).unwrap().out_amount < out_amount);
+    }
+    assert!(fund.quote_exact_out(&usdc, &sol, edge_out).unwrap().in_amount <= max_in);
+
+    // Past the band edge the weight limit fails the quote.
+    let error = |fund: &SymmetryTokenSwap, out_amount: u64| {
+        fund.quote_exact_out(&usdc, &sol, out_amount).unwrap_err().downcast::<SymmetryError>().unwrap()
+    };
+    assert_eq!(error(&fund, edge_out + 1), SymmetryError::WeightLimitExceeded { side: SwapSide::To });
+    assert_eq!(error(&fund, 0), SymmetryError::ZeroAmount);
+
+    // With the band open to the full weight, no input gets more than the fund's 100 SOL.
+    let unbounded = quotable_fund(Pubkey::new_unique(), &fund_state.clone().with_weight_band(10_000, 10_000), &tokens);
+    assert_eq!(error(&unbounded, 200_000_000_000), SymmetryError::OutAmountUnreachable { out_amount: 200_000_000_000 });
+
+    // Under ExactOut the params' in amount is the wanted output.
+    let quote_params = QuoteParams { in_amount: 100_000_000, input_mint: usdc, output_mint: sol };
+    assert_eq!(
+        fund.quote_with_swap_mode(&quote_params, SwapMode::ExactOut).unwrap().in_amount,
+        fund.quote_exact_out(&usdc, &sol, 100_000_000).unwrap().in_amount
+    );
+    assert_eq!(
+        fund.quote_with_swap_mode(&quote_params, SwapMode::ExactIn).unwrap().out_amount,
+        exact_in(100_000_000).unwrap().out_amount
+    );
+}
@@ -0,0 +1,134 @@
+//! Fee token accounts a swap pays into. The program doesn't create them, so a swap into a
+//! mint the protocol, host or manager has no ATA for fails on chain; these helpers find the
+//! missing ones and create them ahead of the swap.
+
+#[cfg(feature = "rpc")]
+use anyhow::Result;
+use jupiter_amm_interface::AccountMap;
+#[cfg(feature = "rpc")]
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::account::Account;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::amms::symmetry_token_swap::SymmetryTokenSwap;
+use crate::transaction::create_associated_token_account_idempotent;
+
+/// `getMultipleAccounts` accepts at most 100 keys per request.
+#[cfg(feature = "rpc")]
+const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+/// An associated token account a swap pays fees into.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct FeeAccount {
+    pub owner: Pubkey,
+    pub mint: Pubkey,
+    pub address: Pubkey,
+}
+
+impl FeeAccount {
+    /// Creates the account unless it exists, paid for by `payer`.
+    pub fn create_instruction(&self, payer: &Pubkey) -> Instruction {
+        create_associated_token_account_idempotent(payer, &self.owner, &self.mint)
+    }
+}
+
+fn is_token_account(account: Option<&Account>) -> bool {
+    account.is_some_and(|account| account.owner == SymmetryTokenSwap::SPL_TOKEN_PROGRAM_ADDRESS && !account.data.is_empty())
+}
+
+impl SymmetryTokenSwap {
+    /// The swap fee, host fee and manager fee accounts of a swap into `output_mint`, without
+    /// duplicates when the host and manager are the same wallet.
+    pub fn swap_fee_token_accounts(&self, output_mint: &Pubkey) -> Vec<FeeAccount> {
+        let fee_accounts = self.swap_fee_accounts(output_mint);
+        let owned = [
+            (self.symmetry_config().swap_fee, fee_accounts.swap_fee),
+            (self.fund_state().host_pubkey, fee_accounts.host_fee),
+            (self.fund_state().manager, fee_accounts.manager_fee),
+        ];
+        let mut accounts: Vec<FeeAccount> = Vec::with_capacity(owned.len());
+        for (owner, address) in owned {
+            if !accounts.iter().any(|account| account.address == address) {
+                accounts.push(FeeAccount { owner, mint: *output_mint, address });
+            }
+        }
+        accounts
+    }
+
+    /// Fee accounts of a swap into `output_mint` that `account_map` doesn't hold as token
+    /// accounts. `account_map` must have been fetched with the fee account addresses.
+    pub fn missing_swap_fee_accounts(&self, output_mint: &Pubkey, account_map: &AccountMap) -> Vec<FeeAccount> {
+        self.swap_fee_token_accounts(output_mint)
+            .into_iter()
+            .filter(|account| !is_token_account(account_map.get(&account.address)))
+            .collect()
+    }
+
+    /// `missing_swap_fee_accounts` with the fee accounts fetched from `client`.
+    #[cfg(feature = "rpc")]
+    pub fn fetch_missing_swap_fee_accounts(&self, client: &RpcClient, output_mint: &Pubkey) -> Result<Vec<FeeAccount>> {
+        let fee_accounts = self.swap_fee_token_accounts(output_mint);
+        let addresses: Vec<Pubkey> = fee_accounts.iter().map(|account| account.address).collect();
+        let mut account_map = AccountMap::new();
+        for chunk in addresses.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            for (address, account) in chunk.iter().zip(client.get_multiple_accounts(chunk)?) {
+                if let Some(account) = account {
+                    account_map.insert(*address, account);
+                }
+            }
+        }
+        Ok(self.missing_swap_fee_accounts(output_mint, &account_map))
+    }
+
+    /// Instructions creating the fee accounts of a swap into `output_mint` that
+    /// `account_map` lacks, to place before the swap.
+    pub fn create_swap_fee_account_instructions(&self, payer: &Pubkey, output_mint: &Pubkey, account_map: &AccountMap) -> Vec<Instruction> {
+        self.missing_swap_fee_accounts(output_mint, account_map)
+            .iter()
+            .map(|account| account.create_instruction(payer))
+            .collect()
+    }
+
+    /// `create_swap_fee_account_instructions` with the fee accounts fetched from `client`.
+    #[cfg(feature = "rpc")]
+    pub fn fetch_create_swap_fee_account_instructions(&self, client: &RpcClient, payer: &Pubkey, output_mint: &Pubkey) -> Result<Vec<Instruction>> {
+        Ok(self.fetch_missing_swap_fee_accounts(client, output_mint)?
+            .iter()
+            .map(|account| account.create_instruction(payer))
+            .collect())
+    }
+}
+
+#[test]
+fn test_missing_swap_fee_accounts() {
+    use jupiter_amm_interface::KeyedAccount;
+
+    use crate::amms::account_builders::{FundStateBuilder, TokenListBuilder};
+    use crate::pda::associated_token_address;
+
+    let wallet = Pubkey::new_unique();
+    let fund_state = FundStateBuilder::new().with_manager(wallet).with_host(wallet);
+    let amm = SymmetryTokenSwap::from_keyed_account(
+        &KeyedAccount { key: Pubkey::new_unique(), account: fund_state.build_account(), params: None },
+        &KeyedAccount { key: SymmetryTokenSwap::TOKEN_LIST_ADDRESS, account: TokenListBuilder::new().build_account(), params: None },
+    ).unwrap();
+
+    let mint = Pubkey::new_unique();
+    let fee_accounts = amm.swap_fee_token_accounts(&mint);
+    assert_eq!(fee_accounts.len(), 2);
+    assert_eq!(fee_accounts[1].address, associated_token_address(&wallet, &mint));
+
+    let mut account_map = AccountMap::new();
+    account_map.insert(fee_accounts[0].address, Account {
+        owner: SymmetryTokenSwap::SPL_TOKEN_PROGRAM_ADDRESS,
+        data: vec![0; 165],
+        ..Account::default()
+    });
+    assert_eq!(amm.missing_swap_fee_accounts(&mint, &account_map), vec![fee_accounts[1]]);
+
+    let payer = Pubkey::new_unique();
+    let instructions = amm.create_swap_fee_account_instructions(&payer, &mint, &account_map);
+    assert_eq!(instructions, vec![create_associated_token_account_idempotent(&payer, &wallet, &mint)]);
+}
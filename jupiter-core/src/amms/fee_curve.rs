@@ -0,0 +1,87 @@
+use anyhow::Result;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::amms::accounts::BPS_DIVIDER;
+use crate::amms::quoter::Quoter;
+use crate::amms::symmetry_token_swap::SymmetryTokenSwap;
+use crate::error::SymmetryError;
+
+/// One sample of a pair's fee curve.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct FeeCurvePoint {
+    pub in_amount: u64,
+    pub out_amount: u64,
+    /// Fees charged by the quote, in bps of the fair (avg price) output.
+    pub effective_fee_bps: u64,
+    /// Shortfall of `out_amount` versus the fair output, in bps. Includes fees as well as
+    /// the oracle confidence spread and curve pricing.
+    pub price_impact_bps: u64,
+}
+
+impl SymmetryTokenSwap {
+    /// Samples `points` evenly spaced input amounts up to `max_amount`. Sampling stops at the
+    /// first amount that can't be quoted (e.g. a weight limit), so the last point is the
+    /// largest quotable sample.
+    pub fn fee_curve(&self, input_mint: &Pubkey, output_mint: &Pubkey, max_amount: u64, points: usize) -> Result<Vec<FeeCurvePoint>> {
+        if points == 0 || max_amount == 0 {
+            return Err(SymmetryError::ZeroAmount.into());
+        }
+        let mut curve = Vec::with_capacity(points);
+        let quoter = match Quoter::for_pair(self, input_mint, output_mint) {
+            Ok(quoter) => quoter,
+            Err(_) => return Ok(curve),
+        };
+        for i in 1..=points as u64 {
+            let in_amount = SymmetryTokenSwap::mul_div(max_amount, i, points as u64);
+            if in_amount == 0 {
+                continue;
+            }
+            let quote = match quoter.quote_amount(in_amount) {
+                Ok(quote) => quote,
+                Err(_) => break,
+            };
+            let fair_amount = self.fair_out_amount(input_mint, output_mint, in_amount)
+                .ok_or_else(|| self.token_not_in_fund(input_mint, output_mint))?;
+            curve.push(FeeCurvePoint {
+                in_amount,
+                out_amount: quote.out_amount,
+                effective_fee_bps: SymmetryTokenSwap::mul_div(quote.fee_amount, BPS_DIVIDER, fair_amount),
+                price_impact_bps: SymmetryTokenSwap::mul_div(fair_amount.saturating_sub(quote.out_amount), BPS_DIVIDER, fair_amount),
+            });
+        }
+        Ok(curve)
+    }
+}
+
+#[test]
+fn test_fee_curve() {
+    use jupiter_amm_interface::{Amm, QuoteParams};
+
+    use crate::amms::account_builders::{quotable_fund, FixtureToken, FundStateBuilder};
+    use crate::amms::accounts::WEIGHT_MULTIPLIER;
+
+    let tokens = [FixtureToken::new(6, 100_000_000), FixtureToken::new(9, 10_000_000_000)];
+    let (usdc, sol) = (tokens[0].mint, tokens[1].mint);
+    // $10,000 of USDC and 100 SOL at $100, each allowed 2.5 points over its 50% target.
+    let fund_state = FundStateBuilder::new()
+        .with_token(0, 10_000_000_000, WEIGHT_MULTIPLIER / 2)
+        .with_token(1, 100_000_000_000, WEIGHT_MULTIPLIER / 2)
+        .with_weight_band(1_000, 5_000);
+    let fund = quotable_fund(Pubkey::new_unique(), &fund_state, &tokens);
+    let quote = |in_amount: u64| fund.quote(&QuoteParams { in_amount, input_mint: usdc, output_mint: sol });
+
+    // Samples every $200 up to $1,000; USDC crosses its band a little under $500.
+    let curve = fund.fee_curve(&usdc, &sol, 1_000_000_000, 5).unwrap();
+    assert_eq!(curve.iter().map(|point| point.in_amount).collect::<Vec<_>>(), vec![200_000_000, 400_000_000]);
+    assert!(quote(600_000_000).is_err());
+    // Both legs move away from their targets and pay 30 bps: 1 - 0.997^2 = 59.91 bps.
+    for point in &curve {
+        assert_eq!((point.effective_fee_bps, point.price_impact_bps), (59, 59));
+        assert_eq!(point.out_amount, quote(point.in_amount).unwrap().out_amount);
+    }
+
+    assert!(fund.fee_curve(&usdc, &sol, 0, 5).is_err());
+    assert!(fund.fee_curve(&usdc, &sol, 1_000_000_000, 0).is_err());
+    assert!(fund.fee_curve(&usdc, &Pubkey::new_unique(), 1_000_000_000, 5).unwrap().is_empty());
+}
@@ -0,0 +1,76 @@
+use anyhow::Result;
+
+use crate::amms::accounts::{mul_div, TokenList, TokenSettings};
+use crate::error::SymmetryError;
+
+/// How a swap's total fee is divided. Everything but `fund_fee` leaves the fund.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct FeeSplit {
+    pub symmetry_fee: u64,
+    pub host_fee: u64,
+    pub manager_fee: u64,
+    /// Share kept by the fund's LPs.
+    pub fund_fee: u64,
+}
+
+impl FeeSplit {
+    pub fn leaving_fund(&self) -> u64 {
+        self.symmetry_fee.saturating_add(self.host_fee).saturating_add(self.manager_fee)
+    }
+}
+
+/// Splits swap fees between Symmetry, the host, the manager and the fund. Implement it to
+/// simulate fee changes; `OnChainFeeModel` matches the deployed program.
+pub trait FeeModel: Send + Sync {
+    fn split_fees(&self, total_fees: u64, token_list: &TokenList) -> Result<FeeSplit>;
+}
+
+/// The program's fee split: percentages of the total fee stored in the `AdditionalData`
+/// of token list entry 0.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct OnChainFeeModel;
+
+impl OnChainFeeModel {
+    /// Splits `total_fees` by the percentages in `fee_settings`, token list entry 0. Fails if
+    /// the percentages add up to more than 100.
+    pub fn split_with_settings(total_fees: u64, fee_settings: &TokenSettings) -> Result<FeeSplit> {
+        let fee_split = fee_settings.additional_data();
+        let symmetry_fee = mul_div(total_fees, fee_split.symmetry_fee_pct as u64, 100);
+        let host_fee = mul_div(total_fees, fee_split.host_fee_pct as u64, 100);
+        let manager_fee = mul_div(total_fees, fee_split.manager_fee_pct as u64, 100);
+        let fund_fee = total_fees
+            .checked_sub(symmetry_fee)
+            .and_then(|fee| fee.checked_sub(host_fee))
+            .and_then(|fee| fee.checked_sub(manager_fee))
+            .ok_or(SymmetryError::Arithmetic("Fee split exceeds the total fee"))?;
+        Ok(FeeSplit {
+            symmetry_fee,
+            host_fee,
+            manager_fee,
+            fund_fee,
+        })
+    }
+}
+
+impl FeeModel for OnChainFeeModel {
+    fn split_fees(&self, total_fees: u64, token_list: &TokenList) -> Result<FeeSplit> {
+        OnChainFeeModel::split_with_settings(total_fees, &token_list.list[0])
+    }
+}
+
+#[test]
+fn test_on_chain_fee_split() {
+    let mut fee_settings = TokenSettings::empty();
+    fee_settings.additional_data[60..63].copy_from_slice(&[20, 10, 30]);
+    let split = OnChainFeeModel::split_with_settings(1_000, &fee_settings).unwrap();
+    assert_eq!(split, FeeSplit { symmetry_fee: 200, host_fee: 100, manager_fee: 300, fund_fee: 400 });
+    assert_eq!(split.leaving_fund(), 600);
+
+    // Percentages past 100 would leave the fund a negative share.
+    fee_settings.additional_data[60..63].copy_from_slice(&[60, 30, 20]);
+    assert_eq!(
+        OnChainFeeModel::split_with_settings(1_000, &fee_settings).unwrap_err().downcast_ref::<SymmetryError>(),
+        Some(&SymmetryError::Arithmetic("Fee split exceeds the total fee"))
+    );
+}
@@ -0,0 +1,78 @@
+use anyhow::{Error, Result};
+use solana_sdk::{pubkey, pubkey::Pubkey};
+use std::convert::TryInto;
+
+use crate::amms::symmetry_token_swap::SymmetryTokenSwap;
+
+pub const TOKEN_METADATA_PROGRAM_ID: Pubkey = pubkey!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+
+/// `Key::MetadataV1`, the first byte of a Metaplex metadata account.
+const METADATA_V1_KEY: u8 = 4;
+/// key (1) + update authority (32) + mint (32).
+const METADATA_NAME_OFFSET: usize = 65;
+
+/// Name and symbol of a fund's share token, from its Metaplex metadata account.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct FundMetadata {
+    pub name: String,
+    pub symbol: String,
+}
+
+/// Metaplex metadata PDA of `mint`.
+pub fn metadata_address(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"metadata", TOKEN_METADATA_PROGRAM_ID.as_ref(), mint.as_ref()],
+        &TOKEN_METADATA_PROGRAM_ID,
+    ).0
+}
+
+/// Reads a borsh string at `offset`, returning it without the NUL padding Metaplex adds,
+/// and the offset after it.
+fn read_padded_string(data: &[u8], offset: usize) -> Result<(String, usize)> {
+    let len = data.get(offset..offset + 4)
+        .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()) as usize)
+        .ok_or_else(|| Error::msg("Metadata account too short"))?;
+    let bytes = data.get(offset + 4..offset + 4 + len)
+        .ok_or_else(|| Error::msg("Metadata account too short"))?;
+    let value = String::from_utf8_lossy(bytes).trim_end_matches('\0').trim().to_string();
+    Ok((value, offset + 4 + len))
+}
+
+impl FundMetadata {
+    pub fn parse(account_data: &[u8]) -> Result<FundMetadata> {
+        if account_data.first() != Some(&METADATA_V1_KEY) {
+            return Err(Error::msg("Not a token metadata account"));
+        }
+        let (name, offset) = read_padded_string(account_data, METADATA_NAME_OFFSET)?;
+        let (symbol, _) = read_padded_string(account_data, offset)?;
+        Ok(FundMetadata { name, symbol })
+    }
+}
+
+impl SymmetryTokenSwap {
+    /// Metadata account of the fund's share token, holding the fund's name and symbol.
+    pub fn fund_metadata_address(&self) -> Pubkey {
+        metadata_address(&self.fund_state().fund_token)
+    }
+}
+
+#[test]
+fn test_fund_metadata_parse() {
+    let mut data = vec![METADATA_V1_KEY];
+    data.extend_from_slice(&[0u8; 64]);
+    let mut name = b"Symmetry SOL Index".to_vec();
+    name.resize(32, 0);
+    data.extend_from_slice(&32u32.to_le_bytes());
+    data.extend_from_slice(&name);
+    let mut symbol = b"SOLI".to_vec();
+    symbol.resize(10, 0);
+    data.extend_from_slice(&10u32.to_le_bytes());
+    data.extend_from_slice(&symbol);
+
+    let metadata = FundMetadata::parse(&data).unwrap();
+    assert_eq!(metadata.name, "Symmetry SOL Index");
+    assert_eq!(metadata.symbol, "SOLI");
+    assert!(FundMetadata::parse(&data[..80]).is_err());
+    assert!(FundMetadata::parse(&[0u8; 100]).is_err());
+}
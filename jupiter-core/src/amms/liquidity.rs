@@ -0,0 +1,116 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::amms::quoter::Quoter;
+use crate::amms::symmetry_token_swap::SymmetryTokenSwap;
+
+/// What `quote()` does when the output before fees exceeds the fund's holding of the
+/// output token, where the swap can only be partly filled.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LiquidityPolicy {
+    /// Cap the output at the holding and set `Quote::not_enough_liquidity`.
+    #[default]
+    Flag,
+    /// Fail quotes with `SymmetryError::NotEnoughLiquidity`.
+    Reject,
+}
+
+/// How large a swap of one pair a fund can take, for sizing orders against it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct PairLiquiditySummary {
+    /// The fund's holding of the output token.
+    pub output_reserve: u64,
+    /// Largest in amount whose output before fees fits in `output_reserve`. Larger swaps are
+    /// capped (or rejected under `LiquidityPolicy::Reject`).
+    pub max_in_before_clamp: u64,
+    /// Quoted output at `max_in_before_weight_violation`, the most one uncapped swap returns.
+    pub max_out_before_clamp: u64,
+    /// Largest in amount, up to `max_in_before_clamp`, that passes the weight checks.
+    pub max_in_before_weight_violation: u64,
+}
+
+/// Largest amount in `0..=upper` satisfying `passes`, which must hold for a prefix of the
+/// range. Zero if nothing passes.
+pub(crate) fn largest_passing(upper: u64, passes: impl Fn(u64) -> bool) -> u64 {
+    let (mut low, mut high) = (0u64, upper);
+    while low < high {
+        let mid = low + (high - low).div_ceil(2);
+        if passes(mid) {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+    low
+}
+
+impl Quoter<'_> {
+    /// Largest in amount whose output before fees fits in the fund's holding of the output
+    /// token. Larger swaps are capped (or rejected under `LiquidityPolicy::Reject`).
+    pub fn max_in_before_clamp(&self) -> u64 {
+        let from = self.setup().from_token_settings;
+        let to = self.setup().to_token_settings;
+        let output_reserve = self.fund().fund_state().current_comp_amount[self.setup().to_token_index];
+
+        // Bisect below twice the price-implied estimate: the USD conversions return 0 on
+        // overflow, so the pre-fee output stops growing with the in amount far above it.
+        let estimate = SymmetryTokenSwap::usd_value_to_amount(
+            SymmetryTokenSwap::amount_to_usd_value(output_reserve.saturating_add(1), to.decimals, to.oracle_price.buy_price),
+            from.decimals,
+            from.oracle_price.sell_price,
+        );
+        largest_passing(estimate.saturating_mul(2).saturating_add(1), |in_amount| {
+            self.pre_fee_out_amount(in_amount) <= output_reserve
+        })
+    }
+
+    /// Largest in amount, up to `max_in_before_clamp()`, that quotes without hitting the
+    /// weight limits. Assumes the checks pass for every amount below the first one that fails.
+    pub fn max_in_amount(&self) -> u64 {
+        self.max_in_before_weight_violation(self.max_in_before_clamp())
+    }
+
+    fn max_in_before_weight_violation(&self, max_in_before_clamp: u64) -> u64 {
+        largest_passing(max_in_before_clamp, |in_amount| {
+            in_amount == 0 || self.quote_amount(in_amount).is_ok()
+        })
+    }
+}
+
+impl SymmetryTokenSwap {
+    /// Reserve and size limits of `input_mint` -> `output_mint`, found by bisecting quotes.
+    /// Assumes the weight checks pass for every amount below the first one that fails.
+    pub fn pair_liquidity(&self, input_mint: &Pubkey, output_mint: &Pubkey) -> Result<PairLiquiditySummary> {
+        let quoter = Quoter::for_pair(self, input_mint, output_mint)?;
+        let output_reserve = self.fund_state().current_comp_amount[quoter.setup().to_token_index];
+        let max_in_before_clamp = quoter.max_in_before_clamp();
+        let max_in_before_weight_violation = quoter.max_in_before_weight_violation(max_in_before_clamp);
+        let max_out_before_clamp = match max_in_before_weight_violation {
+            0 => 0,
+            in_amount => quoter.quote_amount(in_amount)?.out_amount,
+        };
+        Ok(PairLiquiditySummary {
+            output_reserve,
+            max_in_before_clamp,
+            max_out_before_clamp,
+            max_in_before_weight_violation,
+        })
+    }
+
+    /// Largest in amount of `input_mint` -> `output_mint` that still quotes, bisecting
+    /// against the weight limits. Zero if no amount clears them.
+    pub fn max_in_amount(&self, input_mint: &Pubkey, output_mint: &Pubkey) -> Result<u64> {
+        Ok(Quoter::for_pair(self, input_mint, output_mint)?.max_in_amount())
+    }
+}
+
+#[test]
+fn test_largest_passing() {
+    assert_eq!(largest_passing(1_000, |amount| amount <= 437), 437);
+    assert_eq!(largest_passing(1_000, |amount| amount <= 5_000), 1_000);
+    assert_eq!(largest_passing(1_000, |_| false), 0);
+    assert_eq!(largest_passing(u64::MAX, |amount| amount < u64::MAX), u64::MAX - 1);
+}
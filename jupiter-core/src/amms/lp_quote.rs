@@ -0,0 +1,266 @@
+//! Quotes for minting fund tokens with a deposit and for burning them in a withdrawal.
+
+use anyhow::Result;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::amms::accounts::BPS_DIVIDER;
+use crate::amms::symmetry_token_swap::SymmetryTokenSwap;
+use crate::error::SymmetryError;
+
+/// Result of quoting a deposit into the fund. USD values use `ONE_USD` precision.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct DepositQuote {
+    /// Value of the deposit at oracle avg prices.
+    pub fair_value: u64,
+    /// Value credited to the depositor after spread, curve and target-weight fees.
+    pub deposit_value: u64,
+    /// `fair_value - deposit_value`.
+    pub swap_fee_value: u64,
+    /// Fund tokens minted before deposit fees.
+    pub gross_fund_tokens: u64,
+    pub manager_fee: u64,
+    pub host_fee: u64,
+    /// Fund tokens received by the depositor.
+    pub fund_tokens_out: u64,
+    /// Fund worth the deposit was priced against.
+    pub fund_worth: u64,
+}
+
+/// What a withdrawing LP receives.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WithdrawOutput {
+    /// A pro-rata share of every fund token.
+    Basket,
+    /// A single fund token, after the rest of the share is swapped into it inside the fund.
+    SingleMint(Pubkey),
+}
+
+/// Result of quoting a withdrawal. USD values use `ONE_USD` precision.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct WithdrawQuote {
+    pub fund_tokens: u64,
+    /// Token amounts received, per mint.
+    pub amounts: Vec<(Pubkey, u64)>,
+    /// Value of the burned share of the fund at oracle avg prices.
+    pub fair_value: u64,
+    /// Value of `amounts` at oracle avg prices.
+    pub received_value: u64,
+    /// `fair_value - received_value`: spread and fees of the internal swaps.
+    pub fee_value: u64,
+}
+
+impl SymmetryTokenSwap {
+    /// Fund tokens minted for depositing `amount` of a single fund token at the current NAV.
+    pub fn quote_deposit(&self, mint: &Pubkey, amount: u64) -> Result<DepositQuote> {
+        self.quote_basket_deposit(&[(*mint, amount)])
+    }
+
+    /// Fund tokens minted for depositing several fund tokens at once. Each token is valued
+    /// like the sell leg of a swap: at its sell price along the sell curve, with
+    /// `token_swap_fee_before_tw_bps` below target weight and `after_tw_bps` above it.
+    /// The manager and host deposit fees are then taken from the minted fund tokens.
+    pub fn quote_basket_deposit(&self, amounts: &[(Pubkey, u64)]) -> Result<DepositQuote> {
+        let fund_state = self.fund_state();
+        if fund_state.supply_outstanding == 0 {
+            return Err(SymmetryError::NoFundTokensOutstanding.into());
+        }
+        let fund_worth = self.fund_worth()?;
+        if fund_worth == 0 {
+            return Err(SymmetryError::ZeroFundWorth.into());
+        }
+
+        let mut quote = DepositQuote { fund_worth, ..DepositQuote::default() };
+        for (mint, amount) in amounts {
+            let index = self.composition_index(mint)
+                .ok_or(SymmetryError::TokenNotInFund { mint: *mint })?;
+            let token_id = fund_state.current_comp_token[index] as usize;
+            let settings = self.token_list().list[token_id];
+            quote.fair_value = quote.fair_value
+                .checked_add(SymmetryTokenSwap::amount_to_usd_value(*amount, settings.decimals, settings.oracle_price.avg_price))
+                .ok_or(SymmetryError::Arithmetic("Deposit value overflows u64"))?;
+            quote.deposit_value = quote.deposit_value
+                .checked_add(SymmetryTokenSwap::compute_value_of_sold_token(
+                    *amount,
+                    settings,
+                    settings.oracle_price,
+                    fund_state.current_comp_amount[index],
+                    self.target_amount(mint)?,
+                    self.curve_data().sell[token_id],
+                ))
+                .ok_or(SymmetryError::Arithmetic("Deposit value overflows u64"))?;
+        }
+        quote.swap_fee_value = quote.fair_value.saturating_sub(quote.deposit_value);
+        quote.gross_fund_tokens = SymmetryTokenSwap::mul_div(quote.deposit_value, fund_state.supply_outstanding, fund_worth);
+        quote.manager_fee = SymmetryTokenSwap::mul_div(quote.gross_fund_tokens, fund_state.manager_fee, BPS_DIVIDER);
+        quote.host_fee = SymmetryTokenSwap::mul_div(quote.gross_fund_tokens, fund_state.host_fee, BPS_DIVIDER);
+        quote.fund_tokens_out = quote.manager_fee
+            .checked_add(quote.host_fee)
+            .and_then(|fees| quote.gross_fund_tokens.checked_sub(fees))
+            .ok_or(SymmetryError::Arithmetic("Deposit fees exceed the minted amount"))?;
+        Ok(quote)
+    }
+
+    /// Assets received for burning `fund_tokens`. A basket withdrawal returns the pro-rata
+    /// share of each token. A single-mint withdrawal values every other token's share like
+    /// the sell leg of a swap and buys the output token with it along its buy curve,
+    /// capped at what the fund holds.
+    pub fn quote_withdraw(&self, fund_tokens: u64, output: WithdrawOutput) -> Result<WithdrawQuote> {
+        let fund_state = self.fund_state();
+        if fund_state.supply_outstanding == 0 {
+            return Err(SymmetryError::NoFundTokensOutstanding.into());
+        }
+        if fund_tokens > fund_state.supply_outstanding {
+            return Err(SymmetryError::WithdrawExceedsSupply {
+                fund_tokens,
+                supply_outstanding: fund_state.supply_outstanding,
+            }.into());
+        }
+        let avg_value = |token_id: usize, amount: u64| {
+            let settings = self.token_list().list[token_id];
+            SymmetryTokenSwap::amount_to_usd_value(amount, settings.decimals, settings.oracle_price.avg_price)
+        };
+
+        let mut basket = Vec::with_capacity(fund_state.num_tokens());
+        let mut fair_value: u64 = 0;
+        for i in 0..fund_state.num_tokens() {
+            let token_id = fund_state.current_comp_token[i] as usize;
+            let amount = SymmetryTokenSwap::mul_div(fund_state.current_comp_amount[i], fund_tokens, fund_state.supply_outstanding);
+            fair_value = fair_value.checked_add(avg_value(token_id, amount))
+                .ok_or(SymmetryError::Arithmetic("Withdraw value overflows u64"))?;
+            basket.push((i, token_id, amount));
+        }
+
+        let amounts: Vec<(Pubkey, u64)> = match output {
+            WithdrawOutput::Basket => basket
+                .iter()
+                .map(|&(_, token_id, amount)| (self.token_list().list[token_id].token_mint, amount))
+                .collect(),
+            WithdrawOutput::SingleMint(mint) => {
+                let out_index = self.composition_index(&mint)
+                    .ok_or(SymmetryError::TokenNotInFund { mint })?;
+                let out_token_id = fund_state.current_comp_token[out_index] as usize;
+                let out_settings = self.token_list().list[out_token_id];
+                let mut value: u64 = 0;
+                for &(i, token_id, amount) in basket.iter().filter(|(i, _, _)| *i != out_index) {
+                    let settings = self.token_list().list[token_id];
+                    value = value
+                        .checked_add(SymmetryTokenSwap::compute_value_of_sold_token(
+                            amount,
+                            settings,
+                            settings.oracle_price,
+                            fund_state.current_comp_amount[i],
+                            self.target_amount(&settings.token_mint)?,
+                            self.curve_data().sell[token_id],
+                        ))
+                        .ok_or(SymmetryError::Arithmetic("Withdraw value overflows u64"))?;
+                }
+                let pro_rata_out = basket[out_index].2;
+                let remaining_out = fund_state.current_comp_amount[out_index] - pro_rata_out;
+                let bought = SymmetryTokenSwap::compute_amount_of_bought_token(
+                    value,
+                    out_settings,
+                    out_settings.oracle_price,
+                    remaining_out,
+                    self.target_amount(&mint)?,
+                    self.curve_data().buy[out_token_id],
+                );
+                vec![(mint, pro_rata_out + bought.min(remaining_out))]
+            }
+        };
+
+        let received_value = amounts
+            .iter()
+            .try_fold(0u64, |total, (mint, amount)| {
+                let index = self.composition_index(mint).unwrap_or_default();
+                total.checked_add(avg_value(fund_state.current_comp_token[index] as usize, *amount))
+            })
+            .ok_or(SymmetryError::Arithmetic("Withdraw value overflows u64"))?;
+        Ok(WithdrawQuote {
+            fund_tokens,
+            amounts,
+            fair_value,
+            received_value,
+            fee_value: fair_value.saturating_sub(received_value),
+        })
+    }
+}
+
+#[test]
+fn test_quote_deposit() {
+    use crate::amms::account_builders::{quotable_fund, FixtureToken, FundStateBuilder};
+    use crate::amms::accounts::{ONE_USD, WEIGHT_MULTIPLIER};
+
+    // $10,000 of USDC and 100 SOL at $100 backing 20,000 fund tokens, with 1% manager and
+    // 0.5% host deposit fees.
+    let tokens = [FixtureToken::new(6, 100_000_000), FixtureToken::new(9, 10_000_000_000)];
+    let fund_state = FundStateBuilder::new()
+        .with_token(0, 10_000_000_000, WEIGHT_MULTIPLIER / 2)
+        .with_token(1, 100_000_000_000, WEIGHT_MULTIPLIER / 2)
+        .with_weight_band(1_000, 5_000)
+        .with_supply_outstanding(20_000_000_000)
+        .with_deposit_fees(100, 50);
+    let fund = quotable_fund(Pubkey::new_unique(), &fund_state, &tokens);
+    let (usdc, sol) = (tokens[0].mint, tokens[1].mint);
+
+    let quote = fund.quote_deposit(&usdc, 100_000_000).unwrap();
+    assert_eq!(quote.fund_worth, 20_000 * ONE_USD);
+    assert_eq!(quote.fair_value, 100 * ONE_USD);
+    assert!(quote.deposit_value < quote.fair_value && quote.deposit_value > 99 * ONE_USD);
+    assert_eq!(quote.swap_fee_value, quote.fair_value - quote.deposit_value);
+    assert_eq!(quote.gross_fund_tokens, SymmetryTokenSwap::mul_div(quote.deposit_value, 20_000_000_000, 20_000 * ONE_USD));
+    assert_eq!(quote.manager_fee, quote.gross_fund_tokens / 100);
+    assert_eq!(quote.host_fee, quote.gross_fund_tokens / 200);
+    assert_eq!(quote.fund_tokens_out, quote.gross_fund_tokens - quote.manager_fee - quote.host_fee);
+
+    let basket = fund.quote_basket_deposit(&[(usdc, 100_000_000), (sol, 1_000_000_000)]).unwrap();
+    assert_eq!(basket.fair_value, 200 * ONE_USD);
+
+    // Each leg is worth $10M (10^19 in ONE_USD precision); together they overflow u64.
+    let overflow = fund.quote_basket_deposit(&[(usdc, 10_000_000_000_000), (usdc, 10_000_000_000_000)]).unwrap_err();
+    assert!(matches!(overflow.downcast_ref::<SymmetryError>(), Some(SymmetryError::Arithmetic(_))));
+    let unheld = Pubkey::new_unique();
+    assert_eq!(
+        fund.quote_deposit(&unheld, 1).unwrap_err().downcast_ref::<SymmetryError>(),
+        Some(&SymmetryError::TokenNotInFund { mint: unheld })
+    );
+}
+
+#[test]
+fn test_quote_withdraw() {
+    use crate::amms::account_builders::{quotable_fund, FixtureToken, FundStateBuilder};
+    use crate::amms::accounts::{ONE_USD, WEIGHT_MULTIPLIER};
+
+    let tokens = [FixtureToken::new(6, 100_000_000), FixtureToken::new(9, 10_000_000_000)];
+    let fund_state = FundStateBuilder::new()
+        .with_token(0, 10_000_000_000, WEIGHT_MULTIPLIER / 2)
+        .with_token(1, 100_000_000_000, WEIGHT_MULTIPLIER / 2)
+        .with_weight_band(1_000, 5_000)
+        .with_supply_outstanding(20_000_000_000);
+    let fund = quotable_fund(Pubkey::new_unique(), &fund_state, &tokens);
+    let (usdc, sol) = (tokens[0].mint, tokens[1].mint);
+
+    // 1% of the supply: $100 of USDC and 1 SOL.
+    let basket = fund.quote_withdraw(200_000_000, WithdrawOutput::Basket).unwrap();
+    assert_eq!(basket.amounts, vec![(usdc, 100_000_000), (sol, 1_000_000_000)]);
+    assert_eq!(basket.fair_value, 200 * ONE_USD);
+    assert_eq!(basket.received_value, 200 * ONE_USD);
+    assert_eq!(basket.fee_value, 0);
+
+    // The SOL share is sold into USDC inside the fund, at a spread.
+    let single = fund.quote_withdraw(200_000_000, WithdrawOutput::SingleMint(usdc)).unwrap();
+    assert_eq!(single.amounts.len(), 1);
+    assert!(single.amounts[0].1 > 199_000_000 && single.amounts[0].1 < 200_000_000);
+    assert_eq!(single.fee_value, single.fair_value - single.received_value);
+
+    assert_eq!(
+        fund.quote_withdraw(20_000_000_001, WithdrawOutput::Basket).unwrap_err().downcast_ref::<SymmetryError>(),
+        Some(&SymmetryError::WithdrawExceedsSupply { fund_tokens: 20_000_000_001, supply_outstanding: 20_000_000_000 })
+    );
+    let unheld = Pubkey::new_unique();
+    assert_eq!(
+        fund.quote_withdraw(1, WithdrawOutput::SingleMint(unheld)).unwrap_err().downcast_ref::<SymmetryError>(),
+        Some(&SymmetryError::TokenNotInFund { mint: unheld })
+    );
+}
@@ -0,0 +1,101 @@
+//! Manager deposit fees still held as fund tokens.
+//!
+//! `FundState::manager_fee` (bytes 80..88 of the fund account) is the manager's cut of each
+//! deposit in bps. The Symmetry program takes it when a deposit mints fund tokens: the
+//! manager's share is minted as fund tokens to the manager's associated token account for
+//! `FundState::fund_token`, next to the depositor's. Nothing in the fund account records
+//! the fees accrued so far, so the accrual below reads that token account's balance. It
+//! counts any fund tokens the manager holds there, including their own deposits, and
+//! misses fees the manager has already moved or redeemed.
+
+use anyhow::{Error, Result};
+use solana_sdk::program_pack::Pack;
+use solana_sdk::pubkey::Pubkey;
+use spl_token::state::Account as TokenAccount;
+
+use crate::amms::accounts::BPS_DIVIDER;
+use crate::amms::symmetry_token_swap::SymmetryTokenSwap;
+use crate::pda::manager_fee_ata;
+
+/// Manager fees held in the manager's fund token account, not yet redeemed for the
+/// underlying tokens. USD values use `ONE_USD` precision.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ManagerFeeAccrual {
+    pub fee_account: Pubkey,
+    /// Manager deposit fee, in bps.
+    pub manager_fee_bps: u64,
+    pub fund_tokens: u64,
+    /// `fund_tokens` at the fund's current NAV.
+    pub usd_value: u64,
+    /// `fund_tokens` as a share of the outstanding supply, in bps.
+    pub share_of_supply_bps: u64,
+}
+
+impl SymmetryTokenSwap {
+    /// The manager's fund token ATA, which deposit fees are minted to as fund tokens.
+    pub fn manager_fee_account(&self) -> Pubkey {
+        manager_fee_ata(&self.fund_state().manager, &self.fund_state().fund_token)
+    }
+
+    /// Values the fund tokens in the manager's fee account (`fee_account_data`, the account
+    /// at `manager_fee_account()`) against the fund's current worth and supply.
+    pub fn manager_fee_accrual(&self, fee_account_data: &[u8]) -> Result<ManagerFeeAccrual> {
+        let fund_state = self.fund_state();
+        let fee_account = TokenAccount::unpack(fee_account_data)?;
+        if fee_account.mint != fund_state.fund_token {
+            return Err(Error::msg("Fee account does not hold the fund token"));
+        }
+        if fee_account.owner != fund_state.manager {
+            return Err(Error::msg("Fee account is not owned by the fund manager"));
+        }
+        Ok(ManagerFeeAccrual {
+            fee_account: self.manager_fee_account(),
+            manager_fee_bps: fund_state.manager_fee,
+            fund_tokens: fee_account.amount,
+            usd_value: SymmetryTokenSwap::mul_div(fee_account.amount, self.fund_worth()?, fund_state.supply_outstanding),
+            share_of_supply_bps: SymmetryTokenSwap::mul_div(fee_account.amount, BPS_DIVIDER, fund_state.supply_outstanding),
+        })
+    }
+}
+
+#[test]
+fn test_manager_fee_accrual() {
+    use spl_token::state::AccountState;
+
+    use crate::amms::account_builders::{quotable_fund, FixtureToken, FundStateBuilder};
+    use crate::amms::accounts::{ONE_USD, WEIGHT_MULTIPLIER};
+
+    let manager = Pubkey::new_unique();
+    let fund_token = Pubkey::new_unique();
+    // $20,000 of assets against 20,000 fund tokens of 6 decimals, 0.5% manager deposit fee.
+    let fund_state = FundStateBuilder::new()
+        .with_manager(manager)
+        .with_fund_token(fund_token)
+        .with_supply_outstanding(20_000_000_000)
+        .with_deposit_fees(50, 0)
+        .with_token(0, 10_000_000_000, WEIGHT_MULTIPLIER / 2)
+        .with_token(1, 100_000_000_000, WEIGHT_MULTIPLIER / 2);
+    let fund = quotable_fund(
+        Pubkey::new_unique(),
+        &fund_state,
+        &[FixtureToken::new(6, 100_000_000), FixtureToken::new(9, 10_000_000_000)],
+    );
+    assert_eq!(fund.manager_fee_account(), manager_fee_ata(&manager, &fund_token));
+
+    let token_account = |mint: Pubkey, owner: Pubkey, amount: u64| {
+        let mut data = vec![0u8; TokenAccount::LEN];
+        TokenAccount::pack(TokenAccount { mint, owner, amount, state: AccountState::Initialized, ..TokenAccount::default() }, &mut data).unwrap();
+        data
+    };
+    // 100 fund tokens of fees: 0.5% of the supply, worth $100.
+    let accrual = fund.manager_fee_accrual(&token_account(fund_token, manager, 100_000_000)).unwrap();
+    assert_eq!(accrual.fee_account, fund.manager_fee_account());
+    assert_eq!(accrual.manager_fee_bps, 50);
+    assert_eq!(accrual.fund_tokens, 100_000_000);
+    assert_eq!(accrual.usd_value, 100 * ONE_USD);
+    assert_eq!(accrual.share_of_supply_bps, 50);
+
+    assert!(fund.manager_fee_accrual(&token_account(Pubkey::new_unique(), manager, 1)).is_err());
+    assert!(fund.manager_fee_accrual(&token_account(fund_token, Pubkey::new_unique(), 1)).is_err());
+}
@@ -1,7 +1,41 @@
-mod amm;
-mod spl_token_swap_amm;
-mod symmetry_token_swap;
-mod accounts;
+pub mod account_owners;
+#[cfg(any(test, feature = "test-fixtures"))]
+pub mod account_builders;
+pub mod account_views;
+pub mod amm;
+pub mod amm_config;
+pub mod spl_token_swap_amm;
+pub mod symmetry_config;
+pub mod symmetry_token_swap;
+pub mod accounts;
+pub mod compact_fund;
+pub mod consistency;
+pub mod denomination;
+pub mod exact_out;
+pub mod fee_accounts;
+pub mod fee_curve;
+pub mod fee_model;
+pub mod fund_metadata;
+pub mod liquidity;
+pub mod lp_quote;
+pub mod manager_fees;
+pub mod nav;
+pub mod oracle_fallback;
+pub mod oracle_type;
+pub mod order_sizing;
+pub mod partial_fill;
+pub mod price_impact;
+pub mod pyth_pull;
+pub mod quote_details;
+pub mod quote_fees;
+pub(crate) mod quote_source;
+pub mod quoter;
+pub mod rebalance_cost;
+pub mod shared_accounts;
+pub mod switchboard;
+pub mod target_weight;
 
 #[cfg(test)]
+mod curve_properties;
+#[cfg(all(test, feature = "rpc"))]
 mod test_harness;
@@ -0,0 +1,37 @@
+use anyhow::Result;
+use rust_decimal::Decimal;
+
+use crate::amms::accounts::ONE_USD_DECIMALS;
+use crate::amms::symmetry_token_swap::SymmetryTokenSwap;
+use crate::error::SymmetryError;
+
+/// USD value of one whole fund token given the fund's worth (in `ONE_USD` precision) and
+/// the fund token supply in base units.
+pub fn nav_per_share(fund_worth: u64, supply: u64, decimals: u8) -> Result<Decimal> {
+    if supply == 0 {
+        return Err(SymmetryError::NoFundTokensOutstanding.into());
+    }
+    let worth = Decimal::from_i128_with_scale(fund_worth as i128, ONE_USD_DECIMALS);
+    let shares = Decimal::from_i128_with_scale(supply as i128, decimals as u32);
+    Ok(worth.checked_div(shares).ok_or(SymmetryError::Arithmetic("NAV per share overflows"))?)
+}
+
+impl SymmetryTokenSwap {
+    /// USD value of one fund token at the oracle average prices, against the supply of the
+    /// fund token mint loaded by the last update.
+    pub fn nav_per_share(&self) -> Result<Decimal> {
+        let mint = self.fund_token_mint().ok_or(SymmetryError::FundTokenMintNotLoaded)?;
+        nav_per_share(self.fund_worth()?, mint.supply, mint.decimals)
+    }
+}
+
+#[test]
+fn test_nav_per_share() {
+    // $1,500 of assets against 1,000 fund tokens of 6 decimals.
+    let nav = nav_per_share(1_500 * crate::amms::accounts::ONE_USD, 1_000_000_000, 6).unwrap();
+    assert_eq!(nav, Decimal::new(15, 1));
+    assert_eq!(
+        nav_per_share(1, 0, 6).unwrap_err().downcast_ref::<SymmetryError>(),
+        Some(&SymmetryError::NoFundTokensOutstanding)
+    );
+}
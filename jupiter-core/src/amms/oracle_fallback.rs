@@ -0,0 +1,129 @@
+//! Secondary oracles per mint, read when a token's primary oracle is offline so one stalled
+//! feed doesn't leave the whole fund unquotable.
+//!
+//! Fallbacks only change how this crate prices the fund. The program still reads the
+//! primary oracles passed with every instruction, so a swap quoted from a fallback price
+//! executes only if the program accepts the primary's state at that point.
+
+use anyhow::Result;
+use jupiter_amm_interface::{try_get_account_data, AccountMap};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::amms::accounts::{OraclePrice, TokenSettings};
+use crate::amms::oracle_type::OracleType;
+use crate::amms::shared_accounts::SharedAccountCache;
+use crate::amms::symmetry_token_swap::SymmetryTokenSwap;
+
+/// An oracle to price a token from when its primary oracle is offline.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FallbackOracle {
+    pub oracle_account: Pubkey,
+    pub oracle_type: OracleType,
+    /// Price slot within the account, for internal oracles.
+    pub oracle_index: u8,
+}
+
+impl FallbackOracle {
+    pub fn new(oracle_account: Pubkey, oracle_type: OracleType) -> Self {
+        Self { oracle_account, oracle_type, oracle_index: 0 }
+    }
+
+    /// `token_settings` reading this oracle instead of the primary one. Confidence and fee
+    /// settings stay the token's own.
+    fn token_settings(&self, token_settings: TokenSettings) -> TokenSettings {
+        TokenSettings {
+            oracle_account: self.oracle_account,
+            oracle_type: self.oracle_type.into(),
+            oracle_index: self.oracle_index,
+            ..token_settings
+        }
+    }
+}
+
+/// Which oracle priced a token in the last `update()`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OracleSource {
+    Primary,
+    Fallback,
+}
+
+impl SymmetryTokenSwap {
+    /// Price of token list entry `token_id` from its primary oracle, or from its mint's
+    /// fallback oracle when the primary is offline or can't be read and the fallback is
+    /// live. Without a usable fallback the primary's price or error stands.
+    pub(crate) fn load_oracle_price(
+        &self,
+        account_map: &AccountMap,
+        cache: &mut SharedAccountCache,
+        token_id: usize,
+    ) -> Result<(OraclePrice, OracleSource)> {
+        let token_settings = self.token_list().list[token_id];
+        let primary = self.load_price_from(account_map, cache, token_id, token_settings);
+        if matches!(primary, Ok(price) if price.oracle_live != 0) {
+            return primary.map(|price| (price, OracleSource::Primary));
+        }
+        if let Some(fallback) = self.fallback_oracles().get(&token_settings.token_mint) {
+            let fallback_settings = fallback.token_settings(token_settings);
+            if let Ok(price) = self.load_price_from(account_map, cache, token_id, fallback_settings) {
+                if price.oracle_live != 0 {
+                    return Ok((price, OracleSource::Fallback));
+                }
+            }
+        }
+        primary.map(|price| (price, OracleSource::Primary))
+    }
+
+    /// The oracle price read through `token_settings`, with the EMA as average price when
+    /// `AmmConfig::use_ema_price` is set.
+    fn load_price_from(
+        &self,
+        account_map: &AccountMap,
+        cache: &mut SharedAccountCache,
+        token_id: usize,
+        token_settings: TokenSettings,
+    ) -> Result<OraclePrice> {
+        let mut price = cache.oracle_price(account_map, token_id, token_settings)?;
+        if self.config().use_ema_price {
+            let account_data = try_get_account_data(account_map, &token_settings.oracle_account)?;
+            if let Some(ema_price) = OraclePrice::load_ema_price(account_data, token_settings)? {
+                price.avg_price = ema_price;
+            }
+        }
+        Ok(price)
+    }
+}
+
+#[test]
+fn test_fallback_oracle() {
+    use jupiter_amm_interface::Amm;
+
+    use crate::amms::account_builders::{fixture_accounts, quotable_fund, FixtureToken, FundStateBuilder, OracleAccountBuilder};
+    use crate::amms::accounts::{ONE_USD, WEIGHT_MULTIPLIER};
+    use crate::error::SymmetryError;
+
+    let tokens = [FixtureToken::new(6, 100_000_000), FixtureToken::new(9, 10_000_000_000)];
+    let (usdc, sol) = (tokens[0].mint, tokens[1].mint);
+    let fund_state = FundStateBuilder::new()
+        .with_token(0, 10_000_000_000, WEIGHT_MULTIPLIER / 2)
+        .with_token(1, 100_000_000_000, WEIGHT_MULTIPLIER / 2);
+    let key = Pubkey::new_unique();
+    let amm = quotable_fund(key, &fund_state, &tokens);
+
+    // SOL's Pyth feed last published 100 slots ago; its Switchboard feed is current.
+    let sol_fallback = Pubkey::new_unique();
+    let mut account_map = fixture_accounts(key, &fund_state, &tokens);
+    account_map.insert(tokens[1].oracle, tokens[1].oracle_account(910));
+    account_map.insert(sol_fallback, OracleAccountBuilder::switchboard(101 * 10i128.pow(18), 0, 5, 1_005).build_account());
+
+    let mut without_fallback = amm.clone();
+    without_fallback.update(&account_map).unwrap();
+    let error = without_fallback.fund_worth().unwrap_err();
+    assert_eq!(error.downcast_ref::<SymmetryError>(), Some(&SymmetryError::OracleOffline { mint: sol }));
+
+    let mut amm = amm.with_fallback_oracle(sol, FallbackOracle::new(sol_fallback, OracleType::SwitchboardOnDemand));
+    assert!(amm.get_accounts_to_update().contains(&sol_fallback));
+    amm.update(&account_map).unwrap();
+    assert_eq!(amm.oracle_source(&sol), Some(OracleSource::Fallback));
+    assert_eq!(amm.oracle_source(&usdc), Some(OracleSource::Primary));
+    assert_eq!(amm.fund_worth().unwrap(), 20_100 * ONE_USD);
+}
@@ -0,0 +1,76 @@
+use std::convert::TryFrom;
+
+use solana_sdk::pubkey::Pubkey;
+
+use crate::amms::account_owners::PYTH_ORACLE_PROGRAM_ID;
+use crate::amms::accounts::ORACLE_ACCOUNT_SIZE;
+use crate::amms::pyth_pull::{PYTH_PULL_ORACLE_TYPE, PYTH_RECEIVER_PROGRAM_ID};
+use crate::amms::switchboard::{SWITCHBOARD_ON_DEMAND_ORACLE_TYPE, SWITCHBOARD_ON_DEMAND_PROGRAM_ID};
+use crate::amms::symmetry_token_swap::SymmetryTokenSwap;
+use crate::error::SymmetryError;
+
+/// How a token is priced, from `TokenSettings::oracle_type`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum OracleType {
+    /// Legacy Pyth push price account.
+    PythPush = 0,
+    /// Symmetry's internal oracle, one of up to 50 prices the program writes to one account.
+    SymmetryCustom = 1,
+    /// Pyth pull `PriceUpdateV2` account.
+    PythPull = PYTH_PULL_ORACLE_TYPE,
+    /// Switchboard On-Demand pull feed.
+    SwitchboardOnDemand = SWITCHBOARD_ON_DEMAND_ORACLE_TYPE,
+}
+
+impl OracleType {
+    /// Size of the oracle account.
+    pub fn account_size(self) -> usize {
+        ORACLE_ACCOUNT_SIZE[self as usize]
+    }
+
+    /// Program expected to own the oracle account on mainnet.
+    pub fn expected_owner(self) -> Pubkey {
+        match self {
+            OracleType::PythPush => PYTH_ORACLE_PROGRAM_ID,
+            OracleType::SymmetryCustom => SymmetryTokenSwap::SYMMETRY_PROGRAM_ADDRESS,
+            OracleType::PythPull => PYTH_RECEIVER_PROGRAM_ID,
+            OracleType::SwitchboardOnDemand => SWITCHBOARD_ON_DEMAND_PROGRAM_ID,
+        }
+    }
+}
+
+impl TryFrom<u8> for OracleType {
+    type Error = SymmetryError;
+
+    fn try_from(oracle_type: u8) -> Result<Self, Self::Error> {
+        match oracle_type {
+            0 => Ok(OracleType::PythPush),
+            1 => Ok(OracleType::SymmetryCustom),
+            PYTH_PULL_ORACLE_TYPE => Ok(OracleType::PythPull),
+            SWITCHBOARD_ON_DEMAND_ORACLE_TYPE => Ok(OracleType::SwitchboardOnDemand),
+            _ => Err(SymmetryError::UnsupportedOracleType { oracle_type }),
+        }
+    }
+}
+
+impl From<OracleType> for u8 {
+    fn from(oracle_type: OracleType) -> u8 {
+        oracle_type as u8
+    }
+}
+
+#[test]
+fn test_oracle_type_roundtrip() {
+    use crate::amms::accounts::{OraclePrice, TokenSettings};
+
+    for oracle_type in [OracleType::PythPush, OracleType::SymmetryCustom, OracleType::PythPull, OracleType::SwitchboardOnDemand] {
+        assert_eq!(OracleType::try_from(u8::from(oracle_type)).unwrap(), oracle_type);
+    }
+    assert_eq!(OracleType::SymmetryCustom.account_size(), 809);
+    assert!(matches!(OracleType::try_from(4), Err(SymmetryError::UnsupportedOracleType { oracle_type: 4 })));
+
+    let token_settings = TokenSettings { oracle_type: 4, ..TokenSettings::empty() };
+    let error = OraclePrice::load(&[0; 809], token_settings).unwrap_err();
+    assert_eq!(error.downcast_ref::<SymmetryError>(), Some(&SymmetryError::UnsupportedOracleType { oracle_type: 4 }));
+}
@@ -0,0 +1,177 @@
+use anyhow::{Error, Result};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::amms::accounts::{TokenPriceData, NUM_OF_POINTS_IN_CURVE_DATA};
+use crate::amms::quoter::Quoter;
+use crate::amms::symmetry_token_swap::SymmetryTokenSwap;
+use crate::error::SymmetryError;
+
+/// Why the marginal price of a pair changes at a given input amount.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KneeKind {
+    /// The input token reaches its target amount: fees switch from `before_tw` to `after_tw`.
+    InputTargetWeight,
+    /// The output token drops below its target amount: fees switch from `before_tw` to `after_tw`.
+    OutputTargetWeight,
+    /// A sell curve interval of the input token ends.
+    InputCurveStep,
+    /// A buy curve interval of the output token ends (converted to input units at avg prices).
+    OutputCurveStep,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct FeeKnee {
+    pub in_amount: u64,
+    pub kind: KneeKind,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct OrderSizing {
+    /// Knee points below the requested amount, ascending.
+    pub knees: Vec<FeeKnee>,
+    /// Candidate with the best out-per-in ratio.
+    pub in_amount: u64,
+    pub out_amount: u64,
+}
+
+/// Input amounts at which cumulative curve steps end, after skipping `offset`.
+fn curve_boundaries(curve: &TokenPriceData, offset: u64) -> Vec<u64> {
+    let mut cumulative: u64 = 0;
+    let mut boundaries = Vec::new();
+    for step in 0..NUM_OF_POINTS_IN_CURVE_DATA {
+        cumulative = cumulative.saturating_add(curve.amount[step]);
+        if cumulative > offset {
+            boundaries.push(cumulative - offset);
+        }
+    }
+    boundaries
+}
+
+impl SymmetryTokenSwap {
+    /// Input amounts where the effective price of `input_mint` -> `output_mint` changes,
+    /// mirroring the interval walk in `compute_value_of_sold_token`/`compute_amount_of_bought_token`.
+    pub fn fee_knees(&self, input_mint: &Pubkey, output_mint: &Pubkey) -> Result<Vec<FeeKnee>> {
+        let fund_state = self.fund_state();
+        let from_index = self.composition_index(input_mint)
+            .ok_or(SymmetryError::TokenNotInFund { mint: *input_mint })?;
+        let to_index = self.composition_index(output_mint)
+            .ok_or(SymmetryError::TokenNotInFund { mint: *output_mint })?;
+        let from_id = fund_state.current_comp_token[from_index] as usize;
+        let to_id = fund_state.current_comp_token[to_index] as usize;
+        let from_settings = self.token_list().list[from_id];
+        let to_settings = self.token_list().list[to_id];
+
+        let from_amount = fund_state.current_comp_amount[from_index];
+        let to_amount = fund_state.current_comp_amount[to_index];
+        let from_target = self.target_amount(input_mint)?;
+        let to_target = self.target_amount(output_mint)?;
+
+        // Output-side amounts are mapped back to input units through avg prices.
+        let to_input_units = |out_amount: u64| {
+            SymmetryTokenSwap::usd_value_to_amount(
+                SymmetryTokenSwap::amount_to_usd_value(out_amount, to_settings.decimals, to_settings.oracle_price.avg_price),
+                from_settings.decimals,
+                from_settings.oracle_price.avg_price,
+            )
+        };
+
+        let mut knees = Vec::new();
+        if from_amount < from_target {
+            knees.push(FeeKnee { in_amount: from_target - from_amount, kind: KneeKind::InputTargetWeight });
+        }
+        if to_amount > to_target {
+            knees.push(FeeKnee { in_amount: to_input_units(to_amount - to_target), kind: KneeKind::OutputTargetWeight });
+        }
+        for boundary in curve_boundaries(&self.curve_data().sell[from_id], from_amount.saturating_sub(from_target)) {
+            knees.push(FeeKnee { in_amount: boundary, kind: KneeKind::InputCurveStep });
+        }
+        for boundary in curve_boundaries(&self.curve_data().buy[to_id], to_target.saturating_sub(to_amount)) {
+            knees.push(FeeKnee { in_amount: to_input_units(boundary), kind: KneeKind::OutputCurveStep });
+        }
+        knees.retain(|knee| knee.in_amount > 0);
+        knees.sort_by_key(|knee| knee.in_amount);
+        knees.dedup_by_key(|knee| knee.in_amount);
+        Ok(knees)
+    }
+
+    /// For an order of up to `max_in_amount`, quotes every knee point below it plus the full
+    /// amount and returns the size with the best out-per-in. Sizes that fail to quote
+    /// (e.g. weight limits) are skipped.
+    pub fn optimal_order_size(&self, input_mint: &Pubkey, output_mint: &Pubkey, max_in_amount: u64) -> Result<OrderSizing> {
+        let mut knees = self.fee_knees(input_mint, output_mint)?;
+        knees.retain(|knee| knee.in_amount < max_in_amount);
+
+        let quoter = Quoter::for_pair(self, input_mint, output_mint)?;
+        let mut best: Option<(u64, u64)> = None;
+        let candidates = knees.iter().map(|knee| knee.in_amount).chain(std::iter::once(max_in_amount));
+        for in_amount in candidates {
+            let quote = match quoter.quote_amount(in_amount) {
+                Ok(quote) => quote,
+                Err(_) => continue,
+            };
+            // Compare out/in ratios without division: a_out * b_in > b_out * a_in
+            let better = match best {
+                Some((best_in, best_out)) => {
+                    (quote.out_amount as u128) * (best_in as u128) > (best_out as u128) * (in_amount as u128)
+                }
+                None => true,
+            };
+            if better {
+                best = Some((in_amount, quote.out_amount));
+            }
+        }
+        let (in_amount, out_amount) = best.ok_or_else(|| Error::msg("No order size up to the requested amount can be quoted"))?;
+        Ok(OrderSizing { knees, in_amount, out_amount })
+    }
+}
+
+#[test]
+fn test_optimal_order_size() {
+    use jupiter_amm_interface::{Amm, QuoteParams};
+
+    use crate::amms::account_builders::{quotable_fund, FixtureToken, FundStateBuilder};
+    use crate::amms::accounts::WEIGHT_MULTIPLIER;
+
+    let tokens = [FixtureToken::new(6, 100_000_000), FixtureToken::new(9, 10_000_000_000), FixtureToken::new(6, 100_000_000)];
+    let (usdc, sol) = (tokens[0].mint, tokens[1].mint);
+    // $6,000 of USDC, 120 SOL at $100 and $2,000 of a third token, targeting a third each.
+    let fund_state = FundStateBuilder::new()
+        .with_token(0, 6_000_000_000, WEIGHT_MULTIPLIER / 3)
+        .with_token(1, 120_000_000_000, WEIGHT_MULTIPLIER / 3)
+        .with_token(2, 2_000_000_000, WEIGHT_MULTIPLIER / 3)
+        .with_weight_band(10_000, 10_000);
+    let fund = quotable_fund(Pubkey::new_unique(), &fund_state, &tokens);
+
+    // USDC is $666.66 under its target and SOL $5,333.33 over it; the flat fixture curves
+    // add no curve steps.
+    let usdc_to_target = fund.target_amount(&usdc).unwrap() - 6_000_000_000;
+    assert_eq!(usdc_to_target, 666_666_666);
+    let knees = fund.fee_knees(&usdc, &sol).unwrap();
+    assert_eq!(knees, vec![
+        FeeKnee { in_amount: usdc_to_target, kind: KneeKind::InputTargetWeight },
+        FeeKnee { in_amount: 5_333_333_333, kind: KneeKind::OutputTargetWeight },
+    ]);
+    // The other way round both legs are past their targets from the first unit.
+    assert!(fund.fee_knees(&sol, &usdc).unwrap().is_empty());
+
+    // Up to $2,000: stopping at USDC's target keeps both legs at 10 bps, beating the
+    // full amount whose remainder pays 30 bps on the sell leg.
+    let sizing = fund.optimal_order_size(&usdc, &sol, 2_000_000_000).unwrap();
+    assert_eq!(sizing.knees, knees[..1]);
+    assert_eq!(sizing.in_amount, usdc_to_target);
+    let quote = |in_amount: u64| fund.quote(&QuoteParams { in_amount, input_mint: usdc, output_mint: sol }).unwrap().out_amount;
+    assert_eq!(sizing.out_amount, quote(usdc_to_target));
+    assert!((sizing.out_amount as u128) * 2_000_000_000 > (quote(2_000_000_000) as u128) * (usdc_to_target as u128));
+
+    // Below the first knee the whole order is the only candidate.
+    let sizing = fund.optimal_order_size(&usdc, &sol, 100_000_000).unwrap();
+    assert!(sizing.knees.is_empty());
+    assert_eq!((sizing.in_amount, sizing.out_amount), (100_000_000, quote(100_000_000)));
+    let unknown = Pubkey::new_unique();
+    assert_eq!(
+        fund.fee_knees(&usdc, &unknown).unwrap_err().downcast_ref::<SymmetryError>(),
+        Some(&SymmetryError::TokenNotInFund { mint: unknown })
+    );
+}
@@ -0,0 +1,45 @@
+use anyhow::{Error, Result};
+use jupiter_amm_interface::{Quote, QuoteParams};
+use serde::{Deserialize, Serialize};
+
+use crate::amms::quoter::Quoter;
+use crate::amms::symmetry_token_swap::{PostSwapWeights, SymmetryTokenSwap};
+use crate::error::SymmetryError;
+
+/// What `quote()` does when the in amount would push a token past its weight limits.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum WeightLimitPolicy {
+    /// Fail the quote with `SymmetryError::WeightLimitExceeded`.
+    #[default]
+    Reject,
+    /// Quote the largest in amount that clears the limits instead. `Quote::in_amount` is then
+    /// below the requested amount, so routers can send the rest elsewhere.
+    PartialFill,
+}
+
+pub(crate) fn is_weight_limit_exceeded(error: &Error) -> bool {
+    matches!(error.downcast_ref::<SymmetryError>(), Some(SymmetryError::WeightLimitExceeded { .. }))
+}
+
+impl SymmetryTokenSwap {
+    /// Quote of the largest part of `quote_params.in_amount` that clears the weight limits,
+    /// found with `max_in_amount`. Fails if no amount does.
+    pub fn quote_partial_fill(&self, quote_params: &QuoteParams) -> Result<(Quote, PostSwapWeights)> {
+        let quoter = Quoter::for_pair(self, &quote_params.input_mint, &quote_params.output_mint)?;
+        let in_amount = quoter.max_in_amount().min(quote_params.in_amount);
+        if in_amount == 0 {
+            return Err(SymmetryError::NoFillableAmount.into());
+        }
+        quoter.quote_amount_with_weights(in_amount)
+    }
+}
+
+#[test]
+fn test_is_weight_limit_exceeded() {
+    use crate::error::SwapSide;
+
+    assert!(is_weight_limit_exceeded(&SymmetryError::WeightLimitExceeded { side: SwapSide::To }.into()));
+    assert!(!is_weight_limit_exceeded(&SymmetryError::FundLpDisabled.into()));
+    assert!(!is_weight_limit_exceeded(&Error::msg("From token weight exceeds max allowed weight")));
+}
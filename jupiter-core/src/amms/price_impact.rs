@@ -0,0 +1,65 @@
+//! Price impact of quotes: the shortfall of the output from the fair output at oracle avg
+//! prices. It includes fees as well as the oracle confidence spread and curve pricing, so it
+//! is what a UI should warn on for large trades against thin curve data.
+//!
+//! jupiter-amm-interface's `Quote` has no price impact field, so `Amm::quote` doesn't report
+//! it; call `quote_with_price_impact` where the impact is needed.
+
+use anyhow::Result;
+use jupiter_amm_interface::{Quote, QuoteParams};
+use rust_decimal::Decimal;
+
+use crate::amms::accounts::BPS_DIVIDER;
+use crate::amms::symmetry_token_swap::SymmetryTokenSwap;
+
+/// Shortfall of `out_amount` from `fair_out_amount` in percent, at the precision of
+/// `Quote::fee_pct`. Zero when the output isn't below the fair output.
+pub fn price_impact_pct(fair_out_amount: u64, out_amount: u64) -> Decimal {
+    let shortfall = SymmetryTokenSwap::mul_div(fair_out_amount.saturating_sub(out_amount), BPS_DIVIDER * 100, fair_out_amount);
+    Decimal::new(shortfall as i64, 4)
+}
+
+impl SymmetryTokenSwap {
+    /// `quote()` together with its price impact in percent, which `quote()` itself can't return.
+    pub fn quote_with_price_impact(&self, quote_params: &QuoteParams) -> Result<(Quote, Decimal)> {
+        let (quote, _) = self.quote_with_weights(quote_params)?;
+        let fair_amount = self.fair_out_amount(&quote_params.input_mint, &quote_params.output_mint, quote.in_amount)
+            .ok_or_else(|| self.token_not_in_fund(&quote_params.input_mint, &quote_params.output_mint))?;
+        let price_impact_pct = price_impact_pct(fair_amount, quote.out_amount);
+        Ok((quote, price_impact_pct))
+    }
+}
+
+#[test]
+fn test_price_impact_pct() {
+    assert_eq!(price_impact_pct(1_000_000, 995_000), Decimal::new(5_000, 4));
+    assert_eq!(price_impact_pct(1_000_000, 1_000_001), Decimal::ZERO);
+    assert_eq!(price_impact_pct(0, 0), Decimal::ZERO);
+    assert_eq!(price_impact_pct(3, 2).to_string(), "33.3333");
+}
+
+#[test]
+fn test_quote_with_price_impact() {
+    use jupiter_amm_interface::Amm;
+    use solana_sdk::pubkey::Pubkey;
+
+    use crate::amms::account_builders::{quotable_fund, FixtureToken, FundStateBuilder};
+    use crate::amms::accounts::WEIGHT_MULTIPLIER;
+
+    let tokens = [FixtureToken::new(6, 100_000_000), FixtureToken::new(9, 10_000_000_000)];
+    let fund_state = FundStateBuilder::new()
+        .with_token(0, 10_000_000_000, WEIGHT_MULTIPLIER / 2)
+        .with_token(1, 100_000_000_000, WEIGHT_MULTIPLIER / 2)
+        .with_weight_band(1_000, 5_000);
+    let fund = quotable_fund(Pubkey::new_unique(), &fund_state, &tokens);
+    // $100 of USDC into SOL at $100 is worth 1 SOL before fees.
+    let quote_params = QuoteParams { in_amount: 100_000_000, input_mint: tokens[0].mint, output_mint: tokens[1].mint };
+    let (quote, impact) = fund.quote_with_price_impact(&quote_params).unwrap();
+    assert_eq!(format!("{:?}", quote), format!("{:?}", fund.quote(&quote_params).unwrap()));
+    assert_eq!(fund.fair_out_amount(&tokens[0].mint, &tokens[1].mint, quote.in_amount), Some(1_000_000_000));
+    assert_eq!(impact, price_impact_pct(1_000_000_000, quote.out_amount));
+    // The fund starts at its target weights, so both legs pay the 30 bps after-target fee:
+    // 1 - 0.997^2 = 0.5991%.
+    assert_eq!(quote.out_amount, 994_009_000);
+    assert_eq!(impact, Decimal::new(5_991, 4));
+}
@@ -0,0 +1,151 @@
+use anyhow::Result;
+use solana_sdk::{pubkey, pubkey::Pubkey};
+use std::convert::TryInto;
+
+use crate::error::SymmetryError;
+
+/// `TokenSettings::oracle_type` of tokens priced by a Pyth pull oracle.
+pub const PYTH_PULL_ORACLE_TYPE: u8 = 2;
+
+/// Pyth receiver program owning `PriceUpdateV2` accounts.
+pub const PYTH_RECEIVER_PROGRAM_ID: Pubkey = pubkey!("rec5EKMGg6MxZYaMdyBfgwp4d5rB9T1VQH5pJv5LtFJ");
+
+/// Allocated size of a `PriceUpdateV2` account, with room for the longer verification level.
+pub const PRICE_UPDATE_V2_SIZE: usize = 134;
+
+/// Seconds after `publish_time` a pull oracle price is still treated as live, as for
+/// internal oracles.
+pub const PYTH_PULL_MAX_AGE_SECONDS: i64 = 40;
+
+/// Anchor discriminator of `PriceUpdateV2`.
+const PRICE_UPDATE_V2_DISCRIMINATOR: [u8; 8] = [34, 241, 35, 99, 157, 126, 244, 205];
+
+/// How many Wormhole guardian signatures were checked when the update was posted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VerificationLevel {
+    Partial { num_signatures: u8 },
+    Full,
+}
+
+/// A Pyth pull oracle price account, written by the Pyth receiver program.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PriceUpdateV2 {
+    pub write_authority: Pubkey,
+    pub verification_level: VerificationLevel,
+    pub feed_id: [u8; 32],
+    pub price: i64,
+    pub conf: u64,
+    pub exponent: i32,
+    pub publish_time: i64,
+    pub prev_publish_time: i64,
+    pub ema_price: i64,
+    pub ema_conf: u64,
+    pub posted_slot: u64,
+}
+
+impl PriceUpdateV2 {
+    pub fn parse(account_data: &[u8]) -> Result<PriceUpdateV2> {
+        let too_short = |min: usize| SymmetryError::AccountTooShort {
+            account: "PriceUpdateV2",
+            min,
+            got: account_data.len(),
+        };
+        let discriminator: [u8; 8] = account_data.get(..8).ok_or_else(|| too_short(8))?.try_into().unwrap();
+        if discriminator != PRICE_UPDATE_V2_DISCRIMINATOR {
+            return Err(SymmetryError::DiscriminatorMismatch {
+                account: "PriceUpdateV2",
+                expected: PRICE_UPDATE_V2_DISCRIMINATOR,
+                got: discriminator,
+            }.into());
+        }
+        let level = account_data.get(40..42).ok_or_else(|| too_short(42))?;
+        let (verification_level, message_offset) = match (level[0], level[1]) {
+            (0, num_signatures) => (VerificationLevel::Partial { num_signatures }, 42),
+            (1, _) => (VerificationLevel::Full, 41),
+            (tag, _) => return Err(SymmetryError::InvalidVerificationLevel { tag }.into()),
+        };
+        let message = account_data
+            .get(message_offset..message_offset + 92)
+            .ok_or_else(|| too_short(message_offset + 92))?;
+        let bytes = |offset: usize| -> [u8; 8] { message[offset..offset + 8].try_into().unwrap() };
+        Ok(PriceUpdateV2 {
+            write_authority: Pubkey::new_from_array(account_data[8..40].try_into().unwrap()),
+            verification_level,
+            feed_id: message[..32].try_into().unwrap(),
+            price: i64::from_le_bytes(bytes(32)),
+            conf: u64::from_le_bytes(bytes(40)),
+            exponent: i32::from_le_bytes(message[48..52].try_into().unwrap()),
+            publish_time: i64::from_le_bytes(bytes(52)),
+            prev_publish_time: i64::from_le_bytes(bytes(60)),
+            ema_price: i64::from_le_bytes(bytes(68)),
+            ema_conf: u64::from_le_bytes(bytes(76)),
+            posted_slot: u64::from_le_bytes(bytes(84)),
+        })
+    }
+
+    /// Whether the price may be used at `unix_timestamp`: fully verified, positive, recent,
+    /// and with a confidence interval under a tenth of the price.
+    pub fn is_live(&self, unix_timestamp: i64) -> bool {
+        self.verification_level == VerificationLevel::Full
+            && self.price > 0
+            && unix_timestamp <= self.publish_time + PYTH_PULL_MAX_AGE_SECONDS
+            && (self.conf as u128) * 10 <= self.price as u128
+    }
+}
+
+/// `PriceUpdateV2` account data posting `price`, used by `OracleAccountBuilder::pyth_pull`.
+#[cfg(any(test, feature = "test-fixtures"))]
+pub(crate) fn price_update_v2_data(verification_level: VerificationLevel, price: i64, conf: u64, exponent: i32, publish_time: i64) -> Vec<u8> {
+    let mut data = PRICE_UPDATE_V2_DISCRIMINATOR.to_vec();
+    data.extend_from_slice(&[7u8; 32]);
+    match verification_level {
+        VerificationLevel::Partial { num_signatures } => data.extend_from_slice(&[0, num_signatures]),
+        VerificationLevel::Full => data.push(1),
+    }
+    data.extend_from_slice(&[9u8; 32]);
+    data.extend_from_slice(&price.to_le_bytes());
+    data.extend_from_slice(&conf.to_le_bytes());
+    data.extend_from_slice(&exponent.to_le_bytes());
+    data.extend_from_slice(&publish_time.to_le_bytes());
+    data.extend_from_slice(&(publish_time - 1).to_le_bytes());
+    data.extend_from_slice(&price.to_le_bytes());
+    data.extend_from_slice(&conf.to_le_bytes());
+    data.extend_from_slice(&1_234u64.to_le_bytes());
+    data.resize(PRICE_UPDATE_V2_SIZE, 0);
+    data
+}
+
+#[test]
+fn test_price_update_v2_parse() {
+    let data = price_update_v2_data(VerificationLevel::Full, 15_000_000_000, 5_000_000, -8, 1_700_000_000);
+    let update = PriceUpdateV2::parse(&data).unwrap();
+    assert_eq!(update.verification_level, VerificationLevel::Full);
+    assert_eq!(update.price, 15_000_000_000);
+    assert_eq!(update.conf, 5_000_000);
+    assert_eq!(update.exponent, -8);
+    assert_eq!(update.publish_time, 1_700_000_000);
+    assert_eq!(update.posted_slot, 1_234);
+    assert!(update.is_live(1_700_000_040));
+    assert!(!update.is_live(1_700_000_041));
+
+    let partial = price_update_v2_data(VerificationLevel::Partial { num_signatures: 5 }, 15_000_000_000, 5_000_000, -8, 1_700_000_000);
+    let update = PriceUpdateV2::parse(&partial).unwrap();
+    assert_eq!(update.verification_level, VerificationLevel::Partial { num_signatures: 5 });
+    assert_eq!(update.posted_slot, 1_234);
+    assert!(!update.is_live(1_700_000_000));
+
+    assert_eq!(
+        PriceUpdateV2::parse(&data[..100]).unwrap_err().downcast_ref::<SymmetryError>(),
+        Some(&SymmetryError::AccountTooShort { account: "PriceUpdateV2", min: 133, got: 100 })
+    );
+    assert!(matches!(
+        PriceUpdateV2::parse(&[0u8; PRICE_UPDATE_V2_SIZE]).unwrap_err().downcast_ref::<SymmetryError>(),
+        Some(SymmetryError::DiscriminatorMismatch { account: "PriceUpdateV2", .. })
+    ));
+    let mut invalid = data.clone();
+    invalid[40] = 2;
+    assert_eq!(
+        PriceUpdateV2::parse(&invalid).unwrap_err().downcast_ref::<SymmetryError>(),
+        Some(&SymmetryError::InvalidVerificationLevel { tag: 2 })
+    );
+}
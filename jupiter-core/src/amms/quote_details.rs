@@ -0,0 +1,197 @@
+//! Opt-in quotes carrying the intermediate values `quote()` computes, for debugging
+//! differences from on-chain execution.
+
+use anyhow::Result;
+use jupiter_amm_interface::{Quote, QuoteParams};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::amms::accounts::TokenSettings;
+use crate::amms::symmetry_token_swap::{PostSwapWeights, SymmetryTokenSwap};
+use crate::amms::target_weight::TargetWeightSplit;
+
+/// One curve interval a swap leg traded through.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct CurveStep {
+    /// Curve point index, or `NUM_OF_POINTS_IN_CURVE_DATA` past the last point.
+    pub step: usize,
+    /// USD price per whole token in the interval, with `ONE_USD` precision.
+    pub price: u64,
+    /// Tokens sold into the fund, or bought from it net of fees.
+    pub amount: u64,
+    /// USD value of the interval before fees.
+    pub value: u64,
+    pub fees: u64,
+}
+
+/// One side of a detailed quote.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct QuoteLegDetails {
+    pub mint: Pubkey,
+    pub token_id: u64,
+    /// Oracle sell price on the sell leg, buy price on the buy leg, before curve pricing.
+    pub oracle_price: u64,
+    pub avg_price: u64,
+    /// Fund holding of the token before the swap.
+    pub start_amount: u64,
+    pub target_amount: u64,
+    pub split: TargetWeightSplit,
+    pub steps: Vec<CurveStep>,
+}
+
+/// A quote with every intermediate value it was computed from.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct QuoteDetails {
+    pub quote: Quote,
+    pub fund_worth: u64,
+    pub sell: QuoteLegDetails,
+    pub buy: QuoteLegDetails,
+    /// USD value of the sold tokens net of sell-side fees, which the buy leg spends.
+    pub sold_value: u64,
+    /// Output of the buy curve. `quote.out_amount` is lower when the fund's holding or the
+    /// no-fee output caps it.
+    pub curve_out_amount: u64,
+    pub weights: PostSwapWeights,
+    /// Highest weight the sold token may reach, in `WEIGHT_MULTIPLIER` units.
+    pub max_from_weight: u64,
+    /// Lowest weight the bought token may fall to.
+    pub min_to_weight: u64,
+    /// Fund tokens valued at their last price because their oracle is offline, with
+    /// `AmmConfig::allow_stale_oracles` set.
+    pub stale_oracles: Vec<Pubkey>,
+}
+
+fn leg(mint: Pubkey, token_id: u64, token_settings: &TokenSettings, oracle_price: u64, start_amount: u64, target_amount: u64) -> QuoteLegDetails {
+    QuoteLegDetails {
+        mint,
+        token_id,
+        oracle_price,
+        avg_price: token_settings.oracle_price.avg_price,
+        start_amount,
+        target_amount,
+        split: TargetWeightSplit::default(),
+        steps: Vec::new(),
+    }
+}
+
+impl SymmetryTokenSwap {
+    /// `quote()` with its intermediate values. The curves are stepped again for the quoted
+    /// in amount, so use `quote()` on hot paths.
+    pub fn quote_details(&self, quote_params: &QuoteParams) -> Result<QuoteDetails> {
+        let (quote, weights, setup) = self.quote_with_setup(quote_params)?;
+        let fund_state = self.fund_state();
+        let curve_data = self.curve_data();
+
+        let from_settings = setup.from_token_settings;
+        let to_settings = setup.to_token_settings;
+        let mut sell = leg(
+            quote_params.input_mint,
+            setup.from_token_id,
+            &from_settings,
+            from_settings.oracle_price.sell_price,
+            fund_state.current_comp_amount[setup.from_token_index],
+            setup.from_token_target_amount,
+        );
+        let mut buy = leg(
+            quote_params.output_mint,
+            setup.to_token_id,
+            &to_settings,
+            to_settings.oracle_price.buy_price,
+            fund_state.current_comp_amount[setup.to_token_index],
+            setup.to_token_target_amount,
+        );
+
+        let (sold_value, sell_split) = SymmetryTokenSwap::step_sold_token(
+            quote.in_amount,
+            from_settings,
+            from_settings.oracle_price,
+            sell.start_amount,
+            sell.target_amount,
+            curve_data.sell[setup.from_token_id as usize],
+            |step| sell.steps.push(step),
+        );
+        let (curve_out_amount, buy_split) = SymmetryTokenSwap::step_bought_token(
+            sold_value,
+            to_settings,
+            to_settings.oracle_price,
+            buy.start_amount,
+            buy.target_amount,
+            curve_data.buy[setup.to_token_id as usize],
+            |step| buy.steps.push(step),
+        );
+        sell.split = sell_split;
+        buy.split = buy_split;
+
+        Ok(QuoteDetails {
+            quote,
+            fund_worth: setup.fund_worth,
+            sell,
+            buy,
+            sold_value,
+            curve_out_amount,
+            weights,
+            max_from_weight: self.allowed_weight_band(setup.from_token_index).max_weight,
+            min_to_weight: self.allowed_weight_band(setup.to_token_index).min_weight,
+            stale_oracles: self.stale_oracle_mints(),
+        })
+    }
+}
+
+#[test]
+fn test_quote_details() {
+    use jupiter_amm_interface::{Amm, KeyedAccount};
+
+    use solana_sdk::clock::Clock;
+
+    use crate::amms::account_builders::{CurveDataBuilder, FundStateBuilder, OracleAccountBuilder, TokenListBuilder};
+    use crate::amms::accounts::{TokenPriceData, ONE_USD, USE_CURVE_DATA, WEIGHT_MULTIPLIER};
+
+    let usdc = Pubkey::new_unique();
+    let sol = Pubkey::new_unique();
+    let usdc_oracle = Pubkey::new_unique();
+    let sol_oracle = Pubkey::new_unique();
+    let token = |mint: Pubkey, decimals: u8, oracle_account: Pubkey, use_curve_data: u8| TokenSettings {
+        token_mint: mint,
+        decimals,
+        oracle_account,
+        oracle_confidence_pct: 10,
+        is_live: 1,
+        lp_on: 1,
+        use_curve_data,
+        token_swap_fee_after_tw_bps: 10,
+        ..TokenSettings::empty()
+    };
+    let fund = KeyedAccount {
+        key: Pubkey::new_unique(),
+        account: FundStateBuilder::new()
+            .with_token(0, 10_000_000_000, WEIGHT_MULTIPLIER / 2)
+            .with_token(1, 100_000_000_000, WEIGHT_MULTIPLIER / 2)
+            .with_weight_band(1_000, 5_000)
+            .build_account(),
+        params: None,
+    };
+    let token_list = TokenListBuilder::new().with_token(token(usdc, 6, usdc_oracle, 0)).with_token(token(sol, 9, sol_oracle, USE_CURVE_DATA));
+    let mut amm = SymmetryTokenSwap::from_keyed_account(
+        &fund,
+        &KeyedAccount { key: SymmetryTokenSwap::TOKEN_LIST_ADDRESS, account: token_list.build_account(), params: None },
+    ).unwrap();
+    let mut buy_curve = TokenPriceData { amount: [1_000_000_000_000; 10], price: [100 * ONE_USD; 10] };
+    buy_curve.amount[0] = 500_000_000;
+    buy_curve.price[1] = 101 * ONE_USD;
+    amm.update_with_clock(&[
+        (fund.key, fund.account.clone()),
+        (SymmetryTokenSwap::CURVE_DATA_ADDRESS, CurveDataBuilder::new().with_buy_curve(1, buy_curve).build_account()),
+        (usdc_oracle, OracleAccountBuilder::pyth(100_000_000, 0, -8, 1_000).build_account()),
+        (sol_oracle, OracleAccountBuilder::pyth(10_000_000_000, 0, -8, 1_000).build_account()),
+    ].into(), Clock { slot: 1_010, ..Clock::default() }).unwrap();
+
+    let quote_params = QuoteParams { in_amount: 100_000_000, input_mint: usdc, output_mint: sol };
+    let details = amm.quote_details(&quote_params).unwrap();
+    assert_eq!(details.quote.out_amount, amm.quote(&quote_params).unwrap().out_amount);
+    assert_eq!(details.sell.steps.len(), 1);
+    assert_eq!(details.sold_value, details.sell.split.value_after_tw + details.sell.split.value_before_tw - details.sell.split.total_fees());
+    assert_eq!(details.buy.steps.iter().map(|step| step.price).collect::<Vec<_>>(), vec![100 * ONE_USD, 101 * ONE_USD]);
+    assert_eq!(details.buy.steps.iter().map(|step| step.amount).sum::<u64>(), details.curve_out_amount);
+}
@@ -0,0 +1,78 @@
+use anyhow::Result;
+use jupiter_amm_interface::{Quote, QuoteParams};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::amms::fee_model::{FeeModel, FeeSplit};
+use crate::amms::accounts::TokenList;
+use crate::amms::symmetry_token_swap::SymmetryTokenSwap;
+
+/// Who receives a quote's `fee_amount`, in `fee_mint` (the output token).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct QuoteFees {
+    pub fee_mint: Pubkey,
+    pub symmetry_fee: u64,
+    pub host_fee: u64,
+    pub manager_fee: u64,
+    /// Share kept by the fund's LPs.
+    pub fund_fee: u64,
+}
+
+impl QuoteFees {
+    pub fn new(fee_mint: Pubkey, split: FeeSplit) -> Self {
+        Self {
+            fee_mint,
+            symmetry_fee: split.symmetry_fee,
+            host_fee: split.host_fee,
+            manager_fee: split.manager_fee,
+            fund_fee: split.fund_fee,
+        }
+    }
+
+    pub fn split(&self) -> FeeSplit {
+        FeeSplit {
+            symmetry_fee: self.symmetry_fee,
+            host_fee: self.host_fee,
+            manager_fee: self.manager_fee,
+            fund_fee: self.fund_fee,
+        }
+    }
+
+    /// Equals the quote's `fee_amount`.
+    pub fn total(&self) -> u64 {
+        self.split().leaving_fund() + self.fund_fee
+    }
+}
+
+fn quote_fees(fee_model: &dyn FeeModel, quote: &Quote, token_list: &TokenList) -> Result<QuoteFees> {
+    Ok(QuoteFees::new(quote.fee_mint, fee_model.split_fees(quote.fee_amount, token_list)?))
+}
+
+impl SymmetryTokenSwap {
+    /// `quote()` together with how its fee is split.
+    pub fn quote_with_fees(&self, quote_params: &QuoteParams) -> Result<(Quote, QuoteFees)> {
+        let (quote, _) = self.quote_with_weights(quote_params)?;
+        let fees = self.fee_breakdown(&quote)?;
+        Ok((quote, fees))
+    }
+
+    /// Splits the fee of a `quote` computed from this fund's current state.
+    pub fn fee_breakdown(&self, quote: &Quote) -> Result<QuoteFees> {
+        quote_fees(self.fee_model(), quote, self.token_list())
+    }
+}
+
+#[test]
+fn test_quote_fees() {
+    use crate::amms::fee_model::OnChainFeeModel;
+
+    let mut token_list: Box<TokenList> = bytemuck::allocation::zeroed_box();
+    token_list.list[0].additional_data[60..63].copy_from_slice(&[20, 10, 30]);
+    let fee_mint = Pubkey::new_unique();
+    let quote = Quote { fee_amount: 1_001, fee_mint, ..Quote::default() };
+    let fees = quote_fees(&OnChainFeeModel, &quote, &token_list).unwrap();
+    assert_eq!(fees.fee_mint, fee_mint);
+    assert_eq!((fees.symmetry_fee, fees.host_fee, fees.manager_fee), (200, 100, 300));
+    assert_eq!(fees.fund_fee, 401);
+    assert_eq!(fees.total(), quote.fee_amount);
+}
@@ -0,0 +1,125 @@
+//! Fund data a quote reads, so `SymmetryTokenSwap` and `CompactFund` share one quoting path.
+
+use anyhow::Result;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::amms::accounts::{mul_div, FundState, TokenPriceData, TokenSettings, BPS_DIVIDER, LP_DISABLED, WEIGHT_MULTIPLIER};
+use crate::amms::amm_config::AmmConfig;
+use crate::amms::consistency::RebalancePolicy;
+use crate::amms::fee_model::FeeSplit;
+use crate::amms::liquidity::LiquidityPolicy;
+use crate::amms::symmetry_token_swap::{SymmetryTokenSwap, WeightBand};
+use crate::error::SymmetryError;
+
+pub(crate) trait QuoteSource {
+    fn fund_key(&self) -> Pubkey;
+    fn fund_state(&self) -> &FundState;
+    /// Token list index of `mint`.
+    fn token_id(&self, mint: &Pubkey) -> Option<usize>;
+    /// Settings of the token at token list index `token_id`.
+    fn token_settings(&self, token_id: usize) -> Option<&TokenSettings>;
+    fn sell_curve(&self, token_id: usize) -> TokenPriceData;
+    fn buy_curve(&self, token_id: usize) -> TokenPriceData;
+    fn quote_config(&self) -> &AmmConfig;
+    fn rebalance_policy(&self) -> RebalancePolicy;
+    fn liquidity_policy(&self) -> LiquidityPolicy;
+    fn split_fees(&self, total_fees: u64) -> Result<FeeSplit>;
+
+    /// Index of `mint` in the fund's current composition, if it is one of the fund's tokens.
+    fn composition_index(&self, mint: &Pubkey) -> Option<usize> {
+        let token_id = self.token_id(mint)? as u64;
+        self.fund_state().token_ids().iter().position(|&x| x == token_id)
+    }
+
+    /// Fails with `SymmetryError::TokenLpDisabled` unless the token at `token_id` in the token
+    /// list is LP enabled.
+    fn check_token_lp_on(&self, token_id: usize) -> Result<()> {
+        if let Some(settings) = self.token_settings(token_id) {
+            if settings.lp_on == LP_DISABLED {
+                return Err(SymmetryError::TokenLpDisabled { fund: self.fund_key(), mint: settings.token_mint }.into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Fund worth requiring a live oracle only for the composition indices accepted by
+    /// `live_required`. Other tokens with an offline oracle are valued at the last price it
+    /// holds, unless that price is zero.
+    fn fund_worth_requiring_live<L: Fn(usize) -> bool>(&self, live_required: L) -> Result<u64> {
+        let fund_state = self.fund_state();
+        let mut fund_worth: u128 = 0;
+        for (i, &token_id) in fund_state.token_ids().iter().enumerate() {
+            let token_settings = self.token_settings(token_id as usize)
+                .ok_or(SymmetryError::Arithmetic("Fund token missing from the token list"))?;
+            let token_price = token_settings.oracle_price;
+            if token_price.oracle_live == 0 && (live_required(i) || token_price.avg_price == 0) {
+                return Err(SymmetryError::OracleOffline { mint: token_settings.token_mint }.into())
+            }
+            fund_worth += SymmetryTokenSwap::amount_to_usd_value(
+                fund_state.current_comp_amount[i],
+                token_settings.decimals,
+                token_price.avg_price
+            ) as u128;
+        }
+        u64::try_from(fund_worth).map_err(|_| SymmetryError::Arithmetic("Fund worth overflows u64").into())
+    }
+
+    /// Fails with `SymmetryError::RebalanceInProgress` while the fund is mid-rebalance under
+    /// `RebalancePolicy::Reject`.
+    fn check_rebalance_policy(&self) -> Result<()> {
+        let fund_state = self.fund_state();
+        if self.rebalance_policy() == RebalancePolicy::Reject && fund_state.rebalance_in_progress() {
+            return Err(SymmetryError::RebalanceInProgress {
+                fund: self.fund_key(),
+                active_buy_states: fund_state.active_buy_states,
+                sell_state: fund_state.sell_state,
+                rebalance_sell_state: fund_state.rebalance_sell_state,
+            }.into())
+        }
+        Ok(())
+    }
+
+    /// Fund worth a quote of the tokens at the composition indices accepted by `quoted` is
+    /// priced against. Every oracle must be live unless `allow_stale_oracles` is set, in which
+    /// case only the quoted tokens' must.
+    fn quote_fund_worth<Q: Fn(usize) -> bool>(&self, quoted: Q) -> Result<u64> {
+        if self.quote_config().allow_stale_oracles {
+            self.fund_worth_requiring_live(quoted)
+        } else {
+            self.fund_worth_requiring_live(|_| true)
+        }
+    }
+
+    /// Amount of the token at composition index `token_index` the fund would hold at exactly
+    /// its target weight, given `fund_worth`.
+    fn target_amount_at(&self, token_index: usize, token_settings: &TokenSettings, fund_worth: u64) -> u64 {
+        let fund_state = self.fund_state();
+        SymmetryTokenSwap::usd_value_to_amount(
+            mul_div(fund_state.target_weight[token_index], fund_worth, fund_state.weight_sum),
+            token_settings.decimals,
+            token_settings.oracle_price.avg_price
+        )
+    }
+
+    /// Band around the target weight of the token at composition index `token_index`, offset
+    /// by `rebalance_threshold * lp_offset_threshold` (bps of bps), as enforced by `quote()`.
+    fn allowed_weight_band(&self, token_index: usize) -> WeightBand {
+        let fund_state = self.fund_state();
+        let allowed_offset = fund_state.rebalance_threshold * fund_state.lp_offset_threshold;
+        let target_weight = fund_state.target_weight.get(token_index).copied().unwrap_or_default();
+        let max_weight = mul_div(
+            target_weight,
+            BPS_DIVIDER * BPS_DIVIDER + allowed_offset,
+            BPS_DIVIDER * BPS_DIVIDER
+        );
+        let min_weight = mul_div(
+            target_weight,
+            (BPS_DIVIDER * BPS_DIVIDER).saturating_sub(allowed_offset),
+            BPS_DIVIDER * BPS_DIVIDER
+        );
+        WeightBand {
+            min_weight,
+            max_weight: max_weight.min(WEIGHT_MULTIPLIER),
+        }
+    }
+}
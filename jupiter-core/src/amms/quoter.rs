@@ -0,0 +1,60 @@
+use anyhow::Result;
+use jupiter_amm_interface::Quote;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::amms::symmetry_token_swap::{PairSetup, PostSwapWeights, SymmetryTokenSwap};
+
+/// Quotes one pair of a fund at many amounts. Token lookups, fund worth and target amounts
+/// are computed once in `for_pair`, so each `quote_amount` only walks the fee curves, e.g.
+/// while a router binary-searches the in amount. Results equal `quote()` for the same state,
+/// which the borrow of the fund keeps fixed.
+pub struct Quoter<'a> {
+    fund: &'a SymmetryTokenSwap,
+    setup: PairSetup,
+}
+
+impl<'a> Quoter<'a> {
+    /// Fails with the same errors `quote()` raises before looking at the amount.
+    pub fn for_pair(fund: &'a SymmetryTokenSwap, input_mint: &Pubkey, output_mint: &Pubkey) -> Result<Self> {
+        Ok(Self {
+            fund,
+            setup: fund.pair_setup(input_mint, output_mint)?,
+        })
+    }
+
+    pub(crate) fn fund(&self) -> &'a SymmetryTokenSwap {
+        self.fund
+    }
+
+    pub(crate) fn setup(&self) -> &PairSetup {
+        &self.setup
+    }
+
+    pub fn input_mint(&self) -> Pubkey {
+        self.setup.from_token_settings.token_mint
+    }
+
+    pub fn output_mint(&self) -> Pubkey {
+        self.setup.to_token_settings.token_mint
+    }
+
+    /// Output before fees and before capping at the fund's holding, at the oracle sell/buy
+    /// prices. `quote()` caps the output when this exceeds the holding.
+    pub fn pre_fee_out_amount(&self, in_amount: u64) -> u64 {
+        let from = &self.setup.from_token_settings;
+        let to = &self.setup.to_token_settings;
+        SymmetryTokenSwap::usd_value_to_amount(
+            SymmetryTokenSwap::amount_to_usd_value(in_amount, from.decimals, from.oracle_price.sell_price),
+            to.decimals,
+            to.oracle_price.buy_price,
+        )
+    }
+
+    pub fn quote_amount(&self, in_amount: u64) -> Result<Quote> {
+        self.fund.quote_pair(&self.setup, in_amount).map(|(quote, _)| quote)
+    }
+
+    pub fn quote_amount_with_weights(&self, in_amount: u64) -> Result<(Quote, PostSwapWeights)> {
+        self.fund.quote_pair(&self.setup, in_amount)
+    }
+}
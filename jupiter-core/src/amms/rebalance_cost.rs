@@ -0,0 +1,114 @@
+use anyhow::{Error, Result};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::amms::accounts::BPS_DIVIDER;
+use crate::amms::symmetry_token_swap::SymmetryTokenSwap;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct TokenRebalanceCost {
+    pub mint: Pubkey,
+    /// Target weight as a fraction of the fund.
+    pub weight: f64,
+    /// Expected number of threshold-triggered rebalances of this token over the horizon.
+    pub expected_rebalances: f64,
+    /// Cost per traded unit, from the oracle buy/sell half-spread, in bps.
+    pub cost_bps: f64,
+    /// Contribution to the fund's drag over the horizon, in bps of fund value.
+    pub drag_bps: f64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct RebalanceCostEstimate {
+    pub horizon_days: f64,
+    pub tokens: Vec<TokenRebalanceCost>,
+    /// Expected underperformance versus holding the target basket, in bps over the horizon.
+    pub total_drag_bps: f64,
+}
+
+impl SymmetryTokenSwap {
+    /// Rough estimate of the drag an LP pays for threshold rebalancing, compared with holding
+    /// the basket directly.
+    ///
+    /// Each token's weight is modelled as Brownian drift with volatility `w * (1 - w) * sigma`.
+    /// A rebalance triggers when it leaves the `rebalance_threshold` band, which happens
+    /// `(1 - w)^2 * sigma^2 / threshold^2` times per unit time. Each one trades `w * threshold`
+    /// of the fund twice (sell and buy) at the token's oracle half-spread. `daily_volatility`
+    /// holds the daily log-return standard deviation per mint; fund tokens missing from it
+    /// contribute nothing.
+    pub fn estimate_rebalance_cost(&self, daily_volatility: &[(Pubkey, f64)], horizon_days: f64) -> Result<RebalanceCostEstimate> {
+        let fund_state = self.fund_state();
+        if fund_state.rebalance_threshold == 0 || fund_state.weight_sum == 0 {
+            return Err(Error::msg("Fund has no rebalance threshold or target weights"));
+        }
+        let threshold = fund_state.rebalance_threshold as f64 / BPS_DIVIDER as f64;
+
+        let mut tokens = Vec::new();
+        for i in 0..fund_state.num_tokens() {
+            let settings = self.token_list().list[fund_state.current_comp_token[i] as usize];
+            let sigma = match daily_volatility.iter().find(|(mint, _)| *mint == settings.token_mint) {
+                Some((_, sigma)) => *sigma,
+                None => continue,
+            };
+            let price = settings.oracle_price;
+            if price.avg_price == 0 {
+                return Err(Error::msg("Token has no oracle price"));
+            }
+            let weight = fund_state.target_weight[i] as f64 / fund_state.weight_sum as f64;
+            let cost = (price.buy_price - price.sell_price) as f64 / (2.0 * price.avg_price as f64);
+            let expected_rebalances = (1.0 - weight).powi(2) * sigma.powi(2) * horizon_days / threshold.powi(2);
+            let drag = expected_rebalances * 2.0 * weight * threshold * cost;
+            tokens.push(TokenRebalanceCost {
+                mint: settings.token_mint,
+                weight,
+                expected_rebalances,
+                cost_bps: cost * BPS_DIVIDER as f64,
+                drag_bps: drag * BPS_DIVIDER as f64,
+            });
+        }
+        let total_drag_bps = tokens.iter().map(|token| token.drag_bps).sum();
+        Ok(RebalanceCostEstimate { horizon_days, tokens, total_drag_bps })
+    }
+}
+
+#[test]
+fn test_estimate_rebalance_cost() {
+    use jupiter_amm_interface::Amm;
+
+    use crate::amms::account_builders::{fixture_accounts, quotable_fund, FixtureToken, FundStateBuilder, OracleAccountBuilder};
+    use crate::amms::accounts::WEIGHT_MULTIPLIER;
+
+    let tokens = [FixtureToken::new(6, 100_000_000), FixtureToken::new(9, 10_000_000_000)];
+    let (usdc, sol) = (tokens[0].mint, tokens[1].mint);
+    // Half USDC, half SOL, rebalanced when a weight drifts 10% from its target.
+    let fund_state = FundStateBuilder::new()
+        .with_token(0, 10_000_000_000, WEIGHT_MULTIPLIER / 2)
+        .with_token(1, 100_000_000_000, WEIGHT_MULTIPLIER / 2)
+        .with_weight_band(1_000, 5_000);
+    let key = Pubkey::new_unique();
+    let mut fund = quotable_fund(key, &fund_state, &tokens);
+    // SOL's Pyth confidence is $10, of which the token list counts 10%: a $1 half-spread.
+    let mut account_map = fixture_accounts(key, &fund_state, &tokens);
+    account_map.insert(tokens[1].oracle, OracleAccountBuilder::pyth(10_000_000_000, 1_000_000_000, -8, 1_000).build_account());
+    fund.update(&account_map).unwrap();
+
+    // 5% daily volatility for a year: 0.25 * 0.05^2 * 365 / 0.1^2 = 22.8 rebalances, each
+    // trading 5% of the fund twice at 100 bps.
+    let estimate = fund.estimate_rebalance_cost(&[(usdc, 0.0), (sol, 0.05)], 365.0).unwrap();
+    assert_eq!(estimate.tokens.len(), 2);
+    let sol_cost = estimate.tokens[1];
+    assert_eq!((sol_cost.mint, sol_cost.weight), (sol, 0.5));
+    assert!((sol_cost.expected_rebalances - 22.8125).abs() < 1e-9);
+    assert!((sol_cost.cost_bps - 100.0).abs() < 1e-9);
+    assert!((sol_cost.drag_bps - 228.125).abs() < 1e-9);
+    assert_eq!((estimate.tokens[0].expected_rebalances, estimate.tokens[0].drag_bps), (0.0, 0.0));
+    assert_eq!(estimate.total_drag_bps, sol_cost.drag_bps);
+
+    // Tokens without a volatility are left out.
+    let estimate = fund.estimate_rebalance_cost(&[(sol, 0.05)], 365.0).unwrap();
+    assert_eq!(estimate.tokens.iter().map(|token| token.mint).collect::<Vec<_>>(), vec![sol]);
+
+    let no_threshold = quotable_fund(key, &fund_state.clone().with_weight_band(0, 5_000), &tokens);
+    assert!(no_threshold.estimate_rebalance_cost(&[(sol, 0.05)], 365.0).is_err());
+}
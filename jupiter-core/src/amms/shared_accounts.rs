@@ -0,0 +1,97 @@
+use anyhow::Result;
+use jupiter_amm_interface::{try_get_account_data, AccountMap};
+use solana_sdk::{clock::Clock, pubkey::Pubkey, sysvar};
+use std::collections::HashMap;
+
+use crate::amms::accounts::{OraclePrice, TokenSettings};
+use crate::error::SymmetryError;
+
+/// Parses of accounts shared by every fund (clock and oracles), reused across the
+/// funds updated from one `AccountMap`. Oracle prices are keyed by token list index and
+/// oracle account, since the same oracle account can serve several tokens.
+///
+/// Only valid for a single account map: create a new cache for every refresh.
+#[derive(Default)]
+pub struct SharedAccountCache {
+    clock: Option<Clock>,
+    oracle_prices: HashMap<(usize, Pubkey), OraclePrice>,
+    oracle_parses: u64,
+    oracle_hits: u64,
+}
+
+impl SharedAccountCache {
+    /// Cache judging oracle staleness at `clock` instead of the clock sysvar.
+    pub fn with_clock(clock: Clock) -> Self {
+        Self {
+            clock: Some(clock),
+            ..Self::default()
+        }
+    }
+
+    /// The injected clock, else the clock sysvar from `account_map`. Fails with
+    /// `MissingAccount` when neither is there rather than judging staleness at slot 0.
+    pub(crate) fn clock(&mut self, account_map: &AccountMap) -> Result<Clock> {
+        if let Some(clock) = &self.clock {
+            return Ok(clock.clone());
+        }
+        let account = account_map
+            .get(&sysvar::clock::ID)
+            .ok_or(SymmetryError::MissingAccount { key: sysvar::clock::ID })?;
+        let clock: Clock = bincode::deserialize(&account.data)?;
+        self.clock = Some(clock.clone());
+        Ok(clock)
+    }
+
+    pub(crate) fn oracle_price(&mut self, account_map: &AccountMap, token_id: usize, token_settings: TokenSettings) -> Result<OraclePrice> {
+        let key = (token_id, token_settings.oracle_account);
+        if let Some(price) = self.oracle_prices.get(&key) {
+            self.oracle_hits += 1;
+            return Ok(*price);
+        }
+        let clock = self.clock(account_map)?;
+        let price = OraclePrice::load_with_clock(try_get_account_data(account_map, &token_settings.oracle_account)?, token_settings, &clock)?;
+        self.oracle_parses += 1;
+        self.oracle_prices.insert(key, price);
+        Ok(price)
+    }
+
+    /// Oracle prices parsed from account data.
+    pub fn oracle_parses(&self) -> u64 {
+        self.oracle_parses
+    }
+
+    /// Oracle prices served from the cache instead of being parsed again.
+    pub fn oracle_hits(&self) -> u64 {
+        self.oracle_hits
+    }
+}
+
+#[test]
+fn test_stale_oracle_rejected_without_clock() {
+    use jupiter_amm_interface::Amm;
+
+    use crate::amms::account_builders::{clock_account, fixture_accounts, quotable_fund, FixtureToken, FundStateBuilder};
+    use crate::amms::accounts::WEIGHT_MULTIPLIER;
+
+    let tokens = [FixtureToken::new(6, 100_000_000), FixtureToken::new(9, 10_000_000_000)];
+    let fund_state = FundStateBuilder::new()
+        .with_token(0, 10_000_000_000, WEIGHT_MULTIPLIER / 2)
+        .with_token(1, 100_000_000_000, WEIGHT_MULTIPLIER / 2)
+        .with_weight_band(1_000, 5_000)
+        .with_supply_outstanding(20_000_000_000);
+    let key = Pubkey::new_unique();
+    let mut fund = quotable_fund(key, &fund_state, &tokens);
+
+    // Both oracles were published at slot 1,000, long before slot 2,000.
+    let mut account_map = fixture_accounts(key, &fund_state, &tokens);
+    account_map.remove(&sysvar::clock::ID);
+
+    // Without a clock the stale prices can't be judged, so the update fails instead of
+    // reading them as fresh at slot 0.
+    let error = fund.update(&account_map).unwrap_err();
+    assert_eq!(error.downcast_ref::<SymmetryError>(), Some(&SymmetryError::MissingAccount { key: sysvar::clock::ID }));
+
+    account_map.insert(sysvar::clock::ID, clock_account(&Clock { slot: 2_000, ..Clock::default() }));
+    fund.update(&account_map).unwrap();
+    assert_eq!(fund.oracle_price(&tokens[1].mint).unwrap().oracle_live, 0);
+}
@@ -1,4 +1,3 @@
-use anchor_lang::prelude::AccountMeta;
 use anyhow::Result;
 use spl_token::state::Account as TokenAccount;
 use std::{collections::HashMap, convert::TryInto};
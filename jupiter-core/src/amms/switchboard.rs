@@ -0,0 +1,146 @@
+use anyhow::Result;
+use solana_sdk::{pubkey, pubkey::Pubkey};
+use std::convert::TryInto;
+
+use crate::error::SymmetryError;
+
+/// `TokenSettings::oracle_type` of tokens priced by a Switchboard On-Demand pull feed.
+pub const SWITCHBOARD_ON_DEMAND_ORACLE_TYPE: u8 = 3;
+
+/// Switchboard On-Demand program owning pull feed accounts.
+pub const SWITCHBOARD_ON_DEMAND_PROGRAM_ID: Pubkey = pubkey!("SBondMDrcV3K4kxZR1HNVT7osZxAHVHgYXL5Ze1oMUv");
+
+/// Size of a `PullFeedAccountData` account, discriminator included.
+pub const PULL_FEED_ACCOUNT_SIZE: usize = 3208;
+
+/// Slots a result stays live when the feed doesn't set `max_staleness`, as for legacy Pyth.
+pub const DEFAULT_PULL_FEED_MAX_STALENESS_SLOTS: u64 = 25;
+
+/// Feed values are fixed point with 18 decimals.
+const PULL_FEED_DECIMALS: u32 = 18;
+
+/// Anchor discriminator of `PullFeedAccountData`.
+const PULL_FEED_DISCRIMINATOR: [u8; 8] = [196, 27, 108, 196, 10, 215, 219, 40];
+
+// Offsets in `PullFeedAccountData`, after 32 oracle submissions of 64 bytes.
+const MIN_SAMPLE_SIZE_OFFSET: usize = 2215;
+const LAST_UPDATE_TIMESTAMP_OFFSET: usize = 2216;
+const RESULT_OFFSET: usize = 2264;
+const MAX_STALENESS_OFFSET: usize = 2392;
+
+/// The aggregated result of a Switchboard On-Demand pull feed. Values keep the feed's
+/// 18-decimal fixed point.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PullFeedResult {
+    pub value: i128,
+    pub std_dev: i128,
+    pub num_samples: u8,
+    pub min_sample_size: u8,
+    /// Slot the result was computed at.
+    pub slot: u64,
+    pub last_update_timestamp: i64,
+    /// Slots after `slot` the feed considers the result valid; 0 if unset.
+    pub max_staleness: u32,
+}
+
+impl PullFeedResult {
+    pub fn parse(account_data: &[u8]) -> Result<PullFeedResult> {
+        if account_data.len() < MAX_STALENESS_OFFSET + 4 {
+            return Err(SymmetryError::AccountTooShort {
+                account: "Switchboard pull feed",
+                min: MAX_STALENESS_OFFSET + 4,
+                got: account_data.len(),
+            }.into());
+        }
+        let discriminator: [u8; 8] = account_data[..8].try_into().unwrap();
+        if discriminator != PULL_FEED_DISCRIMINATOR {
+            return Err(SymmetryError::DiscriminatorMismatch {
+                account: "Switchboard pull feed",
+                expected: PULL_FEED_DISCRIMINATOR,
+                got: discriminator,
+            }.into());
+        }
+        let i128_at = |offset: usize| i128::from_le_bytes(account_data[offset..offset + 16].try_into().unwrap());
+        Ok(PullFeedResult {
+            value: i128_at(RESULT_OFFSET),
+            std_dev: i128_at(RESULT_OFFSET + 16),
+            num_samples: account_data[RESULT_OFFSET + 96],
+            min_sample_size: account_data[MIN_SAMPLE_SIZE_OFFSET],
+            slot: u64::from_le_bytes(account_data[RESULT_OFFSET + 104..RESULT_OFFSET + 112].try_into().unwrap()),
+            last_update_timestamp: i64::from_le_bytes(
+                account_data[LAST_UPDATE_TIMESTAMP_OFFSET..LAST_UPDATE_TIMESTAMP_OFFSET + 8].try_into().unwrap(),
+            ),
+            max_staleness: u32::from_le_bytes(account_data[MAX_STALENESS_OFFSET..MAX_STALENESS_OFFSET + 4].try_into().unwrap()),
+        })
+    }
+
+    /// Whether the result may be used at `slot`: positive, sampled by enough oracles, within
+    /// the feed's staleness window and with a standard deviation under a tenth of the value.
+    pub fn is_live(&self, slot: u64) -> bool {
+        let max_staleness = match self.max_staleness {
+            0 => DEFAULT_PULL_FEED_MAX_STALENESS_SLOTS,
+            max_staleness => max_staleness as u64,
+        };
+        self.value > 0
+            && self.num_samples > 0
+            && self.num_samples >= self.min_sample_size
+            && slot <= self.slot.saturating_add(max_staleness)
+            && self.std_dev.saturating_mul(10) <= self.value
+    }
+
+    /// `value` with `decimals` decimals instead of the feed's 18, floored at zero and
+    /// saturating at `u64::MAX`.
+    pub fn value_with_decimals(&self, decimals: u32) -> u64 {
+        rescale(self.value, decimals)
+    }
+
+    pub fn std_dev_with_decimals(&self, decimals: u32) -> u64 {
+        rescale(self.std_dev, decimals)
+    }
+}
+
+fn rescale(value: i128, decimals: u32) -> u64 {
+    let value = value.max(0) as u128;
+    let scaled = if decimals <= PULL_FEED_DECIMALS {
+        value / 10u128.pow(PULL_FEED_DECIMALS - decimals)
+    } else {
+        value.saturating_mul(10u128.saturating_pow(decimals - PULL_FEED_DECIMALS))
+    };
+    scaled.try_into().unwrap_or(u64::MAX)
+}
+
+/// Pull feed account data with this result, used by `OracleAccountBuilder::switchboard`.
+#[cfg(any(test, feature = "test-fixtures"))]
+pub(crate) fn pull_feed_data(value: i128, std_dev: i128, num_samples: u8, slot: u64, max_staleness: u32) -> Vec<u8> {
+    let mut data = vec![0u8; PULL_FEED_ACCOUNT_SIZE];
+    data[..8].copy_from_slice(&PULL_FEED_DISCRIMINATOR);
+    data[MIN_SAMPLE_SIZE_OFFSET] = 1;
+    data[RESULT_OFFSET..RESULT_OFFSET + 16].copy_from_slice(&value.to_le_bytes());
+    data[RESULT_OFFSET + 16..RESULT_OFFSET + 32].copy_from_slice(&std_dev.to_le_bytes());
+    data[RESULT_OFFSET + 96] = num_samples;
+    data[RESULT_OFFSET + 104..RESULT_OFFSET + 112].copy_from_slice(&slot.to_le_bytes());
+    data[MAX_STALENESS_OFFSET..MAX_STALENESS_OFFSET + 4].copy_from_slice(&max_staleness.to_le_bytes());
+    data
+}
+
+#[test]
+fn test_pull_feed_result_parse() {
+    let data = pull_feed_data(150_000_000_000_000_000_000, 50_000_000_000_000_000, 3, 1_000, 100);
+    let result = PullFeedResult::parse(&data).unwrap();
+    assert_eq!(result.num_samples, 3);
+    assert_eq!(result.value_with_decimals(6), 150_000_000);
+    assert_eq!(result.std_dev_with_decimals(6), 50_000);
+    assert!(result.is_live(1_100));
+    assert!(!result.is_live(1_101));
+
+    let unsampled = PullFeedResult::parse(&pull_feed_data(150, 0, 0, 1_000, 0)).unwrap();
+    assert!(!unsampled.is_live(1_000));
+    assert!(matches!(
+        PullFeedResult::parse(&[0u8; PULL_FEED_ACCOUNT_SIZE]).unwrap_err().downcast_ref::<SymmetryError>(),
+        Some(SymmetryError::DiscriminatorMismatch { account: "Switchboard pull feed", .. })
+    ));
+    assert_eq!(
+        PullFeedResult::parse(&data[..100]).unwrap_err().downcast_ref::<SymmetryError>(),
+        Some(&SymmetryError::AccountTooShort { account: "Switchboard pull feed", min: MAX_STALENESS_OFFSET + 4, got: 100 })
+    );
+}
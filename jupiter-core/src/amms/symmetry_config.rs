@@ -0,0 +1,46 @@
+use solana_sdk::pubkey::Pubkey;
+
+use crate::amms::symmetry_token_swap::SymmetryTokenSwap;
+use crate::pda::associated_token_address;
+
+/// Addresses of the Symmetry deployment a fund belongs to. `default()` is mainnet; set
+/// the fields to quote and build instructions against a devnet deployment or a local fork.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SymmetryConfig {
+    pub program_id: Pubkey,
+    pub token_list: Pubkey,
+    pub curve_data: Pubkey,
+    /// Program authority owning every fund's token accounts.
+    pub pda: Pubkey,
+    /// Owner of the protocol's swap fee accounts.
+    pub swap_fee: Pubkey,
+}
+
+impl SymmetryConfig {
+    pub const MAINNET: SymmetryConfig = SymmetryConfig {
+        program_id: SymmetryTokenSwap::SYMMETRY_PROGRAM_ADDRESS,
+        token_list: SymmetryTokenSwap::TOKEN_LIST_ADDRESS,
+        curve_data: SymmetryTokenSwap::CURVE_DATA_ADDRESS,
+        pda: SymmetryTokenSwap::PDA_ADDRESS,
+        swap_fee: SymmetryTokenSwap::SWAP_FEE_ADDRESS,
+    };
+
+    /// Protocol swap fee account for `mint` on this deployment.
+    pub fn swap_fee_ata(&self, mint: &Pubkey) -> Pubkey {
+        associated_token_address(&self.swap_fee, mint)
+    }
+}
+
+impl Default for SymmetryConfig {
+    fn default() -> Self {
+        SymmetryConfig::MAINNET
+    }
+}
+
+#[test]
+fn test_symmetry_config_defaults_to_mainnet() {
+    let mint = Pubkey::new_unique();
+    assert_eq!(SymmetryConfig::default().swap_fee_ata(&mint), crate::pda::swap_fee_ata(&mint));
+    let devnet = SymmetryConfig { swap_fee: Pubkey::new_unique(), ..SymmetryConfig::default() };
+    assert_ne!(devnet.swap_fee_ata(&mint), crate::pda::swap_fee_ata(&mint));
+}
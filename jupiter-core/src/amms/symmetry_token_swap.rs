@@ -1,8 +1,13 @@
 use anchor_lang::prelude::AccountMeta;
-use anyhow::{Result, Error};
+use anyhow::Result;
+use bytemuck::allocation::zeroed_box;
 
-use solana_sdk::{ pubkey, pubkey::Pubkey, instruction::Instruction};
+use solana_sdk::{ pubkey, pubkey::Pubkey, instruction::Instruction, clock::Clock, sysvar};
 use rust_decimal::Decimal;
+use spl_token::state::Mint;
+use solana_sdk::program_pack::Pack;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 
 use jupiter_amm_interface::Swap;
 use jupiter_amm_interface::{
@@ -10,79 +15,475 @@ use jupiter_amm_interface::{
     SwapParams,
 };
 
-use crate::amms::accounts::{FundState, CurveData, TokenList, OraclePrice, TokenPriceData, TokenSettings};
+use crate::error::{SwapSide, SymmetryError};
+use crate::instruction::SwapIxArgs;
+use crate::math::token_swap::SwapResult;
+use crate::amms::account_owners::check_account_owner;
+use crate::amms::amm_config::AmmConfig;
+use crate::amms::fund_metadata::FundMetadata;
+use crate::amms::fee_model::{FeeModel, FeeSplit, OnChainFeeModel};
+use crate::amms::liquidity::LiquidityPolicy;
+use crate::amms::oracle_fallback::{FallbackOracle, OracleSource};
+use crate::amms::partial_fill::{is_weight_limit_exceeded, WeightLimitPolicy};
+use crate::amms::shared_accounts::SharedAccountCache;
+use crate::amms::symmetry_config::SymmetryConfig;
+use crate::amms::consistency::{RebalancePolicy, SlotConsistency, SlotSpread};
+use crate::amms::quote_details::CurveStep;
+use crate::amms::quote_source::QuoteSource;
+use crate::amms::target_weight::TargetWeightSplit;
+use crate::amms::accounts::{FundState, CurveData, TokenList, OraclePrice, TokenPriceData, TokenSettings, Rounding, mul_div_rounded};
+use crate::amms::account_views::{CurveDataView, TokenListView};
 use crate::amms::accounts::{MAX_TOKENS_IN_ASSET_POOL, NUM_OF_POINTS_IN_CURVE_DATA, USE_CURVE_DATA, BPS_DIVIDER, LP_DISABLED, WEIGHT_MULTIPLIER, FUND_LP_DISABLED};
 
+/// Weights a token may reach through swaps, in `WEIGHT_MULTIPLIER` units.
+/// Swaps into the fund must keep the sold token at or below `max_weight`; swaps out of it
+/// must keep the bought token at or above `min_weight`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct WeightBand {
+    pub min_weight: u64,
+    pub max_weight: u64,
+}
+
+/// From/to token weights around a quoted swap, in `WEIGHT_MULTIPLIER` units. The `after`
+/// weights are the ones `quote()` checks, so they include the `AmmConfig` safety margin.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct PostSwapWeights {
+    pub from_weight_before: u64,
+    pub from_weight_after: u64,
+    pub to_weight_before: u64,
+    pub to_weight_after: u64,
+}
+
+/// Inputs of `quote()` that depend on the pair but not on the amount.
+#[derive(Clone, Copy)]
+pub(crate) struct PairSetup {
+    pub(crate) from_token_id: u64,
+    pub(crate) to_token_id: u64,
+    pub(crate) from_token_index: usize,
+    pub(crate) to_token_index: usize,
+    pub(crate) from_token_settings: TokenSettings,
+    pub(crate) to_token_settings: TokenSettings,
+    pub(crate) fund_worth: u64,
+    pub(crate) from_token_target_amount: u64,
+    pub(crate) to_token_target_amount: u64,
+}
+
+/// Token list index of every listed mint. A mint listed twice maps to its first entry.
+fn index_mints(token_list: &TokenList) -> HashMap<Pubkey, usize> {
+    let mut mint_index = HashMap::with_capacity(token_list.list.len());
+    for (token_id, settings) in token_list.list.iter().enumerate() {
+        mint_index.entry(settings.token_mint).or_insert(token_id);
+    }
+    mint_index
+}
+
+#[derive(Clone)]
+#[non_exhaustive]
 pub struct SymmetryTokenSwap {
     key: Pubkey,
     label: String,
+    fund_metadata: Option<FundMetadata>,
     fund_state: FundState,
-    token_list: TokenList,
-    curve_data: CurveData,
-    program_id: Pubkey,
+    fund_token_mint: Option<Mint>,
+    // Boxed so the 23KB and 32KB arrays stay off the stack when the fund is built or moved.
+    token_list: Box<TokenList>,
+    /// Token list index of each mint, built with the token list since `update()` doesn't
+    /// change the listed mints.
+    mint_index: HashMap<Pubkey, usize>,
+    curve_data: Box<CurveData>,
+    symmetry_config: SymmetryConfig,
+    slot_consistency: SlotConsistency,
+    rebalance_policy: RebalancePolicy,
+    liquidity_policy: LiquidityPolicy,
+    weight_limit_policy: WeightLimitPolicy,
+    config: AmmConfig,
+    fee_model: Arc<dyn FeeModel>,
+    fallback_oracles: HashMap<Pubkey, FallbackOracle>,
+    /// Token list indices priced by their fallback oracle in the last update.
+    fallback_priced: HashSet<usize>,
+    last_update_slot: Option<u64>,
+    optimistic_swaps: u32,
+    /// Set when built from the fund state alone; the next `update()` loads the token list.
+    token_list_pending: bool,
 }
 
 impl SymmetryTokenSwap {
 
-    const SYMMETRY_PROGRAM_ADDRESS: Pubkey = pubkey!("2KehYt3KsEQR53jYcxjbQp2d2kCp4AkuQW68atufRwSr");
-    const TOKEN_LIST_ADDRESS: Pubkey = pubkey!("3SnUughtueoVrhevXTLMf586qvKNNXggNsc7NgoMUU1t");
-    const CURVE_DATA_ADDRESS: Pubkey = pubkey!("4QMjSHuM3iS7Fdfi8kZJfHRKoEJSDHEtEwqbChsTcUVK");
-    const PDA_ADDRESS: Pubkey = pubkey!("BLBYiq48WcLQ5SxiftyKmPtmsZPUBEnDEjqEnKGAR4zx");
-    const SWAP_FEE_ADDRESS: Pubkey = pubkey!("AWfpfzA6FYbqx4JLz75PDgsjH7jtBnnmJ6MXW5zNY2Ei");
+    pub const SYMMETRY_PROGRAM_ADDRESS: Pubkey = pubkey!("2KehYt3KsEQR53jYcxjbQp2d2kCp4AkuQW68atufRwSr");
+    pub const TOKEN_LIST_ADDRESS: Pubkey = pubkey!("3SnUughtueoVrhevXTLMf586qvKNNXggNsc7NgoMUU1t");
+    pub(crate) const CURVE_DATA_ADDRESS: Pubkey = pubkey!("4QMjSHuM3iS7Fdfi8kZJfHRKoEJSDHEtEwqbChsTcUVK");
+    pub(crate) const PDA_ADDRESS: Pubkey = pubkey!("BLBYiq48WcLQ5SxiftyKmPtmsZPUBEnDEjqEnKGAR4zx");
+    pub(crate) const SWAP_FEE_ADDRESS: Pubkey = pubkey!("AWfpfzA6FYbqx4JLz75PDgsjH7jtBnnmJ6MXW5zNY2Ei");
 
-    const ASSOCIATED_TOKEN_PROGRAM_ADDRESS: Pubkey = pubkey!("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
-    const SPL_TOKEN_PROGRAM_ADDRESS: Pubkey = pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+    pub(crate) const ASSOCIATED_TOKEN_PROGRAM_ADDRESS: Pubkey = pubkey!("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+    pub(crate) const SPL_TOKEN_PROGRAM_ADDRESS: Pubkey = pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
 
-    const SYMMETRY_PROGRAM_SWAP_INSTRUCTION_ID: u64 = 219478785678209410;
+    pub const SYMMETRY_PROGRAM_SWAP_INSTRUCTION_ID: u64 = 219478785678209410;
 
     pub fn from_keyed_account(fund_state_account: &KeyedAccount, token_list_account: &KeyedAccount) -> Result<Self> {
-        let fund_state_loader = FundState::load(&fund_state_account.account.data);
-        if let Err(e) = fund_state_loader {
-            return Err(e);
-        }
-        let fund_state = fund_state_loader.unwrap();
-        let token_list_loader = TokenList::load(&token_list_account.account.data);
-        if let Err(e) = token_list_loader {
-            return Err(e);
-        }
-        let token_list = token_list_loader.unwrap();
+        SymmetryTokenSwap::from_keyed_account_with_config(fund_state_account, token_list_account, SymmetryConfig::default())
+    }
+
+    /// `from_keyed_account` for a fund of the deployment at `symmetry_config`'s addresses.
+    pub fn from_keyed_account_with_config(
+        fund_state_account: &KeyedAccount,
+        token_list_account: &KeyedAccount,
+        symmetry_config: SymmetryConfig,
+    ) -> Result<Self> {
+        let fund_state = FundState::load(&fund_state_account.account.data)?;
+        let mut token_list = zeroed_box::<TokenList>();
+        TokenListView::load(&token_list_account.account.data)?.copy_into(&mut token_list);
 
         Ok(Self {
             key: fund_state_account.key,
             label: String::from("Symmetry"),
-            fund_state: fund_state,
-            token_list: token_list,
-            curve_data: CurveData::empty(),
-            program_id: SymmetryTokenSwap::SYMMETRY_PROGRAM_ADDRESS,
+            fund_metadata: None,
+            fund_state,
+            fund_token_mint: None,
+            mint_index: index_mints(&token_list),
+            token_list,
+            curve_data: zeroed_box(),
+            symmetry_config,
+            slot_consistency: SlotConsistency::default(),
+            rebalance_policy: RebalancePolicy::default(),
+            liquidity_policy: LiquidityPolicy::default(),
+            weight_limit_policy: WeightLimitPolicy::default(),
+            config: AmmConfig::default(),
+            fee_model: Arc::new(OnChainFeeModel),
+            fallback_oracles: HashMap::new(),
+            fallback_priced: HashSet::new(),
+            last_update_slot: None,
+            optimistic_swaps: 0,
+            token_list_pending: false,
         })
     }
 
-    fn clone(&self) -> SymmetryTokenSwap {
-        SymmetryTokenSwap {
-            key: self.key,
-            label: self.label.clone(),
-            fund_state: FundState {
-                manager: self.fund_state.manager,
-                host_pubkey: self.fund_state.host_pubkey,
-                num_of_tokens: self.fund_state.num_of_tokens,
-                current_comp_token: self.fund_state.current_comp_token,
-                current_comp_amount: self.fund_state.current_comp_amount,
-                target_weight: self.fund_state.target_weight,
-                weight_sum: self.fund_state.weight_sum,
-                rebalance_threshold: self.fund_state.rebalance_threshold,
-                lp_offset_threshold: self.fund_state.lp_offset_threshold,
-                lp_disabled: self.fund_state.lp_disabled,
-            },
-            token_list: TokenList {
-                num_tokens: self.token_list.num_tokens,
-                list: self.token_list.list
-            },
-            curve_data: CurveData {
-                buy: self.curve_data.buy,
-                sell: self.curve_data.sell
-            },
-            program_id: self.program_id,
+    /// Builds the fund from its state account alone, as `Amm::from_keyed_account` does. The
+    /// token list is read by the first `update()`, whose account map must hold it; oracles
+    /// missing from that map are priced from the next one.
+    pub fn from_fund_state_account(fund_state_account: &KeyedAccount) -> Result<Self> {
+        let mut fund = SymmetryTokenSwap::from_parts(
+            fund_state_account.key,
+            String::from("Symmetry"),
+            FundState::load(&fund_state_account.account.data)?,
+            zeroed_box(),
+            zeroed_box(),
+            SymmetryConfig::default(),
+        );
+        fund.token_list_pending = true;
+        Ok(fund)
+    }
+
+    pub(crate) fn from_parts(
+        key: Pubkey,
+        label: String,
+        fund_state: FundState,
+        token_list: Box<TokenList>,
+        curve_data: Box<CurveData>,
+        symmetry_config: SymmetryConfig,
+    ) -> Self {
+        Self {
+            key,
+            label,
+            fund_metadata: None,
+            fund_state,
+            fund_token_mint: None,
+            mint_index: index_mints(&token_list),
+            token_list,
+            curve_data,
+            symmetry_config,
+            slot_consistency: SlotConsistency::default(),
+            rebalance_policy: RebalancePolicy::default(),
+            liquidity_policy: LiquidityPolicy::default(),
+            weight_limit_policy: WeightLimitPolicy::default(),
+            config: AmmConfig::default(),
+            fee_model: Arc::new(OnChainFeeModel),
+            fallback_oracles: HashMap::new(),
+            fallback_priced: HashSet::new(),
+            last_update_slot: None,
+            optimistic_swaps: 0,
+            token_list_pending: false,
+        }
+    }
+
+    pub fn with_slot_consistency(mut self, slot_consistency: SlotConsistency) -> Self {
+        self.slot_consistency = slot_consistency;
+        self
+    }
+
+    pub fn with_rebalance_policy(mut self, rebalance_policy: RebalancePolicy) -> Self {
+        self.rebalance_policy = rebalance_policy;
+        self
+    }
+
+    pub fn with_liquidity_policy(mut self, liquidity_policy: LiquidityPolicy) -> Self {
+        self.liquidity_policy = liquidity_policy;
+        self
+    }
+
+    pub fn with_weight_limit_policy(mut self, weight_limit_policy: WeightLimitPolicy) -> Self {
+        self.weight_limit_policy = weight_limit_policy;
+        self
+    }
+
+    pub fn with_config(mut self, config: AmmConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Replaces the on-chain fee split used by quotes and `apply_own_swap`.
+    pub fn with_fee_model(mut self, fee_model: Arc<dyn FeeModel>) -> Self {
+        self.fee_model = fee_model;
+        self
+    }
+
+    pub fn fee_model(&self) -> &dyn FeeModel {
+        self.fee_model.as_ref()
+    }
+
+    /// Prices `mint` from `fallback` whenever its primary oracle is offline. `update()` then
+    /// needs the fallback account too; it is listed in `get_accounts_to_update()`.
+    pub fn with_fallback_oracle(mut self, mint: Pubkey, fallback: FallbackOracle) -> Self {
+        self.fallback_oracles.insert(mint, fallback);
+        self
+    }
+
+    /// Fallback oracles by mint.
+    pub fn fallback_oracles(&self) -> &HashMap<Pubkey, FallbackOracle> {
+        &self.fallback_oracles
+    }
+
+    /// Which oracle priced `mint` in the last `update()`, if it is listed.
+    pub fn oracle_source(&self, mint: &Pubkey) -> Option<OracleSource> {
+        let token_id = self.token_id(mint)?;
+        Some(if self.fallback_priced.contains(&token_id) { OracleSource::Fallback } else { OracleSource::Primary })
+    }
+
+    pub fn symmetry_config(&self) -> &SymmetryConfig {
+        &self.symmetry_config
+    }
+
+    pub fn config(&self) -> &AmmConfig {
+        &self.config
+    }
+
+    /// Sets the fund's name and symbol, shown in `label()` when
+    /// `AmmConfig::label_with_fund_symbol` is set.
+    pub fn with_fund_metadata(mut self, fund_metadata: FundMetadata) -> Self {
+        self.fund_metadata = Some(fund_metadata);
+        self
+    }
+
+    pub fn fund_metadata(&self) -> Option<&FundMetadata> {
+        self.fund_metadata.as_ref()
+    }
+
+    /// The fund token mint, once an update's account map included it.
+    pub fn fund_token_mint(&self) -> Option<&Mint> {
+        self.fund_token_mint.as_ref()
+    }
+
+    /// Whether the fund is part-way through a rebalance or refilter, when its composition
+    /// arrays may be transiently inconsistent.
+    pub fn rebalance_in_progress(&self) -> bool {
+        self.fund_state.rebalance_in_progress()
+    }
+
+    /// Highest account slot seen by the last `update_with_slots`, if slots were provided.
+    pub fn last_update_slot(&self) -> Option<u64> {
+        self.last_update_slot
+    }
+
+    /// `update()` with per-account slot metadata (e.g. RPC context slots). The slot spread
+    /// across the accounts is checked against the configured `SlotConsistency` before
+    /// the state is applied.
+    pub fn update_with_slots(&mut self, account_map: &AccountMap, slots: &HashMap<Pubkey, u64>) -> Result<SlotSpread> {
+        let spread = self.slot_consistency.check(&self.get_accounts_to_update(), slots)?;
+        self.update(account_map)?;
+        if spread.max_slot > 0 {
+            self.last_update_slot = Some(spread.max_slot);
         }
+        Ok(spread)
+    }
+
+    /// Copy of the fund with the target weights of the given mints replaced and `weight_sum`
+    /// recomputed, for previewing how a reweight changes quotes and rebalance flows.
+    /// Weights use the same units as `FundState::target_weight`; unlisted tokens keep theirs.
+    pub fn simulate_with_weights(&self, weights: &[(Pubkey, u64)]) -> Result<SymmetryTokenSwap> {
+        let mut simulated = self.clone();
+        for (mint, weight) in weights {
+            let index = self.composition_index(mint)
+                .ok_or(SymmetryError::TokenNotInFund { mint: *mint })?;
+            simulated.fund_state.target_weight[index] = *weight;
+        }
+        simulated.fund_state.weight_sum = simulated.fund_state.target_weight
+            [..simulated.fund_state.num_tokens()]
+            .iter()
+            .try_fold(0u64, |sum, weight| sum.checked_add(*weight))
+            .ok_or(SymmetryError::Arithmetic("Simulated target weights overflow u64"))?;
+        if simulated.fund_state.weight_sum == 0 {
+            return Err(SymmetryError::ZeroWeightSum.into());
+        }
+        Ok(simulated)
+    }
+
+    pub fn fund_state(&self) -> &FundState {
+        &self.fund_state
+    }
+
+    pub fn token_list(&self) -> &TokenList {
+        &self.token_list
+    }
+
+    /// Curves as of the last update. Only entries of tokens in the fund's composition are
+    /// kept up to date.
+    pub fn curve_data(&self) -> &CurveData {
+        &self.curve_data
+    }
+
+    /// Index of `mint` in the fund's current composition, if it is one of the fund's tokens.
+    pub fn composition_index(&self, mint: &Pubkey) -> Option<usize> {
+        QuoteSource::composition_index(self, mint)
+    }
+
+    /// Token list index of `mint`.
+    pub fn token_id(&self, mint: &Pubkey) -> Option<usize> {
+        self.mint_index.get(mint).copied()
+    }
+
+    /// Whether `mint` is in the fund and can currently be swapped against it.
+    pub fn is_lp_enabled(&self, mint: &Pubkey) -> bool {
+        if self.fund_state.lp_disabled == FUND_LP_DISABLED {
+            return false;
+        }
+        match self.composition_index(mint) {
+            Some(index) => self.token_list.list[self.fund_state.current_comp_token[index] as usize].lp_on != LP_DISABLED,
+            None => false,
+        }
+    }
+
+    /// Locally applies a swap we submitted ourselves, so the next leg can be quoted against the
+    /// expected post-trade composition before the fund state account is refetched. The fund
+    /// receives `input_amount`; the output token loses `expected_output_amount` plus the
+    /// symmetry/host/manager shares of `fees` (the fund's own share stays in the pool).
+    /// The adjustment is discarded by the next `update()`.
+    pub fn apply_own_swap(&mut self, input_mint: &Pubkey, output_mint: &Pubkey, swap_result: &SwapResult) -> Result<()> {
+        let from_token_index = self.composition_index(input_mint)
+            .ok_or(SymmetryError::TokenNotInFund { mint: *input_mint })?;
+        let to_token_index = self.composition_index(output_mint)
+            .ok_or(SymmetryError::TokenNotInFund { mint: *output_mint })?;
+        let input_amount: u64 = swap_result.input_amount.try_into()?;
+        let output_amount: u64 = swap_result.expected_output_amount.try_into()?;
+        let total_fees: u64 = swap_result.fees.try_into()?;
+
+        let fees_leaving_fund = self.fee_model.split_fees(total_fees, &self.token_list)?.leaving_fund();
+
+        let from_amount = &mut self.fund_state.current_comp_amount[from_token_index];
+        *from_amount = from_amount.checked_add(input_amount)
+            .ok_or(SymmetryError::Arithmetic("From token amount overflow"))?;
+        let to_amount = &mut self.fund_state.current_comp_amount[to_token_index];
+        *to_amount = output_amount.checked_add(fees_leaving_fund)
+            .and_then(|leaving| to_amount.checked_sub(leaving))
+            .ok_or(SymmetryError::Arithmetic("Swap output exceeds the fund's to token amount"))?;
+        self.optimistic_swaps = self.optimistic_swaps.saturating_add(1);
+        Ok(())
+    }
+
+    /// Number of `apply_own_swap` adjustments not yet confirmed by an `update()`.
+    pub fn optimistic_swaps(&self) -> u32 {
+        self.optimistic_swaps
+    }
+
+    /// Total USD value of the fund's holdings at oracle avg prices.
+    /// Fails if any fund token has an offline oracle.
+    pub fn fund_worth(&self) -> Result<u64> {
+        self.fund_worth_requiring_live(|_| true)
+    }
+
+    /// `fund_worth()` requiring a live oracle only for the composition indices accepted by
+    /// `live_required`.
+    fn fund_worth_requiring_live(&self, live_required: impl Fn(usize) -> bool) -> Result<u64> {
+        QuoteSource::fund_worth_requiring_live(self, live_required)
+    }
+
+    /// Fund tokens whose oracle was offline at the last `update()`, in composition order.
+    pub fn stale_oracle_mints(&self) -> Vec<Pubkey> {
+        self.fund_state.token_ids()
+            .iter()
+            .map(|&token_id| &self.token_list.list[token_id as usize])
+            .filter(|token_settings| token_settings.oracle_price.oracle_live == 0)
+            .map(|token_settings| token_settings.token_mint)
+            .collect()
+    }
+
+    /// Amount of `mint` the fund would hold at exactly its target weight, as used by `quote()`:
+    /// it honours the rebalance policy and `allow_stale_oracles` the same way.
+    pub fn target_amount(&self, mint: &Pubkey) -> Result<u64> {
+        let index = self.composition_index(mint)
+            .ok_or(SymmetryError::TokenNotInFund { mint: *mint })?;
+        self.check_rebalance_policy()?;
+        let token_settings = self.token_list.list[self.fund_state.current_comp_token[index] as usize];
+        let fund_worth = self.quote_fund_worth(|i| i == index)?;
+        Ok(self.target_amount_at(index, &token_settings, fund_worth))
+    }
+
+    /// Band around the target weight of the token at composition index `token_index`, offset
+    /// by `rebalance_threshold * lp_offset_threshold` (bps of bps), as enforced by `quote()`.
+    pub fn allowed_weight_band(&self, token_index: usize) -> WeightBand {
+        QuoteSource::allowed_weight_band(self, token_index)
+    }
+
+    /// `TokenNotInFund` for whichever of the two mints is missing from the composition.
+    pub(crate) fn token_not_in_fund(&self, input_mint: &Pubkey, output_mint: &Pubkey) -> SymmetryError {
+        let mint = if self.composition_index(input_mint).is_none() { input_mint } else { output_mint };
+        SymmetryError::TokenNotInFund { mint: *mint }
+    }
+
+    /// Output for `in_amount` at oracle avg prices with no fees or curve, the reference
+    /// `quote()` measures its fee percentage against.
+    pub fn fair_out_amount(&self, input_mint: &Pubkey, output_mint: &Pubkey, in_amount: u64) -> Option<u64> {
+        let from_token_settings = self.token_list.list[self.fund_state.current_comp_token[self.composition_index(input_mint)?] as usize];
+        let to_token_settings = self.token_list.list[self.fund_state.current_comp_token[self.composition_index(output_mint)?] as usize];
+        Some(SymmetryTokenSwap::usd_value_to_amount(
+            SymmetryTokenSwap::amount_to_usd_value(
+                in_amount,
+                from_token_settings.decimals,
+                from_token_settings.oracle_price.avg_price
+            ),
+            to_token_settings.decimals,
+            to_token_settings.oracle_price.avg_price
+        ))
+    }
+
+    /// Sell, avg and buy oracle prices of `mint` as of the last `update()`, if it is one of
+    /// the fund's tokens.
+    pub fn oracle_price(&self, mint: &Pubkey) -> Option<OraclePrice> {
+        let index = self.composition_index(mint)?;
+        Some(self.token_list.list[self.fund_state.current_comp_token[index] as usize].oracle_price)
+    }
+
+    /// Oracle prices of every fund token as of the last `update()`, in composition order.
+    pub fn all_prices(&self) -> Vec<(Pubkey, OraclePrice)> {
+        self.fund_state.token_ids()
+            .iter()
+            .map(|&token_id| {
+                let token_settings = &self.token_list.list[token_id as usize];
+                (token_settings.token_mint, token_settings.oracle_price)
+            })
+            .collect()
+    }
+
+    /// USD value of the fund's holding of `mint` at the oracle avg price.
+    pub fn token_worth(&self, mint: &Pubkey) -> Option<u64> {
+        let index = self.composition_index(mint)?;
+        let token_settings = self.token_list.list[self.fund_state.current_comp_token[index] as usize];
+        Some(SymmetryTokenSwap::amount_to_usd_value(
+            self.fund_state.current_comp_amount[index],
+            token_settings.decimals,
+            token_settings.oracle_price.avg_price
+        ))
     }
 
     pub fn mul_div(a: u64, b: u64, c: u64) -> u64 {
@@ -93,12 +494,22 @@ impl SymmetryTokenSwap {
         }
     }
 
+    /// Rounds down; see `amount_to_usd_value_rounded`.
     pub fn amount_to_usd_value(amount: u64, decimals: u8, price: u64) -> u64 {
-        SymmetryTokenSwap::mul_div(amount, price, u64::pow(10,decimals as u32))
+        SymmetryTokenSwap::amount_to_usd_value_rounded(amount, decimals, price, Rounding::Floor)
     }
 
+    /// Rounds down; see `usd_value_to_amount_rounded`.
     pub fn usd_value_to_amount(worth: u64, decimals: u8, price: u64) -> u64 {
-        SymmetryTokenSwap::mul_div(worth, u64::pow(10,decimals as u32), price)
+        SymmetryTokenSwap::usd_value_to_amount_rounded(worth, decimals, price, Rounding::Floor)
+    }
+
+    pub fn amount_to_usd_value_rounded(amount: u64, decimals: u8, price: u64, rounding: Rounding) -> u64 {
+        mul_div_rounded(amount, price, u64::pow(10,decimals as u32), rounding)
+    }
+
+    pub fn usd_value_to_amount_rounded(worth: u64, decimals: u8, price: u64, rounding: Rounding) -> u64 {
+        mul_div_rounded(worth, u64::pow(10,decimals as u32), price, rounding)
     }
 
     pub fn compute_value_of_sold_token(
@@ -109,8 +520,35 @@ impl SymmetryTokenSwap {
         target_amount: u64,
         curve_data: TokenPriceData
     ) -> u64 {
+        SymmetryTokenSwap::compute_value_of_sold_token_with_split(amount, token_settings, price, start_amount, target_amount, curve_data).0
+    }
+
+    /// Same as [`SymmetryTokenSwap::compute_value_of_sold_token`], also returning how the trade split
+    /// around the target amount.
+    pub fn compute_value_of_sold_token_with_split(
+        amount: u64,
+        token_settings: TokenSettings,
+        price: OraclePrice,
+        start_amount: u64,
+        target_amount: u64,
+        curve_data: TokenPriceData
+    ) -> (u64, TargetWeightSplit) {
+        SymmetryTokenSwap::step_sold_token(amount, token_settings, price, start_amount, target_amount, curve_data, |_| {})
+    }
+
+    /// Steps `amount` through the sell curve, reporting each interval it trades in to `on_step`.
+    pub(crate) fn step_sold_token(
+        amount: u64,
+        token_settings: TokenSettings,
+        price: OraclePrice,
+        start_amount: u64,
+        target_amount: u64,
+        curve_data: TokenPriceData,
+        mut on_step: impl FnMut(CurveStep),
+    ) -> (u64, TargetWeightSplit) {
+        let mut split = TargetWeightSplit::default();
         let mut current_amount = start_amount;
-        let mut curve_offset = if start_amount > target_amount { start_amount - target_amount } else { 0 };
+        let mut curve_offset = start_amount.saturating_sub(target_amount);
         let mut current_output_value: u64 = 0;
         let mut amount_left: u64 = amount;
         let mut current_price = price.sell_price;
@@ -118,9 +556,11 @@ impl SymmetryTokenSwap {
         for step in 0..NUM_OF_POINTS_IN_CURVE_DATA+1 {
             let step_amount = if step < NUM_OF_POINTS_IN_CURVE_DATA
                 { curve_data.amount[step] } else { amount_left };
-            if step < NUM_OF_POINTS_IN_CURVE_DATA && curve_data.price[step] < current_price {
-                if token_settings.use_curve_data == USE_CURVE_DATA
-                    { current_price = curve_data.price[step]; }
+            if step < NUM_OF_POINTS_IN_CURVE_DATA
+                && curve_data.price[step] < current_price
+                && token_settings.use_curve_data == USE_CURVE_DATA
+            {
+                current_price = curve_data.price[step];
             }
             if step == NUM_OF_POINTS_IN_CURVE_DATA { curve_offset = 0; }
             if step_amount <= curve_offset {
@@ -131,31 +571,42 @@ impl SymmetryTokenSwap {
             curve_offset = 0;
             if amount_in_interval > amount_left { amount_in_interval = amount_left };
             let mut amount_before_tw = amount_in_interval;
-            if current_amount >= target_amount
-                { amount_before_tw = 0; } else
-            if current_amount + amount_in_interval >= target_amount
-                { amount_before_tw -= current_amount + amount_in_interval - target_amount; }
+            if current_amount >= target_amount {
+                amount_before_tw = 0;
+            } else if current_amount.saturating_add(amount_in_interval) >= target_amount {
+                amount_before_tw -= current_amount.saturating_add(amount_in_interval) - target_amount;
+            }
             let amount_after_tw = amount_in_interval - amount_before_tw;
-            let value_before_tw = SymmetryTokenSwap::amount_to_usd_value(
+            let value_before_tw = SymmetryTokenSwap::amount_to_usd_value_rounded(
                 amount_before_tw,
                 token_settings.decimals,
-                current_price
+                current_price,
+                Rounding::Floor
             );
-            let value_after_tw = SymmetryTokenSwap::amount_to_usd_value(
+            let value_after_tw = SymmetryTokenSwap::amount_to_usd_value_rounded(
                 amount_after_tw,
                 token_settings.decimals,
-                current_price
+                current_price,
+                Rounding::Floor
             );
-            let fees =
-                SymmetryTokenSwap::mul_div(value_before_tw, token_settings.token_swap_fee_before_tw_bps as u64, BPS_DIVIDER) +
-                SymmetryTokenSwap::mul_div(value_after_tw, token_settings.token_swap_fee_after_tw_bps as u64, BPS_DIVIDER);
-            current_output_value += value_before_tw + value_after_tw - fees;
+            let fees_before_tw = SymmetryTokenSwap::mul_div(value_before_tw, token_settings.token_swap_fee_before_tw_bps as u64, BPS_DIVIDER);
+            let fees_after_tw = SymmetryTokenSwap::mul_div(value_after_tw, token_settings.token_swap_fee_after_tw_bps as u64, BPS_DIVIDER);
+            let fees = fees_before_tw + fees_after_tw;
+            split.add(value_before_tw, value_after_tw, fees_before_tw, fees_after_tw);
+            on_step(CurveStep {
+                step,
+                price: current_price,
+                amount: amount_in_interval,
+                value: value_before_tw + value_after_tw,
+                fees,
+            });
+            current_output_value = current_output_value.saturating_add(value_before_tw + value_after_tw - fees);
             amount_left -= amount_in_interval;
-            current_amount += amount_in_interval;
+            current_amount = current_amount.saturating_add(amount_in_interval);
             if amount_left == 0 { break; }
         };
-        
-        current_output_value
+
+        (current_output_value, split)
     }
 
     pub fn compute_amount_of_bought_token(
@@ -166,18 +617,50 @@ impl SymmetryTokenSwap {
         target_amount: u64,
         curve_data: TokenPriceData,
     ) -> u64 {
+        SymmetryTokenSwap::compute_amount_of_bought_token_with_split(value, token_settings, price, start_amount, target_amount, curve_data).0
+    }
+
+    /// Same as [`SymmetryTokenSwap::compute_amount_of_bought_token`], also returning how the trade split
+    /// around the target amount.
+    pub fn compute_amount_of_bought_token_with_split(
+        value: u64,
+        token_settings: TokenSettings,
+        price: OraclePrice,
+        start_amount: u64,
+        target_amount: u64,
+        curve_data: TokenPriceData,
+    ) -> (u64, TargetWeightSplit) {
+        SymmetryTokenSwap::step_bought_token(value, token_settings, price, start_amount, target_amount, curve_data, |_| {})
+    }
+
+    /// Steps `value` through the buy curve, reporting each interval it trades in to `on_step`.
+    pub(crate) fn step_bought_token(
+        value: u64,
+        token_settings: TokenSettings,
+        price: OraclePrice,
+        start_amount: u64,
+        target_amount: u64,
+        curve_data: TokenPriceData,
+        mut on_step: impl FnMut(CurveStep),
+    ) -> (u64, TargetWeightSplit) {
+        let mut split = TargetWeightSplit::default();
         let mut current_amount = start_amount;
-        let mut curve_offset = if start_amount < target_amount { target_amount - start_amount } else { 0 };
+        let mut curve_offset = target_amount.saturating_sub(start_amount);
         let mut current_output_amount: u64 = 0;
         let mut value_left: u64 = value;
         let mut current_price = price.buy_price;
 
         for step in 0..NUM_OF_POINTS_IN_CURVE_DATA+1 {
-            let step_amount = if step < NUM_OF_POINTS_IN_CURVE_DATA
-                { curve_data.amount[step] } else
-                { SymmetryTokenSwap::usd_value_to_amount(value_left * 2, token_settings.decimals, current_price) };
-            if step < NUM_OF_POINTS_IN_CURVE_DATA && curve_data.price[step] > current_price {
-                if token_settings.use_curve_data == USE_CURVE_DATA { current_price = curve_data.price[step]; };
+            let step_amount = if step < NUM_OF_POINTS_IN_CURVE_DATA {
+                curve_data.amount[step]
+            } else {
+                SymmetryTokenSwap::usd_value_to_amount(value_left.saturating_mul(2), token_settings.decimals, current_price)
+            };
+            if step < NUM_OF_POINTS_IN_CURVE_DATA
+                && curve_data.price[step] > current_price
+                && token_settings.use_curve_data == USE_CURVE_DATA
+            {
+                current_price = curve_data.price[step];
             }
             if step == NUM_OF_POINTS_IN_CURVE_DATA { curve_offset = 0; }
             if step_amount <= curve_offset {
@@ -187,332 +670,391 @@ impl SymmetryTokenSwap {
             let mut amount_in_interval = step_amount - curve_offset;
             curve_offset = 0;
 
-            let mut value_in_interval = SymmetryTokenSwap::amount_to_usd_value(amount_in_interval, token_settings.decimals, current_price);
+            // The interval's tokens cost at least their value: round the cost up.
+            let mut value_in_interval = SymmetryTokenSwap::amount_to_usd_value_rounded(amount_in_interval, token_settings.decimals, current_price, Rounding::Ceil);
             if value_in_interval > value_left {
                 value_in_interval = value_left;
                 amount_in_interval = SymmetryTokenSwap::usd_value_to_amount(value_in_interval, token_settings.decimals, current_price);
             }
 
             let mut value_before_tw = value_in_interval;
-            if current_amount <= target_amount
-                { value_before_tw = 0; } else
-            if current_amount <= target_amount + amount_in_interval
-                { value_before_tw -= SymmetryTokenSwap::amount_to_usd_value(target_amount + amount_in_interval - current_amount, token_settings.decimals, current_price)}
+            if current_amount <= target_amount {
+                value_before_tw = 0;
+            } else if current_amount <= target_amount.saturating_add(amount_in_interval) {
+                value_before_tw = value_before_tw.saturating_sub(SymmetryTokenSwap::amount_to_usd_value(
+                    target_amount.saturating_add(amount_in_interval) - current_amount,
+                    token_settings.decimals,
+                    current_price
+                ));
+            }
             let value_after_tw = value_in_interval - value_before_tw;
 
-            let fees =
-                SymmetryTokenSwap::mul_div(value_before_tw, token_settings.token_swap_fee_before_tw_bps as u64, BPS_DIVIDER) +
-                SymmetryTokenSwap::mul_div(value_after_tw, token_settings.token_swap_fee_after_tw_bps as u64, BPS_DIVIDER);
+            let fees_before_tw = SymmetryTokenSwap::mul_div(value_before_tw, token_settings.token_swap_fee_before_tw_bps as u64, BPS_DIVIDER);
+            let fees_after_tw = SymmetryTokenSwap::mul_div(value_after_tw, token_settings.token_swap_fee_after_tw_bps as u64, BPS_DIVIDER);
+            let fees = fees_before_tw + fees_after_tw;
+            split.add(value_before_tw, value_after_tw, fees_before_tw, fees_after_tw);
             
-            let amount_bought = SymmetryTokenSwap::usd_value_to_amount(value_in_interval - fees, token_settings.decimals, current_price);
+            let amount_bought = SymmetryTokenSwap::usd_value_to_amount_rounded(value_in_interval - fees, token_settings.decimals, current_price, Rounding::Floor);
 
-            current_output_amount += amount_bought;
+            on_step(CurveStep {
+                step,
+                price: current_price,
+                amount: amount_bought,
+                value: value_in_interval,
+                fees,
+            });
+
+            current_output_amount = current_output_amount.saturating_add(amount_bought);
             value_left -= value_in_interval;
             if amount_bought > current_amount
                 { current_amount = 0; } else { current_amount -= amount_bought; }
             if value_left == 0 { break; }
         };
 
-        current_output_amount
+        (current_output_amount, split)
     }
 
     
 }
 
-impl Amm for SymmetryTokenSwap {
-
-    fn from_keyed_account(keyed_account: &KeyedAccount) -> Result<Self> {
-        SymmetryTokenSwap::from_keyed_account(keyed_account, keyed_account)
+impl SymmetryTokenSwap {
+    /// `quote()` together with the from/to token weights before and after the swap.
+    pub fn quote_with_weights(&self, quote_params: &QuoteParams) -> Result<(Quote, PostSwapWeights)> {
+        self.quote_with_setup(quote_params).map(|(quote, weights, _)| (quote, weights))
     }
 
-    // fn from_keyed_account(keyed_account_1: &KeyedAccount, keyed_account_2: &KeyedAccount) -> Result<Self> {
-    //     SymmetryTokenSwap::from_keyed_account(keyed_account_1, keyed_account_2)
-    // }
-
-    fn label(&self) -> String {
-        self.label.clone()
+    /// `quote_with_weights` together with the `pair_setup` it quoted against, for callers
+    /// that need the setup's intermediate values without computing it again.
+    pub(crate) fn quote_with_setup(&self, quote_params: &QuoteParams) -> Result<(Quote, PostSwapWeights, PairSetup)> {
+        let setup = self.pair_setup(&quote_params.input_mint, &quote_params.output_mint)?;
+        let (quote, weights) = match self.quote_pair(&setup, quote_params.in_amount) {
+            Err(error) if self.weight_limit_policy == WeightLimitPolicy::PartialFill && is_weight_limit_exceeded(&error) => {
+                self.quote_partial_fill(quote_params).map_err(|_| error)?
+            }
+            result => result?,
+        };
+        Ok((quote, weights, setup))
     }
 
-    fn program_id(&self) -> Pubkey {
-        self.program_id
+    /// Validates the pair and computes the parts of `quote()` that don't depend on the amount.
+    pub(crate) fn pair_setup(&self, input_mint: &Pubkey, output_mint: &Pubkey) -> Result<PairSetup> {
+        pair_setup_for(self, input_mint, output_mint)
     }
 
-    fn key(&self) -> Pubkey {
-        self.key
+    /// Quotes `from_amount` against a `pair_setup` computed from this fund's current state.
+    pub(crate) fn quote_pair(&self, setup: &PairSetup, from_amount: u64) -> Result<(Quote, PostSwapWeights)> {
+        quote_pair_for(self, setup, from_amount)
     }
+}
 
-    fn get_reserve_mints(&self) -> Vec<Pubkey> {
-        let mut vec: Vec<Pubkey> = Vec::new();
-        for i in 0..self.fund_state.num_of_tokens as usize {
-            if self.token_list.list[self.fund_state.current_comp_token[i] as usize].lp_on != LP_DISABLED {
-                vec.push(self.token_list.list[self.fund_state.current_comp_token[i] as usize].token_mint)
-            }
-        }
-        return vec;
-    }
+/// `SymmetryTokenSwap::pair_setup` for any fund representation.
+pub(crate) fn pair_setup_for<F: QuoteSource + ?Sized>(fund: &F, input_mint: &Pubkey, output_mint: &Pubkey) -> Result<PairSetup> {
 
-    fn get_accounts_to_update(&self) -> Vec<Pubkey> {
-        let mut accounts_to_update: Vec<Pubkey> = Vec::new();
-        accounts_to_update.push(SymmetryTokenSwap::CURVE_DATA_ADDRESS);
-        accounts_to_update.push(self.key);
-        for i in 0..MAX_TOKENS_IN_ASSET_POOL {
-            if self.token_list.list[i].oracle_account != Pubkey::default() {
-                accounts_to_update.push(self.token_list.list[i].oracle_account)
-            }
-        }
-        return accounts_to_update;
+    let fund_state = *fund.fund_state();
+    
+    if fund_state.lp_disabled == FUND_LP_DISABLED {
+        return Err(SymmetryError::FundLpDisabled.into())
+    }
+    fund.check_rebalance_policy()?;
+    let from_token_id_option = fund.token_id(input_mint);
+    let to_token_id_option = fund.token_id(output_mint);
+    
+    if from_token_id_option.is_none() {
+        return Err(SymmetryError::TokenNotSupported { mint: *input_mint }.into())
+    }
+    if to_token_id_option.is_none() {
+        return Err(SymmetryError::TokenNotSupported { mint: *output_mint }.into())
     }
 
-    fn update(&mut self, account_map: &AccountMap) -> Result<()> {
-        let curve_data_loader = CurveData::load(try_get_account_data(account_map, &SymmetryTokenSwap::CURVE_DATA_ADDRESS)?);
-        if let Err(e) = curve_data_loader {
-            return Err(e);
-        }
-        self.curve_data = curve_data_loader.unwrap();
+    let from_token_id: u64 = from_token_id_option.unwrap() as u64;
+    let to_token_id: u64 = to_token_id_option.unwrap() as u64;
+    fund.check_token_lp_on(from_token_id as usize)?;
+    fund.check_token_lp_on(to_token_id as usize)?;
 
-        let fund_state_loader = FundState::load(try_get_account_data(account_map, &self.key)?);
-        if let Err(e) = fund_state_loader {
-            return Err(e);
-        }
-        self.fund_state = fund_state_loader.unwrap();
+    let from_token_settings = *fund.token_settings(from_token_id as usize)
+        .ok_or(SymmetryError::TokenNotSupported { mint: *input_mint })?;
+    let to_token_settings = *fund.token_settings(to_token_id as usize)
+        .ok_or(SymmetryError::TokenNotSupported { mint: *output_mint })?;
 
-        for i in 0..MAX_TOKENS_IN_ASSET_POOL {
-            if self.token_list.list[i].oracle_account != Pubkey::default() {
-                let oracle_loader = OraclePrice::load(
-                    try_get_account_data(account_map, &self.token_list.list[i].oracle_account)?,
-                    self.token_list.list[i]
-                );
-                if let Err(e) = oracle_loader {
-                    return Err(e);
-                }
-                self.token_list.list[i].oracle_price = oracle_loader.unwrap();
-            }
-        }
+    let from_token_index_option = fund.composition_index(input_mint);
+    let to_token_index_option = fund.composition_index(output_mint);
 
-        Ok(())
+    if from_token_index_option.is_none() {
+        return Err(SymmetryError::TokenNotInFund { mint: *input_mint }.into())
+    }
+    if to_token_index_option.is_none() {
+        return Err(SymmetryError::TokenNotInFund { mint: *output_mint }.into())
     }
 
-    fn quote(&self, quote_params: &QuoteParams) -> Result<Quote> {
-
-        let fund_state = self.fund_state;
-        let token_list = self.token_list;
-        let curve_data = self.curve_data;
-        
-        if fund_state.lp_disabled == FUND_LP_DISABLED {
-            return Err(Error::msg("Manager has disabled liquidity provision on this fund"))
-        }
-        let from_amount: u64 = quote_params.in_amount;
-        let from_token_id_option = token_list.list.iter().position(|&x| x.token_mint == quote_params.input_mint);
-        let to_token_id_option = token_list.list.iter().position(|&x| x.token_mint == quote_params.output_mint);
-        
-        if from_token_id_option.is_none() {
-            return Err(Error::msg("From token not found in supported tokens"))
-        }
-        if to_token_id_option.is_none() {
-            return Err(Error::msg("To token not found in supported tokens"))
-        }
+    let from_token_index: usize = from_token_index_option.unwrap();
+    let to_token_index: usize = to_token_index_option.unwrap();
     
-        let from_token_id: u64 = from_token_id_option.unwrap() as u64;
-        let to_token_id: u64 = to_token_id_option.unwrap() as u64;
-    
-        let from_token_settings = token_list.list[from_token_id as usize];
-        let to_token_settings = token_list.list[to_token_id as usize];
-    
-        let from_token_index_option = fund_state.current_comp_token.iter()
-            .position(|&x| x == (from_token_id as u64));
-        let to_token_index_option = fund_state.current_comp_token.iter()
-            .position(|&x| x == (to_token_id as u64));
+
+    let fund_worth = fund.quote_fund_worth(|index| index == from_token_index || index == to_token_index)?;
     
-        if from_token_index_option.is_none() {
-            return Err(Error::msg("From token not found in the fund composition"))
-        }
-        if to_token_index_option.is_none() {
-            return Err(Error::msg("To token not found in the fund composition"))
-        }
+    let from_token_target_amount: u64 = fund.target_amount_at(from_token_index, &from_token_settings, fund_worth);
+    let to_token_target_amount: u64 = fund.target_amount_at(to_token_index, &to_token_settings, fund_worth);
 
-        let from_token_index: usize = from_token_index_option.unwrap() as usize;
-        let to_token_index: usize = to_token_index_option.unwrap() as usize;
-        
+    Ok(PairSetup {
+        from_token_id,
+        to_token_id,
+        from_token_index,
+        to_token_index,
+        from_token_settings,
+        to_token_settings,
+        fund_worth,
+        from_token_target_amount,
+        to_token_target_amount,
+    })
+}
 
-        let mut fund_worth = 0;
-        for i in 0..(fund_state.num_of_tokens as usize) {
-            let token = fund_state.current_comp_token[i] as usize;
-            let token_settings = token_list.list[token];
-            let token_price = token_settings.oracle_price;
-            if token_price.oracle_live == 0 {
-                return Err(Error::msg("One of the tokens has offline oracle status"))
-            }
-            fund_worth += SymmetryTokenSwap::amount_to_usd_value(
-                fund_state.current_comp_amount[i],
-                token_settings.decimals,
-                token_price.avg_price
-            );
-        }
-    
-        let from_token_price = from_token_settings.oracle_price;
-        let to_token_price = to_token_settings.oracle_price;
-        
-        let from_token_target_amount: u64 = SymmetryTokenSwap::usd_value_to_amount(
-            SymmetryTokenSwap::mul_div(fund_state.target_weight[from_token_index], fund_worth, fund_state.weight_sum),
-            from_token_settings.decimals,
-            from_token_price.avg_price
-        );
-        let to_token_target_amount: u64 = SymmetryTokenSwap::usd_value_to_amount(
-            SymmetryTokenSwap::mul_div(fund_state.target_weight[to_token_index], fund_worth, fund_state.weight_sum),
-            to_token_settings.decimals,
-            to_token_price.avg_price,
-        );
-    
-        let value = SymmetryTokenSwap::compute_value_of_sold_token(
+/// `SymmetryTokenSwap::quote_pair` for any fund representation.
+pub(crate) fn quote_pair_for<F: QuoteSource + ?Sized>(fund: &F, setup: &PairSetup, from_amount: u64) -> Result<(Quote, PostSwapWeights)> {
+
+    let fund_state = fund.fund_state();
+    let PairSetup {
+        from_token_id,
+        to_token_id,
+        from_token_index,
+        to_token_index,
+        from_token_settings,
+        to_token_settings,
+        fund_worth,
+        from_token_target_amount,
+        to_token_target_amount,
+    } = *setup;
+
+    let from_token_price = from_token_settings.oracle_price;
+    let to_token_price = to_token_settings.oracle_price;
+
+    let value = SymmetryTokenSwap::compute_value_of_sold_token(
+        from_amount,
+        from_token_settings,
+        from_token_price,
+        fund_state.current_comp_amount[from_token_index],
+        from_token_target_amount,
+        fund.sell_curve(from_token_id as usize),
+    );
+
+    let mut to_amount = SymmetryTokenSwap::compute_amount_of_bought_token(
+        value,
+        to_token_settings,
+        to_token_price,
+        fund_state.current_comp_amount[to_token_index],
+        to_token_target_amount,
+        fund.buy_curve(to_token_id as usize),
+    );
+
+    let mut amount_without_fees = SymmetryTokenSwap::usd_value_to_amount(
+        SymmetryTokenSwap::amount_to_usd_value(
             from_amount,
-            from_token_settings,
-            from_token_price,
-            fund_state.current_comp_amount[from_token_index],
-            from_token_target_amount,
-            curve_data.sell[from_token_id as usize],
-        );
-    
-        let mut to_amount = SymmetryTokenSwap::compute_amount_of_bought_token(
-            value,
-            to_token_settings,
-            to_token_price,
-            fund_state.current_comp_amount[to_token_index],
-            to_token_target_amount,
-            curve_data.buy[to_token_id as usize],
-        );
-    
-        let mut amount_without_fees = SymmetryTokenSwap::usd_value_to_amount(
-            SymmetryTokenSwap::amount_to_usd_value(
-                from_amount,
-                from_token_settings.decimals,
-                from_token_price.sell_price
-            ),
-            to_token_settings.decimals,
-            to_token_price.buy_price
-        );
-    
-        let fair_amount = SymmetryTokenSwap::usd_value_to_amount(
-            SymmetryTokenSwap::amount_to_usd_value(
-                from_amount,
-                from_token_settings.decimals,
-                from_token_price.avg_price
-            ),
-            to_token_settings.decimals,
-            to_token_price.avg_price
-        );
-    
-        if amount_without_fees > fund_state.current_comp_amount[to_token_index] {
-            amount_without_fees = fund_state.current_comp_amount[to_token_index];
-        }
-    
-        if to_amount > amount_without_fees {
-            to_amount = amount_without_fees
-        }
-    
-        let total_fees = amount_without_fees - to_amount;
-    
-        let symmetry_bps = token_list.list[0].additional_data[60];
-        let symmetry_fee = SymmetryTokenSwap::mul_div(total_fees, symmetry_bps as u64, 100);
-    
-        let host_bps = token_list.list[0].additional_data[61];
-        let host_fee = SymmetryTokenSwap::mul_div(total_fees, host_bps as u64, 100);
-    
-        let manager_bps = token_list.list[0].additional_data[62];
-        let manager_fee = SymmetryTokenSwap::mul_div(total_fees, manager_bps as u64, 100);
-    
-        let fund_fee = total_fees - symmetry_fee - host_fee - manager_fee;
-    
-        let fee_bps = SymmetryTokenSwap::mul_div(
-            amount_without_fees - to_amount,
-            BPS_DIVIDER * 100,
-            fair_amount
-        );
-        
-        let from_token_worth_before_swap = SymmetryTokenSwap::amount_to_usd_value(
-            fund_state.current_comp_amount[from_token_index],
             from_token_settings.decimals,
-            from_token_price.avg_price
-        );
-        let to_token_worth_before_swap = SymmetryTokenSwap::amount_to_usd_value(
-            fund_state.current_comp_amount[to_token_index],
-            to_token_settings.decimals,
-            to_token_price.avg_price
-        );
-    
-        let safe_from_amount = from_amount * 101 / 100;
-        let from_token_worth_after_swap = SymmetryTokenSwap::amount_to_usd_value(
-            fund_state.current_comp_amount[from_token_index] + safe_from_amount,
+            from_token_price.sell_price
+        ),
+        to_token_settings.decimals,
+        to_token_price.buy_price
+    );
+
+    let fair_amount = SymmetryTokenSwap::usd_value_to_amount(
+        SymmetryTokenSwap::amount_to_usd_value(
+            from_amount,
             from_token_settings.decimals,
             from_token_price.avg_price
-        );
-        let mut safe_to_amount = (amount_without_fees - fund_fee) * 101 / 100;
-        if safe_to_amount > fund_state.current_comp_amount[to_token_index] {
-            safe_to_amount = fund_state.current_comp_amount[to_token_index];
+        ),
+        to_token_settings.decimals,
+        to_token_price.avg_price
+    );
+
+    let not_enough_liquidity = amount_without_fees > fund_state.current_comp_amount[to_token_index];
+    if not_enough_liquidity {
+        if fund.liquidity_policy() == LiquidityPolicy::Reject {
+            return Err(SymmetryError::NotEnoughLiquidity {
+                fund: fund.fund_key(),
+                output_mint: to_token_settings.token_mint,
+                requested_out: amount_without_fees,
+                available_out: fund_state.current_comp_amount[to_token_index],
+            }.into())
         }
-        let to_token_worth_after_swap= SymmetryTokenSwap::amount_to_usd_value(
-            fund_state.current_comp_amount[to_token_index] - safe_to_amount,
-            to_token_settings.decimals,
-            to_token_price.avg_price
-        );
-    
-        fund_worth = fund_worth + from_token_worth_after_swap;
-        fund_worth = fund_worth + to_token_worth_after_swap;
-        fund_worth = if fund_worth < from_token_worth_before_swap { 0 } else { fund_worth - from_token_worth_before_swap };
-        fund_worth = if fund_worth < to_token_worth_before_swap { 0 } else { fund_worth - to_token_worth_before_swap };
+        amount_without_fees = fund_state.current_comp_amount[to_token_index];
+    }
+
+    if to_amount > amount_without_fees {
+        to_amount = amount_without_fees
+    }
+
+    let total_fees = amount_without_fees - to_amount;
+
+    let fund_fee = fund.split_fees(total_fees)?.fund_fee;
+
+    let fee_bps = SymmetryTokenSwap::mul_div(
+        amount_without_fees - to_amount,
+        BPS_DIVIDER * 100,
+        fair_amount
+    );
     
-        let from_new_weight = SymmetryTokenSwap::mul_div(
-            from_token_worth_after_swap,
-            WEIGHT_MULTIPLIER,
-            fund_worth
-        );
-        let to_new_weight = SymmetryTokenSwap::mul_div(
-            to_token_worth_after_swap,
-            WEIGHT_MULTIPLIER,
-            fund_worth
-        );
+    let from_token_worth_before_swap = SymmetryTokenSwap::amount_to_usd_value(
+        fund_state.current_comp_amount[from_token_index],
+        from_token_settings.decimals,
+        from_token_price.avg_price
+    );
+    let to_token_worth_before_swap = SymmetryTokenSwap::amount_to_usd_value(
+        fund_state.current_comp_amount[to_token_index],
+        to_token_settings.decimals,
+        to_token_price.avg_price
+    );
+
+    let safe_from_amount = fund.quote_config().with_safety_margin(from_amount)?;
+    // Round the sold token's weight up and the bought token's down, so rounding never
+    // lets a swap past the weight band.
+    let from_token_worth_after_swap = SymmetryTokenSwap::amount_to_usd_value_rounded(
+        fund_state.current_comp_amount[from_token_index]
+            .checked_add(safe_from_amount)
+            .ok_or(SymmetryError::Arithmetic("From token amount overflow"))?,
+        from_token_settings.decimals,
+        from_token_price.avg_price,
+        Rounding::Ceil
+    );
+    let mut safe_to_amount = fund.quote_config().with_safety_margin(amount_without_fees - fund_fee)?;
+    if safe_to_amount > fund_state.current_comp_amount[to_token_index] {
+        safe_to_amount = fund_state.current_comp_amount[to_token_index];
+    }
+    let to_token_worth_after_swap= SymmetryTokenSwap::amount_to_usd_value_rounded(
+        fund_state.current_comp_amount[to_token_index] - safe_to_amount,
+        to_token_settings.decimals,
+        to_token_price.avg_price,
+        Rounding::Floor
+    );
+
+    let fund_worth_after_swap = fund_worth as i128
+        + from_token_worth_after_swap as i128
+        + to_token_worth_after_swap as i128
+        - from_token_worth_before_swap as i128
+        - to_token_worth_before_swap as i128;
+    if fund_worth_after_swap < 0 {
+        return Err(SymmetryError::Arithmetic("Fund worth after swap is negative").into())
+    }
+    let fund_worth_after_swap = u64::try_from(fund_worth_after_swap)
+        .map_err(|_| SymmetryError::Arithmetic("Fund worth after swap overflows u64"))?;
+
+    let from_new_weight = SymmetryTokenSwap::mul_div(
+        from_token_worth_after_swap,
+        WEIGHT_MULTIPLIER,
+        fund_worth_after_swap
+    );
+    let to_new_weight = SymmetryTokenSwap::mul_div(
+        to_token_worth_after_swap,
+        WEIGHT_MULTIPLIER,
+        fund_worth_after_swap
+    );
+
+    let allowed_from_target_weight = fund.allowed_weight_band(from_token_index).max_weight;
+    let allowed_to_target_weight = fund.allowed_weight_band(to_token_index).min_weight;
     
-        let allowed_offset = fund_state.rebalance_threshold * fund_state.lp_offset_threshold;
+    let removing_dust =
+        from_token_id == 0 &&
+        fund_state.target_weight[to_token_index] == 0;
+
+    if from_new_weight > allowed_from_target_weight && (!removing_dust) {
+        return Err(SymmetryError::WeightLimitExceeded { side: SwapSide::From }.into())
+    }
     
-        let mut allowed_from_target_weight = SymmetryTokenSwap::mul_div(
-            fund_state.target_weight[from_token_index],
-            BPS_DIVIDER * BPS_DIVIDER + allowed_offset,
-            BPS_DIVIDER * BPS_DIVIDER
-        );
-        let allowed_to_target_weight = SymmetryTokenSwap::mul_div(
-            fund_state.target_weight[to_token_index],
-            BPS_DIVIDER * BPS_DIVIDER - allowed_offset,
-            BPS_DIVIDER * BPS_DIVIDER
-        );
-        if allowed_from_target_weight > WEIGHT_MULTIPLIER {
-            allowed_from_target_weight = WEIGHT_MULTIPLIER;
+    if to_new_weight < allowed_to_target_weight {
+        return Err(SymmetryError::WeightLimitExceeded { side: SwapSide::To }.into())
+    }
+
+    let quote = Quote {
+        in_amount: from_amount,
+        out_amount: to_amount,
+        fee_amount: total_fees,
+        fee_mint: to_token_settings.token_mint,
+        fee_pct: Decimal::new(fee_bps as i64, 4),
+        not_enough_liquidity,
+        ..Quote::default()
+    };
+    let weights = PostSwapWeights {
+        from_weight_before: SymmetryTokenSwap::mul_div(from_token_worth_before_swap, WEIGHT_MULTIPLIER, fund_worth),
+        from_weight_after: from_new_weight,
+        to_weight_before: SymmetryTokenSwap::mul_div(to_token_worth_before_swap, WEIGHT_MULTIPLIER, fund_worth),
+        to_weight_after: to_new_weight,
+    };
+    Ok((quote, weights))
+}
+
+impl SymmetryTokenSwap {
+    /// `update()` judging oracle staleness at `clock` rather than the clock sysvar in
+    /// `account_map`, e.g. to replay recorded accounts at the time they were fetched.
+    pub fn update_with_clock(&mut self, account_map: &AccountMap, clock: Clock) -> Result<()> {
+        self.update_with_cache(account_map, &mut SharedAccountCache::with_clock(clock))
+    }
+
+    /// `update()` reusing the clock and oracle prices already parsed for other funds from
+    /// the same `account_map`. Only the curves of the fund's composition tokens are read.
+    pub fn update_with_cache(&mut self, account_map: &AccountMap, cache: &mut SharedAccountCache) -> Result<()> {
+        if self.config.verify_account_owners {
+            self.verify_account_owners(account_map)?;
         }
-        
-        let removing_dust =
-            from_token_id == 0 as u64 &&
-            fund_state.target_weight[to_token_index] == 0;
+        let loading_token_list = self.token_list_pending;
+        if loading_token_list {
+            self.load_pending_token_list(account_map)?;
+        }
+        let curve_data = CurveDataView::load(try_get_account_data(account_map, &self.symmetry_config.curve_data)?)?;
 
-        if from_new_weight > allowed_from_target_weight && (!removing_dust) {
-            return Err(Error::msg("From token weight exceeds max allowed weight"))
+        self.fund_state = FundState::load(try_get_account_data(account_map, &self.key)?)?;
+        // Optional so account maps recorded before the mint was requested still apply.
+        if let Some(account) = account_map.get(&self.fund_state.fund_token) {
+            self.fund_token_mint = Some(Mint::unpack(&account.data)?);
         }
-        
-        if to_new_weight < allowed_to_target_weight {
-            return Err(Error::msg("To token weight exceeds min allowed weight"))
+        curve_data.copy_into(self.fund_state.current_comp_token, &mut self.curve_data);
+        self.optimistic_swaps = 0;
+
+        self.fallback_priced.clear();
+        for i in 0..MAX_TOKENS_IN_ASSET_POOL {
+            let oracle_account = self.token_list.list[i].oracle_account;
+            if oracle_account != Pubkey::default() {
+                if loading_token_list && !account_map.contains_key(&oracle_account) {
+                    continue;
+                }
+                let (price, source) = self.load_oracle_price(account_map, cache, i)?;
+                if source == OracleSource::Fallback {
+                    self.fallback_priced.insert(i);
+                }
+                self.token_list.list[i].oracle_price = price;
+            }
         }
 
-        Ok(Quote {
-            in_amount: quote_params.in_amount,
-            out_amount: to_amount,
-            fee_amount: total_fees,
-            fee_mint: quote_params.output_mint,
-            fee_pct: Decimal::new(fee_bps as i64, 4),
-            ..Quote::default()
-        })
+        Ok(())
     }
 
-    fn get_swap_and_account_metas(
+    fn load_pending_token_list(&mut self, account_map: &AccountMap) -> Result<()> {
+        let token_list = self.symmetry_config.token_list;
+        if self.config.verify_account_owners {
+            let account = account_map.get(&token_list)
+                .ok_or(SymmetryError::MissingAccount { key: token_list })?;
+            check_account_owner(&token_list, account, &self.symmetry_config.program_id)?;
+        }
+        TokenListView::load(try_get_account_data(account_map, &token_list)?)?.copy_into(&mut self.token_list);
+        self.mint_index = index_mints(&self.token_list);
+        self.token_list_pending = false;
+        Ok(())
+    }
+
+    /// Oracle accounts of the fund's tokens in composition order, passed as remaining
+    /// accounts to every instruction that values the fund.
+    pub(crate) fn oracle_account_metas(&self) -> Vec<AccountMeta> {
+        (0..self.fund_state.num_tokens())
+            .map(|i| {
+                AccountMeta::new_readonly(self.token_list.list[self.fund_state.current_comp_token[i] as usize].oracle_account, false)
+            })
+            .collect()
+    }
+
+    /// The Symmetry swap instruction for `swap_params`, with oracle accounts appended. The
+    /// program fails the swap if it would return less than `min_amount_out`.
+    pub fn build_swap_instruction(
         &self,
         swap_params: &SwapParams,
-    ) -> Result<SwapAndAccountMetas> {
+        min_amount_out: u64,
+    ) -> Result<Instruction> {
         let SwapParams {
             in_amount,
             source_mint,
@@ -520,102 +1062,196 @@ impl Amm for SymmetryTokenSwap {
             source_token_account,
             destination_token_account,
             token_transfer_authority,
-            open_order_address,
             quote_mint_to_referrer,
-            jupiter_program_id
+            ..
         } = swap_params;
         
-        let from_token_id_option = self.token_list.list.iter().position(|&x| x.token_mint == *source_mint);
-        let to_token_id_option = self.token_list.list.iter().position(|&x| x.token_mint == *destination_mint);
+        let from_token_id_option = self.token_id(source_mint);
+        let to_token_id_option = self.token_id(destination_mint);
         
         if from_token_id_option.is_none() {
-            return Err(Error::msg("From token not found in supported tokens"))
+            return Err(SymmetryError::TokenNotSupported { mint: *source_mint }.into())
         }
         if to_token_id_option.is_none() {
-            return Err(Error::msg("To token not found in supported tokens"))
+            return Err(SymmetryError::TokenNotSupported { mint: *destination_mint }.into())
         }
 
         let from_token_id: u64 = from_token_id_option.unwrap() as u64;
         let to_token_id: u64 = to_token_id_option.unwrap() as u64;
+        self.check_token_lp_on(from_token_id as usize)?;
+        self.check_token_lp_on(to_token_id as usize)?;
+
+        let mut fee_accounts = self.swap_fee_accounts(destination_mint);
+        if self.config.route_host_fee_to_referrer {
+            if let Some(referrer) = quote_mint_to_referrer.and_then(|referrers| referrers.get(destination_mint)) {
+                fee_accounts.host_fee = *referrer;
+            }
+        }
 
-        let swap_to_fee: Pubkey = Pubkey::find_program_address(
-            &[
-                &SymmetryTokenSwap::SWAP_FEE_ADDRESS.to_bytes(),
-                &SymmetryTokenSwap::SPL_TOKEN_PROGRAM_ADDRESS.to_bytes(),
-                &destination_mint.to_bytes()
-            ], 
-            &SymmetryTokenSwap::ASSOCIATED_TOKEN_PROGRAM_ADDRESS
-        ).0;
-        let host_to_fee: Pubkey = Pubkey::find_program_address(
-            &[
-                &self.fund_state.host_pubkey.to_bytes(),
-                &SymmetryTokenSwap::SPL_TOKEN_PROGRAM_ADDRESS.to_bytes(),
-                &destination_mint.to_bytes()
-            ], 
-            &SymmetryTokenSwap::ASSOCIATED_TOKEN_PROGRAM_ADDRESS
-        ).0;
-        let manager_to_fee: Pubkey = Pubkey::find_program_address(
-            &[
-                &self.fund_state.manager.to_bytes(),
-                &SymmetryTokenSwap::SPL_TOKEN_PROGRAM_ADDRESS.to_bytes(),
-                &destination_mint.to_bytes()
-            ], 
-            &SymmetryTokenSwap::ASSOCIATED_TOKEN_PROGRAM_ADDRESS
-        ).0;
-
-        let mut account_metas: Vec<AccountMeta> = Vec::new();
-        account_metas.push(AccountMeta::new(*token_transfer_authority, true));
-        account_metas.push(AccountMeta::new(self.key, false));
-        account_metas.push(AccountMeta::new_readonly(SymmetryTokenSwap::PDA_ADDRESS, false));
-        account_metas.push(AccountMeta::new(self.token_list.list[from_token_id as usize].pda_token_account, false));
-        account_metas.push(AccountMeta::new(*source_token_account, false));
-        account_metas.push(AccountMeta::new(self.token_list.list[to_token_id as usize].pda_token_account, false));
-        account_metas.push(AccountMeta::new(*destination_token_account, false));
-        account_metas.push(AccountMeta::new(swap_to_fee, false));
-        account_metas.push(AccountMeta::new(host_to_fee, false));
-        account_metas.push(AccountMeta::new(manager_to_fee, false));
-        account_metas.push(AccountMeta::new_readonly(SymmetryTokenSwap::TOKEN_LIST_ADDRESS, false));
-        account_metas.push(AccountMeta::new_readonly(SymmetryTokenSwap::CURVE_DATA_ADDRESS, false));
-        account_metas.push(AccountMeta::new_readonly(SymmetryTokenSwap::SPL_TOKEN_PROGRAM_ADDRESS, false));
+        let mut account_metas: Vec<AccountMeta> = vec![
+            AccountMeta::new(*token_transfer_authority, true),
+            AccountMeta::new(self.key, false),
+            AccountMeta::new_readonly(self.symmetry_config.pda, false),
+            AccountMeta::new(self.token_list.list[from_token_id as usize].pda_token_account, false),
+            AccountMeta::new(*source_token_account, false),
+            AccountMeta::new(self.token_list.list[to_token_id as usize].pda_token_account, false),
+            AccountMeta::new(*destination_token_account, false),
+            AccountMeta::new(fee_accounts.swap_fee, false),
+            AccountMeta::new(fee_accounts.host_fee, false),
+            AccountMeta::new(fee_accounts.manager_fee, false),
+            AccountMeta::new_readonly(self.symmetry_config.token_list, false),
+            AccountMeta::new_readonly(self.symmetry_config.curve_data, false),
+            AccountMeta::new_readonly(SymmetryTokenSwap::SPL_TOKEN_PROGRAM_ADDRESS, false),
+        ];
 
         // Pyth Oracle accounts are being passed as remaining accounts
-        for i in 0..self.fund_state.num_of_tokens as usize {
-            account_metas.push(
-                AccountMeta::new_readonly(self.token_list.list[self.fund_state.current_comp_token[i] as usize].oracle_account, false)
-            );
-        }
+        account_metas.extend(self.oracle_account_metas());
 
-        let instruction_n: u64 = SymmetryTokenSwap::SYMMETRY_PROGRAM_SWAP_INSTRUCTION_ID;
-        let minimum_amount_out: u64 = 0;
-        let mut data = Vec::new();
-        data.extend_from_slice(&instruction_n.to_le_bytes());
-        data.extend_from_slice(&from_token_id.to_le_bytes());
-        data.extend_from_slice(&to_token_id.to_le_bytes());
-        data.extend_from_slice(&in_amount.to_le_bytes());
-        data.extend_from_slice(&minimum_amount_out.to_le_bytes());
-    
-        let swap_instruction = Instruction {
-            program_id: SymmetryTokenSwap::SYMMETRY_PROGRAM_ADDRESS,
-            accounts: account_metas.clone(),
+        let data = SwapIxArgs {
+            from_token_id,
+            to_token_id,
+            amount: *in_amount,
+            minimum_amount_out: min_amount_out,
+        }.pack();
+
+        Ok(Instruction {
+            program_id: self.symmetry_config.program_id,
+            accounts: account_metas,
             data,
-        };
+        })
+    }
+}
+
+impl Amm for SymmetryTokenSwap {
 
+    fn from_keyed_account(keyed_account: &KeyedAccount) -> Result<Self> {
+        SymmetryTokenSwap::from_fund_state_account(keyed_account)
+    }
+
+    fn label(&self) -> String {
+        match &self.fund_metadata {
+            Some(metadata) if self.config.label_with_fund_symbol && !metadata.symbol.is_empty() => {
+                format!("{}: {}", self.label, metadata.symbol)
+            }
+            _ => self.label.clone(),
+        }
+    }
+
+    fn program_id(&self) -> Pubkey {
+        self.symmetry_config.program_id
+    }
+
+    fn key(&self) -> Pubkey {
+        self.key
+    }
+
+    fn get_reserve_mints(&self) -> Vec<Pubkey> {
+        let mut vec: Vec<Pubkey> = Vec::new();
+        for i in 0..self.fund_state.num_tokens() {
+            if self.token_list.list[self.fund_state.current_comp_token[i] as usize].lp_on != LP_DISABLED {
+                vec.push(self.token_list.list[self.fund_state.current_comp_token[i] as usize].token_mint)
+            }
+        }
+        vec
+    }
+
+    fn get_accounts_to_update(&self) -> Vec<Pubkey> {
+        let mut accounts_to_update: Vec<Pubkey> = vec![
+            self.symmetry_config.curve_data,
+            self.key,
+            sysvar::clock::ID,
+            self.fund_state.fund_token,
+        ];
+        if self.token_list_pending {
+            accounts_to_update.push(self.symmetry_config.token_list);
+        }
+        for i in 0..MAX_TOKENS_IN_ASSET_POOL {
+            if self.token_list.list[i].oracle_account != Pubkey::default() {
+                accounts_to_update.push(self.token_list.list[i].oracle_account)
+            }
+        }
+        accounts_to_update.extend(self.fallback_oracles.values().map(|fallback| fallback.oracle_account));
+        accounts_to_update
+    }
+
+    fn update(&mut self, account_map: &AccountMap) -> Result<()> {
+        self.update_with_cache(account_map, &mut SharedAccountCache::default())
+    }
+
+    /// Always exact-in; see `quote_with_swap_mode` for ExactOut.
+    fn quote(&self, quote_params: &QuoteParams) -> Result<Quote> {
+        self.quote_with_weights(quote_params).map(|(quote, _)| quote)
+    }
+
+    fn get_swap_and_account_metas(
+        &self,
+        swap_params: &SwapParams,
+    ) -> Result<SwapAndAccountMetas> {
+        // Jupiter builds its own instruction data from these metas, so no minimum is needed.
+        let swap_instruction = self.build_swap_instruction(swap_params, 0)?;
         Ok(SwapAndAccountMetas {
             swap: Swap::TokenSwap,
-            account_metas,
+            account_metas: swap_instruction.accounts,
         })
     }
 
+    /// The oracle accounts are only known once the token list is loaded, and follow it when
+    /// the fund's tokens change.
+    fn has_dynamic_accounts(&self) -> bool {
+        true
+    }
+
     fn clone_amm(&self) -> Box<dyn Amm + Send + Sync> {
         Box::new(self.clone())
     }
 }
 
+impl QuoteSource for SymmetryTokenSwap {
+    fn fund_key(&self) -> Pubkey {
+        self.key
+    }
+
+    fn fund_state(&self) -> &FundState {
+        &self.fund_state
+    }
+
+    fn token_id(&self, mint: &Pubkey) -> Option<usize> {
+        self.mint_index.get(mint).copied()
+    }
+
+    fn token_settings(&self, token_id: usize) -> Option<&TokenSettings> {
+        self.token_list.list.get(token_id)
+    }
+
+    fn sell_curve(&self, token_id: usize) -> TokenPriceData {
+        self.curve_data.sell[token_id]
+    }
+
+    fn buy_curve(&self, token_id: usize) -> TokenPriceData {
+        self.curve_data.buy[token_id]
+    }
+
+    fn quote_config(&self) -> &AmmConfig {
+        &self.config
+    }
+
+    fn rebalance_policy(&self) -> RebalancePolicy {
+        self.rebalance_policy
+    }
+
+    fn liquidity_policy(&self) -> LiquidityPolicy {
+        self.liquidity_policy
+    }
+
+    fn split_fees(&self, total_fees: u64) -> Result<FeeSplit> {
+        self.fee_model.split_fees(total_fees, &self.token_list)
+    }
+}
+
+#[cfg(feature = "rpc")]
 #[test]
 fn test_symetry_token_swap() {
-    const WSOL_TOKEN_MINT: Pubkey = pubkey!("So11111111111111111111111111111111111111112");
     const USDC_TOKEN_MINT: Pubkey = pubkey!("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v");
-    const USDT_TOKEN_MINT: Pubkey = pubkey!("Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB");
     const MSOL_TOKEN_MINT: Pubkey = pubkey!("mSoLzYCxHdYgdzU16g5QSh3i5K3z3KZK7ytfqcJm7So");
 
     use crate::amms::test_harness::AmmTestHarness;
@@ -648,7 +1284,7 @@ fn test_symetry_token_swap() {
     let quote = token_swap
         .quote(&QuoteParams {
             input_mint: from_token_mint,
-            in_amount: in_amount,
+            in_amount,
             output_mint: to_token_mint,
         })
         .unwrap();
@@ -674,7 +1310,7 @@ fn test_symetry_token_swap() {
         &SymmetryTokenSwap::ASSOCIATED_TOKEN_PROGRAM_ADDRESS
     ).0;
     let swap_and_account_metas = token_swap.get_swap_and_account_metas(&SwapParams {
-        in_amount: in_amount,
+        in_amount,
         source_mint: from_token_mint, 
         destination_mint: to_token_mint,
         source_token_account: user_source,
@@ -684,4 +1320,204 @@ fn test_symetry_token_swap() {
         quote_mint_to_referrer: Option::None,
         jupiter_program_id: &Pubkey::default(),
     }).unwrap();
+    assert!(!swap_and_account_metas.account_metas.is_empty());
+}
+
+#[test]
+fn test_rounding_directions() {
+    assert_eq!(SymmetryTokenSwap::amount_to_usd_value_rounded(11, 1, 3, Rounding::Floor), 3);
+    assert_eq!(SymmetryTokenSwap::amount_to_usd_value_rounded(11, 1, 3, Rounding::Ceil), 4);
+    assert_eq!(SymmetryTokenSwap::usd_value_to_amount_rounded(10, 0, 3, Rounding::Floor), 3);
+    assert_eq!(SymmetryTokenSwap::usd_value_to_amount_rounded(10, 0, 3, Rounding::Ceil), 4);
+    assert_eq!(SymmetryTokenSwap::amount_to_usd_value(11, 1, 3), 3);
+    assert_eq!(SymmetryTokenSwap::usd_value_to_amount(10, 0, 3), 3);
+
+    let mut token_settings = TokenSettings::empty();
+    token_settings.decimals = 1;
+    let price = OraclePrice { sell_price: 3, avg_price: 3, buy_price: 3, oracle_live: 1 };
+    let curve = CurveData::empty().sell[0];
+    // Value credited for sold tokens rounds down: 1.1 tokens at 3 is worth 3.3 -> 3.
+    assert_eq!(SymmetryTokenSwap::compute_value_of_sold_token(11, token_settings, price, 100, 100, curve), 3);
+
+    token_settings.decimals = 0;
+    // Tokens bought round down: 10 of value at 3 buys 3.33 -> 3.
+    assert_eq!(SymmetryTokenSwap::compute_amount_of_bought_token(10, token_settings, price, 100, 100, curve), 3);
+}
+
+#[test]
+fn test_index_mints() {
+    let mut token_list = zeroed_box::<TokenList>();
+    let mint = Pubkey::new_unique();
+    token_list.list[3].token_mint = mint;
+    token_list.list[7].token_mint = mint;
+    let mint_index = index_mints(&token_list);
+    assert_eq!(mint_index.get(&mint), Some(&3));
+    assert_eq!(mint_index.get(&Pubkey::default()), Some(&0));
+    assert_eq!(mint_index.get(&Pubkey::new_unique()), None);
+}
+
+#[test]
+fn test_allow_stale_oracles() {
+    use crate::amms::account_builders::{fixture_accounts, quotable_fund, FixtureToken, FundStateBuilder};
+    use crate::amms::accounts::ONE_USD;
+
+    let tokens: [FixtureToken; 3] = std::array::from_fn(|_| FixtureToken::new(6, 100_000_000));
+    let mints: Vec<Pubkey> = tokens.iter().map(|token| token.mint).collect();
+    let fund_state = FundStateBuilder::new()
+        .with_token(0, 10_000_000_000, WEIGHT_MULTIPLIER / 3)
+        .with_token(1, 10_000_000_000, WEIGHT_MULTIPLIER / 3)
+        .with_token(2, 10_000_000_000, WEIGHT_MULTIPLIER / 3)
+        .with_weight_band(1_000, 5_000);
+    let key = Pubkey::new_unique();
+    let mut amm = quotable_fund(key, &fund_state, &tokens);
+    // The third token's oracle last published 100 slots ago.
+    let mut account_map = fixture_accounts(key, &fund_state, &tokens);
+    account_map.insert(tokens[2].oracle, tokens[2].oracle_account(910));
+    amm.update(&account_map).unwrap();
+    assert_eq!(amm.stale_oracle_mints(), vec![mints[2]]);
+
+    let quote_params = QuoteParams { in_amount: 1_000_000, input_mint: mints[0], output_mint: mints[1] };
+    let stale = |quote_params: &QuoteParams, amm: &SymmetryTokenSwap| amm.quote(quote_params).unwrap_err().downcast::<SymmetryError>().ok();
+    assert_eq!(stale(&quote_params, &amm), Some(SymmetryError::OracleOffline { mint: mints[2] }));
+
+    let amm = amm.with_config(AmmConfig { allow_stale_oracles: true, ..AmmConfig::default() });
+    let details = amm.quote_details(&quote_params).unwrap();
+    assert_eq!(details.fund_worth, 30_000 * ONE_USD);
+    assert_eq!(details.stale_oracles, vec![mints[2]]);
+    let from_stale = QuoteParams { input_mint: mints[2], ..quote_params };
+    assert_eq!(stale(&from_stale, &amm), Some(SymmetryError::OracleOffline { mint: mints[2] }));
 }
+
+#[test]
+fn test_amm_from_fund_state_account() {
+    use crate::amms::account_builders::{fixture_accounts, fixture_token_list, quotable_fund, FixtureToken, FundStateBuilder};
+
+    let tokens = [FixtureToken::new(6, 100_000_000), FixtureToken::new(9, 10_000_000_000)];
+    let fund_state = FundStateBuilder::new()
+        .with_token(0, 10_000_000_000, WEIGHT_MULTIPLIER / 2)
+        .with_token(1, 100_000_000_000, WEIGHT_MULTIPLIER / 2)
+        .with_weight_band(1_000, 5_000);
+    let fund = KeyedAccount { key: Pubkey::new_unique(), account: fund_state.build_account(), params: None };
+    let mut account_map = fixture_accounts(fund.key, &fund_state, &tokens);
+    account_map.insert(SymmetryTokenSwap::TOKEN_LIST_ADDRESS, fixture_token_list(&tokens).build_account());
+
+    let mut amm = <SymmetryTokenSwap as Amm>::from_keyed_account(&fund).unwrap();
+    assert!(amm.get_accounts_to_update().contains(&SymmetryTokenSwap::TOKEN_LIST_ADDRESS));
+    amm.update(&account_map).unwrap();
+    assert!(!amm.get_accounts_to_update().contains(&SymmetryTokenSwap::TOKEN_LIST_ADDRESS));
+    assert_eq!(amm.get_reserve_mints(), vec![tokens[0].mint, tokens[1].mint]);
+
+    let with_token_list = quotable_fund(fund.key, &fund_state, &tokens);
+    let quote_params = QuoteParams { in_amount: 100_000_000, input_mint: tokens[0].mint, output_mint: tokens[1].mint };
+    let quote = amm.quote(&quote_params).unwrap();
+    assert!(quote.out_amount > 0);
+    assert_eq!(quote.out_amount, with_token_list.quote(&quote_params).unwrap().out_amount);
+}
+
+#[test]
+fn test_composition_bounds() {
+    use crate::amms::account_builders::{quotable_fund, FixtureToken, FundStateBuilder};
+    use crate::amms::accounts::NUM_TOKENS_IN_FUND;
+
+    let tokens: [FixtureToken; 3] = std::array::from_fn(|_| FixtureToken::new(6, 100_000_000));
+    let mints: Vec<Pubkey> = tokens.iter().map(|token| token.mint).collect();
+    // Token 0 is listed but not held; the unused composition slots also read as token id 0.
+    let fund_state = FundStateBuilder::new()
+        .with_token(1, 10_000_000_000, WEIGHT_MULTIPLIER / 2)
+        .with_token(2, 10_000_000_000, WEIGHT_MULTIPLIER / 2)
+        .with_weight_band(1_000, 5_000);
+    let mut amm = quotable_fund(Pubkey::new_unique(), &fund_state, &tokens);
+
+    assert_eq!(amm.composition_index(&mints[0]), None);
+    let from_unheld = QuoteParams { in_amount: 1_000_000, input_mint: mints[0], output_mint: mints[1] };
+    assert_eq!(
+        amm.quote(&from_unheld).unwrap_err().downcast::<SymmetryError>().ok(),
+        Some(SymmetryError::TokenNotInFund { mint: mints[0] })
+    );
+
+    // A token count past the composition arrays is clamped rather than panicking.
+    amm.fund_state.num_of_tokens = NUM_TOKENS_IN_FUND as u64 + 5;
+    assert_eq!(amm.fund_state.num_tokens(), NUM_TOKENS_IN_FUND);
+    assert_eq!(amm.composition_index(&mints[1]), Some(0));
+    assert!(amm.stale_oracle_mints().len() <= NUM_TOKENS_IN_FUND);
+}
+
+#[test]
+fn test_apply_own_swap_and_simulated_weights() {
+    use crate::amms::account_builders::{quotable_fund, FixtureToken, FundStateBuilder};
+
+    let tokens = [FixtureToken::new(6, 100_000_000), FixtureToken::new(9, 10_000_000_000)];
+    let fund_state = FundStateBuilder::new()
+        .with_token(0, 10_000_000_000, WEIGHT_MULTIPLIER / 2)
+        .with_token(1, 100_000_000_000, WEIGHT_MULTIPLIER / 2)
+        .with_weight_band(1_000, 5_000);
+    let mut fund = quotable_fund(Pubkey::new_unique(), &fund_state, &tokens);
+    let (usdc, sol) = (tokens[0].mint, tokens[1].mint);
+
+    // 100 USDC in, 0.99 SOL out and a 0.01 SOL fee, 60% of which leaves the fund.
+    let swap_result = SwapResult {
+        fee_pct: Decimal::ZERO,
+        fees: 10_000_000,
+        input_amount: 100_000_000,
+        expected_output_amount: 990_000_000,
+        not_enough_liquidity: false,
+    };
+    fund.apply_own_swap(&usdc, &sol, &swap_result).unwrap();
+    assert_eq!(fund.fund_state().current_comp_amount[..2], [10_100_000_000, 99_004_000_000]);
+    assert_eq!(fund.optimistic_swaps(), 1);
+
+    let too_large = SwapResult { expected_output_amount: u64::MAX as u128, ..swap_result };
+    assert_eq!(
+        fund.apply_own_swap(&usdc, &sol, &too_large).unwrap_err().downcast_ref::<SymmetryError>(),
+        Some(&SymmetryError::Arithmetic("Swap output exceeds the fund's to token amount"))
+    );
+    assert_eq!(fund.fund_state().current_comp_amount[1], 99_004_000_000);
+
+    let simulated = fund.simulate_with_weights(&[(usdc, WEIGHT_MULTIPLIER / 4)]).unwrap();
+    assert_eq!(simulated.fund_state().weight_sum, WEIGHT_MULTIPLIER * 3 / 4);
+    assert_eq!(
+        fund.simulate_with_weights(&[(usdc, u64::MAX), (sol, 1)]).err().unwrap().downcast_ref::<SymmetryError>(),
+        Some(&SymmetryError::Arithmetic("Simulated target weights overflow u64"))
+    );
+    assert_eq!(
+        fund.simulate_with_weights(&[(usdc, 0), (sol, 0)]).err().unwrap().downcast_ref::<SymmetryError>(),
+        Some(&SymmetryError::ZeroWeightSum)
+    );
+}
+
+#[test]
+fn test_amm_refetches_dynamic_accounts() {
+    use crate::amms::account_builders::{fixture_accounts, fixture_token_list, FixtureToken, FundStateBuilder};
+
+    let tokens = [FixtureToken::new(6, 100_000_000), FixtureToken::new(9, 10_000_000_000)];
+    let fund_state = FundStateBuilder::new()
+        .with_token(0, 10_000_000_000, WEIGHT_MULTIPLIER / 2)
+        .with_token(1, 100_000_000_000, WEIGHT_MULTIPLIER / 2)
+        .with_weight_band(1_000, 5_000);
+    let fund = KeyedAccount { key: Pubkey::new_unique(), account: fund_state.build_account(), params: None };
+    let mut chain = fixture_accounts(fund.key, &fund_state, &tokens);
+    chain.insert(SymmetryTokenSwap::TOKEN_LIST_ADDRESS, fixture_token_list(&tokens).build_account());
+    // A router fetches exactly the accounts the AMM asks for before each update.
+    let fetch = |amm: &SymmetryTokenSwap| -> AccountMap {
+        amm.get_accounts_to_update().iter()
+            .filter_map(|key| chain.get(key).map(|account| (*key, account.clone())))
+            .collect()
+    };
+    let quote_params = QuoteParams { in_amount: 100_000_000, input_mint: tokens[0].mint, output_mint: tokens[1].mint };
+    let (usdc_oracle, sol_oracle) = (tokens[0].oracle, tokens[1].oracle);
+
+    let mut amm = <SymmetryTokenSwap as Amm>::from_keyed_account(&fund).unwrap();
+    assert!(amm.has_dynamic_accounts());
+    let first_accounts = amm.get_accounts_to_update();
+    assert!(!first_accounts.contains(&usdc_oracle));
+    amm.update(&fetch(&amm)).unwrap();
+    // The oracles were not fetched with the token list, so the prices aren't loaded yet.
+    assert!(amm.quote(&quote_params).is_err());
+
+    let accounts = amm.get_accounts_to_update();
+    assert_ne!(accounts, first_accounts);
+    assert!(accounts.contains(&usdc_oracle) && accounts.contains(&sol_oracle));
+    amm.update(&fetch(&amm)).unwrap();
+    assert!(amm.quote(&quote_params).unwrap().out_amount > 0);
+}
+
@@ -0,0 +1,169 @@
+use anyhow::Result;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::amms::symmetry_token_swap::SymmetryTokenSwap;
+
+/// How one leg of a swap splits around the token's target amount. Values use `ONE_USD`
+/// precision and are measured before fees.
+///
+/// On the sell leg, the part that brings the fund up to the target is priced at
+/// `token_swap_fee_before_tw_bps` and the rest at `token_swap_fee_after_tw_bps`. On the buy
+/// leg, the part taken from holdings above the target is the "before" part.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct TargetWeightSplit {
+    pub value_before_tw: u64,
+    pub value_after_tw: u64,
+    pub fees_before_tw: u64,
+    pub fees_after_tw: u64,
+}
+
+impl TargetWeightSplit {
+    pub(crate) fn add(&mut self, value_before_tw: u64, value_after_tw: u64, fees_before_tw: u64, fees_after_tw: u64) {
+        self.value_before_tw += value_before_tw;
+        self.value_after_tw += value_after_tw;
+        self.fees_before_tw += fees_before_tw;
+        self.fees_after_tw += fees_after_tw;
+    }
+
+    pub fn total_fees(&self) -> u64 {
+        self.fees_before_tw + self.fees_after_tw
+    }
+}
+
+/// One side of a swap as priced by the target-weight fee model.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct TargetWeightLeg {
+    pub mint: Pubkey,
+    /// Fund holding of the token before the swap.
+    pub start_amount: u64,
+    /// Amount of the token at its target weight, the boundary between the two fee tiers.
+    pub target_amount: u64,
+    pub fee_before_tw_bps: u8,
+    pub fee_after_tw_bps: u8,
+    pub split: TargetWeightSplit,
+}
+
+/// Target-weight fee breakdown of a quote.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct TargetWeightQuote {
+    /// The token sold to the fund.
+    pub sell: TargetWeightLeg,
+    /// The token bought from the fund.
+    pub buy: TargetWeightLeg,
+}
+
+impl SymmetryTokenSwap {
+    /// Breaks `in_amount` of `input_mint` into `output_mint` down by target-weight fee tier,
+    /// using the same pair checks, target amounts and curves as `quote()`.
+    pub fn quote_target_weight_split(&self, input_mint: &Pubkey, output_mint: &Pubkey, in_amount: u64) -> Result<TargetWeightQuote> {
+        let setup = self.pair_setup(input_mint, output_mint)?;
+        let fund_state = self.fund_state();
+        let (from_index, to_index) = (setup.from_token_index, setup.to_token_index);
+        let (from_token_settings, to_token_settings) = (setup.from_token_settings, setup.to_token_settings);
+        let from_target_amount = setup.from_token_target_amount;
+        let to_target_amount = setup.to_token_target_amount;
+
+        let (value, sell_split) = SymmetryTokenSwap::compute_value_of_sold_token_with_split(
+            in_amount,
+            from_token_settings,
+            from_token_settings.oracle_price,
+            fund_state.current_comp_amount[from_index],
+            from_target_amount,
+            self.curve_data().sell[setup.from_token_id as usize],
+        );
+        let (_, buy_split) = SymmetryTokenSwap::compute_amount_of_bought_token_with_split(
+            value,
+            to_token_settings,
+            to_token_settings.oracle_price,
+            fund_state.current_comp_amount[to_index],
+            to_target_amount,
+            self.curve_data().buy[setup.to_token_id as usize],
+        );
+
+        Ok(TargetWeightQuote {
+            sell: TargetWeightLeg {
+                mint: *input_mint,
+                start_amount: fund_state.current_comp_amount[from_index],
+                target_amount: from_target_amount,
+                fee_before_tw_bps: from_token_settings.token_swap_fee_before_tw_bps,
+                fee_after_tw_bps: from_token_settings.token_swap_fee_after_tw_bps,
+                split: sell_split,
+            },
+            buy: TargetWeightLeg {
+                mint: *output_mint,
+                start_amount: fund_state.current_comp_amount[to_index],
+                target_amount: to_target_amount,
+                fee_before_tw_bps: to_token_settings.token_swap_fee_before_tw_bps,
+                fee_after_tw_bps: to_token_settings.token_swap_fee_after_tw_bps,
+                split: buy_split,
+            },
+        })
+    }
+}
+
+#[test]
+fn test_target_weight_split_follows_quote_setup() {
+    use jupiter_amm_interface::AccountMap;
+    use solana_sdk::clock::Clock;
+
+    use crate::amms::account_builders::{quotable_fund, CurveDataBuilder, FixtureToken, FundStateBuilder, OracleAccountBuilder};
+    use crate::amms::accounts::WEIGHT_MULTIPLIER;
+    use crate::amms::amm_config::AmmConfig;
+    use crate::amms::consistency::RebalancePolicy;
+    use crate::error::SymmetryError;
+
+    // $10,000 of USDC, 100 SOL at $100 and $10,000 of a third token whose oracle went stale.
+    let tokens = [
+        FixtureToken::new(6, 100_000_000),
+        FixtureToken::new(9, 10_000_000_000),
+        FixtureToken::new(6, 100_000_000),
+    ];
+    let (usdc, sol, stale) = (tokens[0].mint, tokens[1].mint, tokens[2].mint);
+    let fund_state = FundStateBuilder::new()
+        .with_token(0, 10_000_000_000, WEIGHT_MULTIPLIER / 3)
+        .with_token(1, 100_000_000_000, WEIGHT_MULTIPLIER / 3)
+        .with_token(2, 10_000_000_000, WEIGHT_MULTIPLIER / 3)
+        .with_weight_band(1_000, 5_000);
+    let key = Pubkey::new_unique();
+    let fund = quotable_fund(key, &fund_state, &tokens);
+    let oracle = |id: usize| fund.token_list().list[id].oracle_account;
+    let account_map: AccountMap = [
+        (key, fund_state.build_account()),
+        (SymmetryTokenSwap::CURVE_DATA_ADDRESS, CurveDataBuilder::new().build_account()),
+        (oracle(0), OracleAccountBuilder::pyth(100_000_000, 0, -8, 1_000).build_account()),
+        (oracle(1), OracleAccountBuilder::pyth(10_000_000_000, 0, -8, 1_000).build_account()),
+        (oracle(2), OracleAccountBuilder::pyth(100_000_000, 0, -8, 900).build_account()),
+    ].into();
+    let clock = Clock { slot: 1_010, ..Clock::default() };
+
+    let mut strict = fund.clone();
+    strict.update_with_clock(&account_map, clock.clone()).unwrap();
+    let error = strict.quote_target_weight_split(&usdc, &sol, 1_000_000_000).unwrap_err();
+    assert_eq!(error.downcast_ref::<SymmetryError>(), Some(&SymmetryError::OracleOffline { mint: stale }));
+    assert!(strict.target_amount(&usdc).is_err());
+
+    let mut lenient = fund.clone().with_config(AmmConfig { allow_stale_oracles: true, ..AmmConfig::default() });
+    lenient.update_with_clock(&account_map, clock).unwrap();
+    let split = lenient.quote_target_weight_split(&usdc, &sol, 1_000_000_000).unwrap();
+    assert_eq!(split.sell.target_amount, lenient.target_amount(&usdc).unwrap());
+    assert_eq!(split.buy.target_amount, lenient.target_amount(&sol).unwrap());
+    assert_eq!(split.sell.target_amount, 10_000_000_000);
+
+    let rebalancing = FundStateBuilder::new()
+        .with_token(0, 10_000_000_000, WEIGHT_MULTIPLIER / 2)
+        .with_token(1, 100_000_000_000, WEIGHT_MULTIPLIER / 2)
+        .with_weight_band(1_000, 5_000)
+        .with_rebalance_states(1, 0, 0);
+    let fund = quotable_fund(key, &rebalancing, &tokens[..2]).with_rebalance_policy(RebalancePolicy::Reject);
+    assert!(matches!(
+        fund.target_amount(&usdc).unwrap_err().downcast_ref::<SymmetryError>(),
+        Some(SymmetryError::RebalanceInProgress { .. })
+    ));
+    assert!(matches!(
+        fund.quote_target_weight_split(&usdc, &sol, 1_000_000_000).unwrap_err().downcast_ref::<SymmetryError>(),
+        Some(SymmetryError::RebalanceInProgress { .. })
+    ));
+}
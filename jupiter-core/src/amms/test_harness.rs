@@ -1,24 +1,57 @@
-use anyhow::Result;
+use anyhow::{Error, Result};
 use jupiter_amm_interface::{AccountMap, Amm, KeyedAccount};
 use solana_client::rpc_client::RpcClient;
 use solana_sdk::pubkey::Pubkey;
 use std::collections::HashMap;
+use std::path::Path;
 
 use crate::config;
+use crate::fixture::{self, AmmFixture};
+
+/// Where the harness reads accounts from: a live node, or a fixture `record` wrote.
+enum AccountSource {
+    Rpc(Box<RpcClient>),
+    Fixture(AccountMap),
+}
 
 pub struct AmmTestHarness {
-    pub client: RpcClient,
+    source: AccountSource,
 }
 
 impl AmmTestHarness {
     pub fn new() -> Self {
         Self {
-            client: RpcClient::new(config::RPC_URL),
+            source: AccountSource::Rpc(Box::new(RpcClient::new(config::RPC_URL))),
         }
     }
 
+    /// Harness replaying the accounts and clock of the fixture at `path`, without RPC access.
+    pub fn from_fixture(path: impl AsRef<Path>) -> Result<Self> {
+        let account_map = AmmFixture::load(path.as_ref())?.account_map()?;
+        Ok(Self {
+            source: AccountSource::Fixture(account_map),
+        })
+    }
+
+    /// Fetches `fund` and the accounts its quotes depend on at one slot and writes them to
+    /// a fixture at `path`.
+    pub fn record(&self, fund: Pubkey, path: impl AsRef<Path>) -> Result<AmmFixture> {
+        let AccountSource::Rpc(client) = &self.source else {
+            return Err(Error::msg("Recording needs an RPC harness"));
+        };
+        let fixture = fixture::record(client, &fund)?;
+        fixture.save(path.as_ref())?;
+        Ok(fixture)
+    }
+
     pub fn get_keyed_account(&self, key: Pubkey) -> Result<KeyedAccount> {
-        let account = self.client.get_account(&key)?;
+        let account = match &self.source {
+            AccountSource::Rpc(client) => client.get_account(&key)?,
+            AccountSource::Fixture(account_map) => account_map
+                .get(&key)
+                .ok_or_else(|| Error::msg(format!("Fixture is missing account {key}")))?
+                .clone(),
+        };
         Ok(KeyedAccount {
             key,
             account,
@@ -30,18 +63,47 @@ impl AmmTestHarness {
         let accounts_to_update = amm.get_accounts_to_update();
         println!("{:?}", accounts_to_update);
 
-        let accounts_map: AccountMap = self
-            .client
+        let client = match &self.source {
+            AccountSource::Rpc(client) => client,
+            // Fixture accounts include the clock sysvar they were read with.
+            AccountSource::Fixture(account_map) => return amm.update(account_map).unwrap(),
+        };
+        let accounts_map: AccountMap = client
             .get_multiple_accounts(&accounts_to_update)
             .unwrap()
             .iter()
             .enumerate()
             .fold(HashMap::new(), |mut m, (index, account)| {
                 if let Some(account) = account {
-                    m.insert(accounts_to_update[index], account.clone().into());
+                    m.insert(accounts_to_update[index], account.clone());
                 }
                 m
             });
         amm.update(&accounts_map).unwrap();
     }
 }
+
+/// Records the fund `test_symetry_token_swap` quotes. Run with `--ignored` and a reachable
+/// `config::RPC_URL`; `SYMMETRY_FIXTURE_PATH` overrides where the fixture is written.
+#[test]
+#[ignore]
+fn test_record_fixture() {
+    use solana_sdk::pubkey;
+
+    use crate::amms::symmetry_token_swap::SymmetryTokenSwap;
+
+    const FUND_STATE_ACCOUNT: Pubkey = pubkey!("4RofqKG4d6jfUD2HjtWb2F9UkLJvJ7P3kFmyuhX7H88d");
+
+    let path = std::env::var("SYMMETRY_FIXTURE_PATH")
+        .map(std::path::PathBuf::from)
+        .unwrap_or_else(|_| std::env::temp_dir().join(format!("{FUND_STATE_ACCOUNT}.json")));
+    let fixture = AmmTestHarness::new().record(FUND_STATE_ACCOUNT, &path).unwrap();
+
+    let harness = AmmTestHarness::from_fixture(&path).unwrap();
+    let mut amm = SymmetryTokenSwap::from_keyed_account(
+        &harness.get_keyed_account(FUND_STATE_ACCOUNT).unwrap(),
+        &harness.get_keyed_account(SymmetryTokenSwap::TOKEN_LIST_ADDRESS).unwrap(),
+    ).unwrap();
+    harness.update_amm(&mut amm);
+    println!("Recorded {} accounts at slot {} to {}", fixture.accounts.len(), fixture.slot, path.display());
+}
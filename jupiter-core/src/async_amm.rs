@@ -0,0 +1,183 @@
+use anyhow::{Error, Result};
+use jupiter_amm_interface::{AccountMap, Amm, Quote, QuoteParams};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::amms::symmetry_token_swap::SymmetryTokenSwap;
+
+/// `getMultipleAccounts` accepts at most 100 keys per request.
+const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+/// Refreshes accounts older than this many slots before quoting, by default (~4s).
+pub const DEFAULT_MAX_AGE_SLOTS: u64 = 10;
+
+/// A `SymmetryTokenSwap` that fetches its own accounts over async RPC, for bots that want
+/// fresh quotes without running a registry or account watcher.
+pub struct AsyncSymmetryAmm {
+    client: Arc<RpcClient>,
+    amm: SymmetryTokenSwap,
+    max_age_slots: u64,
+    last_refresh_slot: Option<u64>,
+}
+
+impl AsyncSymmetryAmm {
+    /// Wraps an AMM built with `from_keyed_account`. Its accounts are fetched on the first
+    /// `quote_fresh` or `refresh`.
+    pub fn new(client: Arc<RpcClient>, amm: SymmetryTokenSwap) -> Self {
+        Self {
+            client,
+            amm,
+            max_age_slots: DEFAULT_MAX_AGE_SLOTS,
+            last_refresh_slot: None,
+        }
+    }
+
+    pub fn with_max_age_slots(mut self, max_age_slots: u64) -> Self {
+        self.max_age_slots = max_age_slots;
+        self
+    }
+
+    pub fn amm(&self) -> &SymmetryTokenSwap {
+        &self.amm
+    }
+
+    pub fn into_inner(self) -> SymmetryTokenSwap {
+        self.amm
+    }
+
+    /// Context slot of the last refresh.
+    pub fn last_refresh_slot(&self) -> Option<u64> {
+        self.last_refresh_slot
+    }
+
+    /// Fetches every account the AMM depends on and applies them with their context slots,
+    /// so the AMM's `SlotConsistency` is checked. Returns the refresh slot.
+    pub async fn refresh(&mut self) -> Result<u64> {
+        let keys = self.amm.get_accounts_to_update();
+        let mut account_map = AccountMap::new();
+        let mut slots = HashMap::new();
+        for chunk in keys.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            let response = self.client
+                .get_multiple_accounts_with_commitment(chunk, self.client.commitment())
+                .await?;
+            for (key, account) in chunk.iter().zip(response.value) {
+                let account = account.ok_or_else(|| Error::msg(format!("Account {key} not found")))?;
+                account_map.insert(*key, account);
+                slots.insert(*key, response.context.slot);
+            }
+        }
+        let spread = self.amm.update_with_slots(&account_map, &slots)?;
+        self.last_refresh_slot = Some(spread.max_slot);
+        Ok(spread.max_slot)
+    }
+
+    /// Whether the accounts were last refreshed more than `max_age_slots` before `slot`.
+    pub fn is_stale(&self, slot: u64) -> bool {
+        match self.last_refresh_slot {
+            Some(last_refresh_slot) => slot.saturating_sub(last_refresh_slot) > self.max_age_slots,
+            None => true,
+        }
+    }
+
+    /// Quotes against accounts no older than `max_age_slots`, refreshing them first if needed.
+    pub async fn quote_fresh(&mut self, quote_params: &QuoteParams) -> Result<Quote> {
+        let slot = self.client.get_slot().await?;
+        if self.is_stale(slot) {
+            self.refresh().await?;
+        }
+        self.amm.quote(quote_params)
+    }
+}
+
+#[test]
+fn test_refresh_and_quote_fresh() {
+    use serde_json::json;
+    use solana_account_decoder::{UiAccount, UiAccountEncoding};
+    use solana_client::rpc_client::Mocks;
+    use solana_client::rpc_request::RpcRequest;
+    use solana_sdk::account::Account;
+    use solana_sdk::clock::Clock;
+    use solana_sdk::program_pack::Pack;
+    use solana_sdk::pubkey::Pubkey;
+    use solana_sdk::sysvar;
+    use spl_token::state::Mint;
+
+    use crate::amms::account_builders::{quotable_fund, CurveDataBuilder, FixtureToken, FundStateBuilder, OracleAccountBuilder};
+    use crate::amms::accounts::WEIGHT_MULTIPLIER;
+
+    let fund_key = Pubkey::new_unique();
+    let fund_token = Pubkey::new_unique();
+    let tokens = [FixtureToken::new(6, 100_000_000), FixtureToken::new(9, 10_000_000_000)];
+    let fund_state = |usdc_amount: u64| FundStateBuilder::new()
+        .with_fund_token(fund_token)
+        .with_token(0, usdc_amount, WEIGHT_MULTIPLIER / 2)
+        .with_token(1, 100_000_000_000, WEIGHT_MULTIPLIER / 2)
+        .with_weight_band(1_000, 5_000);
+    let amm = quotable_fund(fund_key, &fund_state(10_000_000_000), &tokens);
+    let quote_params = QuoteParams {
+        in_amount: 100_000_000,
+        input_mint: tokens[0].mint,
+        output_mint: tokens[1].mint,
+    };
+    let stored_quote = amm.quote(&quote_params).unwrap();
+
+    // On chain the fund has since paid out some of its USDC.
+    let mut on_chain = AccountMap::new();
+    on_chain.insert(fund_key, fund_state(9_000_000_000).build_account());
+    on_chain.insert(SymmetryTokenSwap::CURVE_DATA_ADDRESS, CurveDataBuilder::new().build_account());
+    on_chain.insert(sysvar::clock::ID, Account {
+        data: bincode::serialize(&Clock { slot: 1_005, ..Clock::default() }).unwrap(),
+        ..Account::default()
+    });
+    let mut mint = vec![0u8; Mint::LEN];
+    Mint::pack(Mint { decimals: 6, is_initialized: true, ..Mint::default() }, &mut mint).unwrap();
+    on_chain.insert(fund_token, Account { data: mint, ..Account::default() });
+    for (i, token) in tokens.iter().enumerate() {
+        let oracle_account = amm.token_list().list[i].oracle_account;
+        on_chain.insert(oracle_account, OracleAccountBuilder::pyth(token.price, 0, -8, 1_000).build_account());
+    }
+    let keys = amm.get_accounts_to_update();
+    let multiple_accounts = |slot: u64, missing: Option<Pubkey>| json!({
+        "context": { "slot": slot },
+        "value": keys.iter()
+            .map(|key| (Some(*key) != missing).then(|| UiAccount::encode(key, &on_chain[key], UiAccountEncoding::Base64, None, None)))
+            .collect::<Vec<_>>(),
+    });
+    let client = |mocks: Mocks| Arc::new(RpcClient::new_mock_with_mocks("succeeds".to_string(), mocks));
+    let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+
+    // Never refreshed, so the first quote fetches every account at the context slot.
+    let mut async_amm = AsyncSymmetryAmm::new(
+        client([
+            (RpcRequest::GetSlot, json!(50)),
+            (RpcRequest::GetMultipleAccounts, multiple_accounts(50, None)),
+        ].into()),
+        amm.clone(),
+    );
+    assert!(async_amm.is_stale(0));
+    let quote = runtime.block_on(async_amm.quote_fresh(&quote_params)).unwrap();
+    assert_eq!(async_amm.last_refresh_slot(), Some(50));
+    assert_eq!(async_amm.amm().fund_state().current_comp_amount[0], 9_000_000_000);
+    assert_ne!(format!("{quote:?}"), format!("{stored_quote:?}"));
+    assert_eq!(format!("{quote:?}"), format!("{:?}", async_amm.amm().quote(&quote_params).unwrap()));
+
+    // Within `max_age_slots` of the refresh, quotes use the loaded accounts.
+    assert!(!async_amm.is_stale(60));
+    assert!(async_amm.is_stale(61));
+    let mut async_amm = async_amm.with_max_age_slots(20);
+    assert!(!async_amm.is_stale(70));
+    async_amm.client = client([(RpcRequest::GetSlot, json!(70))].into());
+    let quote = runtime.block_on(async_amm.quote_fresh(&quote_params)).unwrap();
+    assert_eq!(async_amm.last_refresh_slot(), Some(50));
+    assert_eq!(format!("{quote:?}"), format!("{:?}", async_amm.amm().quote(&quote_params).unwrap()));
+
+    // An account the RPC doesn't return fails the refresh.
+    let mut async_amm = AsyncSymmetryAmm::new(
+        client([(RpcRequest::GetMultipleAccounts, multiple_accounts(50, Some(fund_token)))].into()),
+        amm,
+    );
+    let err = runtime.block_on(async_amm.refresh()).unwrap_err();
+    assert_eq!(err.to_string(), format!("Account {fund_token} not found"));
+    assert_eq!(async_amm.last_refresh_slot(), None);
+}
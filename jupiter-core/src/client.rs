@@ -0,0 +1,156 @@
+use anyhow::{Error, Result};
+use jupiter_amm_interface::{AccountMap, Amm, KeyedAccount};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey};
+
+use crate::amms::symmetry_token_swap::SymmetryTokenSwap;
+
+/// `getMultipleAccounts` accepts at most 100 keys per request.
+pub const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+/// How `fetch_account_map_with_config` queries the RPC node.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FetchConfig {
+    pub commitment: CommitmentConfig,
+    /// Keys per `getMultipleAccounts` request, at most `MAX_MULTIPLE_ACCOUNTS`.
+    pub chunk_size: usize,
+}
+
+impl FetchConfig {
+    /// `chunk_size` of `MAX_MULTIPLE_ACCOUNTS` at the client's commitment.
+    pub fn for_client(rpc: &RpcClient) -> Self {
+        Self {
+            commitment: rpc.commitment(),
+            chunk_size: MAX_MULTIPLE_ACCOUNTS,
+        }
+    }
+
+    pub fn with_commitment(mut self, commitment: CommitmentConfig) -> Self {
+        self.commitment = commitment;
+        self
+    }
+
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.clamp(1, MAX_MULTIPLE_ACCOUNTS);
+        self
+    }
+}
+
+/// Fetches the accounts `amm.get_accounts_to_update()` names at the client's commitment.
+/// Accounts that don't exist on chain are left out of the map.
+pub async fn fetch_account_map(rpc: &RpcClient, amm: &dyn Amm) -> Result<AccountMap> {
+    fetch_account_map_with_config(rpc, amm, FetchConfig::for_client(rpc)).await
+}
+
+pub async fn fetch_account_map_with_config(rpc: &RpcClient, amm: &dyn Amm, config: FetchConfig) -> Result<AccountMap> {
+    let keys = amm.get_accounts_to_update();
+    let mut account_map = AccountMap::new();
+    for chunk in keys.chunks(config.chunk_size.clamp(1, MAX_MULTIPLE_ACCOUNTS)) {
+        let response = rpc.get_multiple_accounts_with_commitment(chunk, config.commitment).await?;
+        for (key, account) in chunk.iter().zip(response.value) {
+            if let Some(account) = account {
+                account_map.insert(*key, account);
+            }
+        }
+    }
+    Ok(account_map)
+}
+
+/// Fetches the accounts `amm` depends on and applies them with `update()`.
+pub async fn refresh(rpc: &RpcClient, amm: &mut dyn Amm) -> Result<()> {
+    let account_map = fetch_account_map(rpc, amm).await?;
+    amm.update(&account_map)
+}
+
+/// Fetches a fund and the token list, then every account its quotes depend on, returning
+/// an AMM ready to quote.
+pub async fn load_fund(rpc: &RpcClient, fund: &Pubkey) -> Result<SymmetryTokenSwap> {
+    let keys = [*fund, SymmetryTokenSwap::TOKEN_LIST_ADDRESS];
+    let response = rpc.get_multiple_accounts_with_commitment(&keys, rpc.commitment()).await?;
+    let mut keyed_accounts = keys.iter().zip(response.value).map(|(key, account)| -> Result<KeyedAccount> {
+        let account = account.ok_or_else(|| Error::msg(format!("Account {key} not found")))?;
+        Ok(KeyedAccount { key: *key, account, params: None })
+    });
+    let fund_state_account = keyed_accounts.next().unwrap()?;
+    let token_list_account = keyed_accounts.next().unwrap()?;
+    let mut amm = SymmetryTokenSwap::from_keyed_account_checked(&fund_state_account, &token_list_account)?;
+    refresh(rpc, &mut amm).await?;
+    Ok(amm)
+}
+
+#[test]
+fn test_load_fund_from_fixture() {
+    use jupiter_amm_interface::QuoteParams;
+    use solana_client::rpc_client::RpcClientConfig;
+    use solana_sdk::clock::Clock;
+    use solana_sdk::sysvar;
+
+    use crate::amms::account_builders::{CurveDataBuilder, FundStateBuilder, OracleAccountBuilder, TokenListBuilder};
+    use crate::amms::accounts::{TokenSettings, WEIGHT_MULTIPLIER};
+    use crate::fixture::{AmmFixture, FixtureRpcSender};
+
+    let usdc = Pubkey::new_unique();
+    let sol = Pubkey::new_unique();
+    let usdc_oracle = Pubkey::new_unique();
+    let sol_oracle = Pubkey::new_unique();
+    let token = |mint: Pubkey, decimals: u8, oracle_account: Pubkey| TokenSettings {
+        token_mint: mint,
+        decimals,
+        oracle_account,
+        oracle_confidence_pct: 10,
+        is_live: 1,
+        lp_on: 1,
+        ..TokenSettings::empty()
+    };
+    let fund = Pubkey::new_unique();
+    let clock = Clock { slot: 1_010, ..Clock::default() };
+    let mut account_map = AccountMap::new();
+    account_map.insert(fund, FundStateBuilder::new()
+        .with_token(0, 10_000_000_000, WEIGHT_MULTIPLIER / 2)
+        .with_token(1, 100_000_000_000, WEIGHT_MULTIPLIER / 2)
+        .with_weight_band(1_000, 5_000)
+        .build_account());
+    account_map.insert(SymmetryTokenSwap::TOKEN_LIST_ADDRESS, TokenListBuilder::new()
+        .with_token(token(usdc, 6, usdc_oracle))
+        .with_token(token(sol, 9, sol_oracle))
+        .build_account());
+    account_map.insert(SymmetryTokenSwap::CURVE_DATA_ADDRESS, CurveDataBuilder::new().build_account());
+    account_map.insert(usdc_oracle, OracleAccountBuilder::pyth(100_000_000, 10_000, -8, 1_000).build_account());
+    account_map.insert(sol_oracle, OracleAccountBuilder::pyth(10_000_000_000, 1_000_000, -8, 1_000).build_account());
+    account_map.insert(sysvar::clock::ID, solana_sdk::account::Account {
+        data: bincode::serialize(&clock).unwrap(),
+        ..solana_sdk::account::Account::default()
+    });
+    let fixture = AmmFixture::new(&fund, 1_010, &account_map).unwrap();
+    let rpc = RpcClient::new_sender(
+        FixtureRpcSender::new(&fixture).unwrap(),
+        RpcClientConfig::with_commitment(CommitmentConfig::confirmed()),
+    );
+    let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+
+    // The loaded fund quotes exactly like the fixture replayed offline.
+    let amm = runtime.block_on(load_fund(&rpc, &fund)).unwrap();
+    let quote_params = QuoteParams { in_amount: 100_000_000, input_mint: usdc, output_mint: sol };
+    assert_eq!(
+        format!("{:?}", amm.quote(&quote_params).unwrap()),
+        format!("{:?}", fixture.amm().unwrap().quote(&quote_params).unwrap()),
+    );
+
+    // Fetching in chunks returns the same accounts, leaving out the unset fund token mint.
+    let config = FetchConfig::for_client(&rpc).with_chunk_size(2);
+    let fetched = runtime.block_on(fetch_account_map_with_config(&rpc, &amm, config)).unwrap();
+    let mut keys: Vec<_> = fetched.keys().copied().collect();
+    keys.sort();
+    let mut expected: Vec<_> = amm.get_accounts_to_update().into_iter()
+        .filter(|key| account_map.contains_key(key))
+        .collect();
+    expected.sort();
+    assert_eq!(keys, expected);
+    assert!(!fetched.contains_key(&Pubkey::default()));
+    assert_eq!(FetchConfig::for_client(&rpc).with_chunk_size(0).chunk_size, 1);
+    assert_eq!(FetchConfig::for_client(&rpc).with_chunk_size(500).chunk_size, MAX_MULTIPLE_ACCOUNTS);
+
+    let missing = Pubkey::new_unique();
+    let err = runtime.block_on(load_fund(&rpc, &missing)).err().unwrap();
+    assert_eq!(err.to_string(), format!("Account {missing} not found"));
+}
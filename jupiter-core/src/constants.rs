@@ -3,3 +3,5 @@ use solana_sdk::pubkey::Pubkey;
 
 pub const USDC_MINT: Pubkey = pubkey!("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v");
 pub const USDT_MINT: Pubkey = pubkey!("Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB");
+/// Wrapped SOL, the SPL token mint backed by native lamports.
+pub const WSOL_MINT: Pubkey = pubkey!("So11111111111111111111111111111111111111112");
@@ -0,0 +1,183 @@
+use anyhow::{Error, Result};
+use jupiter_amm_interface::{AccountMap, Amm, KeyedAccount};
+use solana_account_decoder::{UiAccountEncoding, UiDataSliceConfig};
+use solana_client::{
+    rpc_client::RpcClient,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, RpcFilterType},
+};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::amms::account_views::TokenListView;
+use crate::amms::accounts::{
+    FundStateHeader, FUND_STATE_ACCOUNT_SIZE, FUND_STATE_DISCRIMINATOR, FUND_STATE_HEADER_SIZE,
+    FUND_STATE_HOST_OFFSET, FUND_STATE_MANAGER_OFFSET,
+};
+use crate::amms::fund_metadata::FundMetadata;
+use crate::amms::symmetry_token_swap::SymmetryTokenSwap;
+
+/// `getMultipleAccounts` accepts at most 100 keys per request.
+const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+/// Filters evaluated by the RPC node (memcmp), so non-matching funds are never downloaded.
+/// `mints` can't be expressed as a memcmp and is checked against fund headers instead.
+#[derive(Clone, Debug, Default)]
+pub struct DiscoveryFilter {
+    pub manager: Option<Pubkey>,
+    pub host: Option<Pubkey>,
+    /// Every mint listed here must be in the fund's composition.
+    pub mints: Vec<Pubkey>,
+}
+
+impl DiscoveryFilter {
+    fn rpc_filters(&self) -> Vec<RpcFilterType> {
+        let mut filters = vec![
+            RpcFilterType::DataSize(FUND_STATE_ACCOUNT_SIZE as u64),
+            RpcFilterType::Memcmp(Memcmp::new_base58_encoded(0, &FUND_STATE_DISCRIMINATOR)),
+        ];
+        if let Some(manager) = self.manager {
+            filters.push(RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+                FUND_STATE_MANAGER_OFFSET,
+                manager.as_ref(),
+            )));
+        }
+        if let Some(host) = self.host {
+            filters.push(RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+                FUND_STATE_HOST_OFFSET,
+                host.as_ref(),
+            )));
+        }
+        filters
+    }
+}
+
+/// Scans the Symmetry program for fund states, downloading only the first
+/// `FUND_STATE_HEADER_SIZE` bytes of each account.
+pub fn fetch_fund_headers(
+    client: &RpcClient,
+    filter: &DiscoveryFilter,
+) -> Result<Vec<(Pubkey, FundStateHeader)>> {
+    let config = RpcProgramAccountsConfig {
+        filters: Some(filter.rpc_filters()),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            data_slice: Some(UiDataSliceConfig {
+                offset: 0,
+                length: FUND_STATE_HEADER_SIZE,
+            }),
+            ..RpcAccountInfoConfig::default()
+        },
+        ..RpcProgramAccountsConfig::default()
+    };
+    client
+        .get_program_accounts_with_config(&SymmetryTokenSwap::SYMMETRY_PROGRAM_ADDRESS, config)?
+        .into_iter()
+        .map(|(key, account)| Ok((key, FundStateHeader::load(&account.data)?)))
+        .collect()
+}
+
+/// Every Symmetry fund matching `filter`. Without `mints` the program is scanned once for
+/// full accounts; otherwise only headers are scanned and the matching funds fetched.
+pub fn find_all_funds(client: &RpcClient, filter: &DiscoveryFilter) -> Result<Vec<KeyedAccount>> {
+    if filter.mints.is_empty() {
+        let config = RpcProgramAccountsConfig {
+            filters: Some(filter.rpc_filters()),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                ..RpcAccountInfoConfig::default()
+            },
+            ..RpcProgramAccountsConfig::default()
+        };
+        return Ok(client
+            .get_program_accounts_with_config(&SymmetryTokenSwap::SYMMETRY_PROGRAM_ADDRESS, config)?
+            .into_iter()
+            .map(|(key, account)| KeyedAccount { key, account, params: None })
+            .collect());
+    }
+    let token_list = client.get_account(&SymmetryTokenSwap::TOKEN_LIST_ADDRESS)?;
+    let token_list = TokenListView::load(&token_list.data)?;
+    let token_ids: Option<Vec<u64>> = filter.mints.iter()
+        .map(|mint| token_list.position(mint).map(|token_id| token_id as u64))
+        .collect();
+    let Some(token_ids) = token_ids else {
+        return Ok(Vec::new());
+    };
+    let keys: Vec<Pubkey> = fetch_fund_headers(client, filter)?
+        .into_iter()
+        .filter(|(_, header)| holds_tokens(header, &token_ids))
+        .map(|(key, _)| key)
+        .collect();
+    fetch_fund_states(client, &keys)
+}
+
+fn holds_tokens(header: &FundStateHeader, token_ids: &[u64]) -> bool {
+    token_ids.iter().all(|token_id| header.token_ids().contains(token_id))
+}
+
+/// Fetches full fund state accounts for funds selected from their headers.
+/// Keys that no longer exist on chain are skipped.
+pub fn fetch_fund_states(client: &RpcClient, keys: &[Pubkey]) -> Result<Vec<KeyedAccount>> {
+    let mut keyed_accounts = Vec::with_capacity(keys.len());
+    for chunk in keys.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        let accounts = client.get_multiple_accounts(chunk)?;
+        keyed_accounts.extend(chunk.iter().zip(accounts).filter_map(|(key, account)| {
+            Some(KeyedAccount {
+                key: *key,
+                account: account?,
+                params: None,
+            })
+        }));
+    }
+    Ok(keyed_accounts)
+}
+
+/// Fetches `keys` in batches. Keys that don't exist on chain are left out of the map.
+pub fn fetch_account_map(client: &RpcClient, keys: &[Pubkey]) -> Result<AccountMap> {
+    let mut account_map = AccountMap::new();
+    for keyed_account in fetch_fund_states(client, keys)? {
+        account_map.insert(keyed_account.key, keyed_account.account);
+    }
+    Ok(account_map)
+}
+
+/// Fetches a fund, its token list and every account its quotes depend on, returning an
+/// AMM ready to quote.
+pub fn load_fund(client: &RpcClient, fund: &Pubkey) -> Result<SymmetryTokenSwap> {
+    let mut keyed_accounts = fetch_fund_states(client, &[*fund, SymmetryTokenSwap::TOKEN_LIST_ADDRESS])?;
+    if keyed_accounts.len() != 2 {
+        return Err(Error::msg("Fund state or token list account not found"));
+    }
+    let token_list_account = keyed_accounts.pop().unwrap();
+    let fund_state_account = keyed_accounts.pop().unwrap();
+    let mut amm = SymmetryTokenSwap::from_keyed_account_checked(&fund_state_account, &token_list_account)?;
+
+    let metadata_address = amm.fund_metadata_address();
+    let mut keys = amm.get_accounts_to_update();
+    keys.push(metadata_address);
+    let account_map = fetch_account_map(client, &keys)?;
+    amm.update(&account_map)?;
+    if let Some(metadata) = account_map.get(&metadata_address).and_then(|account| FundMetadata::parse(&account.data).ok()) {
+        amm = amm.with_fund_metadata(metadata);
+    }
+    Ok(amm)
+}
+
+#[test]
+fn test_discovery_filter() {
+    let filter = DiscoveryFilter {
+        manager: Some(Pubkey::new_unique()),
+        mints: vec![Pubkey::new_unique()],
+        ..DiscoveryFilter::default()
+    };
+    // Data size, discriminator and manager; the mint is matched against headers.
+    assert_eq!(filter.rpc_filters().len(), 3);
+
+    let mut data = vec![0u8; FUND_STATE_HEADER_SIZE];
+    data[..8].copy_from_slice(&FUND_STATE_DISCRIMINATOR);
+    data[168..176].copy_from_slice(&2u64.to_le_bytes());
+    data[176..184].copy_from_slice(&4u64.to_le_bytes());
+    data[184..192].copy_from_slice(&9u64.to_le_bytes());
+    let header = FundStateHeader::load(&data).unwrap();
+    assert!(holds_tokens(&header, &[9, 4]));
+    assert!(!holds_tokens(&header, &[4, 0]));
+}
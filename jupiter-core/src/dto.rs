@@ -0,0 +1,158 @@
+//! Serde wire types shared by the quote servers and third-party APIs.
+//!
+//! Pubkeys are base58 strings and token amounts are decimal strings, so values survive
+//! JSON parsers that read numbers as doubles. Top-level types carry `DTO_VERSION`;
+//! breaking changes to any type here bump it. With the `json-schema` feature every type
+//! derives `schemars::JsonSchema`, for generating OpenAPI specs.
+
+use jupiter_amm_interface::{Amm, Quote};
+use serde::{Deserialize, Serialize};
+
+use crate::amms::accounts::{BPS_DIVIDER, FUND_LP_DISABLED, LP_DISABLED};
+use crate::amms::symmetry_token_swap::SymmetryTokenSwap;
+
+pub const DTO_VERSION: u16 = 1;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct TokenAllocationDto {
+    pub mint: String,
+    pub amount: String,
+    pub target_weight_bps: u64,
+    /// Oracle avg price, in `ONE_USD` precision.
+    pub price: String,
+    pub lp_enabled: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct FundDto {
+    pub version: u16,
+    pub address: String,
+    pub label: String,
+    pub manager: String,
+    pub host: String,
+    pub fund_token: String,
+    /// Share token name and symbol, when the fund's metadata was loaded.
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub symbol: Option<String>,
+    #[serde(default)]
+    pub manager_fee_bps: u64,
+    #[serde(default)]
+    pub host_fee_bps: u64,
+    #[serde(default)]
+    pub actively_managed: bool,
+    /// Seconds between rule refilters, reweights and rebalances.
+    #[serde(default)]
+    pub refilter_interval: u64,
+    #[serde(default)]
+    pub reweight_interval: u64,
+    #[serde(default)]
+    pub rebalance_interval: u64,
+    pub supply_outstanding: String,
+    /// Fund worth in `ONE_USD` precision, absent while any of the fund's oracles is offline.
+    pub fund_worth: Option<String>,
+    pub lp_enabled: bool,
+    pub rebalance_in_progress: bool,
+    pub tokens: Vec<TokenAllocationDto>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct QuoteDto {
+    pub version: u16,
+    pub in_amount: String,
+    pub out_amount: String,
+    pub fee_amount: String,
+    pub fee_mint: String,
+    pub fee_pct: String,
+    pub not_enough_liquidity: bool,
+    pub min_in_amount: Option<String>,
+    pub min_out_amount: Option<String>,
+}
+
+impl From<&SymmetryTokenSwap> for FundDto {
+    fn from(fund: &SymmetryTokenSwap) -> Self {
+        let fund_state = fund.fund_state();
+        let tokens = (0..fund_state.num_tokens())
+            .map(|i| {
+                let settings = fund.token_list().list[fund_state.current_comp_token[i] as usize];
+                TokenAllocationDto {
+                    mint: settings.token_mint.to_string(),
+                    amount: fund_state.current_comp_amount[i].to_string(),
+                    target_weight_bps: SymmetryTokenSwap::mul_div(fund_state.target_weight[i], BPS_DIVIDER, fund_state.weight_sum),
+                    price: settings.oracle_price.avg_price.to_string(),
+                    lp_enabled: settings.lp_on != LP_DISABLED,
+                }
+            })
+            .collect();
+        FundDto {
+            version: DTO_VERSION,
+            address: fund.key().to_string(),
+            label: fund.label(),
+            manager: fund_state.manager.to_string(),
+            host: fund_state.host_pubkey.to_string(),
+            fund_token: fund_state.fund_token.to_string(),
+            name: fund.fund_metadata().map(|metadata| metadata.name.clone()),
+            symbol: fund.fund_metadata().map(|metadata| metadata.symbol.clone()),
+            manager_fee_bps: fund_state.manager_fee,
+            host_fee_bps: fund_state.host_fee,
+            actively_managed: fund_state.actively_managed != 0,
+            refilter_interval: fund_state.refilter_interval,
+            reweight_interval: fund_state.reweight_interval,
+            rebalance_interval: fund_state.rebalance_interval,
+            supply_outstanding: fund_state.supply_outstanding.to_string(),
+            fund_worth: fund.fund_worth().ok().map(|worth| worth.to_string()),
+            lp_enabled: fund_state.lp_disabled != FUND_LP_DISABLED,
+            rebalance_in_progress: fund.rebalance_in_progress(),
+            tokens,
+        }
+    }
+}
+
+impl From<&Quote> for QuoteDto {
+    fn from(quote: &Quote) -> Self {
+        QuoteDto {
+            version: DTO_VERSION,
+            in_amount: quote.in_amount.to_string(),
+            out_amount: quote.out_amount.to_string(),
+            fee_amount: quote.fee_amount.to_string(),
+            fee_mint: quote.fee_mint.to_string(),
+            fee_pct: quote.fee_pct.to_string(),
+            not_enough_liquidity: quote.not_enough_liquidity,
+            min_in_amount: quote.min_in_amount.map(|amount| amount.to_string()),
+            min_out_amount: quote.min_out_amount.map(|amount| amount.to_string()),
+        }
+    }
+}
+
+#[test]
+fn test_fund_dto() {
+    use jupiter_amm_interface::KeyedAccount;
+    use solana_sdk::pubkey::Pubkey;
+
+    use crate::amms::account_builders::{FundStateBuilder, TokenListBuilder};
+    use crate::amms::fund_metadata::FundMetadata;
+
+    let fund = SymmetryTokenSwap::from_keyed_account(
+        &KeyedAccount { key: Pubkey::new_unique(), account: FundStateBuilder::new().build_account(), params: None },
+        &KeyedAccount { key: SymmetryTokenSwap::TOKEN_LIST_ADDRESS, account: TokenListBuilder::new().build_account(), params: None },
+    ).unwrap();
+    assert_eq!(FundDto::from(&fund).name, None);
+
+    let fund = fund.with_fund_metadata(FundMetadata { name: "Solana Index".to_string(), symbol: "SOLI".to_string() });
+    let dto = FundDto::from(&fund);
+    assert_eq!(dto.symbol.as_deref(), Some("SOLI"));
+
+    // Payloads from before the fund settings were added still parse.
+    let mut json = serde_json::to_value(&dto).unwrap();
+    for field in ["name", "symbol", "managerFeeBps", "hostFeeBps", "activelyManaged", "refilterInterval", "reweightInterval", "rebalanceInterval"] {
+        json.as_object_mut().unwrap().remove(field);
+    }
+    assert_eq!(serde_json::from_value::<FundDto>(json).unwrap().name, None);
+}
@@ -0,0 +1,131 @@
+//! Errors raised while loading accounts, quoting and building swaps. The SDK's functions
+//! return `anyhow::Result`; downcast with `error.downcast_ref::<SymmetryError>()`.
+
+use solana_sdk::pubkey::Pubkey;
+
+/// Leg of a swap an error refers to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SwapSide {
+    From,
+    To,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+#[non_exhaustive]
+pub enum SymmetryError {
+    #[error("Wrong account size for {account}: expected {expected} bytes, got {got}")]
+    AccountSizeMismatch {
+        account: &'static str,
+        expected: usize,
+        got: usize,
+    },
+    #[error("Account data too short for {account}: expected at least {min} bytes, got {got}")]
+    AccountTooShort {
+        account: &'static str,
+        min: usize,
+        got: usize,
+    },
+    #[error("Wrong discriminator for {account}: expected {expected:?}, got {got:?}")]
+    DiscriminatorMismatch {
+        account: &'static str,
+        expected: [u8; 8],
+        got: [u8; 8],
+    },
+    #[error("Unsupported oracle type {oracle_type}")]
+    UnsupportedOracleType { oracle_type: u8 },
+    #[error("Token {mint} has offline oracle status")]
+    OracleOffline { mint: Pubkey },
+    #[error("Token {mint} not found in supported tokens")]
+    TokenNotSupported { mint: Pubkey },
+    #[error("Token {mint} not found in the fund composition")]
+    TokenNotInFund { mint: Pubkey },
+    #[error("{} token weight exceeds {} allowed weight", match side { SwapSide::From => "From", SwapSide::To => "To" }, match side { SwapSide::From => "max", SwapSide::To => "min" })]
+    WeightLimitExceeded { side: SwapSide },
+    #[error("Manager has disabled liquidity provision on this fund")]
+    FundLpDisabled,
+    #[error("Fund has no fund tokens outstanding")]
+    NoFundTokensOutstanding,
+    #[error("Fund has no value")]
+    ZeroFundWorth,
+    #[error("Target weights sum to zero")]
+    ZeroWeightSum,
+    #[error("Withdrawal of {fund_tokens} fund tokens exceeds the supply of {supply_outstanding}")]
+    WithdrawExceedsSupply { fund_tokens: u64, supply_outstanding: u64 },
+    #[error("Instruction is for program {program_id}, not Symmetry")]
+    NotSymmetryProgram { program_id: Pubkey },
+    #[error("Instruction data too short for a swap: expected {expected} bytes, got {got}")]
+    SwapDataTooShort { expected: usize, got: usize },
+    #[error("Not a Symmetry swap instruction")]
+    NotSwapInstruction,
+    #[error("Token id {token_id} is not in the token list")]
+    UnknownTokenId { token_id: u64 },
+    #[error("Account {key} is missing from the account map")]
+    MissingAccount { key: Pubkey },
+    /// An account is not owned by the program expected to write it, e.g. spoofed data in
+    /// a shared account cache.
+    #[error("Account {account} is owned by {actual_owner}, expected {expected_owner}")]
+    AccountOwnerMismatch {
+        account: Pubkey,
+        expected_owner: Pubkey,
+        actual_owner: Pubkey,
+    },
+    #[error("Invalid {account} account data")]
+    InvalidAccountData { account: &'static str },
+    #[error("Invalid PriceUpdateV2 verification level {tag}")]
+    InvalidVerificationLevel { tag: u8 },
+    /// The accounts of an update were read too many slots apart under `SlotSpreadPolicy::Reject`.
+    #[error("Account slots span {} slots ({min_slot}..={max_slot}), max allowed is {max_slot_spread}", max_slot - min_slot)]
+    SlotSpreadExceeded {
+        min_slot: u64,
+        max_slot: u64,
+        max_slot_spread: u64,
+    },
+    /// Quote rejected under `RebalancePolicy::Reject`.
+    #[error("Fund {fund} has a rebalance in progress")]
+    RebalanceInProgress {
+        fund: Pubkey,
+        active_buy_states: u64,
+        sell_state: u64,
+        rebalance_sell_state: u64,
+    },
+    /// Quote rejected under `LiquidityPolicy::Reject`. `requested_out` is the output before
+    /// fees the in amount would buy, `available_out` the fund's holding of the output token.
+    #[error("Fund {fund} holds {available_out} of {output_mint}, less than the {requested_out} requested")]
+    NotEnoughLiquidity {
+        fund: Pubkey,
+        output_mint: Pubkey,
+        requested_out: u64,
+        available_out: u64,
+    },
+    /// The token's `lp_on` flag is off in the token list. Routes built from an older
+    /// `get_reserve_mints()` can still ask for one.
+    #[error("Liquidity provision is disabled for {mint} in fund {fund}")]
+    TokenLpDisabled { fund: Pubkey, mint: Pubkey },
+    #[error("Amount must be positive")]
+    ZeroAmount,
+    #[error("No in amount reaches the requested out amount of {out_amount}")]
+    OutAmountUnreachable { out_amount: u64 },
+    #[error("No part of the in amount clears the weight limits")]
+    NoFillableAmount,
+    #[error("Fund token mint not loaded")]
+    FundTokenMintNotLoaded,
+    #[error("Unsupported snapshot version {version}")]
+    UnsupportedSnapshotVersion { version: u16 },
+    /// A fund value or amount left the range of u64 (or went negative).
+    #[error("{0}")]
+    Arithmetic(&'static str),
+}
+
+#[test]
+fn test_symmetry_error_messages() {
+    assert_eq!(
+        SymmetryError::WeightLimitExceeded { side: SwapSide::From }.to_string(),
+        "From token weight exceeds max allowed weight"
+    );
+    assert_eq!(
+        SymmetryError::WeightLimitExceeded { side: SwapSide::To }.to_string(),
+        "To token weight exceeds min allowed weight"
+    );
+    let error: anyhow::Error = SymmetryError::FundLpDisabled.into();
+    assert_eq!(error.downcast_ref::<SymmetryError>(), Some(&SymmetryError::FundLpDisabled));
+}
@@ -0,0 +1,101 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::message::Message;
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+
+use crate::transaction::{SwapRequest, SwapTransactionBuilder};
+
+/// An account of an exported instruction, in the `{ pubkey, isSigner, isWritable }` shape
+/// used by web3.js and multisig UIs.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedAccountMeta {
+    pub pubkey: String,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+/// An instruction as JSON for multisig and governance tooling, which re-wraps it in its own
+/// proposal transaction. `data` is base64.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedInstruction {
+    pub program_id: String,
+    pub accounts: Vec<ExportedAccountMeta>,
+    pub data: String,
+}
+
+impl From<&Instruction> for ExportedInstruction {
+    fn from(instruction: &Instruction) -> Self {
+        Self {
+            program_id: instruction.program_id.to_string(),
+            accounts: instruction.accounts.iter()
+                .map(|meta| ExportedAccountMeta {
+                    pubkey: meta.pubkey.to_string(),
+                    is_signer: meta.is_signer,
+                    is_writable: meta.is_writable,
+                })
+                .collect(),
+            data: base64::encode(&instruction.data),
+        }
+    }
+}
+
+impl ExportedInstruction {
+    pub fn to_instruction(&self) -> Result<Instruction> {
+        let accounts = self.accounts.iter()
+            .map(|meta| Ok(AccountMeta {
+                pubkey: Pubkey::from_str(&meta.pubkey)?,
+                is_signer: meta.is_signer,
+                is_writable: meta.is_writable,
+            }))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Instruction {
+            program_id: Pubkey::from_str(&self.program_id)?,
+            accounts,
+            data: base64::decode(&self.data)?,
+        })
+    }
+}
+
+/// Base58 of a serialized legacy message, the format `solana` CLI and Squads accept for
+/// offline signing and transaction import.
+pub fn encode_message_base58(message: &Message) -> String {
+    bs58::encode(message.serialize()).into_string()
+}
+
+impl<'a> SwapTransactionBuilder<'a> {
+    /// The swap's instructions as JSON. The multisig vault should be both the builder's payer
+    /// and the swap user, since the proposal transaction is signed by the vault.
+    pub fn export_instructions(&self, swap: &SwapRequest) -> Result<Vec<ExportedInstruction>> {
+        Ok(self.instructions(swap)?.iter().map(ExportedInstruction::from).collect())
+    }
+
+    /// Legacy message with all instructions, ignoring lookup tables. Large funds may not fit
+    /// in a legacy transaction.
+    pub fn legacy_message(&self, swap: &SwapRequest, recent_blockhash: Hash) -> Result<Message> {
+        Ok(Message::new_with_blockhash(&self.instructions(swap)?, Some(&self.payer()), &recent_blockhash))
+    }
+
+    pub fn export_message_base58(&self, swap: &SwapRequest, recent_blockhash: Hash) -> Result<String> {
+        Ok(encode_message_base58(&self.legacy_message(swap, recent_blockhash)?))
+    }
+}
+
+#[test]
+fn test_exported_instruction_roundtrip() {
+    let instruction = crate::transaction::create_associated_token_account_idempotent(
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+    );
+    let exported = ExportedInstruction::from(&instruction);
+    let json = serde_json::to_string(&exported).unwrap();
+    assert!(json.contains("\"programId\""));
+    assert!(json.contains("\"isWritable\""));
+    let parsed: ExportedInstruction = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed.to_instruction().unwrap(), instruction);
+}
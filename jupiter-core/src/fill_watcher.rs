@@ -0,0 +1,183 @@
+use jupiter_amm_interface::Amm;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+use crate::amms::symmetry_token_swap::SymmetryTokenSwap;
+
+/// Fill inferred from the change of a fund's composition between two observations.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FillEvent {
+    /// Exactly one token balance went up and one went down: a swap (or several
+    /// same-direction swaps) of `amount_in` of `mint_in` for `amount_out` of `mint_out`.
+    Swap {
+        fund: Pubkey,
+        slot: Option<u64>,
+        mint_in: Pubkey,
+        amount_in: u64,
+        mint_out: Pubkey,
+        amount_out: u64,
+    },
+    /// Balances moved in a way that doesn't map to a single swap (several swaps netted
+    /// together, deposits/withdrawals, a rebalance or a composition change).
+    Unattributed {
+        fund: Pubkey,
+        slot: Option<u64>,
+        /// Signed balance change per mint.
+        deltas: Vec<(Pubkey, i128)>,
+    },
+}
+
+type FillCallback = Box<dyn Fn(&FillEvent) + Send + Sync>;
+
+/// Detects fills from successive fund states when scraping transactions is too heavy.
+/// Feed it every refreshed fund with `observe`.
+#[derive(Default)]
+pub struct FillWatcher {
+    last_amounts: HashMap<Pubkey, HashMap<Pubkey, u64>>,
+    callbacks: Vec<FillCallback>,
+    subscribers: Vec<Sender<FillEvent>>,
+}
+
+impl FillWatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn on_fill<F>(&mut self, callback: F)
+    where
+        F: Fn(&FillEvent) + Send + Sync + 'static,
+    {
+        self.callbacks.push(Box::new(callback));
+    }
+
+    /// Channel receiving every event; dropped receivers are pruned on the next event.
+    pub fn subscribe(&mut self) -> Receiver<FillEvent> {
+        let (sender, receiver) = channel();
+        self.subscribers.push(sender);
+        receiver
+    }
+
+    /// Compares the fund's composition with the previous observation and emits the inferred
+    /// event, if anything changed. The first observation of a fund only records its state.
+    pub fn observe(&mut self, fund: &SymmetryTokenSwap) -> Option<FillEvent> {
+        let fund_state = fund.fund_state();
+        let token_list = fund.token_list();
+        let amounts: HashMap<Pubkey, u64> = (0..fund_state.num_tokens())
+            .map(|i| {
+                (
+                    token_list.list[fund_state.current_comp_token[i] as usize].token_mint,
+                    fund_state.current_comp_amount[i],
+                )
+            })
+            .collect();
+        let previous = self.last_amounts.insert(fund.key(), amounts.clone())?;
+        let event = infer_fill(fund.key(), fund.last_update_slot(), &previous, &amounts)?;
+        for callback in &self.callbacks {
+            callback(&event);
+        }
+        self.subscribers.retain(|subscriber| subscriber.send(event.clone()).is_ok());
+        Some(event)
+    }
+
+    pub fn forget(&mut self, fund: &Pubkey) {
+        self.last_amounts.remove(fund);
+    }
+}
+
+pub fn infer_fill(
+    fund: Pubkey,
+    slot: Option<u64>,
+    before: &HashMap<Pubkey, u64>,
+    after: &HashMap<Pubkey, u64>,
+) -> Option<FillEvent> {
+    let mut deltas: Vec<(Pubkey, i128)> = before
+        .keys()
+        .chain(after.keys().filter(|mint| !before.contains_key(mint)))
+        .map(|mint| {
+            let old = before.get(mint).copied().unwrap_or_default() as i128;
+            let new = after.get(mint).copied().unwrap_or_default() as i128;
+            (*mint, new - old)
+        })
+        .filter(|(_, delta)| *delta != 0)
+        .collect();
+    if deltas.is_empty() {
+        return None;
+    }
+    deltas.sort();
+    let same_tokens = before.len() == after.len() && before.keys().all(|mint| after.contains_key(mint));
+    let ins: Vec<&(Pubkey, i128)> = deltas.iter().filter(|(_, delta)| *delta > 0).collect();
+    let outs: Vec<&(Pubkey, i128)> = deltas.iter().filter(|(_, delta)| *delta < 0).collect();
+    if same_tokens && ins.len() == 1 && outs.len() == 1 {
+        return Some(FillEvent::Swap {
+            fund,
+            slot,
+            mint_in: ins[0].0,
+            amount_in: ins[0].1 as u64,
+            mint_out: outs[0].0,
+            amount_out: outs[0].1.unsigned_abs() as u64,
+        });
+    }
+    Some(FillEvent::Unattributed { fund, slot, deltas })
+}
+
+#[test]
+fn test_fill_watcher() {
+    use rust_decimal::Decimal;
+    use std::sync::{Arc, Mutex};
+
+    use crate::amms::account_builders::{quotable_fund, FixtureToken, FundStateBuilder};
+    use crate::amms::accounts::WEIGHT_MULTIPLIER;
+    use crate::math::token_swap::SwapResult;
+
+    let tokens = [FixtureToken::new(6, 100_000_000), FixtureToken::new(9, 10_000_000_000)];
+    let (usdc, sol) = (tokens[0].mint, tokens[1].mint);
+    let fund_state = FundStateBuilder::new()
+        .with_token(0, 10_000_000_000, WEIGHT_MULTIPLIER / 2)
+        .with_token(1, 100_000_000_000, WEIGHT_MULTIPLIER / 2);
+    let mut fund = quotable_fund(Pubkey::new_unique(), &fund_state, &tokens);
+
+    let mut watcher = FillWatcher::new();
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let callback_seen = seen.clone();
+    watcher.on_fill(move |event| callback_seen.lock().unwrap().push(event.clone()));
+    let receiver = watcher.subscribe();
+    assert_eq!(watcher.observe(&fund), None);
+    assert_eq!(watcher.observe(&fund), None);
+
+    // 100 USDC sold into the fund for 0.99 SOL, 0.006 SOL of fees leaving with it.
+    fund.apply_own_swap(&usdc, &sol, &SwapResult {
+        fee_pct: Decimal::ZERO,
+        fees: 10_000_000,
+        input_amount: 100_000_000,
+        expected_output_amount: 990_000_000,
+        not_enough_liquidity: false,
+    }).unwrap();
+    let expected = FillEvent::Swap {
+        fund: fund.key(),
+        slot: None,
+        mint_in: usdc,
+        amount_in: 100_000_000,
+        mint_out: sol,
+        amount_out: 996_000_000,
+    };
+    assert_eq!(watcher.observe(&fund), Some(expected.clone()));
+    assert_eq!(*seen.lock().unwrap(), vec![expected.clone()]);
+    assert_eq!(receiver.try_recv().unwrap(), expected);
+
+    // Forgetting the fund makes the next observation a baseline again.
+    watcher.forget(&fund.key());
+    drop(receiver);
+    assert_eq!(watcher.observe(&fund), None);
+
+    // Both balances up, as on a deposit, can't be a single swap.
+    let before: HashMap<Pubkey, u64> = [(usdc, 100), (sol, 100)].into();
+    let after: HashMap<Pubkey, u64> = [(usdc, 150), (sol, 110)].into();
+    let mut deltas = vec![(usdc, 50), (sol, 10)];
+    deltas.sort();
+    assert_eq!(infer_fill(fund.key(), Some(7), &before, &after), Some(FillEvent::Unattributed { fund: fund.key(), slot: Some(7), deltas }));
+    // A token leaving the composition isn't attributed to a swap either.
+    let after: HashMap<Pubkey, u64> = [(usdc, 200)].into();
+    assert!(matches!(infer_fill(fund.key(), None, &before, &after), Some(FillEvent::Unattributed { .. })));
+    assert_eq!(infer_fill(fund.key(), None, &before, &before), None);
+}
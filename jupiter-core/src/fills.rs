@@ -0,0 +1,359 @@
+use anyhow::{Error, Result};
+use solana_client::{
+    nonblocking::rpc_client::RpcClient, rpc_client::GetConfirmedSignaturesForAddress2Config,
+    rpc_config::RpcTransactionConfig,
+};
+use solana_sdk::{instruction::CompiledInstruction, pubkey::Pubkey, signature::Signature};
+use solana_transaction_status::{
+    EncodedConfirmedTransactionWithStatusMeta, UiInnerInstructions, UiInstruction, UiLoadedAddresses,
+    UiTransactionEncoding, UiTransactionStatusMeta, UiTransactionTokenBalance,
+};
+use std::str::FromStr;
+
+use crate::amms::accounts::TokenList;
+use crate::amms::symmetry_token_swap::SymmetryTokenSwap;
+use crate::instruction::SwapIxArgs;
+
+/// Position of the user's destination token account in the swap instruction accounts.
+const DESTINATION_ACCOUNT_INDEX: usize = 6;
+
+/// A Symmetry swap executed against a fund, decoded from a confirmed transaction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Fill {
+    pub signature: Signature,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub fund: Pubkey,
+    /// Signer that authorized the token transfer.
+    pub authority: Pubkey,
+    pub args: SwapIxArgs,
+    /// Mints resolved from the token ids when a token list was provided.
+    pub from_mint: Option<Pubkey>,
+    pub to_mint: Option<Pubkey>,
+    /// Amount credited to the destination token account, from pre/post token balances.
+    pub out_amount: Option<u64>,
+}
+
+/// Walks a fund's transaction history from newest to oldest, one page of signatures at a time.
+pub struct FillScraper<'a> {
+    client: &'a RpcClient,
+    fund: Pubkey,
+    mints: Vec<Pubkey>,
+    page_size: usize,
+    before: Option<Signature>,
+    until: Option<Signature>,
+    done: bool,
+}
+
+impl<'a> FillScraper<'a> {
+    pub fn new(client: &'a RpcClient, fund: Pubkey) -> Self {
+        Self {
+            client,
+            fund,
+            mints: Vec::new(),
+            page_size: 100,
+            before: None,
+            until: None,
+            done: false,
+        }
+    }
+
+    /// Resolve token ids in fills to mints. A token count past the list's capacity is
+    /// clamped to it.
+    pub fn with_token_list(mut self, token_list: &TokenList) -> Self {
+        let num_tokens = (token_list.num_tokens as usize).min(token_list.list.len());
+        self.mints = token_list.list[..num_tokens]
+            .iter()
+            .map(|token| token.token_mint)
+            .collect();
+        self
+    }
+
+    pub(crate) fn mints(&self) -> &[Pubkey] {
+        &self.mints
+    }
+
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = page_size.clamp(1, 1000);
+        self
+    }
+
+    /// Start scraping below this signature (exclusive).
+    pub fn before(mut self, signature: Signature) -> Self {
+        self.before = Some(signature);
+        self
+    }
+
+    /// Stop once this signature is reached (exclusive).
+    pub fn until(mut self, signature: Signature) -> Self {
+        self.until = Some(signature);
+        self
+    }
+
+    /// Fills from the next page of signatures, or `None` once history is exhausted.
+    pub async fn next_page(&mut self) -> Result<Option<Vec<Fill>>> {
+        let transactions = match self.next_transactions().await? {
+            Some(transactions) => transactions,
+            None => return Ok(None),
+        };
+        let mut fills = Vec::new();
+        for (signature, transaction) in &transactions {
+            fills.extend(decode_fills(signature, transaction, &self.fund, &self.mints)?);
+        }
+        Ok(Some(fills))
+    }
+
+    /// Successful transactions from the next page of signatures, or `None` once history is
+    /// exhausted.
+    pub(crate) async fn next_transactions(&mut self) -> Result<Option<Vec<(Signature, EncodedConfirmedTransactionWithStatusMeta)>>> {
+        if self.done {
+            return Ok(None);
+        }
+        let signatures = self
+            .client
+            .get_signatures_for_address_with_config(
+                &self.fund,
+                GetConfirmedSignaturesForAddress2Config {
+                    before: self.before,
+                    until: self.until,
+                    limit: Some(self.page_size),
+                    commitment: None,
+                },
+            )
+            .await?;
+        if signatures.len() < self.page_size {
+            self.done = true;
+        }
+        let mut transactions = Vec::new();
+        for status in &signatures {
+            let signature = Signature::from_str(&status.signature)?;
+            self.before = Some(signature);
+            if status.err.is_some() {
+                continue;
+            }
+            let transaction = self
+                .client
+                .get_transaction_with_config(
+                    &signature,
+                    RpcTransactionConfig {
+                        encoding: Some(UiTransactionEncoding::Base64),
+                        commitment: None,
+                        max_supported_transaction_version: Some(0),
+                    },
+                )
+                .await?;
+            transactions.push((signature, transaction));
+        }
+        if signatures.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(transactions))
+    }
+
+    /// Drains every remaining page.
+    pub async fn collect_all(&mut self) -> Result<Vec<Fill>> {
+        let mut fills = Vec::new();
+        while let Some(page) = self.next_page().await? {
+            fills.extend(page);
+        }
+        Ok(fills)
+    }
+}
+
+/// Symmetry instructions against a fund in a transaction, top level and CPI.
+pub(crate) struct FundInstructions {
+    /// Static keys followed by lookup table addresses; instruction accounts index into it.
+    pub(crate) account_keys: Vec<Pubkey>,
+    /// Keys that signed the transaction.
+    pub(crate) signers: Vec<Pubkey>,
+    pub(crate) instructions: Vec<CompiledInstruction>,
+}
+
+impl FundInstructions {
+    pub(crate) fn decode(transaction: &EncodedConfirmedTransactionWithStatusMeta, fund: &Pubkey) -> Result<FundInstructions> {
+        let versioned = transaction
+            .transaction
+            .transaction
+            .decode()
+            .ok_or_else(|| Error::msg("Transaction is not binary encoded"))?;
+        let meta = transaction.transaction.meta.as_ref();
+        let static_keys = versioned.message.static_account_keys();
+        let account_keys = account_keys(static_keys, meta)?;
+        let signers = static_keys[..(versioned.message.header().num_required_signatures as usize).min(static_keys.len())].to_vec();
+
+        let mut instructions: Vec<CompiledInstruction> = versioned.message.instructions().to_vec();
+        let inner: Option<&Vec<UiInnerInstructions>> = meta.and_then(|meta| meta.inner_instructions.as_ref().into());
+        if let Some(inner) = inner {
+            for inner_instructions in inner {
+                for instruction in &inner_instructions.instructions {
+                    if let UiInstruction::Compiled(compiled) = instruction {
+                        instructions.push(CompiledInstruction {
+                            program_id_index: compiled.program_id_index,
+                            accounts: compiled.accounts.clone(),
+                            data: bs58::decode(&compiled.data).into_vec()?,
+                        });
+                    }
+                }
+            }
+        }
+        instructions.retain(|instruction| {
+            account_keys.get(instruction.program_id_index as usize) == Some(&SymmetryTokenSwap::SYMMETRY_PROGRAM_ADDRESS)
+                && instruction.accounts.get(1).and_then(|&account| account_keys.get(account as usize)) == Some(fund)
+        });
+        Ok(FundInstructions { account_keys, signers, instructions })
+    }
+}
+
+/// Decodes every Symmetry swap against `fund` in a transaction, including swaps made through
+/// CPI (e.g. Jupiter routes). `mints` maps token ids to mints and may be empty.
+pub fn decode_fills(
+    signature: &Signature,
+    transaction: &EncodedConfirmedTransactionWithStatusMeta,
+    fund: &Pubkey,
+    mints: &[Pubkey],
+) -> Result<Vec<Fill>> {
+    let meta = transaction.transaction.meta.as_ref();
+    let FundInstructions { account_keys, instructions, .. } = FundInstructions::decode(transaction, fund)?;
+
+    let mut fills = Vec::new();
+    for instruction in &instructions {
+        let args = match SwapIxArgs::unpack(&instruction.data) {
+            Ok(args) => args,
+            Err(_) => continue,
+        };
+        let out_amount = match (meta, instruction.accounts.get(DESTINATION_ACCOUNT_INDEX)) {
+            (Some(meta), Some(&index)) => balance_change(meta, index),
+            _ => None,
+        };
+        let authority = instruction
+            .accounts
+            .first()
+            .and_then(|&account| account_keys.get(account as usize));
+        fills.push(Fill {
+            signature: *signature,
+            slot: transaction.slot,
+            block_time: transaction.block_time,
+            fund: *fund,
+            authority: authority.copied().unwrap_or_default(),
+            args,
+            from_mint: mints.get(args.from_token_id as usize).copied(),
+            to_mint: mints.get(args.to_token_id as usize).copied(),
+            out_amount,
+        });
+    }
+    Ok(fills)
+}
+
+/// Static keys followed by addresses loaded from lookup tables (writable, then readonly).
+fn account_keys(static_keys: &[Pubkey], meta: Option<&UiTransactionStatusMeta>) -> Result<Vec<Pubkey>> {
+    let mut keys = static_keys.to_vec();
+    let loaded: Option<&UiLoadedAddresses> = meta.and_then(|meta| meta.loaded_addresses.as_ref().into());
+    if let Some(loaded) = loaded {
+        for key in loaded.writable.iter().chain(loaded.readonly.iter()) {
+            keys.push(Pubkey::from_str(key)?);
+        }
+    }
+    Ok(keys)
+}
+
+pub(crate) fn balance_change(meta: &UiTransactionStatusMeta, account_index: u8) -> Option<u64> {
+    let amount = |balances: Option<&Vec<UiTransactionTokenBalance>>| -> Option<u64> {
+        balances?
+            .iter()
+            .find(|balance| balance.account_index == account_index)
+            .map_or(Some(0), |balance| balance.ui_token_amount.amount.parse().ok())
+    };
+    let pre = amount(Option::from(meta.pre_token_balances.as_ref()))?;
+    let post = amount(Option::from(meta.post_token_balances.as_ref()))?;
+    Some(post.saturating_sub(pre))
+}
+
+#[test]
+fn test_decode_fills() {
+    use serde_json::json;
+    use solana_sdk::instruction::{AccountMeta, Instruction};
+    use solana_sdk::message::Message;
+    use solana_sdk::transaction::{Transaction, VersionedTransaction};
+    use solana_transaction_status::{EncodedTransaction, EncodedTransactionWithStatusMeta, TransactionBinaryEncoding};
+
+    let fund = Pubkey::new_unique();
+    let user = Pubkey::new_unique();
+    let destination = Pubkey::new_unique();
+    let swap = |fund: Pubkey, args: SwapIxArgs| {
+        let mut accounts = vec![AccountMeta::new_readonly(user, true), AccountMeta::new(fund, false)];
+        accounts.extend((0..4).map(|_| AccountMeta::new(Pubkey::new_unique(), false)));
+        accounts.push(AccountMeta::new(destination, false));
+        Instruction::new_with_bytes(SymmetryTokenSwap::SYMMETRY_PROGRAM_ADDRESS, &args.pack(), accounts)
+    };
+    let args = SwapIxArgs { from_token_id: 1, to_token_id: 0, amount: 1_000_000_000, minimum_amount_out: 99_000_000 };
+    let other_fund_args = SwapIxArgs { from_token_id: 0, to_token_id: 1, ..args };
+    let not_a_swap = Instruction::new_with_bytes(SymmetryTokenSwap::SYMMETRY_PROGRAM_ADDRESS, &[1, 2, 3], vec![
+        AccountMeta::new_readonly(user, true),
+        AccountMeta::new(fund, false),
+    ]);
+    let message = Message::new(&[swap(fund, args), swap(Pubkey::new_unique(), other_fund_args), not_a_swap], Some(&user));
+    let destination_index = message.account_keys.iter().position(|key| *key == destination).unwrap() as u8;
+    let transaction = VersionedTransaction::from(Transaction::new_unsigned(message));
+
+    let balance = |amount: u64| json!([{
+        "accountIndex": destination_index,
+        "mint": Pubkey::new_unique().to_string(),
+        "uiTokenAmount": { "uiAmount": null, "decimals": 6, "amount": amount.to_string(), "uiAmountString": "" },
+    }]);
+    let meta: UiTransactionStatusMeta = serde_json::from_value(json!({
+        "err": null,
+        "status": { "Ok": null },
+        "fee": 5_000,
+        "preBalances": [],
+        "postBalances": [],
+        "preTokenBalances": balance(1_000_000),
+        "postTokenBalances": balance(100_500_000),
+    })).unwrap();
+    let transaction = EncodedConfirmedTransactionWithStatusMeta {
+        slot: 250_000_000,
+        transaction: EncodedTransactionWithStatusMeta {
+            transaction: EncodedTransaction::Binary(
+                base64::encode(bincode::serialize(&transaction).unwrap()),
+                TransactionBinaryEncoding::Base64,
+            ),
+            meta: Some(meta),
+            version: None,
+        },
+        block_time: Some(1_700_000_000),
+    };
+
+    let signature = Signature::new_unique();
+    let mints = [Pubkey::new_unique(), Pubkey::new_unique()];
+    let fills = decode_fills(&signature, &transaction, &fund, &mints).unwrap();
+    assert_eq!(fills, vec![Fill {
+        signature,
+        slot: 250_000_000,
+        block_time: Some(1_700_000_000),
+        fund,
+        authority: user,
+        args,
+        from_mint: Some(mints[1]),
+        to_mint: Some(mints[0]),
+        out_amount: Some(99_500_000),
+    }]);
+    // Without a token list the mints stay unresolved.
+    let fills = decode_fills(&signature, &transaction, &fund, &[]).unwrap();
+    assert_eq!((fills[0].from_mint, fills[0].to_mint), (None, None));
+    assert!(decode_fills(&signature, &transaction, &Pubkey::new_unique(), &mints).unwrap().is_empty());
+}
+
+#[test]
+fn test_with_token_list_clamps_token_count() {
+    use crate::amms::accounts::MAX_TOKENS_IN_ASSET_POOL;
+
+    let client = RpcClient::new_mock("succeeds".to_string());
+    let mut token_list = TokenList::empty();
+    token_list.num_tokens = 2;
+    token_list.list[1].token_mint = Pubkey::new_unique();
+    let scraper = FillScraper::new(&client, Pubkey::new_unique()).with_token_list(&token_list);
+    assert_eq!(scraper.mints(), &[Pubkey::default(), token_list.list[1].token_mint]);
+
+    token_list.num_tokens = u64::MAX;
+    let scraper = FillScraper::new(&client, Pubkey::new_unique()).with_token_list(&token_list);
+    assert_eq!(scraper.mints().len(), MAX_TOKENS_IN_ASSET_POOL);
+}
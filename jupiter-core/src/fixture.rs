@@ -0,0 +1,278 @@
+//! Recorded fund accounts for deterministic quote tests.
+//!
+//! A fixture is JSON holding every account a fund's quotes depend on, read at one slot,
+//! together with the clock at that slot. `AmmFixture::amm` rebuilds the fund from it and
+//! judges oracle staleness at the recorded clock, so quotes replay identically without RPC
+//! access. Accounts use the `VectorAccount` encoding of the `vectors` module.
+
+use anyhow::{Error, Result};
+use jupiter_amm_interface::{AccountMap, KeyedAccount};
+use serde::{Deserialize, Serialize};
+use solana_sdk::{clock::Clock, pubkey::Pubkey, sysvar};
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::amms::symmetry_token_swap::SymmetryTokenSwap;
+use crate::vectors::VectorAccount;
+
+pub const FIXTURE_FORMAT_VERSION: u16 = 1;
+
+/// `getMultipleAccounts` accepts at most 100 keys per request.
+#[cfg(feature = "rpc")]
+const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FixtureClock {
+    pub slot: u64,
+    pub epoch: u64,
+    pub unix_timestamp: i64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AmmFixture {
+    pub version: u16,
+    pub fund: String,
+    /// Context slot the accounts were read at.
+    pub slot: u64,
+    pub clock: FixtureClock,
+    /// Fund state, token list and every account `get_accounts_to_update()` names.
+    pub accounts: Vec<VectorAccount>,
+}
+
+impl AmmFixture {
+    /// Fixture of `fund` holding `account_map`, read at `slot`. The clock is taken from the
+    /// clock sysvar in `account_map`, or is `slot` at timestamp 0 without one.
+    pub fn new(fund: &Pubkey, slot: u64, account_map: &AccountMap) -> Result<AmmFixture> {
+        let clock: Clock = match account_map.get(&sysvar::clock::ID) {
+            Some(account) => bincode::deserialize(&account.data)?,
+            None => Clock { slot, ..Clock::default() },
+        };
+        let mut accounts: Vec<_> = account_map
+            .iter()
+            .map(|(key, account)| VectorAccount::from_account(key, account))
+            .collect();
+        accounts.sort_by(|a, b| a.pubkey.cmp(&b.pubkey));
+        Ok(AmmFixture {
+            version: FIXTURE_FORMAT_VERSION,
+            fund: fund.to_string(),
+            slot,
+            clock: FixtureClock {
+                slot: clock.slot,
+                epoch: clock.epoch,
+                unix_timestamp: clock.unix_timestamp,
+            },
+            accounts,
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<AmmFixture> {
+        let fixture: AmmFixture = serde_json::from_slice(&std::fs::read(path)?)?;
+        if fixture.version != FIXTURE_FORMAT_VERSION {
+            return Err(Error::msg(format!("Unsupported fixture format version {}", fixture.version)));
+        }
+        Ok(fixture)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_vec_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn fund(&self) -> Result<Pubkey> {
+        Pubkey::from_str(&self.fund).map_err(|e| Error::msg(format!("Invalid pubkey {}: {e}", self.fund)))
+    }
+
+    pub fn clock(&self) -> Clock {
+        Clock {
+            slot: self.clock.slot,
+            epoch: self.clock.epoch,
+            unix_timestamp: self.clock.unix_timestamp,
+            ..Clock::default()
+        }
+    }
+
+    pub fn account_map(&self) -> Result<AccountMap> {
+        self.accounts.iter().map(VectorAccount::to_account).collect()
+    }
+
+    /// Builds the fund from the recorded accounts and updates it at the recorded clock.
+    pub fn amm(&self) -> Result<SymmetryTokenSwap> {
+        let account_map = self.account_map()?;
+        let keyed_account = |key: Pubkey| -> Result<KeyedAccount> {
+            Ok(KeyedAccount {
+                key,
+                account: account_map.get(&key)
+                    .ok_or_else(|| Error::msg(format!("Fixture is missing account {key}")))?
+                    .clone(),
+                params: None,
+            })
+        };
+        let mut amm = SymmetryTokenSwap::from_keyed_account(
+            &keyed_account(self.fund()?)?,
+            &keyed_account(SymmetryTokenSwap::TOKEN_LIST_ADDRESS)?,
+        )?;
+        amm.update_with_clock(&account_map, self.clock())?;
+        Ok(amm)
+    }
+}
+
+/// Reads the fund state, token list and every account `get_accounts_to_update()` names,
+/// failing if the node served them from different slots.
+#[cfg(feature = "rpc")]
+pub fn record(rpc: &solana_client::rpc_client::RpcClient, fund: &Pubkey) -> Result<AmmFixture> {
+    use jupiter_amm_interface::Amm;
+
+    let fetch = |keys: &[Pubkey]| -> Result<(u64, AccountMap)> {
+        let mut slots = Vec::new();
+        let mut account_map = AccountMap::new();
+        for chunk in keys.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            let response = rpc.get_multiple_accounts_with_commitment(chunk, rpc.commitment())?;
+            slots.push(response.context.slot);
+            for (key, account) in chunk.iter().zip(response.value) {
+                if let Some(account) = account {
+                    account_map.insert(*key, account);
+                }
+            }
+        }
+        let slot = slots[0];
+        if slots.iter().any(|&other| other != slot) {
+            return Err(Error::msg(format!("Accounts were read at different slots {slots:?}")));
+        }
+        Ok((slot, account_map))
+    };
+
+    let (_, listing) = fetch(&[*fund, SymmetryTokenSwap::TOKEN_LIST_ADDRESS])?;
+    let keyed_account = |key: Pubkey| -> Result<KeyedAccount> {
+        let account = listing.get(&key).ok_or_else(|| Error::msg(format!("Account {key} not found")))?;
+        Ok(KeyedAccount { key, account: account.clone(), params: None })
+    };
+    let amm = SymmetryTokenSwap::from_keyed_account_checked(
+        &keyed_account(*fund)?,
+        &keyed_account(SymmetryTokenSwap::TOKEN_LIST_ADDRESS)?,
+    )?;
+
+    let mut keys = vec![*fund, SymmetryTokenSwap::TOKEN_LIST_ADDRESS];
+    for key in amm.get_accounts_to_update() {
+        if !keys.contains(&key) {
+            keys.push(key);
+        }
+    }
+    let (slot, account_map) = fetch(&keys)?;
+    AmmFixture::new(fund, slot, &account_map)
+}
+
+/// Serves a fixture's accounts to an `RpcClient`, answering `getMultipleAccounts`,
+/// `getAccountInfo` and `getSlot` at the recorded slot, as a 1.14 node.
+#[cfg(all(test, feature = "client"))]
+pub(crate) struct FixtureRpcSender {
+    slot: u64,
+    account_map: AccountMap,
+}
+
+#[cfg(all(test, feature = "client"))]
+impl FixtureRpcSender {
+    pub(crate) fn new(fixture: &AmmFixture) -> Result<FixtureRpcSender> {
+        Ok(FixtureRpcSender { slot: fixture.slot, account_map: fixture.account_map()? })
+    }
+
+    fn ui_account(&self, key: &serde_json::Value) -> Option<solana_account_decoder::UiAccount> {
+        use solana_account_decoder::{UiAccount, UiAccountEncoding};
+
+        let key = Pubkey::from_str(key.as_str()?).ok()?;
+        let account = self.account_map.get(&key)?;
+        Some(UiAccount::encode(&key, account, UiAccountEncoding::Base64, None, None))
+    }
+}
+
+#[cfg(all(test, feature = "client"))]
+#[async_trait::async_trait]
+impl solana_client::rpc_sender::RpcSender for FixtureRpcSender {
+    async fn send(
+        &self,
+        request: solana_client::rpc_request::RpcRequest,
+        params: serde_json::Value,
+    ) -> solana_client::client_error::Result<serde_json::Value> {
+        use serde_json::json;
+        use solana_client::client_error::ClientErrorKind;
+        use solana_client::rpc_request::RpcRequest;
+
+        let context = json!({ "slot": self.slot });
+        Ok(match request {
+            RpcRequest::GetSlot => json!(self.slot),
+            RpcRequest::GetVersion => json!({ "solana-core": "1.14.19" }),
+            RpcRequest::GetAccountInfo => json!({ "context": context, "value": self.ui_account(&params[0]) }),
+            RpcRequest::GetMultipleAccounts => {
+                let keys = params[0].as_array().cloned().unwrap_or_default();
+                let accounts: Vec<_> = keys.iter().map(|key| self.ui_account(key)).collect();
+                json!({ "context": context, "value": accounts })
+            }
+            request => return Err(ClientErrorKind::Custom(format!("Fixture can't answer {request}")).into()),
+        })
+    }
+
+    fn get_transport_stats(&self) -> solana_client::rpc_sender::RpcTransportStats {
+        solana_client::rpc_sender::RpcTransportStats::default()
+    }
+
+    fn url(&self) -> String {
+        "fixture".to_string()
+    }
+}
+
+#[test]
+fn test_fixture_roundtrip() {
+    use jupiter_amm_interface::{Amm, QuoteParams};
+    use solana_sdk::account::Account;
+
+    use crate::amms::account_builders::{CurveDataBuilder, FundStateBuilder, OracleAccountBuilder, TokenListBuilder};
+    use crate::amms::accounts::{TokenSettings, WEIGHT_MULTIPLIER};
+
+    let usdc = Pubkey::new_unique();
+    let sol = Pubkey::new_unique();
+    let usdc_oracle = Pubkey::new_unique();
+    let sol_oracle = Pubkey::new_unique();
+    let token = |mint: Pubkey, decimals: u8, oracle_account: Pubkey| TokenSettings {
+        token_mint: mint,
+        decimals,
+        oracle_account,
+        oracle_confidence_pct: 10,
+        is_live: 1,
+        lp_on: 1,
+        ..TokenSettings::empty()
+    };
+    let fund = Pubkey::new_unique();
+    let clock = Clock { slot: 1_010, epoch: 2, unix_timestamp: 1_700_000_000, ..Clock::default() };
+    let mut account_map = AccountMap::new();
+    account_map.insert(fund, FundStateBuilder::new()
+        .with_token(0, 10_000_000_000, WEIGHT_MULTIPLIER / 2)
+        .with_token(1, 100_000_000_000, WEIGHT_MULTIPLIER / 2)
+        .with_weight_band(1_000, 5_000)
+        .build_account());
+    account_map.insert(SymmetryTokenSwap::TOKEN_LIST_ADDRESS, TokenListBuilder::new()
+        .with_token(token(usdc, 6, usdc_oracle))
+        .with_token(token(sol, 9, sol_oracle))
+        .build_account());
+    account_map.insert(SymmetryTokenSwap::CURVE_DATA_ADDRESS, CurveDataBuilder::new().build_account());
+    account_map.insert(usdc_oracle, OracleAccountBuilder::pyth(100_000_000, 10_000, -8, 1_000).build_account());
+    account_map.insert(sol_oracle, OracleAccountBuilder::pyth(10_000_000_000, 1_000_000, -8, 1_000).build_account());
+    account_map.insert(sysvar::clock::ID, Account {
+        data: bincode::serialize(&clock).unwrap(),
+        owner: sysvar::ID,
+        ..Account::default()
+    });
+
+    let path = std::env::temp_dir().join(format!("symmetry-fixture-{fund}.json"));
+    AmmFixture::new(&fund, 1_010, &account_map).unwrap().save(&path).unwrap();
+    let fixture = AmmFixture::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(fixture.clock(), clock);
+    let recorded = fixture.account_map().unwrap();
+    assert_eq!(recorded.len(), account_map.len());
+    assert!(account_map.iter().all(|(key, account)| recorded[key].data == account.data && recorded[key].owner == account.owner));
+
+    let amm = fixture.amm().unwrap();
+    let quote = amm.quote(&QuoteParams { in_amount: 100_000_000, input_mint: usdc, output_mint: sol }).unwrap();
+    assert!(quote.out_amount > 990_000_000 && quote.out_amount < 1_000_000_000);
+}
@@ -0,0 +1,95 @@
+use anyhow::Result;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use std::convert::TryInto;
+
+use crate::amms::accounts::TokenList;
+use crate::amms::symmetry_token_swap::SymmetryTokenSwap;
+use crate::error::SymmetryError;
+
+/// Arguments of the Symmetry swap instruction, in data order after the 8-byte instruction id.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SwapIxArgs {
+    pub from_token_id: u64,
+    pub to_token_id: u64,
+    pub amount: u64,
+    pub minimum_amount_out: u64,
+}
+
+impl SwapIxArgs {
+    pub const DATA_LEN: usize = 40;
+
+    pub fn pack(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(Self::DATA_LEN);
+        data.extend_from_slice(&SymmetryTokenSwap::SYMMETRY_PROGRAM_SWAP_INSTRUCTION_ID.to_le_bytes());
+        data.extend_from_slice(&self.from_token_id.to_le_bytes());
+        data.extend_from_slice(&self.to_token_id.to_le_bytes());
+        data.extend_from_slice(&self.amount.to_le_bytes());
+        data.extend_from_slice(&self.minimum_amount_out.to_le_bytes());
+        data
+    }
+
+    pub fn unpack(data: &[u8]) -> Result<SwapIxArgs> {
+        if data.len() < Self::DATA_LEN {
+            return Err(SymmetryError::SwapDataTooShort { expected: Self::DATA_LEN, got: data.len() }.into());
+        }
+        let read = |offset: usize| u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap_or_default());
+        if read(0) != SymmetryTokenSwap::SYMMETRY_PROGRAM_SWAP_INSTRUCTION_ID {
+            return Err(SymmetryError::NotSwapInstruction.into());
+        }
+        Ok(SwapIxArgs {
+            from_token_id: read(8),
+            to_token_id: read(16),
+            amount: read(24),
+            minimum_amount_out: read(32),
+        })
+    }
+
+    /// Mints of `from_token_id` and `to_token_id` in `token_list`.
+    pub fn mints(&self, token_list: &TokenList) -> Result<(Pubkey, Pubkey)> {
+        let mint = |token_id: u64| -> Result<Pubkey> {
+            match token_list.list.get(token_id as usize) {
+                Some(token) if token_id < token_list.num_tokens => Ok(token.token_mint),
+                _ => Err(SymmetryError::UnknownTokenId { token_id }.into()),
+            }
+        };
+        Ok((mint(self.from_token_id)?, mint(self.to_token_id)?))
+    }
+}
+
+/// Arguments of `instruction` if it is a Symmetry swap.
+pub fn decode_swap_instruction(instruction: &Instruction) -> Result<SwapIxArgs> {
+    if instruction.program_id != SymmetryTokenSwap::SYMMETRY_PROGRAM_ADDRESS {
+        return Err(SymmetryError::NotSymmetryProgram { program_id: instruction.program_id }.into());
+    }
+    SwapIxArgs::unpack(&instruction.data)
+}
+
+#[test]
+fn test_swap_ix_args_roundtrip() {
+    let args = SwapIxArgs {
+        from_token_id: 3,
+        to_token_id: 0,
+        amount: 10_000_000_000,
+        minimum_amount_out: 42,
+    };
+    let data = args.pack();
+    assert_eq!(data.len(), SwapIxArgs::DATA_LEN);
+    assert_eq!(SwapIxArgs::unpack(&data).unwrap(), args);
+    fn error<T: std::fmt::Debug>(result: Result<T>) -> SymmetryError {
+        result.unwrap_err().downcast().unwrap()
+    }
+    assert_eq!(error(SwapIxArgs::unpack(&data[..32])), SymmetryError::SwapDataTooShort { expected: 40, got: 32 });
+    assert_eq!(error(SwapIxArgs::unpack(&[0; SwapIxArgs::DATA_LEN])), SymmetryError::NotSwapInstruction);
+
+    let mut instruction = Instruction { program_id: SymmetryTokenSwap::SYMMETRY_PROGRAM_ADDRESS, accounts: Vec::new(), data };
+    assert_eq!(decode_swap_instruction(&instruction).unwrap(), args);
+    instruction.program_id = Pubkey::new_unique();
+    assert_eq!(
+        error(decode_swap_instruction(&instruction)),
+        SymmetryError::NotSymmetryProgram { program_id: instruction.program_id }
+    );
+    let mut token_list: TokenList = bytemuck::Zeroable::zeroed();
+    token_list.num_tokens = 3;
+    assert_eq!(error(args.mints(&token_list)), SymmetryError::UnknownTokenId { token_id: 3 });
+}
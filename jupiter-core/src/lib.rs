@@ -1,5 +1,55 @@
-mod amms;
-mod math;
+pub mod amms;
+pub mod math;
 
+#[cfg(feature = "account-stream")]
+pub mod account_stream;
+#[cfg(feature = "rpc")]
+pub mod async_amm;
 pub mod config;
 pub mod constants;
+#[cfg(feature = "rpc")]
+pub mod discovery;
+pub mod dto;
+pub mod error;
+pub mod export;
+pub mod fill_watcher;
+#[cfg(feature = "rpc")]
+pub mod fills;
+#[cfg(any(test, feature = "test-fixtures"))]
+pub mod fixture;
+pub mod instruction;
+pub mod lookup_table;
+#[cfg(feature = "rpc")]
+pub mod parity;
+pub mod pda;
+pub mod prelude;
+pub mod registry;
+pub mod rejections;
+pub mod risk;
+#[cfg(feature = "rpc")]
+pub mod sender;
+#[cfg(feature = "rpc")]
+pub mod slippage;
+pub mod snapshot;
+#[cfg(feature = "rpc")]
+pub mod swap_fill;
+#[cfg(feature = "rpc")]
+pub mod timeline;
+pub mod transaction;
+pub mod vectors;
+
+#[cfg(feature = "client")]
+pub mod client;
+
+#[cfg(feature = "websocket")]
+pub mod subscriber;
+
+
+#[cfg(feature = "token-metadata")]
+pub mod token_metadata;
+
+#[cfg(feature = "state-cache")]
+pub mod state_cache;
+
+#[cfg(feature = "wasm")]
+pub mod wasm;
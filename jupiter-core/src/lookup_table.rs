@@ -0,0 +1,141 @@
+//! Address lookup tables holding a fund's static swap accounts.
+//!
+//! A swap names 13 fixed accounts plus one oracle per fund token, too many for a legacy
+//! transaction once other route legs are added. Compiling the v0 transaction against a table
+//! with the accounts that don't depend on the user or the mints leaves one byte per account.
+
+use jupiter_amm_interface::Amm;
+use serde::Serialize;
+use solana_sdk::address_lookup_table_account::AddressLookupTableAccount;
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::pubkey;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::system_program;
+
+use crate::amms::symmetry_token_swap::SymmetryTokenSwap;
+use crate::transaction::SwapTransactionBuilder;
+
+pub const ADDRESS_LOOKUP_TABLE_PROGRAM_ADDRESS: Pubkey = pubkey!("AddressLookupTab1e1111111111111111111111111");
+
+/// Addresses one `ExtendLookupTable` instruction can add and still fit a transaction.
+pub const MAX_ADDRESSES_PER_EXTEND: usize = 30;
+
+/// Address lookup table program instructions, bincode-encoded like the program's own.
+#[derive(Serialize)]
+enum LookupTableInstruction {
+    Create { recent_slot: u64, bump_seed: u8 },
+    /// Unused, but keeps `Extend` at the program's variant index.
+    #[allow(dead_code)]
+    Freeze,
+    Extend { new_addresses: Vec<Pubkey> },
+}
+
+/// Address of the table `authority` creates at `recent_slot`, and its bump seed.
+pub fn derive_lookup_table_address(authority: &Pubkey, recent_slot: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[authority.as_ref(), &recent_slot.to_le_bytes()],
+        &ADDRESS_LOOKUP_TABLE_PROGRAM_ADDRESS,
+    )
+}
+
+/// Creates a lookup table owned by `authority`. `recent_slot` must be a recent finalized slot.
+pub fn create_lookup_table_instruction(authority: &Pubkey, payer: &Pubkey, recent_slot: u64) -> (Instruction, Pubkey) {
+    let (lookup_table, bump_seed) = derive_lookup_table_address(authority, recent_slot);
+    let instruction = Instruction::new_with_bincode(
+        ADDRESS_LOOKUP_TABLE_PROGRAM_ADDRESS,
+        &LookupTableInstruction::Create { recent_slot, bump_seed },
+        vec![
+            AccountMeta::new(lookup_table, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    );
+    (instruction, lookup_table)
+}
+
+/// Adds `addresses` to `lookup_table`, `MAX_ADDRESSES_PER_EXTEND` per instruction. Send each
+/// instruction in its own transaction.
+pub fn extend_lookup_table_instructions(lookup_table: &Pubkey, authority: &Pubkey, payer: &Pubkey, addresses: &[Pubkey]) -> Vec<Instruction> {
+    addresses
+        .chunks(MAX_ADDRESSES_PER_EXTEND)
+        .map(|chunk| Instruction::new_with_bincode(
+            ADDRESS_LOOKUP_TABLE_PROGRAM_ADDRESS,
+            &LookupTableInstruction::Extend { new_addresses: chunk.to_vec() },
+            vec![
+                AccountMeta::new(*lookup_table, false),
+                AccountMeta::new_readonly(*authority, true),
+                AccountMeta::new(*payer, true),
+                AccountMeta::new_readonly(system_program::id(), false),
+            ],
+        ))
+        .collect()
+}
+
+impl SymmetryTokenSwap {
+    /// Accounts every swap against this fund names, whatever the user and mints: the program,
+    /// fund, authority, token list, curve data, token program, and each fund token's vault
+    /// and oracle.
+    pub fn lookup_table_addresses(&self) -> Vec<Pubkey> {
+        let config = self.symmetry_config();
+        let mut addresses = vec![
+            config.program_id,
+            self.key(),
+            config.pda,
+            config.token_list,
+            config.curve_data,
+            SymmetryTokenSwap::SPL_TOKEN_PROGRAM_ADDRESS,
+        ];
+        let fund_state = self.fund_state();
+        for &token_id in fund_state.token_ids() {
+            let token = &self.token_list().list[token_id as usize];
+            addresses.extend([token.pda_token_account, token.oracle_account]);
+        }
+        let mut unique = Vec::with_capacity(addresses.len());
+        for address in addresses {
+            if !unique.contains(&address) {
+                unique.push(address);
+            }
+        }
+        unique
+    }
+
+    /// Instructions creating a lookup table for this fund at `recent_slot` and filling it with
+    /// `lookup_table_addresses()`, and the table's address. The first transaction can carry
+    /// the creation and the first extension; each further extension needs its own. A new
+    /// table is usable one slot after its last extension.
+    pub fn create_lookup_table_instructions(&self, authority: &Pubkey, payer: &Pubkey, recent_slot: u64) -> (Vec<Instruction>, Pubkey) {
+        let (create, lookup_table) = create_lookup_table_instruction(authority, payer, recent_slot);
+        let mut instructions = vec![create];
+        instructions.extend(extend_lookup_table_instructions(&lookup_table, authority, payer, &self.lookup_table_addresses()));
+        (instructions, lookup_table)
+    }
+}
+
+impl<'a> SwapTransactionBuilder<'a> {
+    /// Compiles against `lookup_table`, assumed to hold the fund's `lookup_table_addresses()`,
+    /// without fetching it. Use `with_lookup_table` for tables with other contents.
+    pub fn with_fund_lookup_table(self, lookup_table: Pubkey) -> Self {
+        let addresses = self.fund().lookup_table_addresses();
+        self.with_lookup_table(AddressLookupTableAccount { key: lookup_table, addresses })
+    }
+}
+
+#[test]
+fn test_lookup_table_instruction_encoding() {
+    let authority = Pubkey::new_unique();
+    let payer = Pubkey::new_unique();
+    let (create, lookup_table) = create_lookup_table_instruction(&authority, &payer, 1_234);
+    let (_, bump_seed) = derive_lookup_table_address(&authority, 1_234);
+    let mut data = vec![0, 0, 0, 0];
+    data.extend(1_234u64.to_le_bytes());
+    data.push(bump_seed);
+    assert_eq!(create.data, data);
+    assert_eq!(create.accounts[0].pubkey, lookup_table);
+
+    let addresses: Vec<Pubkey> = (0..MAX_ADDRESSES_PER_EXTEND + 1).map(|_| Pubkey::new_unique()).collect();
+    let extend = extend_lookup_table_instructions(&lookup_table, &authority, &payer, &addresses);
+    assert_eq!(extend.len(), 2);
+    assert_eq!(extend[1].data[..12], [2, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(extend[1].data[12..], addresses[MAX_ADDRESSES_PER_EXTEND].to_bytes());
+}
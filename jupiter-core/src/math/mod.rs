@@ -1,3 +1,4 @@
 pub mod fees;
 pub mod swap_curve_info;
+pub mod symmetry_math;
 pub mod token_swap;
@@ -0,0 +1,118 @@
+//! Inverses of the curve stepping math, for pricing a swap from its output side.
+//!
+//! Both curve functions are non-decreasing in their input, so each inverse bisects for the
+//! smallest input that reaches the requested output. Inputs use the same token settings,
+//! oracle price and curve as the forward functions on `SymmetryTokenSwap`.
+
+use std::convert::Infallible;
+
+use crate::amms::accounts::{OraclePrice, TokenPriceData, TokenSettings};
+use crate::amms::liquidity::largest_passing;
+use crate::amms::symmetry_token_swap::SymmetryTokenSwap;
+
+pub struct SymmetryMath;
+
+/// Smallest input whose `output` reaches `target`, searching below `estimate` and doubling
+/// it while the output falls short. An `Err` output (e.g. a weight check failing above some
+/// input) doesn't count as short, and is returned if the search ends on it. `Ok(None)` if no
+/// `u64` input reaches `target`.
+pub(crate) fn smallest_input_reaching<E>(
+    target: u64,
+    estimate: u64,
+    output: impl Fn(u64) -> Result<u64, E>,
+) -> Result<Option<u64>, E> {
+    if target == 0 {
+        return Ok(Some(0));
+    }
+    let short = |input: u64| input == 0 || output(input).is_ok_and(|reached| reached < target);
+    let mut upper = estimate.max(1);
+    while short(upper) {
+        if upper == u64::MAX {
+            return Ok(None);
+        }
+        upper = upper.saturating_mul(2);
+    }
+    let input = largest_passing(upper, short) + 1;
+    Ok((output(input)? >= target).then_some(input))
+}
+
+impl SymmetryMath {
+    /// Smallest amount of a token to sell to the fund for `value` USD after fees, the inverse
+    /// of `SymmetryTokenSwap::compute_value_of_sold_token`. `None` if no amount is worth it.
+    pub fn invert_sold_value(
+        value: u64,
+        token_settings: TokenSettings,
+        price: OraclePrice,
+        start_amount: u64,
+        target_amount: u64,
+        curve_data: TokenPriceData,
+    ) -> Option<u64> {
+        let estimate = SymmetryTokenSwap::usd_value_to_amount(value, token_settings.decimals, price.sell_price);
+        smallest_input_reaching(value, estimate, |amount| Ok::<_, Infallible>(SymmetryTokenSwap::compute_value_of_sold_token(
+            amount, token_settings, price, start_amount, target_amount, curve_data,
+        ))).ok().flatten()
+    }
+
+    /// Smallest USD value to spend for `amount` of a token bought from the fund, the inverse
+    /// of `SymmetryTokenSwap::compute_amount_of_bought_token`. `None` if no value buys it.
+    pub fn invert_bought_amount(
+        amount: u64,
+        token_settings: TokenSettings,
+        price: OraclePrice,
+        start_amount: u64,
+        target_amount: u64,
+        curve_data: TokenPriceData,
+    ) -> Option<u64> {
+        let estimate = SymmetryTokenSwap::amount_to_usd_value(amount, token_settings.decimals, price.buy_price);
+        smallest_input_reaching(amount, estimate, |value| Ok::<_, Infallible>(SymmetryTokenSwap::compute_amount_of_bought_token(
+            value, token_settings, price, start_amount, target_amount, curve_data,
+        ))).ok().flatten()
+    }
+}
+
+#[test]
+fn test_invert_curve_math() {
+    use crate::amms::accounts::{ONE_USD, USE_CURVE_DATA};
+
+    let token_settings = TokenSettings {
+        decimals: 9,
+        token_swap_fee_before_tw_bps: 5,
+        token_swap_fee_after_tw_bps: 30,
+        use_curve_data: USE_CURVE_DATA,
+        ..TokenSettings::empty()
+    };
+    let price = OraclePrice { sell_price: 99 * ONE_USD, avg_price: 100 * ONE_USD, buy_price: 101 * ONE_USD, oracle_live: 1 };
+    let sell_curve = TokenPriceData { amount: [2_000_000_000; 10], price: std::array::from_fn(|i| (98 - i as u64) * ONE_USD) };
+    let buy_curve = TokenPriceData { amount: [2_000_000_000; 10], price: std::array::from_fn(|i| (102 + i as u64) * ONE_USD) };
+    let (start_amount, target_amount) = (10_000_000_000, 11_000_000_000);
+
+    let sold_value = |amount| SymmetryTokenSwap::compute_value_of_sold_token(amount, token_settings, price, start_amount, target_amount, sell_curve);
+    let value = 500 * ONE_USD;
+    let amount = SymmetryMath::invert_sold_value(value, token_settings, price, start_amount, target_amount, sell_curve).unwrap();
+    assert!(sold_value(amount) >= value && sold_value(amount - 1) < value);
+
+    let bought_amount = |value| SymmetryTokenSwap::compute_amount_of_bought_token(value, token_settings, price, start_amount, target_amount, buy_curve);
+    let amount = 5_000_000_000;
+    let value = SymmetryMath::invert_bought_amount(amount, token_settings, price, start_amount, target_amount, buy_curve).unwrap();
+    assert!(bought_amount(value) >= amount && bought_amount(value - 1) < amount);
+
+    assert_eq!(SymmetryMath::invert_sold_value(0, token_settings, price, start_amount, target_amount, sell_curve), Some(0));
+}
+
+#[test]
+fn test_smallest_input_reaching() {
+    // 3 out per 10 in, weight checks failing above 1_000_000 in.
+    let out_amount_for = |in_amount: u64| -> anyhow::Result<u64> {
+        if in_amount > 1_000_000 {
+            return Err(anyhow::Error::msg("From token weight exceeds max allowed weight"));
+        }
+        Ok(in_amount * 3 / 10)
+    };
+    assert_eq!(smallest_input_reaching(30, 50, out_amount_for).unwrap(), Some(100));
+    assert_eq!(smallest_input_reaching(31, 1, out_amount_for).unwrap(), Some(104));
+    assert_eq!(smallest_input_reaching(300_000, 10, out_amount_for).unwrap(), Some(1_000_000));
+    assert!(smallest_input_reaching(300_001, 10, out_amount_for).is_err());
+    assert_eq!(smallest_input_reaching(0, 10, out_amount_for).unwrap(), Some(0));
+    let capped = |in_amount: u64| Ok::<_, Infallible>(in_amount.min(1_000) * 3 / 10);
+    assert_eq!(smallest_input_reaching(301, 10, capped), Ok(None));
+}
@@ -0,0 +1,147 @@
+//! Checks quotes against the deployed program by simulating the swap they describe.
+//!
+//! `check_quote_parity` quotes a swap, builds its transaction for a wallet holding the input
+//! tokens, runs `simulateTransaction` and compares the destination balance change with the
+//! quoted out amount. Run it after program upgrades to confirm the SDK still matches.
+
+use anyhow::{Error, Result};
+use jupiter_amm_interface::{Amm, QuoteParams};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcSimulateTransactionAccountsConfig, RpcSimulateTransactionConfig};
+use solana_sdk::account::Account;
+use solana_sdk::program_pack::Pack;
+use solana_sdk::pubkey::Pubkey;
+use spl_token::state::Account as TokenAccount;
+
+use crate::amms::symmetry_token_swap::SymmetryTokenSwap;
+use crate::transaction::{associated_token_address, SwapRequest, SwapTransactionBuilder};
+
+/// Result of simulating a swap transaction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SimulatedSwap {
+    /// Increase of the user's output ATA balance.
+    pub out_amount: u64,
+    pub units_consumed: Option<u64>,
+    pub logs: Vec<String>,
+}
+
+/// A quote and the simulated execution of the same swap.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParityReport {
+    pub quoted_out_amount: u64,
+    pub simulated: SimulatedSwap,
+}
+
+impl ParityReport {
+    /// Absolute difference of the quoted and simulated out amounts, in raw output units.
+    pub fn difference(&self) -> u64 {
+        self.quoted_out_amount.abs_diff(self.simulated.out_amount)
+    }
+
+    pub fn within(&self, tolerance: u64) -> bool {
+        self.difference() <= tolerance
+    }
+}
+
+fn token_amount(account: Option<&Account>) -> Result<u64> {
+    match account {
+        Some(account) => Ok(TokenAccount::unpack(&account.data)?.amount),
+        None => Ok(0),
+    }
+}
+
+/// Simulates `swap` with `swap.user` as payer, without signature verification and against
+/// the latest blockhash. The user must hold `swap.in_amount` of the input mint in their ATA.
+pub fn simulate_swap(client: &RpcClient, fund: &SymmetryTokenSwap, swap: &SwapRequest) -> Result<SimulatedSwap> {
+    let destination = associated_token_address(&swap.user, &swap.output_mint);
+    let before = client.get_account_with_commitment(&destination, client.commitment())?.value;
+    let transaction = SwapTransactionBuilder::new(fund, swap.user).unsigned(swap, client.get_latest_blockhash()?)?;
+
+    let result = client.simulate_transaction_with_config(&transaction, RpcSimulateTransactionConfig {
+        sig_verify: false,
+        replace_recent_blockhash: true,
+        commitment: Some(client.commitment()),
+        accounts: Some(RpcSimulateTransactionAccountsConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            addresses: vec![destination.to_string()],
+        }),
+        ..RpcSimulateTransactionConfig::default()
+    })?.value;
+    let logs = result.logs.unwrap_or_default();
+    if let Some(err) = result.err {
+        return Err(Error::msg(format!("Swap simulation failed: {err}\n{}", logs.join("\n"))));
+    }
+    let after = result.accounts
+        .and_then(|accounts| accounts.into_iter().next().flatten())
+        .and_then(|account| account.decode::<Account>())
+        .ok_or_else(|| Error::msg(format!("Simulation returned no account for {destination}")))?;
+    Ok(SimulatedSwap {
+        out_amount: token_amount(Some(&after))?.saturating_sub(token_amount(before.as_ref())?),
+        units_consumed: result.units_consumed,
+        logs,
+    })
+}
+
+/// Quotes `in_amount` of `input_mint` into `output_mint` on `fund` and simulates the swap
+/// from `user`'s ATAs. The swap has no minimum out, so a shortfall shows in the report
+/// instead of failing the simulation.
+pub fn check_quote_parity(client: &RpcClient, fund: &SymmetryTokenSwap, user: Pubkey, quote_params: &QuoteParams) -> Result<ParityReport> {
+    let quote = fund.quote(quote_params)?;
+    let simulated = simulate_swap(client, fund, &SwapRequest {
+        user,
+        input_mint: quote_params.input_mint,
+        output_mint: quote_params.output_mint,
+        in_amount: quote_params.in_amount,
+        minimum_amount_out: 0,
+    })?;
+    Ok(ParityReport {
+        quoted_out_amount: quote.out_amount,
+        simulated,
+    })
+}
+
+/// `check_quote_parity` failing when the simulated out amount differs from the quote by more
+/// than `tolerance` raw output units.
+pub fn assert_quote_parity(
+    client: &RpcClient,
+    fund: &SymmetryTokenSwap,
+    user: Pubkey,
+    quote_params: &QuoteParams,
+    tolerance: u64,
+) -> Result<ParityReport> {
+    let report = check_quote_parity(client, fund, user, quote_params)?;
+    if !report.within(tolerance) {
+        return Err(Error::msg(format!(
+            "Fund {} quoted {} but the program paid {} ({} over tolerance {tolerance})",
+            fund.key(),
+            report.quoted_out_amount,
+            report.simulated.out_amount,
+            report.difference() - tolerance,
+        )));
+    }
+    Ok(report)
+}
+
+#[test]
+fn test_token_amount() {
+    let mut data = vec![0u8; TokenAccount::LEN];
+    TokenAccount::pack(TokenAccount {
+        mint: Pubkey::new_unique(),
+        owner: Pubkey::new_unique(),
+        amount: 42,
+        state: spl_token::state::AccountState::Initialized,
+        ..TokenAccount::default()
+    }, &mut data).unwrap();
+    let account = Account { data, ..Account::default() };
+    assert_eq!(token_amount(Some(&account)).unwrap(), 42);
+    assert_eq!(token_amount(None).unwrap(), 0);
+
+    let report = ParityReport {
+        quoted_out_amount: 100,
+        simulated: SimulatedSwap { out_amount: 97, units_consumed: None, logs: Vec::new() },
+    };
+    assert_eq!(report.difference(), 3);
+    assert!(report.within(3));
+    assert!(!report.within(2));
+}
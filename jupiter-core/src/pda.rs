@@ -0,0 +1,78 @@
+//! Addresses the Symmetry program derives for swaps, for tooling that monitors or sets up
+//! fee accounts without building a swap instruction.
+
+use solana_sdk::pubkey::Pubkey;
+
+use crate::amms::symmetry_token_swap::SymmetryTokenSwap;
+
+/// Associated token account of `owner` for `mint`.
+pub fn associated_token_address(owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            &owner.to_bytes(),
+            &SymmetryTokenSwap::SPL_TOKEN_PROGRAM_ADDRESS.to_bytes(),
+            &mint.to_bytes(),
+        ],
+        &SymmetryTokenSwap::ASSOCIATED_TOKEN_PROGRAM_ADDRESS,
+    ).0
+}
+
+/// Program authority owning every fund's token accounts.
+pub fn fund_authority() -> Pubkey {
+    SymmetryTokenSwap::PDA_ADDRESS
+}
+
+/// Curve data account read by every swap.
+pub fn curve_data_address() -> Pubkey {
+    SymmetryTokenSwap::CURVE_DATA_ADDRESS
+}
+
+/// Owner of the protocol's swap fee accounts.
+pub fn swap_fee_owner() -> Pubkey {
+    SymmetryTokenSwap::SWAP_FEE_ADDRESS
+}
+
+/// Protocol swap fee account for `mint`. Swaps pay their protocol fee in the output token.
+pub fn swap_fee_ata(mint: &Pubkey) -> Pubkey {
+    associated_token_address(&SymmetryTokenSwap::SWAP_FEE_ADDRESS, mint)
+}
+
+/// Host fee account of `host` (the fund's `host_pubkey`) for `mint`.
+pub fn host_fee_ata(host: &Pubkey, mint: &Pubkey) -> Pubkey {
+    associated_token_address(host, mint)
+}
+
+/// Manager fee account of `manager` for `mint`. Swap fees are paid in the output token;
+/// deposit fees are minted to the manager's account for the fund token.
+pub fn manager_fee_ata(manager: &Pubkey, mint: &Pubkey) -> Pubkey {
+    associated_token_address(manager, mint)
+}
+
+/// The three fee accounts a swap into one output mint pays.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct SwapFeeAccounts {
+    pub swap_fee: Pubkey,
+    pub host_fee: Pubkey,
+    pub manager_fee: Pubkey,
+}
+
+impl SymmetryTokenSwap {
+    /// Fee accounts a swap of this fund into `output_mint` pays, in instruction order.
+    pub fn swap_fee_accounts(&self, output_mint: &Pubkey) -> SwapFeeAccounts {
+        SwapFeeAccounts {
+            swap_fee: self.symmetry_config().swap_fee_ata(output_mint),
+            host_fee: host_fee_ata(&self.fund_state().host_pubkey, output_mint),
+            manager_fee: manager_fee_ata(&self.fund_state().manager, output_mint),
+        }
+    }
+}
+
+#[test]
+fn test_fee_atas_match_associated_token_address() {
+    let mint = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    assert_eq!(swap_fee_ata(&mint), associated_token_address(&swap_fee_owner(), &mint));
+    assert_eq!(host_fee_ata(&owner, &mint), manager_fee_ata(&owner, &mint));
+    assert_ne!(swap_fee_ata(&mint), swap_fee_ata(&Pubkey::new_unique()));
+}
@@ -0,0 +1,42 @@
+//! Stable import path for the SDK's main types: `use jupiter_core::prelude::*;`, also
+//! available as `symmetry_sdk::prelude`. Items stay re-exported here when the modules
+//! defining them move.
+
+pub use jupiter_amm_interface::{Amm, KeyedAccount, Quote, QuoteParams};
+
+#[cfg(feature = "test-fixtures")]
+pub use crate::amms::account_builders::{
+    CurveDataBuilder, FixtureToken, FundStateBuilder, OracleAccountBuilder, TokenListBuilder,
+};
+pub use crate::amms::account_views::{CurveDataView, TokenListView};
+pub use crate::amms::accounts::{
+    AdditionalData, CurveData, FundState, FundStateHeader, InternalOracle, OracleIndexOutOfBounds,
+    OraclePrice, Rounding, TokenList, TokenSettings,
+};
+pub use crate::amms::amm_config::AmmConfig;
+pub use crate::amms::consistency::{
+    RebalancePolicy, SlotConsistency, SlotSpread, SlotSpreadPolicy,
+};
+pub use crate::amms::denomination::Denomination;
+pub use crate::amms::exact_out::SwapMode;
+pub use crate::amms::fee_accounts::FeeAccount;
+pub use crate::amms::fee_model::{FeeModel, FeeSplit, OnChainFeeModel};
+pub use crate::amms::fund_metadata::FundMetadata;
+pub use crate::amms::liquidity::{LiquidityPolicy, PairLiquiditySummary};
+pub use crate::amms::lp_quote::{DepositQuote, WithdrawOutput, WithdrawQuote};
+pub use crate::amms::oracle_fallback::{FallbackOracle, OracleSource};
+pub use crate::amms::oracle_type::OracleType;
+pub use crate::amms::partial_fill::WeightLimitPolicy;
+pub use crate::amms::quote_details::{CurveStep, QuoteDetails, QuoteLegDetails};
+pub use crate::amms::quote_fees::QuoteFees;
+pub use crate::amms::quoter::Quoter;
+pub use crate::amms::symmetry_config::SymmetryConfig;
+pub use crate::amms::symmetry_token_swap::{PostSwapWeights, SymmetryTokenSwap, WeightBand};
+pub use crate::amms::target_weight::TargetWeightQuote;
+pub use crate::error::{SwapSide, SymmetryError};
+pub use crate::instruction::SwapIxArgs;
+pub use crate::math::symmetry_math::SymmetryMath;
+pub use crate::pda::SwapFeeAccounts;
+pub use crate::registry::{FundFilter, FundRegistry, RefreshReport};
+pub use crate::risk::{RiskConfig, RiskGuard, RiskViolation, RiskViolations};
+pub use crate::snapshot::RegistrySnapshot;
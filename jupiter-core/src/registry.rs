@@ -0,0 +1,435 @@
+use jupiter_amm_interface::{AccountMap, Amm, Quote, QuoteParams};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{BTreeSet, HashMap};
+
+use crate::amms::accounts::FUND_LP_DISABLED;
+use crate::amms::compact_fund::CompactFund;
+use crate::amms::shared_accounts::SharedAccountCache;
+use crate::amms::symmetry_token_swap::SymmetryTokenSwap;
+use crate::rejections::{RejectionCounts, RejectionStats};
+
+/// Liquidity a single fund holds for a given mint.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct FundLiquidity {
+    pub fund: Pubkey,
+    pub composition_index: usize,
+    pub amount: u64,
+}
+
+/// Criteria for selecting funds from the registry. Unset fields match every fund.
+#[derive(Clone, Debug, Default)]
+pub struct FundFilter {
+    /// Minimum fund worth in USD (`ONE_USD` precision); funds with offline oracles never match.
+    pub min_tvl: Option<u64>,
+    pub manager: Option<Pubkey>,
+    pub host: Option<Pubkey>,
+    /// Every mint listed here must be an LP-enabled token of the fund.
+    pub mints: Vec<Pubkey>,
+    /// Skip funds with liquidity provision disabled or no tokens.
+    pub active_only: bool,
+}
+
+impl FundFilter {
+    pub fn matches(&self, fund: &SymmetryTokenSwap) -> bool {
+        let fund_state = fund.fund_state();
+        if self.manager.is_some_and(|manager| manager != fund_state.manager) {
+            return false;
+        }
+        if self.host.is_some_and(|host| host != fund_state.host_pubkey) {
+            return false;
+        }
+        if self.active_only
+            && (fund_state.lp_disabled == FUND_LP_DISABLED || fund_state.num_of_tokens == 0)
+        {
+            return false;
+        }
+        if !self.mints.iter().all(|mint| fund.is_lp_enabled(mint)) {
+            return false;
+        }
+        match self.min_tvl {
+            Some(min_tvl) => fund.fund_worth().is_ok_and(|worth| worth >= min_tvl),
+            None => true,
+        }
+    }
+}
+
+/// A fund's quotable liquidity for a given pair, measured on the output side.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct PairLiquidity {
+    pub fund: Pubkey,
+    /// Amount of the output mint held by the fund.
+    pub output_amount: u64,
+    /// USD value of `output_amount` at the oracle avg price.
+    pub output_value: u64,
+}
+
+/// Outcome of `FundRegistry::update_all`. Failed funds keep their last good state.
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct RefreshReport {
+    pub updated: Vec<Pubkey>,
+    pub failed: Vec<(Pubkey, anyhow::Error)>,
+    /// Oracle accounts parsed, and parses saved by sharing them across funds.
+    pub oracle_parses: u64,
+    pub oracle_hits: u64,
+}
+
+impl RefreshReport {
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+/// Collection of loaded Symmetry funds, keyed by fund state address.
+#[derive(Default)]
+#[non_exhaustive]
+pub struct FundRegistry {
+    funds: HashMap<Pubkey, SymmetryTokenSwap>,
+    /// Quote-only funds kept in compact form. `update_all` doesn't refresh them.
+    compact_funds: HashMap<Pubkey, CompactFund>,
+    rejection_stats: HashMap<Pubkey, RejectionStats>,
+    /// Consecutive failed refreshes of funds whose last `update_all` failed.
+    failed_refreshes: HashMap<Pubkey, u32>,
+    snapshot_slot: Option<u64>,
+}
+
+impl FundRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, fund: SymmetryTokenSwap) -> Option<SymmetryTokenSwap> {
+        self.rejection_stats.entry(fund.key()).or_default();
+        self.funds.insert(fund.key(), fund)
+    }
+
+    /// Stores `fund` in compact form for quoting, replacing any compact fund with the same
+    /// key. Re-insert it from a refreshed `SymmetryTokenSwap` to update it.
+    pub fn insert_compact(&mut self, fund: CompactFund) -> Option<CompactFund> {
+        self.rejection_stats.entry(fund.key).or_default();
+        self.compact_funds.insert(fund.key, fund)
+    }
+
+    pub fn get_compact(&self, key: &Pubkey) -> Option<&CompactFund> {
+        self.compact_funds.get(key)
+    }
+
+    pub fn compact_funds(&self) -> impl Iterator<Item = (&Pubkey, &CompactFund)> {
+        self.compact_funds.iter()
+    }
+
+    /// Removes `key` from both the full and the compact funds.
+    pub fn remove(&mut self, key: &Pubkey) -> Option<SymmetryTokenSwap> {
+        self.rejection_stats.remove(key);
+        self.failed_refreshes.remove(key);
+        self.compact_funds.remove(key);
+        self.funds.remove(key)
+    }
+
+    /// Quotes `fund`, full or compact, and counts the result in its rejection stats. Only
+    /// quotes made through the registry are counted, not `Amm::quote` on a fund from `get`.
+    pub fn quote(&self, fund: &Pubkey, quote_params: &QuoteParams) -> Option<anyhow::Result<Quote>> {
+        let result = match self.funds.get(fund) {
+            Some(full) => full.quote(quote_params),
+            None => self.compact_funds.get(fund)?.quote(quote_params),
+        };
+        if let Some(stats) = self.rejection_stats.get(fund) {
+            stats.record(&result);
+        }
+        Some(result)
+    }
+
+    pub fn rejection_counts(&self, fund: &Pubkey) -> Option<RejectionCounts> {
+        self.rejection_stats.get(fund).map(RejectionStats::counts)
+    }
+
+    /// Rejection counts of every fund, most rejections first, so funds that are
+    /// effectively unroutable surface at the top.
+    pub fn rejection_report(&self) -> Vec<(Pubkey, RejectionCounts)> {
+        let mut report: Vec<(Pubkey, RejectionCounts)> = self.rejection_stats.iter()
+            .map(|(key, stats)| (*key, stats.counts()))
+            .collect();
+        report.sort_by(|a, b| b.1.total_rejections().cmp(&a.1.total_rejections()).then(a.0.cmp(&b.0)));
+        report
+    }
+
+    pub fn reset_rejection_stats(&self) {
+        for stats in self.rejection_stats.values() {
+            stats.reset();
+        }
+    }
+
+    pub fn get(&self, key: &Pubkey) -> Option<&SymmetryTokenSwap> {
+        self.funds.get(key)
+    }
+
+    pub fn get_mut(&mut self, key: &Pubkey) -> Option<&mut SymmetryTokenSwap> {
+        self.funds.get_mut(key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.funds.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.funds.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Pubkey, &SymmetryTokenSwap)> {
+        self.funds.iter()
+    }
+
+    /// Slot of the snapshot the registry was warm-started from, until the first `update_all`.
+    pub fn snapshot_slot(&self) -> Option<u64> {
+        self.snapshot_slot
+    }
+
+    pub(crate) fn set_snapshot_slot(&mut self, slot: Option<u64>) {
+        self.snapshot_slot = slot;
+    }
+
+    /// Accounts every fund depends on, deduplicated, for a single batched fetch.
+    pub fn accounts_to_update(&self) -> Vec<Pubkey> {
+        self.funds.values()
+            .flat_map(|fund| fund.get_accounts_to_update())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+
+    /// Updates every fund from `account_map`, which should hold `accounts_to_update()`.
+    /// Fetch the accounts without holding a lock on the registry, then apply them here.
+    /// A fund that fails to update (missing or resized account, bad oracle) keeps its last
+    /// good state and is marked stale; the others are still refreshed. Curve data and each
+    /// oracle are parsed once and shared by every fund referencing them.
+    pub fn update_all(&mut self, account_map: &AccountMap) -> RefreshReport {
+        let mut report = RefreshReport::default();
+        let mut cache = SharedAccountCache::default();
+        for (key, fund) in self.funds.iter_mut() {
+            let mut updated = fund.clone();
+            match updated.update_with_cache(account_map, &mut cache) {
+                Ok(()) => {
+                    *fund = updated;
+                    self.failed_refreshes.remove(key);
+                    report.updated.push(*key);
+                }
+                Err(e) => {
+                    *self.failed_refreshes.entry(*key).or_default() += 1;
+                    report.failed.push((*key, e));
+                }
+            }
+        }
+        self.snapshot_slot = None;
+        report.oracle_parses = cache.oracle_parses();
+        report.oracle_hits = cache.oracle_hits();
+        report
+    }
+
+    /// Whether the fund's last refresh failed, so it is quoting from older state.
+    pub fn is_stale(&self, key: &Pubkey) -> bool {
+        self.failed_refreshes.contains_key(key)
+    }
+
+    /// Stale funds with their number of consecutive failed refreshes.
+    pub fn stale_funds(&self) -> impl Iterator<Item = (&Pubkey, u32)> {
+        self.failed_refreshes.iter().map(|(key, failures)| (key, *failures))
+    }
+
+    pub fn filter<'a>(&'a self, filter: &'a FundFilter) -> impl Iterator<Item = &'a SymmetryTokenSwap> + 'a {
+        self.funds.values().filter(move |fund| filter.matches(fund))
+    }
+
+    /// Funds matching `filter` that can quote `input_mint` -> `output_mint`,
+    /// ranked by the USD value of the output token they hold (largest first).
+    pub fn rank_by_pair_liquidity(
+        &self,
+        input_mint: &Pubkey,
+        output_mint: &Pubkey,
+        filter: &FundFilter,
+    ) -> Vec<PairLiquidity> {
+        let mut result: Vec<PairLiquidity> = self
+            .filter(filter)
+            .filter(|fund| fund.is_lp_enabled(input_mint) && fund.is_lp_enabled(output_mint))
+            .filter_map(|fund| {
+                let index = fund.composition_index(output_mint)?;
+                Some(PairLiquidity {
+                    fund: fund.key(),
+                    output_amount: fund.fund_state().current_comp_amount[index],
+                    output_value: fund.token_worth(output_mint)?,
+                })
+            })
+            .collect();
+        result.sort_by(|a, b| b.output_value.cmp(&a.output_value).then(a.fund.cmp(&b.fund)));
+        result
+    }
+
+    /// Funds whose active composition includes `mint` with LP enabled,
+    /// sorted by the amount of `mint` they hold (largest first).
+    pub fn funds_with_mint(&self, mint: &Pubkey) -> Vec<FundLiquidity> {
+        let mut result: Vec<FundLiquidity> = self
+            .funds
+            .values()
+            .filter(|fund| fund.is_lp_enabled(mint))
+            .filter_map(|fund| {
+                let composition_index = fund.composition_index(mint)?;
+                Some(FundLiquidity {
+                    fund: fund.key(),
+                    composition_index,
+                    amount: fund.fund_state().current_comp_amount[composition_index],
+                })
+            })
+            .collect();
+        result.sort_by(|a, b| b.amount.cmp(&a.amount).then(a.fund.cmp(&b.fund)));
+        result
+    }
+}
+
+#[test]
+fn test_registry_quotes_compact_funds() {
+    use crate::amms::account_builders::{quotable_fund, FixtureToken, FundStateBuilder};
+    use crate::amms::accounts::WEIGHT_MULTIPLIER;
+
+    let tokens = [FixtureToken::new(6, 100_000_000), FixtureToken::new(9, 10_000_000_000)];
+    let fund_state = FundStateBuilder::new()
+        .with_token(0, 10_000_000_000, WEIGHT_MULTIPLIER / 2)
+        .with_token(1, 100_000_000_000, WEIGHT_MULTIPLIER / 2)
+        .with_weight_band(1_000, 5_000);
+    let full = quotable_fund(Pubkey::new_unique(), &fund_state, &tokens);
+    let quote_params = QuoteParams { in_amount: 100_000_000, input_mint: tokens[0].mint, output_mint: tokens[1].mint };
+    let expected = full.quote(&quote_params).unwrap().out_amount;
+
+    let mut registry = FundRegistry::new();
+    registry.insert_compact(CompactFund::from(&full));
+    assert!(registry.get(&full.key()).is_none());
+    assert_eq!(registry.quote(&full.key(), &quote_params).unwrap().unwrap().out_amount, expected);
+    assert_eq!(registry.rejection_counts(&full.key()).unwrap().quotes, 1);
+
+    registry.remove(&full.key());
+    assert!(registry.get_compact(&full.key()).is_none());
+    assert!(registry.quote(&full.key(), &quote_params).is_none());
+}
+
+#[test]
+fn test_funds_with_mint() {
+    use crate::amms::account_builders::{quotable_fund, FixtureToken, FundStateBuilder};
+    use crate::amms::accounts::WEIGHT_MULTIPLIER;
+
+    let tokens = [FixtureToken::new(6, 100_000_000), FixtureToken::new(9, 10_000_000_000), FixtureToken::new(6, 100_000_000)];
+    let (usdc, sol, other) = (tokens[0].mint, tokens[1].mint, tokens[2].mint);
+    // A fund holding 100 SOL, and one holding 250 SOL behind USDC in its composition.
+    let small = quotable_fund(Pubkey::new_unique(), &FundStateBuilder::new()
+        .with_token(1, 100_000_000_000, WEIGHT_MULTIPLIER / 2)
+        .with_token(2, 10_000_000_000, WEIGHT_MULTIPLIER / 2), &tokens);
+    let large = quotable_fund(Pubkey::new_unique(), &FundStateBuilder::new()
+        .with_token(0, 10_000_000_000, WEIGHT_MULTIPLIER / 2)
+        .with_token(1, 250_000_000_000, WEIGHT_MULTIPLIER / 2), &tokens);
+    let disabled = quotable_fund(Pubkey::new_unique(), &FundStateBuilder::new()
+        .with_token(1, 500_000_000_000, WEIGHT_MULTIPLIER)
+        .with_lp_disabled(FUND_LP_DISABLED), &tokens);
+
+    let mut registry = FundRegistry::new();
+    for fund in [small.clone(), large.clone(), disabled] {
+        registry.insert(fund);
+    }
+    assert_eq!(registry.funds_with_mint(&sol), vec![
+        FundLiquidity { fund: large.key(), composition_index: 1, amount: 250_000_000_000 },
+        FundLiquidity { fund: small.key(), composition_index: 0, amount: 100_000_000_000 },
+    ]);
+    assert_eq!(registry.funds_with_mint(&usdc), vec![
+        FundLiquidity { fund: large.key(), composition_index: 0, amount: 10_000_000_000 },
+    ]);
+    assert_eq!(registry.funds_with_mint(&other).len(), 1);
+    assert!(registry.funds_with_mint(&Pubkey::new_unique()).is_empty());
+}
+
+#[test]
+fn test_filter_and_rank_by_pair_liquidity() {
+    use crate::amms::account_builders::{quotable_fund, FixtureToken, FundStateBuilder};
+    use crate::amms::accounts::{ONE_USD, WEIGHT_MULTIPLIER};
+
+    let tokens = [FixtureToken::new(6, 100_000_000), FixtureToken::new(9, 10_000_000_000)];
+    let (usdc, sol) = (tokens[0].mint, tokens[1].mint);
+    let manager = Pubkey::new_unique();
+    let host = Pubkey::new_unique();
+    let fund = |usdc_amount: u64, sol_amount: u64| FundStateBuilder::new()
+        .with_manager(manager)
+        .with_token(0, usdc_amount, WEIGHT_MULTIPLIER / 2)
+        .with_token(1, sol_amount, WEIGHT_MULTIPLIER / 2);
+    // $20,000 and $60,000 funds run by `manager`, the larger hosted by `host`, a $2,000
+    // fund run by someone else, and a disabled fund.
+    let small = quotable_fund(Pubkey::new_unique(), &fund(10_000_000_000, 100_000_000_000), &tokens);
+    let large = quotable_fund(Pubkey::new_unique(), &fund(10_000_000_000, 500_000_000_000).with_host(host), &tokens);
+    let other = quotable_fund(Pubkey::new_unique(), &fund(1_000_000_000, 10_000_000_000).with_manager(Pubkey::new_unique()), &tokens);
+    let disabled = quotable_fund(Pubkey::new_unique(), &fund(10_000_000_000, 900_000_000_000).with_lp_disabled(FUND_LP_DISABLED), &tokens);
+
+    let mut registry = FundRegistry::new();
+    for fund in [small.clone(), large.clone(), other.clone(), disabled.clone()] {
+        registry.insert(fund);
+    }
+    let matching = |filter: &FundFilter| {
+        let mut keys: Vec<Pubkey> = registry.filter(filter).map(|fund| fund.key()).collect();
+        keys.sort();
+        keys
+    };
+    let sorted = |mut keys: Vec<Pubkey>| {
+        keys.sort();
+        keys
+    };
+    assert_eq!(matching(&FundFilter::default()).len(), 4);
+    assert_eq!(matching(&FundFilter { manager: Some(manager), ..FundFilter::default() }), sorted(vec![small.key(), large.key(), disabled.key()]));
+    assert_eq!(matching(&FundFilter { host: Some(host), ..FundFilter::default() }), vec![large.key()]);
+    assert_eq!(matching(&FundFilter { active_only: true, ..FundFilter::default() }), sorted(vec![small.key(), large.key(), other.key()]));
+    assert_eq!(matching(&FundFilter { min_tvl: Some(20_000 * ONE_USD), active_only: true, ..FundFilter::default() }), sorted(vec![small.key(), large.key()]));
+    assert_eq!(matching(&FundFilter { mints: vec![usdc, sol], ..FundFilter::default() }), sorted(vec![small.key(), large.key(), other.key()]));
+    assert!(matching(&FundFilter { mints: vec![Pubkey::new_unique()], ..FundFilter::default() }).is_empty());
+
+    // Ranked by the USD value of SOL held, skipping the disabled fund.
+    let ranked = registry.rank_by_pair_liquidity(&usdc, &sol, &FundFilter::default());
+    assert_eq!(ranked, vec![
+        PairLiquidity { fund: large.key(), output_amount: 500_000_000_000, output_value: 50_000 * ONE_USD },
+        PairLiquidity { fund: small.key(), output_amount: 100_000_000_000, output_value: 10_000 * ONE_USD },
+        PairLiquidity { fund: other.key(), output_amount: 10_000_000_000, output_value: 1_000 * ONE_USD },
+    ]);
+    // Both of the manager's funds hold $10,000 of USDC; ties are ordered by fund key.
+    let ranked = registry.rank_by_pair_liquidity(&sol, &usdc, &FundFilter { manager: Some(manager), ..FundFilter::default() });
+    assert_eq!(ranked.iter().map(|liquidity| liquidity.fund).collect::<Vec<_>>(), sorted(vec![small.key(), large.key()]));
+}
+
+#[test]
+fn test_registry_quote_records_rejections() {
+    use crate::amms::account_builders::{quotable_fund, FixtureToken, FundStateBuilder};
+    use crate::amms::accounts::WEIGHT_MULTIPLIER;
+    use crate::rejections::RejectionReason;
+
+    let tokens = [FixtureToken::new(6, 100_000_000), FixtureToken::new(9, 10_000_000_000)];
+    let fund_state = FundStateBuilder::new()
+        .with_token(0, 10_000_000_000, WEIGHT_MULTIPLIER / 2)
+        .with_token(1, 100_000_000_000, WEIGHT_MULTIPLIER / 2)
+        .with_weight_band(1_000, 5_000);
+    let fund = quotable_fund(Pubkey::new_unique(), &fund_state, &tokens);
+    let key = fund.key();
+    let mut registry = FundRegistry::new();
+    registry.insert(fund);
+    assert_eq!(registry.rejection_counts(&key), Some(RejectionCounts::default()));
+
+    let params = |in_amount: u64, output_mint: Pubkey| QuoteParams { in_amount, input_mint: tokens[0].mint, output_mint };
+    assert!(registry.quote(&key, &params(100_000_000, tokens[1].mint)).unwrap().is_ok());
+    // $5,000 of USDC pushes USDC far past its 5% band.
+    assert!(registry.quote(&key, &params(5_000_000_000, tokens[1].mint)).unwrap().is_err());
+    assert!(registry.quote(&key, &params(100_000_000, Pubkey::new_unique())).unwrap().is_err());
+    assert!(registry.quote(&Pubkey::new_unique(), &params(100_000_000, tokens[1].mint)).is_none());
+    // Quoting the fund directly bypasses the stats.
+    assert!(registry.get(&key).unwrap().quote(&params(5_000_000_000, tokens[1].mint)).is_err());
+
+    let counts = registry.rejection_counts(&key).unwrap();
+    assert_eq!(counts.quotes, 3);
+    assert_eq!(counts.total_rejections(), 2);
+    assert_eq!(counts.get(RejectionReason::WeightLimit), 1);
+    assert_eq!(counts.get(RejectionReason::TokenNotInFund), 1);
+    assert_eq!(registry.rejection_report(), vec![(key, counts)]);
+
+    registry.reset_rejection_stats();
+    assert_eq!(registry.rejection_counts(&key), Some(RejectionCounts::default()));
+}
@@ -0,0 +1,146 @@
+use jupiter_amm_interface::Quote;
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::error::SymmetryError;
+
+/// Why a fund couldn't serve a quote.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RejectionReason {
+    /// The swap would push the sold token above, or the bought token below, its weight band.
+    WeightLimit,
+    /// A fund token's oracle is offline or stale, so the fund can't be valued.
+    OracleOffline,
+    /// One of the mints isn't in the token list or the fund's composition.
+    TokenNotInFund,
+    /// The manager disabled liquidity provision on the fund, or the token list disabled it
+    /// for one of the mints.
+    LpDisabled,
+    /// Rejected under `RebalancePolicy::Reject` while a rebalance is in progress.
+    RebalanceInProgress,
+    /// The quote was capped by, or under `LiquidityPolicy::Reject` rejected for, the fund's
+    /// holding of the output token.
+    LiquidityCap,
+    Other,
+}
+
+impl RejectionReason {
+    pub const ALL: [RejectionReason; 7] = [
+        RejectionReason::WeightLimit,
+        RejectionReason::OracleOffline,
+        RejectionReason::TokenNotInFund,
+        RejectionReason::LpDisabled,
+        RejectionReason::RebalanceInProgress,
+        RejectionReason::LiquidityCap,
+        RejectionReason::Other,
+    ];
+
+    /// Classifies an error returned by `quote()`.
+    pub fn classify(error: &anyhow::Error) -> RejectionReason {
+        match error.downcast_ref::<SymmetryError>() {
+            Some(SymmetryError::WeightLimitExceeded { .. }) => RejectionReason::WeightLimit,
+            Some(SymmetryError::OracleOffline { .. }) => RejectionReason::OracleOffline,
+            Some(SymmetryError::TokenNotSupported { .. } | SymmetryError::TokenNotInFund { .. }) => {
+                RejectionReason::TokenNotInFund
+            }
+            Some(SymmetryError::FundLpDisabled | SymmetryError::TokenLpDisabled { .. }) => RejectionReason::LpDisabled,
+            Some(SymmetryError::RebalanceInProgress { .. }) => RejectionReason::RebalanceInProgress,
+            Some(SymmetryError::NotEnoughLiquidity { .. }) => RejectionReason::LiquidityCap,
+            _ => RejectionReason::Other,
+        }
+    }
+
+    /// Reason a quote result counts as a rejection, if any.
+    pub fn of_result(result: &anyhow::Result<Quote>) -> Option<RejectionReason> {
+        match result {
+            Ok(quote) if quote.not_enough_liquidity => Some(RejectionReason::LiquidityCap),
+            Ok(_) => None,
+            Err(error) => Some(RejectionReason::classify(error)),
+        }
+    }
+
+    fn index(self) -> usize {
+        RejectionReason::ALL.iter().position(|reason| *reason == self).unwrap_or_default()
+    }
+}
+
+/// Point-in-time copy of a fund's `RejectionStats`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RejectionCounts {
+    pub quotes: u64,
+    pub rejections: Vec<(RejectionReason, u64)>,
+}
+
+impl RejectionCounts {
+    pub fn total_rejections(&self) -> u64 {
+        self.rejections.iter().map(|(_, count)| count).sum()
+    }
+
+    pub fn get(&self, reason: RejectionReason) -> u64 {
+        self.rejections.iter()
+            .find(|(r, _)| *r == reason)
+            .map(|(_, count)| *count)
+            .unwrap_or_default()
+    }
+}
+
+/// Quote and rejection counters of one fund. Lock-free, so quoting stays `&self`.
+#[derive(Debug, Default)]
+pub struct RejectionStats {
+    quotes: AtomicU64,
+    rejections: [AtomicU64; RejectionReason::ALL.len()],
+}
+
+impl RejectionStats {
+    pub fn record(&self, result: &anyhow::Result<Quote>) {
+        self.quotes.fetch_add(1, Ordering::Relaxed);
+        if let Some(reason) = RejectionReason::of_result(result) {
+            self.rejections[reason.index()].fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn counts(&self) -> RejectionCounts {
+        RejectionCounts {
+            quotes: self.quotes.load(Ordering::Relaxed),
+            rejections: RejectionReason::ALL.iter()
+                .map(|reason| (*reason, self.rejections[reason.index()].load(Ordering::Relaxed)))
+                .filter(|(_, count)| *count > 0)
+                .collect(),
+        }
+    }
+
+    pub fn reset(&self) {
+        self.quotes.store(0, Ordering::Relaxed);
+        for counter in &self.rejections {
+            counter.store(0, Ordering::Relaxed);
+        }
+    }
+}
+
+#[test]
+fn test_rejection_stats_by_reason() {
+    use crate::error::SwapSide;
+    use solana_sdk::pubkey::Pubkey;
+
+    let stats = RejectionStats::default();
+    stats.record(&Ok(Quote::default()));
+    stats.record(&Err(SymmetryError::WeightLimitExceeded { side: SwapSide::From }.into()));
+    stats.record(&Err(SymmetryError::OracleOffline { mint: Pubkey::new_unique() }.into()));
+    stats.record(&Err(SymmetryError::TokenNotInFund { mint: Pubkey::new_unique() }.into()));
+    stats.record(&Ok(Quote { not_enough_liquidity: true, ..Quote::default() }));
+    stats.record(&Err(SymmetryError::TokenLpDisabled { fund: Pubkey::new_unique(), mint: Pubkey::new_unique() }.into()));
+    // Untyped errors aren't classified by their message.
+    stats.record(&Err(anyhow::Error::msg("From token weight exceeds max allowed weight")));
+
+    let counts = stats.counts();
+    assert_eq!(counts.quotes, 7);
+    assert_eq!(counts.total_rejections(), 6);
+    assert_eq!(counts.get(RejectionReason::WeightLimit), 1);
+    assert_eq!(counts.get(RejectionReason::OracleOffline), 1);
+    assert_eq!(counts.get(RejectionReason::TokenNotInFund), 1);
+    assert_eq!(counts.get(RejectionReason::LiquidityCap), 1);
+    assert_eq!(counts.get(RejectionReason::LpDisabled), 1);
+    assert_eq!(counts.get(RejectionReason::Other), 1);
+}
@@ -0,0 +1,227 @@
+use anyhow::Result;
+use jupiter_amm_interface::{Amm, Quote, QuoteParams};
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signer::signers::Signers;
+use solana_sdk::transaction::VersionedTransaction;
+
+use crate::amms::accounts::{mul_div, OraclePrice, BPS_DIVIDER};
+use crate::amms::quoter::Quoter;
+use crate::amms::symmetry_token_swap::SymmetryTokenSwap;
+use crate::transaction::{SwapRequest, SwapTransactionBuilder};
+
+/// Limits a swap must respect before it is quoted as acceptable or built. Unset limits
+/// aren't checked. USD values use `ONE_USD` precision.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RiskConfig {
+    /// Largest in amount, valued at the input token's average oracle price.
+    pub max_notional_usd: Option<u64>,
+    /// Largest shortfall of the quoted output, fees included, versus the output at the
+    /// average oracle prices.
+    pub max_price_impact_bps: Option<u64>,
+    /// Widest oracle confidence interval of either token, as the buy price's distance from
+    /// the average price.
+    pub max_oracle_confidence_bps: Option<u64>,
+    /// Smallest fund worth at the average oracle prices.
+    pub min_fund_tvl_usd: Option<u64>,
+}
+
+impl RiskConfig {
+    pub fn with_max_notional_usd(mut self, max_notional_usd: u64) -> Self {
+        self.max_notional_usd = Some(max_notional_usd);
+        self
+    }
+
+    pub fn with_max_price_impact_bps(mut self, max_price_impact_bps: u64) -> Self {
+        self.max_price_impact_bps = Some(max_price_impact_bps);
+        self
+    }
+
+    pub fn with_max_oracle_confidence_bps(mut self, max_oracle_confidence_bps: u64) -> Self {
+        self.max_oracle_confidence_bps = Some(max_oracle_confidence_bps);
+        self
+    }
+
+    pub fn with_min_fund_tvl_usd(mut self, min_fund_tvl_usd: u64) -> Self {
+        self.min_fund_tvl_usd = Some(min_fund_tvl_usd);
+        self
+    }
+
+    /// Limits `quote` of `fund` for `quote_params` breaks. Fails only if the pair can't be
+    /// valued (unknown mints, offline oracle).
+    pub fn check(&self, fund: &SymmetryTokenSwap, quote_params: &QuoteParams, quote: &Quote) -> Result<Vec<RiskViolation>> {
+        let quoter = Quoter::for_pair(fund, &quote_params.input_mint, &quote_params.output_mint)?;
+        let from = quoter.setup().from_token_settings;
+        let to = quoter.setup().to_token_settings;
+        let mut violations = Vec::new();
+
+        let notional_usd = SymmetryTokenSwap::amount_to_usd_value(quote_params.in_amount, from.decimals, from.oracle_price.avg_price);
+        if let Some(max_usd) = self.max_notional_usd {
+            if notional_usd > max_usd {
+                violations.push(RiskViolation::Notional { notional_usd, max_usd });
+            }
+        }
+        if let Some(max_bps) = self.max_price_impact_bps {
+            let mid_out_amount = SymmetryTokenSwap::usd_value_to_amount(notional_usd, to.decimals, to.oracle_price.avg_price);
+            let impact_bps = price_impact_bps(mid_out_amount, quote.out_amount);
+            if impact_bps > max_bps {
+                violations.push(RiskViolation::PriceImpact { impact_bps, max_bps });
+            }
+        }
+        if let Some(max_bps) = self.max_oracle_confidence_bps {
+            for token in [from, to] {
+                let confidence_bps = confidence_bps(&token.oracle_price);
+                if confidence_bps > max_bps {
+                    violations.push(RiskViolation::OracleConfidence { mint: token.token_mint, confidence_bps, max_bps });
+                }
+            }
+        }
+        if let Some(min_usd) = self.min_fund_tvl_usd {
+            let tvl_usd = quoter.setup().fund_worth;
+            if tvl_usd < min_usd {
+                violations.push(RiskViolation::FundTvl { tvl_usd, min_usd });
+            }
+        }
+        Ok(violations)
+    }
+}
+
+/// Shortfall of `out_amount` versus `mid_out_amount`, in bps. Zero when it isn't short.
+fn price_impact_bps(mid_out_amount: u64, out_amount: u64) -> u64 {
+    if mid_out_amount == 0 {
+        return 0;
+    }
+    mul_div(mid_out_amount.saturating_sub(out_amount), BPS_DIVIDER, mid_out_amount)
+}
+
+fn confidence_bps(price: &OraclePrice) -> u64 {
+    if price.avg_price == 0 {
+        return 0;
+    }
+    mul_div(price.buy_price.saturating_sub(price.avg_price), BPS_DIVIDER, price.avg_price)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RiskViolation {
+    Notional { notional_usd: u64, max_usd: u64 },
+    PriceImpact { impact_bps: u64, max_bps: u64 },
+    OracleConfidence { mint: Pubkey, confidence_bps: u64, max_bps: u64 },
+    FundTvl { tvl_usd: u64, min_usd: u64 },
+}
+
+impl std::fmt::Display for RiskViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RiskViolation::Notional { notional_usd, max_usd } => {
+                write!(f, "notional {notional_usd} exceeds {max_usd}")
+            }
+            RiskViolation::PriceImpact { impact_bps, max_bps } => {
+                write!(f, "price impact {impact_bps} bps exceeds {max_bps} bps")
+            }
+            RiskViolation::OracleConfidence { mint, confidence_bps, max_bps } => {
+                write!(f, "oracle confidence of {mint} is {confidence_bps} bps, above {max_bps} bps")
+            }
+            RiskViolation::FundTvl { tvl_usd, min_usd } => {
+                write!(f, "fund worth {tvl_usd} is below {min_usd}")
+            }
+        }
+    }
+}
+
+/// Returned (inside `anyhow::Error`) by `RiskGuard` when a swap breaks any limit.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct RiskViolations {
+    pub fund: Pubkey,
+    pub violations: Vec<RiskViolation>,
+}
+
+impl std::fmt::Display for RiskViolations {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Swap against fund {} breaks risk limits: ", self.fund)?;
+        for (i, violation) in self.violations.iter().enumerate() {
+            if i > 0 {
+                write!(f, "; ")?;
+            }
+            write!(f, "{violation}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for RiskViolations {}
+
+/// `SwapTransactionBuilder` that quotes every swap and refuses those breaking `RiskConfig`.
+pub struct RiskGuard<'a> {
+    builder: SwapTransactionBuilder<'a>,
+    config: RiskConfig,
+}
+
+impl<'a> RiskGuard<'a> {
+    pub fn new(builder: SwapTransactionBuilder<'a>, config: RiskConfig) -> Self {
+        Self { builder, config }
+    }
+
+    pub fn config(&self) -> &RiskConfig {
+        &self.config
+    }
+
+    pub fn builder(&self) -> &SwapTransactionBuilder<'a> {
+        &self.builder
+    }
+
+    /// The fund's quote, or `RiskViolations` if it breaks a limit.
+    pub fn quote(&self, quote_params: &QuoteParams) -> Result<Quote> {
+        let fund = self.builder.fund();
+        let quote = fund.quote(quote_params)?;
+        let violations = self.config.check(fund, quote_params, &quote)?;
+        if !violations.is_empty() {
+            return Err(RiskViolations { fund: fund.key(), violations }.into());
+        }
+        Ok(quote)
+    }
+
+    fn check(&self, swap: &SwapRequest) -> Result<()> {
+        self.quote(&QuoteParams {
+            in_amount: swap.in_amount,
+            input_mint: swap.input_mint,
+            output_mint: swap.output_mint,
+        })
+        .map(|_| ())
+    }
+
+    pub fn instructions(&self, swap: &SwapRequest) -> Result<Vec<Instruction>> {
+        self.check(swap)?;
+        self.builder.instructions(swap)
+    }
+
+    pub fn build<S: Signers>(&self, swap: &SwapRequest, recent_blockhash: Hash, signers: &S) -> Result<VersionedTransaction> {
+        self.check(swap)?;
+        self.builder.build(swap, recent_blockhash, signers)
+    }
+
+    pub fn unsigned(&self, swap: &SwapRequest, recent_blockhash: Hash) -> Result<VersionedTransaction> {
+        self.check(swap)?;
+        self.builder.unsigned(swap, recent_blockhash)
+    }
+}
+
+#[test]
+fn test_risk_measures() {
+    assert_eq!(price_impact_bps(10_000, 9_950), 50);
+    assert_eq!(price_impact_bps(10_000, 10_100), 0);
+    assert_eq!(price_impact_bps(0, 5), 0);
+
+    let price = OraclePrice { sell_price: 990, avg_price: 1_000, buy_price: 1_010, oracle_live: 1 };
+    assert_eq!(confidence_bps(&price), 100);
+
+    let violations = RiskViolations {
+        fund: Pubkey::default(),
+        violations: vec![
+            RiskViolation::PriceImpact { impact_bps: 80, max_bps: 50 },
+            RiskViolation::FundTvl { tvl_usd: 1, min_usd: 2 },
+        ],
+    };
+    assert!(violations.to_string().ends_with("price impact 80 bps exceeds 50 bps; fund worth 1 is below 2"));
+}
@@ -0,0 +1,245 @@
+use anyhow::{Error, Result};
+use solana_client::client_error::ClientErrorKind;
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcSendTransactionConfig, RpcTransactionConfig};
+use solana_client::rpc_request::{RpcError, RpcResponseErrorData};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::signature::Signature;
+use solana_sdk::signer::signers::Signers;
+use solana_transaction_status::UiTransactionEncoding;
+use std::thread::sleep;
+use std::time::Duration;
+
+use crate::slippage::realized_out_amount;
+use crate::transaction::{SwapRequest, SwapTransactionBuilder};
+
+/// How a swap submitted by `send_and_confirm_swap` ended.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SwapOutcome {
+    Confirmed {
+        signature: Signature,
+        slot: u64,
+        /// Output token credited to the user, from the transaction's token balances.
+        out_amount: Option<u64>,
+    },
+    /// Every attempt's blockhash expired before the transaction landed.
+    Expired {
+        signatures: Vec<Signature>,
+    },
+    /// The transaction failed in preflight or on chain.
+    Failed {
+        signature: Signature,
+        error: String,
+        logs: Vec<String>,
+    },
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct SendConfig {
+    /// Number of blockhashes to try before giving up.
+    pub max_attempts: u32,
+    /// Delay between status polls. The transaction is resent on every poll.
+    pub poll_interval: Duration,
+    pub commitment: CommitmentConfig,
+    pub skip_preflight: bool,
+}
+
+impl Default for SendConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            poll_interval: Duration::from_secs(2),
+            commitment: CommitmentConfig::confirmed(),
+            skip_preflight: false,
+        }
+    }
+}
+
+/// Signs and sends the swap, polling until it is confirmed, fails, or its blockhash expires.
+/// An expired attempt is re-signed with a fresh blockhash, up to `config.max_attempts` times.
+pub fn send_and_confirm_swap<S: Signers>(
+    client: &RpcClient,
+    builder: &SwapTransactionBuilder,
+    swap: &SwapRequest,
+    signers: &S,
+    config: &SendConfig,
+) -> Result<SwapOutcome> {
+    let send_config = RpcSendTransactionConfig {
+        skip_preflight: config.skip_preflight,
+        preflight_commitment: Some(config.commitment.commitment),
+        max_retries: Some(0),
+        ..RpcSendTransactionConfig::default()
+    };
+    let mut signatures = Vec::new();
+
+    for _ in 0..config.max_attempts {
+        let (blockhash, last_valid_block_height) = client.get_latest_blockhash_with_commitment(config.commitment)?;
+        let transaction = builder.build(swap, blockhash, signers)?;
+        let signature = transaction.signatures[0];
+        signatures.push(signature);
+
+        loop {
+            if let Err(e) = client.send_transaction_with_config(&transaction, send_config) {
+                if let ClientErrorKind::RpcError(RpcError::RpcResponseError {
+                    data: RpcResponseErrorData::SendTransactionPreflightFailure(simulation),
+                    ..
+                }) = e.kind()
+                {
+                    return Ok(SwapOutcome::Failed {
+                        signature,
+                        error: simulation.err.as_ref().map(|err| err.to_string()).unwrap_or_else(|| e.to_string()),
+                        logs: simulation.logs.clone().unwrap_or_default(),
+                    });
+                }
+            }
+            sleep(config.poll_interval);
+
+            let status = client.get_signature_statuses(&[signature])?.value.remove(0);
+            if let Some(status) = status {
+                if let Some(err) = status.err {
+                    return Ok(SwapOutcome::Failed {
+                        signature,
+                        error: err.to_string(),
+                        logs: transaction_logs(client, &signature, config.commitment).unwrap_or_default(),
+                    });
+                }
+                if status.satisfies_commitment(config.commitment) {
+                    return Ok(SwapOutcome::Confirmed {
+                        signature,
+                        slot: status.slot,
+                        out_amount: confirmed_out_amount(client, &signature, swap, config.commitment).ok(),
+                    });
+                }
+            }
+            if client.get_block_height_with_commitment(config.commitment)? > last_valid_block_height {
+                break;
+            }
+        }
+    }
+    Ok(SwapOutcome::Expired { signatures })
+}
+
+fn fetch_meta(
+    client: &RpcClient,
+    signature: &Signature,
+    commitment: CommitmentConfig,
+) -> Result<solana_transaction_status::UiTransactionStatusMeta> {
+    client
+        .get_transaction_with_config(signature, RpcTransactionConfig {
+            encoding: Some(UiTransactionEncoding::Base64),
+            commitment: Some(commitment),
+            max_supported_transaction_version: Some(0),
+        })?
+        .transaction
+        .meta
+        .ok_or_else(|| Error::msg("Transaction has no status meta"))
+}
+
+fn transaction_logs(client: &RpcClient, signature: &Signature, commitment: CommitmentConfig) -> Result<Vec<String>> {
+    let logs: Option<Vec<String>> = fetch_meta(client, signature, commitment)?.log_messages.into();
+    Ok(logs.unwrap_or_default())
+}
+
+fn confirmed_out_amount(client: &RpcClient, signature: &Signature, swap: &SwapRequest, commitment: CommitmentConfig) -> Result<u64> {
+    realized_out_amount(&fetch_meta(client, signature, commitment)?, &swap.user, &swap.output_mint)
+}
+
+#[test]
+fn test_send_and_confirm_swap() {
+    use serde_json::json;
+    use solana_client::rpc_client::Mocks;
+    use solana_client::rpc_request::RpcRequest;
+    use solana_sdk::hash::Hash;
+    use solana_sdk::pubkey::Pubkey;
+    use solana_sdk::signature::{Keypair, Signer};
+
+    use crate::amms::account_builders::{quotable_fund, FixtureToken, FundStateBuilder};
+    use crate::amms::accounts::WEIGHT_MULTIPLIER;
+
+    let tokens = [FixtureToken::new(6, 100_000_000), FixtureToken::new(9, 10_000_000_000)];
+    let fund_state = FundStateBuilder::new()
+        .with_token(0, 10_000_000_000, WEIGHT_MULTIPLIER / 2)
+        .with_token(1, 100_000_000_000, WEIGHT_MULTIPLIER / 2);
+    let fund = quotable_fund(Pubkey::new_unique(), &fund_state, &tokens);
+    let user = Keypair::new();
+    let builder = SwapTransactionBuilder::new(&fund, user.pubkey());
+    let swap = SwapRequest {
+        user: user.pubkey(),
+        input_mint: tokens[0].mint,
+        output_mint: tokens[1].mint,
+        in_amount: 100_000_000,
+        minimum_amount_out: 990_000_000,
+    };
+    let config = SendConfig { max_attempts: 1, poll_interval: Duration::ZERO, ..SendConfig::default() };
+    let blockhash = |last_valid_block_height: u64| json!({
+        "context": { "slot": 1 },
+        "value": { "blockhash": Hash::new_unique().to_string(), "lastValidBlockHeight": last_valid_block_height },
+    });
+    // The mock RPC can't decode v0 transactions, so `sendTransaction` answers with a
+    // signature of its own; the sender ignores send errors and relies on status polls.
+    let client = |mut mocks: Mocks| {
+        mocks.insert(RpcRequest::SendTransaction, json!(Signature::default().to_string()));
+        RpcClient::new_mock_with_mocks("succeeds", mocks)
+    };
+    let confirmed_transaction = |err: serde_json::Value, logs: Vec<&str>| {
+        let balance = |amount: u64| json!([{
+            "accountIndex": 3,
+            "mint": swap.output_mint.to_string(),
+            "owner": swap.user.to_string(),
+            "uiTokenAmount": { "uiAmount": null, "decimals": 9, "amount": amount.to_string(), "uiAmountString": "" },
+        }]);
+        json!({
+            "slot": 7,
+            "transaction": ["", "base64"],
+            "meta": {
+                "err": err,
+                "status": if err.is_null() { json!({ "Ok": null }) } else { json!({ "Err": err }) },
+                "fee": 5_000,
+                "preBalances": [],
+                "postBalances": [],
+                "logMessages": logs,
+                "preTokenBalances": balance(0),
+                "postTokenBalances": balance(993_000_000),
+            },
+            "blockTime": null,
+        })
+    };
+
+    // Confirmed on the first poll; the output is read from the transaction's balances.
+    let outcome = send_and_confirm_swap(
+        &client([(RpcRequest::GetTransaction, confirmed_transaction(json!(null), vec![]))].into()),
+        &builder, &swap, &[&user], &config,
+    ).unwrap();
+    assert!(matches!(outcome, SwapOutcome::Confirmed { slot: 1, out_amount: Some(993_000_000), .. }), "{outcome:?}");
+
+    // Failed on chain: the error and the program logs are reported.
+    let failed_status = json!({
+        "context": { "slot": 1 },
+        "value": [{ "slot": 5, "confirmations": null, "err": "AccountInUse", "status": { "Err": "AccountInUse" }, "confirmationStatus": "confirmed" }],
+    });
+    let outcome = send_and_confirm_swap(
+        &client([
+            (RpcRequest::GetSignatureStatuses, failed_status),
+            (RpcRequest::GetTransaction, confirmed_transaction(json!("AccountInUse"), vec!["Program log: failed"])),
+        ].into()),
+        &builder, &swap, &[&user], &config,
+    ).unwrap();
+    match outcome {
+        SwapOutcome::Failed { error, logs, .. } => {
+            assert_eq!(error, solana_sdk::transaction::TransactionError::AccountInUse.to_string());
+            assert_eq!(logs, vec!["Program log: failed".to_string()]);
+        }
+        outcome => panic!("{outcome:?}"),
+    }
+
+    // Never seen before the blockhash expired (block height 1234 is past 1000).
+    let unseen = json!({ "context": { "slot": 1 }, "value": [null] });
+    let outcome = send_and_confirm_swap(
+        &client([(RpcRequest::GetLatestBlockhash, blockhash(1_000)), (RpcRequest::GetSignatureStatuses, unseen)].into()),
+        &builder, &swap, &[&user], &config,
+    ).unwrap();
+    match outcome {
+        SwapOutcome::Expired { signatures } => assert_eq!(signatures.len(), 1),
+        outcome => panic!("{outcome:?}"),
+    }
+}
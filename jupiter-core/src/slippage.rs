@@ -0,0 +1,241 @@
+use anyhow::{Error, Result};
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+use solana_transaction_status::{UiTransactionStatusMeta, UiTransactionTokenBalance};
+use std::collections::HashMap;
+
+/// A swap built by this SDK, remembered until its transaction is confirmed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct QuotedSwap {
+    pub fund: Pubkey,
+    pub input_mint: Pubkey,
+    pub output_mint: Pubkey,
+    /// Owner of the destination token account (the swap's `token_transfer_authority`).
+    pub owner: Pubkey,
+    pub in_amount: u64,
+    pub quoted_out_amount: u64,
+}
+
+/// Quoted vs realized output of one confirmed swap.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RealizedSwap {
+    pub quoted: QuotedSwap,
+    pub realized_out_amount: u64,
+    /// Shortfall versus the quote in bps; negative when the fill beat the quote.
+    pub slippage_bps: i64,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SlippageStats {
+    pub count: u64,
+    pub total_quoted_out: u128,
+    pub total_realized_out: u128,
+    pub sum_slippage_bps: i128,
+    pub worst_slippage_bps: i64,
+    pub best_slippage_bps: i64,
+}
+
+impl SlippageStats {
+    pub fn average_slippage_bps(&self) -> Option<i64> {
+        if self.count == 0 {
+            return None;
+        }
+        (self.sum_slippage_bps / self.count as i128).try_into().ok()
+    }
+
+    fn record(&mut self, swap: &RealizedSwap) {
+        if self.count == 0 {
+            self.worst_slippage_bps = swap.slippage_bps;
+            self.best_slippage_bps = swap.slippage_bps;
+        } else {
+            self.worst_slippage_bps = self.worst_slippage_bps.max(swap.slippage_bps);
+            self.best_slippage_bps = self.best_slippage_bps.min(swap.slippage_bps);
+        }
+        self.count += 1;
+        self.total_quoted_out += swap.quoted.quoted_out_amount as u128;
+        self.total_realized_out += swap.realized_out_amount as u128;
+        self.sum_slippage_bps += swap.slippage_bps as i128;
+    }
+}
+
+/// (fund, input mint, output mint)
+pub type PairKey = (Pubkey, Pubkey, Pubkey);
+
+/// Matches confirmed transactions against the quotes they were built from and
+/// aggregates realized slippage per fund and pair.
+#[derive(Default)]
+pub struct SlippageTracker {
+    pending: HashMap<Signature, QuotedSwap>,
+    stats: HashMap<PairKey, SlippageStats>,
+}
+
+impl SlippageTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record_quote(&mut self, signature: Signature, quoted: QuotedSwap) {
+        self.pending.insert(signature, quoted);
+    }
+
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Consumes the confirmed meta of a tracked transaction. Returns `None` for signatures
+    /// that were never recorded; failed transactions are dropped without affecting stats.
+    pub fn ingest(&mut self, signature: &Signature, meta: &UiTransactionStatusMeta) -> Result<Option<RealizedSwap>> {
+        let quoted = match self.pending.remove(signature) {
+            Some(quoted) => quoted,
+            None => return Ok(None),
+        };
+        if meta.err.is_some() {
+            return Ok(None);
+        }
+        let realized_out_amount = realized_out_amount(meta, &quoted.owner, &quoted.output_mint)?;
+        let slippage_bps = if quoted.quoted_out_amount == 0 {
+            0
+        } else {
+            ((quoted.quoted_out_amount as i128 - realized_out_amount as i128) * 10_000
+                / quoted.quoted_out_amount as i128) as i64
+        };
+        let realized = RealizedSwap {
+            quoted,
+            realized_out_amount,
+            slippage_bps,
+        };
+        self.stats
+            .entry((quoted.fund, quoted.input_mint, quoted.output_mint))
+            .or_default()
+            .record(&realized);
+        Ok(Some(realized))
+    }
+
+    pub fn stats(&self, fund: &Pubkey, input_mint: &Pubkey, output_mint: &Pubkey) -> Option<&SlippageStats> {
+        self.stats.get(&(*fund, *input_mint, *output_mint))
+    }
+
+    pub fn all_stats(&self) -> impl Iterator<Item = (&PairKey, &SlippageStats)> {
+        self.stats.iter()
+    }
+
+    /// Stats merged over every pair of `fund`.
+    pub fn fund_stats(&self, fund: &Pubkey) -> SlippageStats {
+        let mut merged = SlippageStats::default();
+        for ((key_fund, _, _), stats) in &self.stats {
+            if key_fund != fund || stats.count == 0 {
+                continue;
+            }
+            if merged.count == 0 {
+                merged.worst_slippage_bps = stats.worst_slippage_bps;
+                merged.best_slippage_bps = stats.best_slippage_bps;
+            } else {
+                merged.worst_slippage_bps = merged.worst_slippage_bps.max(stats.worst_slippage_bps);
+                merged.best_slippage_bps = merged.best_slippage_bps.min(stats.best_slippage_bps);
+            }
+            merged.count += stats.count;
+            merged.total_quoted_out += stats.total_quoted_out;
+            merged.total_realized_out += stats.total_realized_out;
+            merged.sum_slippage_bps += stats.sum_slippage_bps;
+        }
+        merged
+    }
+}
+
+/// Net increase of `owner`'s `mint` balance between the pre and post token balances.
+pub fn realized_out_amount(meta: &UiTransactionStatusMeta, owner: &Pubkey, mint: &Pubkey) -> Result<u64> {
+    let pre = owned_balance(Option::from(meta.pre_token_balances.as_ref()), owner, mint)?;
+    let post = owned_balance(Option::from(meta.post_token_balances.as_ref()), owner, mint)?;
+    Ok(post.saturating_sub(pre))
+}
+
+fn owned_balance(balances: Option<&Vec<UiTransactionTokenBalance>>, owner: &Pubkey, mint: &Pubkey) -> Result<u64> {
+    let balances = balances.ok_or_else(|| Error::msg("Transaction meta has no token balances"))?;
+    let owner = owner.to_string();
+    let mint = mint.to_string();
+    let mut total: u64 = 0;
+    for balance in balances {
+        let balance_owner: Option<&String> = balance.owner.as_ref().into();
+        if balance.mint == mint && balance_owner == Some(&owner) {
+            total += balance.ui_token_amount.amount.parse::<u64>()?;
+        }
+    }
+    Ok(total)
+}
+
+#[test]
+fn test_slippage_tracker() {
+    use serde_json::json;
+
+    let fund = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let (usdc, sol, bonk) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let balance = |account_index: u8, mint: &Pubkey, owner: &Pubkey, amount: u64| json!({
+        "accountIndex": account_index,
+        "mint": mint.to_string(),
+        "owner": owner.to_string(),
+        "uiTokenAmount": { "uiAmount": null, "decimals": 9, "amount": amount.to_string(), "uiAmountString": "" },
+    });
+    // `owner`'s SOL account goes from `pre` to `post`; another wallet's SOL and `owner`'s
+    // USDC move too and must not count. `pre: None` is an account created by the swap.
+    let meta = |pre: Option<u64>, post: u64, err: bool| -> UiTransactionStatusMeta {
+        let other = Pubkey::new_unique();
+        let mut pre_balances = vec![balance(2, &usdc, &owner, 5_000_000), balance(3, &sol, &other, 0)];
+        if let Some(pre) = pre {
+            pre_balances.push(balance(1, &sol, &owner, pre));
+        }
+        serde_json::from_value(json!({
+            "err": if err { json!("AccountInUse") } else { json!(null) },
+            "status": if err { json!({ "Err": "AccountInUse" }) } else { json!({ "Ok": null }) },
+            "fee": 5_000,
+            "preBalances": [],
+            "postBalances": [],
+            "preTokenBalances": pre_balances,
+            "postTokenBalances": [
+                balance(1, &sol, &owner, post),
+                balance(2, &usdc, &owner, 4_000_000),
+                balance(3, &sol, &other, 7_000_000),
+            ],
+        })).unwrap()
+    };
+    let quoted = |output_mint: Pubkey, quoted_out_amount: u64| QuotedSwap {
+        fund,
+        input_mint: usdc,
+        output_mint,
+        owner,
+        in_amount: 1_000_000,
+        quoted_out_amount,
+    };
+
+    let mut tracker = SlippageTracker::new();
+    let signatures: Vec<Signature> = (0..4).map(|_| Signature::new_unique()).collect();
+    tracker.record_quote(signatures[0], quoted(sol, 1_000_000));
+    tracker.record_quote(signatures[1], quoted(sol, 1_000_000));
+    tracker.record_quote(signatures[2], quoted(sol, 1_000_000));
+    tracker.record_quote(signatures[3], quoted(bonk, 0));
+    assert_eq!(tracker.pending(), 4);
+
+    // Filled 0.5% short of the quote, then 0.1% above it into a new account.
+    let realized = tracker.ingest(&signatures[0], &meta(Some(2_000_000), 2_995_000, false)).unwrap().unwrap();
+    assert_eq!((realized.realized_out_amount, realized.slippage_bps), (995_000, 50));
+    let realized = tracker.ingest(&signatures[1], &meta(None, 1_001_000, false)).unwrap().unwrap();
+    assert_eq!((realized.realized_out_amount, realized.slippage_bps), (1_001_000, -10));
+    // Failed and unknown transactions leave the stats alone.
+    assert!(tracker.ingest(&signatures[2], &meta(Some(0), 0, true)).unwrap().is_none());
+    assert!(tracker.ingest(&signatures[2], &meta(Some(0), 1, false)).unwrap().is_none());
+    // A zero quote has no slippage to measure.
+    assert_eq!(tracker.ingest(&signatures[3], &meta(Some(0), 0, false)).unwrap().unwrap().slippage_bps, 0);
+    assert_eq!(tracker.pending(), 0);
+
+    let stats = tracker.stats(&fund, &usdc, &sol).unwrap();
+    assert_eq!(stats.count, 2);
+    assert_eq!((stats.total_quoted_out, stats.total_realized_out), (2_000_000, 1_996_000));
+    assert_eq!((stats.worst_slippage_bps, stats.best_slippage_bps), (50, -10));
+    assert_eq!(stats.average_slippage_bps(), Some(20));
+    assert!(tracker.stats(&fund, &sol, &usdc).is_none());
+
+    let merged = tracker.fund_stats(&fund);
+    assert_eq!(merged.count, 3);
+    assert_eq!((merged.worst_slippage_bps, merged.best_slippage_bps), (50, -10));
+    assert_eq!(merged.average_slippage_bps(), Some(13));
+    assert_eq!(tracker.fund_stats(&Pubkey::new_unique()).average_slippage_bps(), None);
+}
@@ -0,0 +1,130 @@
+use anyhow::{Context, Error, Result};
+use jupiter_amm_interface::{AccountMap, KeyedAccount};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "rpc")]
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{account::Account, pubkey::Pubkey};
+use std::path::Path;
+
+use crate::amms::shared_accounts::SharedAccountCache;
+use crate::amms::symmetry_token_swap::SymmetryTokenSwap;
+#[cfg(feature = "rpc")]
+use crate::discovery::fetch_account_map;
+use crate::error::SymmetryError;
+use crate::registry::FundRegistry;
+
+pub const REGISTRY_SNAPSHOT_VERSION: u16 = 1;
+
+/// Raw accounts of a set of funds at a point in time, enough to build a quotable registry
+/// without any RPC call. Stored as bincode; ship one with the service binary
+/// (`include_bytes!`) or write one periodically with `save`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct RegistrySnapshot {
+    pub version: u16,
+    /// Context slot the accounts were fetched at.
+    pub slot: u64,
+    pub token_list: Account,
+    pub funds: Vec<(Pubkey, Account)>,
+    /// Curve data and oracle accounts, shared by all funds.
+    pub accounts: AccountMap,
+}
+
+impl RegistrySnapshot {
+    /// Fetches `funds`, the token list and every account they depend on.
+    #[cfg(feature = "rpc")]
+    pub fn fetch(client: &RpcClient, funds: &[Pubkey]) -> Result<RegistrySnapshot> {
+        let slot = client.get_slot()?;
+        let token_list = client.get_account(&SymmetryTokenSwap::TOKEN_LIST_ADDRESS)?;
+        let fund_accounts = fetch_account_map(client, funds)?;
+
+        let mut registry = FundRegistry::new();
+        let mut funds = Vec::with_capacity(fund_accounts.len());
+        for (key, account) in fund_accounts {
+            registry.insert(SymmetryTokenSwap::from_keyed_account(
+                &KeyedAccount { key, account: account.clone(), params: None },
+                &KeyedAccount { key: SymmetryTokenSwap::TOKEN_LIST_ADDRESS, account: token_list.clone(), params: None },
+            )?);
+            funds.push((key, account));
+        }
+        let accounts = fetch_account_map(client, &registry.accounts_to_update())?;
+        Ok(RegistrySnapshot {
+            version: REGISTRY_SNAPSHOT_VERSION,
+            slot,
+            token_list,
+            funds,
+            accounts,
+        })
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<RegistrySnapshot> {
+        let snapshot: RegistrySnapshot = bincode::deserialize(bytes)?;
+        if snapshot.version != REGISTRY_SNAPSHOT_VERSION {
+            return Err(SymmetryError::UnsupportedSnapshotVersion { version: snapshot.version }.into());
+        }
+        Ok(snapshot)
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        Ok(bincode::serialize(self)?)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<RegistrySnapshot> {
+        let bytes = std::fs::read(path.as_ref())
+            .with_context(|| format!("failed to read registry snapshot {}", path.as_ref().display()))?;
+        RegistrySnapshot::from_bytes(&bytes)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        std::fs::write(path.as_ref(), self.to_bytes()?)
+            .with_context(|| format!("failed to write registry snapshot {}", path.as_ref().display()))
+    }
+}
+
+impl FundRegistry {
+    /// Registry of the snapshot's funds, quotable immediately at the snapshot's prices.
+    /// Funds whose accounts no longer parse are skipped and returned with their error.
+    /// `snapshot_slot()` stays set until the first `update_all` with live accounts.
+    pub fn from_snapshot(snapshot: &RegistrySnapshot) -> (FundRegistry, Vec<(Pubkey, Error)>) {
+        let mut registry = FundRegistry::new();
+        let mut failed = Vec::new();
+        let mut cache = SharedAccountCache::default();
+        for (key, account) in &snapshot.funds {
+            let fund = SymmetryTokenSwap::from_keyed_account(
+                &KeyedAccount { key: *key, account: account.clone(), params: None },
+                &KeyedAccount { key: SymmetryTokenSwap::TOKEN_LIST_ADDRESS, account: snapshot.token_list.clone(), params: None },
+            )
+            .and_then(|mut fund| {
+                fund.update_with_cache(&snapshot.accounts, &mut cache)?;
+                Ok(fund)
+            });
+            match fund {
+                Ok(fund) => {
+                    registry.insert(fund);
+                }
+                Err(e) => failed.push((*key, e)),
+            }
+        }
+        registry.set_snapshot_slot(Some(snapshot.slot));
+        (registry, failed)
+    }
+}
+
+#[test]
+fn test_snapshot_version_check() {
+    let snapshot = RegistrySnapshot {
+        version: REGISTRY_SNAPSHOT_VERSION,
+        slot: 7,
+        token_list: Account::default(),
+        funds: Vec::new(),
+        accounts: AccountMap::new(),
+    };
+    assert_eq!(RegistrySnapshot::from_bytes(&snapshot.to_bytes().unwrap()).unwrap().slot, 7);
+
+    let future = RegistrySnapshot { version: REGISTRY_SNAPSHOT_VERSION + 1, ..snapshot };
+    let error = RegistrySnapshot::from_bytes(&future.to_bytes().unwrap()).unwrap_err();
+    assert_eq!(
+        error.downcast_ref::<SymmetryError>(),
+        Some(&SymmetryError::UnsupportedSnapshotVersion { version: REGISTRY_SNAPSHOT_VERSION + 1 })
+    );
+}
@@ -0,0 +1,223 @@
+use anyhow::{Context, Result};
+use jupiter_amm_interface::{AccountMap, KeyedAccount};
+use serde::{Deserialize, Serialize};
+use solana_sdk::{account::Account, clock::Clock, pubkey::Pubkey};
+use std::{convert::TryInto, path::Path};
+
+use crate::amms::symmetry_token_swap::SymmetryTokenSwap;
+use crate::error::SymmetryError;
+
+/// Version 2 added `clock`.
+pub const FUND_SNAPSHOT_VERSION: u16 = 2;
+
+/// Everything needed to rebuild a quotable fund as of `slot`: the fund state and
+/// token list used by `from_keyed_account`, the accounts passed to `update()`, and the
+/// clock oracle staleness was judged at.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FundSnapshot {
+    pub version: u16,
+    pub fund: Pubkey,
+    pub slot: u64,
+    pub clock: Clock,
+    pub fund_state: Account,
+    pub token_list: Account,
+    pub accounts: AccountMap,
+}
+
+impl FundSnapshot {
+    /// Checks the version before decoding the rest, so snapshots written by an older
+    /// layout fail with `UnsupportedSnapshotVersion` rather than a garbled decode.
+    pub fn from_bytes(bytes: &[u8]) -> Result<FundSnapshot> {
+        let version: u16 = bincode::deserialize(bytes)?;
+        if version != FUND_SNAPSHOT_VERSION {
+            return Err(SymmetryError::UnsupportedSnapshotVersion { version }.into());
+        }
+        Ok(bincode::deserialize(bytes)?)
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        Ok(bincode::serialize(self)?)
+    }
+
+    pub fn to_amm(&self) -> Result<SymmetryTokenSwap> {
+        let mut amm = SymmetryTokenSwap::from_keyed_account(
+            &KeyedAccount {
+                key: self.fund,
+                account: self.fund_state.clone(),
+                params: None,
+            },
+            &KeyedAccount {
+                key: SymmetryTokenSwap::TOKEN_LIST_ADDRESS,
+                account: self.token_list.clone(),
+                params: None,
+            },
+        )?;
+        amm.update_with_clock(&self.accounts, self.clock.clone())?;
+        Ok(amm)
+    }
+}
+
+/// Embedded on-disk store of fund snapshots keyed by fund pubkey + slot.
+pub struct StateCache {
+    db: sled::Db,
+}
+
+impl StateCache {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let db = sled::open(path.as_ref())
+            .with_context(|| format!("failed to open state cache {}", path.as_ref().display()))?;
+        Ok(Self { db })
+    }
+
+    /// Slot is stored big-endian after the fund key so a prefix scan yields snapshots in slot order.
+    fn key(fund: &Pubkey, slot: u64) -> [u8; 40] {
+        let mut key = [0u8; 40];
+        key[..32].copy_from_slice(fund.as_ref());
+        key[32..].copy_from_slice(&slot.to_be_bytes());
+        key
+    }
+
+    pub fn put(&self, snapshot: &FundSnapshot) -> Result<()> {
+        let value = snapshot.to_bytes()?;
+        self.db.insert(Self::key(&snapshot.fund, snapshot.slot), value)?;
+        Ok(())
+    }
+
+    pub fn get(&self, fund: &Pubkey, slot: u64) -> Result<Option<FundSnapshot>> {
+        self.db
+            .get(Self::key(fund, slot))?
+            .map(|value| FundSnapshot::from_bytes(&value))
+            .transpose()
+    }
+
+    /// Most recent snapshot stored for `fund`.
+    pub fn latest(&self, fund: &Pubkey) -> Result<Option<FundSnapshot>> {
+        self.db
+            .scan_prefix(fund.as_ref())
+            .next_back()
+            .transpose()?
+            .map(|(_, value)| FundSnapshot::from_bytes(&value))
+            .transpose()
+    }
+
+    /// Latest snapshot for `fund` at or before `slot`, for resuming backtests.
+    pub fn at_or_before(&self, fund: &Pubkey, slot: u64) -> Result<Option<FundSnapshot>> {
+        self.db
+            .range(Self::key(fund, 0)..=Self::key(fund, slot))
+            .next_back()
+            .transpose()?
+            .map(|(_, value)| FundSnapshot::from_bytes(&value))
+            .transpose()
+    }
+
+    pub fn slots(&self, fund: &Pubkey) -> Result<Vec<u64>> {
+        self.db
+            .scan_prefix(fund.as_ref())
+            .keys()
+            .map(|key| Ok(u64::from_be_bytes(key?[32..40].try_into()?)))
+            .collect()
+    }
+
+    /// Removes snapshots of `fund` older than `slot`; returns how many were dropped.
+    pub fn prune_before(&self, fund: &Pubkey, slot: u64) -> Result<usize> {
+        let mut removed = 0;
+        for key in self.db.range(Self::key(fund, 0)..Self::key(fund, slot)).keys() {
+            self.db.remove(key?)?;
+            removed += 1;
+        }
+        Ok(removed)
+    }
+
+    pub fn flush(&self) -> Result<()> {
+        self.db.flush()?;
+        Ok(())
+    }
+}
+
+#[test]
+fn test_state_cache_hits_and_pruning() {
+    use crate::amms::account_builders::{CurveDataBuilder, FundStateBuilder, OracleAccountBuilder, TokenListBuilder};
+    use crate::amms::accounts::{TokenSettings, ONE_USD, WEIGHT_MULTIPLIER};
+
+    let usdc = Pubkey::new_unique();
+    let usdc_oracle = Pubkey::new_unique();
+    let token_list = TokenListBuilder::new().with_token(TokenSettings {
+        token_mint: usdc,
+        decimals: 6,
+        oracle_account: usdc_oracle,
+        oracle_confidence_pct: 10,
+        is_live: 1,
+        lp_on: 1,
+        ..TokenSettings::empty()
+    });
+    let fund = Pubkey::new_unique();
+    let snapshot = |slot: u64, usdc_amount: u64| {
+        let fund_state = FundStateBuilder::new().with_token(0, usdc_amount, WEIGHT_MULTIPLIER).build_account();
+        FundSnapshot {
+            version: FUND_SNAPSHOT_VERSION,
+            fund,
+            slot,
+            clock: Clock { slot, ..Clock::default() },
+            fund_state: fund_state.clone(),
+            token_list: token_list.build_account(),
+            accounts: [
+                (fund, fund_state),
+                (SymmetryTokenSwap::CURVE_DATA_ADDRESS, CurveDataBuilder::new().build_account()),
+                (usdc_oracle, OracleAccountBuilder::pyth(100_000_000, 0, -8, slot).build_account()),
+            ].into(),
+        }
+    };
+
+    let path = std::env::temp_dir().join(format!("symmetry-state-cache-{}-{}", std::process::id(), Pubkey::new_unique()));
+    let cache = StateCache::open(&path).unwrap();
+    assert!(cache.latest(&fund).unwrap().is_none());
+    for (slot, usdc_amount) in [(100, 1_000_000_000), (200, 2_000_000_000), (300, 3_000_000_000)] {
+        cache.put(&snapshot(slot, usdc_amount)).unwrap();
+    }
+    // Another fund's snapshots don't show up under `fund`.
+    cache.put(&FundSnapshot { fund: Pubkey::new_unique(), ..snapshot(400, 1) }).unwrap();
+
+    assert_eq!(cache.slots(&fund).unwrap(), vec![100, 200, 300]);
+    assert_eq!(cache.latest(&fund).unwrap().unwrap().slot, 300);
+    assert_eq!(cache.at_or_before(&fund, 250).unwrap().unwrap().slot, 200);
+    assert!(cache.at_or_before(&fund, 99).unwrap().is_none());
+    assert!(cache.get(&fund, 150).unwrap().is_none());
+    let amm = cache.get(&fund, 200).unwrap().unwrap().to_amm().unwrap();
+    assert_eq!(amm.fund_worth().unwrap(), 2_000 * ONE_USD);
+    assert_eq!(amm.oracle_price(&usdc).unwrap().oracle_live, 1);
+
+    // The snapshot's own clock judges the oracles, so a fund quoted at slot 200 stays
+    // quotable even without a clock sysvar among its accounts.
+    let stale = FundSnapshot { clock: Clock { slot: 100_000, ..Clock::default() }, ..snapshot(200, 1) };
+    assert_eq!(stale.to_amm().unwrap().oracle_price(&usdc).unwrap().oracle_live, 0);
+
+    // Snapshots written by an older layout are rejected by version.
+    let old = FundSnapshot { version: 1, ..snapshot(200, 1) };
+    assert_eq!(
+        FundSnapshot::from_bytes(&old.to_bytes().unwrap()).unwrap_err().downcast_ref::<SymmetryError>(),
+        Some(&SymmetryError::UnsupportedSnapshotVersion { version: 1 })
+    );
+
+    // Re-putting a slot replaces its snapshot.
+    cache.put(&snapshot(200, 4_000_000_000)).unwrap();
+    assert_eq!(cache.get(&fund, 200).unwrap().unwrap().to_amm().unwrap().fund_worth().unwrap(), 4_000 * ONE_USD);
+
+    assert_eq!(cache.prune_before(&fund, 300).unwrap(), 2);
+    assert_eq!(cache.slots(&fund).unwrap(), vec![300]);
+    assert!(cache.get(&fund, 100).unwrap().is_none());
+    assert!(cache.at_or_before(&fund, 250).unwrap().is_none());
+    cache.flush().unwrap();
+    drop(cache);
+
+    // Snapshots survive reopening the store. sled's flusher thread releases the file lock
+    // shortly after the last handle is dropped, so the reopen may need a few tries.
+    let cache = (0..50)
+        .find_map(|_| StateCache::open(&path).ok().or_else(|| {
+            std::thread::sleep(std::time::Duration::from_millis(20));
+            None
+        }))
+        .unwrap();
+    assert_eq!(cache.latest(&fund).unwrap().unwrap().slot, 300);
+    drop(cache);
+    std::fs::remove_dir_all(&path).unwrap();
+}
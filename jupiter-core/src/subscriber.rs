@@ -0,0 +1,140 @@
+//! Keeps an AMM current from websocket `accountSubscribe` notifications instead of polling.
+
+use anyhow::{Error, Result};
+use futures::{stream::select_all, FutureExt, StreamExt};
+use jupiter_amm_interface::{AccountMap, Amm};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::nonblocking::{pubsub_client::PubsubClient, rpc_client::RpcClient};
+use solana_client::rpc_config::RpcAccountInfoConfig;
+use solana_sdk::{account::Account, commitment_config::CommitmentConfig, pubkey::Pubkey};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use tokio::task::JoinHandle;
+
+use crate::client::fetch_account_map;
+
+/// An AMM shared with a background task that applies every account notification for the
+/// accounts `get_accounts_to_update()` names. Readers take the lock for each quote, so the
+/// state they see is never part-way through an update.
+pub struct AccountSubscriber<T> {
+    amm: Arc<RwLock<T>>,
+    failed_updates: Arc<AtomicU64>,
+    closed: Arc<AtomicBool>,
+    task: JoinHandle<Result<()>>,
+}
+
+impl<T: Amm + Send + Sync + 'static> AccountSubscriber<T> {
+    /// Fetches the AMM's accounts with `rpc`, then subscribes to each of them on `ws_url` at
+    /// the client's commitment. Must be called within a tokio runtime. The token list is
+    /// only read at construction, so listing changes need a rebuilt AMM.
+    pub async fn start(rpc: &RpcClient, ws_url: &str, mut amm: T) -> Result<Self> {
+        let account_map = fetch_account_map(rpc, &amm).await?;
+        amm.update(&account_map)?;
+        let keys = amm.get_accounts_to_update();
+        let pubsub = PubsubClient::new(ws_url).await?;
+
+        let amm = Arc::new(RwLock::new(amm));
+        let failed_updates = Arc::new(AtomicU64::new(0));
+        let closed = Arc::new(AtomicBool::new(false));
+        let notifications = apply_notifications(pubsub, keys, rpc.commitment(), account_map, amm.clone(), failed_updates.clone());
+        let task_closed = closed.clone();
+        let task = tokio::spawn(async move {
+            let result = notifications.await;
+            task_closed.store(true, Ordering::Relaxed);
+            result
+        });
+        Ok(Self { amm, failed_updates, closed, task })
+    }
+}
+
+impl<T> AccountSubscriber<T> {
+    pub fn amm(&self) -> Arc<RwLock<T>> {
+        self.amm.clone()
+    }
+
+    /// Copy of the AMM as of the last applied notification.
+    pub fn snapshot(&self) -> T
+    where
+        T: Clone,
+    {
+        self.amm.read().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+    }
+
+    /// Notifications whose `update()` failed; the AMM keeps its previous state for those.
+    pub fn failed_updates(&self) -> u64 {
+        self.failed_updates.load(Ordering::Relaxed)
+    }
+
+    /// Whether the subscriptions have closed, after which the AMM is no longer updated.
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::Relaxed)
+    }
+
+    /// Drops the subscriptions. The AMM keeps its last state.
+    pub fn stop(&self) {
+        self.task.abort();
+        self.closed.store(true, Ordering::Relaxed);
+    }
+}
+
+impl<T> Drop for AccountSubscriber<T> {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn apply_notifications<T: Amm>(
+    pubsub: PubsubClient,
+    keys: Vec<Pubkey>,
+    commitment: CommitmentConfig,
+    mut account_map: AccountMap,
+    amm: Arc<RwLock<T>>,
+    failed_updates: Arc<AtomicU64>,
+) -> Result<()> {
+    let config = RpcAccountInfoConfig {
+        encoding: Some(UiAccountEncoding::Base64),
+        commitment: Some(commitment),
+        ..RpcAccountInfoConfig::default()
+    };
+    let mut streams = Vec::with_capacity(keys.len());
+    for key in keys {
+        let (stream, _unsubscribe) = pubsub.account_subscribe(&key, Some(config.clone())).await?;
+        streams.push(stream.map(move |response| (key, response.value.decode::<Account>())));
+    }
+    let mut notifications = select_all(streams);
+    while let Some((key, account)) = notifications.next().await {
+        let mut changed = apply(&mut account_map, key, account);
+        // Apply notifications that arrived together, e.g. for one slot, with one update.
+        while let Some(Some((key, account))) = notifications.next().now_or_never() {
+            changed |= apply(&mut account_map, key, account);
+        }
+        if !changed {
+            continue;
+        }
+        let mut amm = amm.write().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if amm.update(&account_map).is_err() {
+            failed_updates.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+    Err(Error::msg("Account subscriptions closed"))
+}
+
+fn apply(account_map: &mut AccountMap, key: Pubkey, account: Option<Account>) -> bool {
+    match account {
+        Some(account) => {
+            account_map.insert(key, account);
+            true
+        }
+        None => false,
+    }
+}
+
+#[test]
+fn test_apply_notification() {
+    let mut account_map = AccountMap::new();
+    let key = Pubkey::new_unique();
+    assert!(!apply(&mut account_map, key, None));
+    assert!(account_map.is_empty());
+    assert!(apply(&mut account_map, key, Some(Account { lamports: 5, ..Account::default() })));
+    assert_eq!(account_map[&key].lamports, 5);
+}
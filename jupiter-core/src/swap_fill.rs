@@ -0,0 +1,180 @@
+//! Settlement view of Symmetry swaps in confirmed transactions.
+//!
+//! A `SwapFill` resolves the swap's token ids to mints and reads what the swap paid out from
+//! the token balances the runtime records, so it holds whatever the program logs. The output
+//! amount and each fee are the net balance changes of the user's destination account and of
+//! the three fee accounts over the whole transaction. `symmetry_invocations` splits raw logs
+//! into the Symmetry program's invocations, for pipelines that only keep logs.
+
+use anyhow::{Error, Result};
+use solana_sdk::pubkey::Pubkey;
+use solana_transaction_status::EncodedConfirmedTransactionWithStatusMeta;
+
+use crate::amms::accounts::TokenList;
+use crate::amms::symmetry_token_swap::SymmetryTokenSwap;
+use crate::fills::{balance_change, FundInstructions};
+use crate::instruction::SwapIxArgs;
+
+/// Positions of the user's destination account and the swap, host and manager fee accounts
+/// in the swap instruction accounts.
+const DESTINATION_ACCOUNT_INDEX: usize = 6;
+const FEE_ACCOUNT_INDICES: [usize; 3] = [7, 8, 9];
+
+/// Fees a swap paid, in raw units of the output token.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SwapFillFees {
+    pub swap_fee: u64,
+    pub host_fee: u64,
+    pub manager_fee: u64,
+}
+
+impl SwapFillFees {
+    pub fn total(&self) -> u64 {
+        self.swap_fee.saturating_add(self.host_fee).saturating_add(self.manager_fee)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SwapFill {
+    pub fund: Pubkey,
+    pub from_mint: Pubkey,
+    pub to_mint: Pubkey,
+    /// Input amount of the swap instruction.
+    pub in_amount: u64,
+    /// Amount credited to the user's destination account.
+    pub out_amount: u64,
+    /// Fees credited to the fee accounts. When the host and manager share an account the
+    /// credit is reported once, as host fee.
+    pub fees: SwapFillFees,
+}
+
+/// Swaps against `fund` in `transaction`, top level and CPI, with token ids resolved through
+/// `token_list`. Fails if the transaction carries no token balances.
+pub fn decode_swap_fills(
+    transaction: &EncodedConfirmedTransactionWithStatusMeta,
+    fund: &Pubkey,
+    token_list: &TokenList,
+) -> Result<Vec<SwapFill>> {
+    let meta = transaction
+        .transaction
+        .meta
+        .as_ref()
+        .ok_or_else(|| Error::msg("Transaction has no status meta"))?;
+    let FundInstructions { instructions, .. } = FundInstructions::decode(transaction, fund)?;
+
+    let mut fills = Vec::new();
+    for instruction in &instructions {
+        let Ok(args) = SwapIxArgs::unpack(&instruction.data) else {
+            continue;
+        };
+        let credited = |position: usize| -> Result<u64> {
+            let &account = instruction
+                .accounts
+                .get(position)
+                .ok_or_else(|| Error::msg("Swap instruction has too few accounts"))?;
+            balance_change(meta, account).ok_or_else(|| Error::msg("Transaction has no token balances"))
+        };
+        let [swap_fee, host_fee, manager_fee] = FEE_ACCOUNT_INDICES;
+        let (from_mint, to_mint) = args.mints(token_list)?;
+        let same_account = |a: usize, b: usize| instruction.accounts.get(a) == instruction.accounts.get(b);
+        fills.push(SwapFill {
+            fund: *fund,
+            from_mint,
+            to_mint,
+            in_amount: args.amount,
+            out_amount: credited(DESTINATION_ACCOUNT_INDEX)?,
+            fees: SwapFillFees {
+                swap_fee: credited(swap_fee)?,
+                host_fee: credited(host_fee)?,
+                manager_fee: if same_account(host_fee, manager_fee) { 0 } else { credited(manager_fee)? },
+            },
+        });
+    }
+    Ok(fills)
+}
+
+/// One invocation of the Symmetry program, from its `invoke` log line to its result.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SymmetryInvocation {
+    /// Invocation depth, 1 for a top-level instruction.
+    pub depth: usize,
+    /// `Program log:` messages, without the prefix.
+    pub messages: Vec<String>,
+    /// Base64-decoded `Program data:` payloads.
+    pub data: Vec<Vec<u8>>,
+    pub units_consumed: Option<u64>,
+    pub succeeded: bool,
+}
+
+/// Splits transaction logs into the Symmetry program's invocations, in order. Logs of
+/// programs the Symmetry program invokes are not included in its messages.
+pub fn symmetry_invocations(logs: &[String]) -> Vec<SymmetryInvocation> {
+    let program = SymmetryTokenSwap::SYMMETRY_PROGRAM_ADDRESS.to_string();
+    let mut invocations: Vec<SymmetryInvocation> = Vec::new();
+    // Programs currently executing, innermost last, with the index of their invocation.
+    let mut stack: Vec<(String, Option<usize>)> = Vec::new();
+    for log in logs {
+        let Some(rest) = log.strip_prefix("Program ") else {
+            continue;
+        };
+        let current = stack.last().and_then(|(_, index)| *index);
+        if let Some(message) = rest.strip_prefix("log: ") {
+            if let Some(index) = current {
+                invocations[index].messages.push(message.to_string());
+            }
+        } else if let Some(data) = rest.strip_prefix("data: ") {
+            if let Some(index) = current {
+                invocations[index].data.push(base64::decode(data).unwrap_or_default());
+            }
+        } else if let Some((id, result)) = rest.split_once(' ') {
+            if result.starts_with("invoke [") {
+                let index = (id == program).then(|| {
+                    invocations.push(SymmetryInvocation { depth: stack.len() + 1, ..SymmetryInvocation::default() });
+                    invocations.len() - 1
+                });
+                stack.push((id.to_string(), index));
+            } else if stack.last().is_some_and(|(top, _)| top == id) {
+                if let Some(units) = result.strip_prefix("consumed ") {
+                    if let Some(index) = current {
+                        invocations[index].units_consumed = units.split(' ').next().and_then(|units| units.parse().ok());
+                    }
+                } else if result == "success" || result.starts_with("failed") {
+                    if let Some(index) = current {
+                        invocations[index].succeeded = result == "success";
+                    }
+                    stack.pop();
+                }
+            }
+        }
+    }
+    invocations
+}
+
+#[test]
+fn test_symmetry_invocations() {
+    let program = SymmetryTokenSwap::SYMMETRY_PROGRAM_ADDRESS;
+    let token_program = SymmetryTokenSwap::SPL_TOKEN_PROGRAM_ADDRESS;
+    let logs: Vec<String> = [
+        "Program ComputeBudget111111111111111111111111111111 invoke [1]".to_string(),
+        "Program ComputeBudget111111111111111111111111111111 success".to_string(),
+        format!("Program {program} invoke [1]"),
+        "Program log: swap".to_string(),
+        format!("Program {token_program} invoke [2]"),
+        "Program log: Instruction: Transfer".to_string(),
+        format!("Program {token_program} consumed 4645 of 180000 compute units"),
+        format!("Program {token_program} success"),
+        "Program data: AQID".to_string(),
+        format!("Program {program} consumed 52000 of 200000 compute units"),
+        format!("Program {program} success"),
+    ]
+    .into();
+
+    let invocations = symmetry_invocations(&logs);
+    assert_eq!(invocations, vec![SymmetryInvocation {
+        depth: 1,
+        messages: vec!["swap".to_string()],
+        data: vec![vec![1, 2, 3]],
+        units_consumed: Some(52_000),
+        succeeded: true,
+    }]);
+}
@@ -0,0 +1,462 @@
+//! Chronological history of a fund, merged from its transactions and from fund states
+//! observed over time.
+//!
+//! Only the swap instruction has a known layout, so other Symmetry instructions on the fund
+//! are reported by their 8-byte instruction id. The SDK doesn't know the claim-fee
+//! instruction's id either, so callers pass the ids to report as fee claims; deposits and
+//! withdrawals that also pay the manager stay plain instructions. Rebalances and weight
+//! changes come from comparing observed fund states, since past account states can't be
+//! fetched over RPC.
+
+use anyhow::Result;
+use jupiter_amm_interface::Amm;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+use solana_transaction_status::{EncodedConfirmedTransactionWithStatusMeta, UiTransactionTokenBalance};
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+use crate::amms::symmetry_token_swap::SymmetryTokenSwap;
+use crate::fill_watcher::{infer_fill, FillEvent};
+use crate::fills::{decode_fills, Fill, FillScraper, FundInstructions};
+use crate::instruction::SwapIxArgs;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TimelineEvent {
+    Swap(Fill),
+    /// A claim-fee instruction on the fund.
+    FeeClaim {
+        signature: Signature,
+        slot: u64,
+        block_time: Option<i64>,
+        /// The manager, or the host if the transaction credited only the host.
+        recipient: Pubkey,
+        /// Amount the transaction credited to the recipient's token accounts, per mint.
+        credits: Vec<(Pubkey, u64)>,
+    },
+    /// Any other Symmetry instruction on the fund: deposits, withdrawals, rebalance steps
+    /// and manager edits.
+    Instruction {
+        signature: Signature,
+        slot: u64,
+        block_time: Option<i64>,
+        /// First 8 bytes of the instruction data, little endian.
+        instruction_id: u64,
+        signers: Vec<Pubkey>,
+        by_manager: bool,
+    },
+    /// Target weights changed between two observations. Weights are in `WEIGHT_MULTIPLIER`
+    /// units, per mint, before and after.
+    WeightChange {
+        slot: u64,
+        changes: Vec<(Pubkey, u64, u64)>,
+    },
+    /// Token balances moved between two observations in a way no single swap explains.
+    CompositionChange {
+        slot: u64,
+        /// Signed balance change per mint.
+        deltas: Vec<(Pubkey, i128)>,
+        /// The fund had a rebalance in progress at either observation.
+        during_rebalance: bool,
+    },
+}
+
+impl TimelineEvent {
+    pub fn slot(&self) -> u64 {
+        match self {
+            TimelineEvent::Swap(fill) => fill.slot,
+            TimelineEvent::FeeClaim { slot, .. }
+            | TimelineEvent::Instruction { slot, .. }
+            | TimelineEvent::WeightChange { slot, .. }
+            | TimelineEvent::CompositionChange { slot, .. } => *slot,
+        }
+    }
+}
+
+/// Events of `fund` in `slots`, oldest first. Transactions are scraped from the newest
+/// backwards, so a range far in the past still pages through everything after it.
+/// `observations` are states of the same fund (e.g. from a `StateCache` or snapshots);
+/// ones without `last_update_slot()` are ignored. Instructions whose id is in
+/// `claim_fee_instruction_ids` are reported as `FeeClaim`s.
+pub async fn timeline(
+    client: &RpcClient,
+    fund: &SymmetryTokenSwap,
+    slots: RangeInclusive<u64>,
+    observations: &[SymmetryTokenSwap],
+    claim_fee_instruction_ids: &[u64],
+) -> Result<Vec<TimelineEvent>> {
+    let mut scraper = FillScraper::new(client, fund.key()).with_token_list(fund.token_list());
+    let mut events = Vec::new();
+    'pages: while let Some(transactions) = scraper.next_transactions().await? {
+        for (signature, transaction) in &transactions {
+            if transaction.slot < *slots.start() {
+                break 'pages;
+            }
+            if slots.contains(&transaction.slot) {
+                events.extend(transaction_events(signature, transaction, fund, scraper.mints(), claim_fee_instruction_ids)?);
+            }
+        }
+    }
+    events.extend(
+        observation_events(observations)
+            .into_iter()
+            .filter(|event| slots.contains(&event.slot())),
+    );
+    events.sort_by_key(TimelineEvent::slot);
+    Ok(events)
+}
+
+/// Swaps, fee claims and other instructions on `fund` in one transaction. Instructions whose
+/// id is in `claim_fee_instruction_ids` are fee claims.
+pub fn transaction_events(
+    signature: &Signature,
+    transaction: &EncodedConfirmedTransactionWithStatusMeta,
+    fund: &SymmetryTokenSwap,
+    mints: &[Pubkey],
+    claim_fee_instruction_ids: &[u64],
+) -> Result<Vec<TimelineEvent>> {
+    let mut events: Vec<TimelineEvent> = decode_fills(signature, transaction, &fund.key(), mints)?
+        .into_iter()
+        .map(TimelineEvent::Swap)
+        .collect();
+    let FundInstructions { signers, instructions, .. } = FundInstructions::decode(transaction, &fund.key())?;
+    let manager = fund.fund_state().manager;
+    let host = fund.fund_state().host_pubkey;
+
+    for instruction in &instructions {
+        if SwapIxArgs::unpack(&instruction.data).is_ok() {
+            continue;
+        }
+        let instruction_id = instruction.data.get(..8)
+            .map(|id| u64::from_le_bytes(id.try_into().unwrap()))
+            .unwrap_or_default();
+        if claim_fee_instruction_ids.contains(&instruction_id) {
+            let mut recipient = manager;
+            let mut credits = fee_credits(transaction, &manager);
+            if credits.is_empty() && host != Pubkey::default() {
+                let host_credits = fee_credits(transaction, &host);
+                if !host_credits.is_empty() {
+                    recipient = host;
+                    credits = host_credits;
+                }
+            }
+            events.push(TimelineEvent::FeeClaim {
+                signature: *signature,
+                slot: transaction.slot,
+                block_time: transaction.block_time,
+                recipient,
+                credits,
+            });
+            continue;
+        }
+        events.push(TimelineEvent::Instruction {
+            signature: *signature,
+            slot: transaction.slot,
+            block_time: transaction.block_time,
+            instruction_id,
+            by_manager: signers.contains(&manager),
+            signers: signers.clone(),
+        });
+    }
+    Ok(events)
+}
+
+/// Credits to token accounts owned by `owner` in a transaction, per mint.
+fn fee_credits(transaction: &EncodedConfirmedTransactionWithStatusMeta, owner: &Pubkey) -> Vec<(Pubkey, u64)> {
+    let meta = match transaction.transaction.meta.as_ref() {
+        Some(meta) => meta,
+        None => return Vec::new(),
+    };
+    let owner = owner.to_string();
+    let owned = |balances: Option<&Vec<UiTransactionTokenBalance>>| -> HashMap<u8, (String, u64)> {
+        balances
+            .into_iter()
+            .flatten()
+            .filter(|balance| Option::<&String>::from(balance.owner.as_ref()) == Some(&owner))
+            .filter_map(|balance| {
+                let amount = balance.ui_token_amount.amount.parse().ok()?;
+                Some((balance.account_index, (balance.mint.clone(), amount)))
+            })
+            .collect()
+    };
+    let pre = owned(Option::from(meta.pre_token_balances.as_ref()));
+    let post = owned(Option::from(meta.post_token_balances.as_ref()));
+    let mut credits: Vec<(Pubkey, u64)> = post
+        .iter()
+        .filter_map(|(index, (mint, amount))| {
+            let before = pre.get(index).map_or(0, |(_, amount)| *amount);
+            let mint = Pubkey::from_str(mint).ok()?;
+            (*amount > before).then(|| (mint, amount - before))
+        })
+        .collect();
+    credits.sort();
+    credits
+}
+
+/// Weight and composition changes between consecutive `observations`, ordered by slot.
+/// Changes a single swap explains are left out: swaps come from transactions.
+pub fn observation_events(observations: &[SymmetryTokenSwap]) -> Vec<TimelineEvent> {
+    let mut observations: Vec<(u64, &SymmetryTokenSwap)> = observations
+        .iter()
+        .filter_map(|fund| Some((fund.last_update_slot()?, fund)))
+        .collect();
+    observations.sort_by_key(|(slot, _)| *slot);
+
+    let mut events = Vec::new();
+    for pair in observations.windows(2) {
+        let (_, before) = pair[0];
+        let (slot, after) = pair[1];
+        let (before_weights, before_amounts) = composition(before);
+        let (after_weights, after_amounts) = composition(after);
+
+        let mut changes: Vec<(Pubkey, u64, u64)> = before_weights
+            .keys()
+            .chain(after_weights.keys().filter(|mint| !before_weights.contains_key(mint)))
+            .map(|mint| {
+                (
+                    *mint,
+                    before_weights.get(mint).copied().unwrap_or_default(),
+                    after_weights.get(mint).copied().unwrap_or_default(),
+                )
+            })
+            .filter(|(_, old, new)| old != new)
+            .collect();
+        if !changes.is_empty() {
+            changes.sort();
+            events.push(TimelineEvent::WeightChange { slot, changes });
+        }
+        if let Some(FillEvent::Unattributed { deltas, .. }) = infer_fill(after.key(), Some(slot), &before_amounts, &after_amounts) {
+            events.push(TimelineEvent::CompositionChange {
+                slot,
+                deltas,
+                during_rebalance: before.rebalance_in_progress() || after.rebalance_in_progress(),
+            });
+        }
+    }
+    events
+}
+
+/// Target weight and amount of each of the fund's tokens, by mint.
+fn composition(fund: &SymmetryTokenSwap) -> (HashMap<Pubkey, u64>, HashMap<Pubkey, u64>) {
+    let fund_state = fund.fund_state();
+    let mut weights = HashMap::new();
+    let mut amounts = HashMap::new();
+    for i in 0..fund_state.num_tokens() {
+        let mint = fund.token_list().list[fund_state.current_comp_token[i] as usize].token_mint;
+        weights.insert(mint, fund_state.target_weight[i]);
+        amounts.insert(mint, fund_state.current_comp_amount[i]);
+    }
+    (weights, amounts)
+}
+
+#[test]
+fn test_transaction_events() {
+    use serde_json::json;
+    use solana_sdk::instruction::{AccountMeta, Instruction};
+    use solana_sdk::message::Message;
+    use solana_sdk::transaction::{Transaction, VersionedTransaction};
+    use solana_transaction_status::{EncodedTransaction, EncodedTransactionWithStatusMeta, TransactionBinaryEncoding, UiTransactionStatusMeta};
+
+    use crate::amms::account_builders::{quotable_fund, FixtureToken, FundStateBuilder};
+    use crate::amms::accounts::WEIGHT_MULTIPLIER;
+
+    let manager = Pubkey::new_unique();
+    let host = Pubkey::new_unique();
+    let tokens = [FixtureToken::new(6, 100_000_000), FixtureToken::new(9, 10_000_000_000)];
+    let fund_state = FundStateBuilder::new()
+        .with_manager(manager)
+        .with_host(host)
+        .with_token(0, 10_000_000_000, WEIGHT_MULTIPLIER / 2)
+        .with_token(1, 100_000_000_000, WEIGHT_MULTIPLIER / 2)
+        .with_weight_band(1_000, 5_000);
+    let fund = quotable_fund(Pubkey::new_unique(), &fund_state, &tokens);
+
+    const CLAIM_FEE_ID: u64 = 7;
+    const DEPOSIT_ID: u64 = 8;
+    let instruction = |signer: Pubkey, id: u64| Instruction::new_with_bytes(
+        SymmetryTokenSwap::SYMMETRY_PROGRAM_ADDRESS,
+        &id.to_le_bytes(),
+        vec![AccountMeta::new_readonly(signer, true), AccountMeta::new(fund.key(), false)],
+    );
+    let manager_account = Pubkey::new_unique();
+    let host_account = Pubkey::new_unique();
+    // `balances` are (token account, owner, mint, pre, post) rows.
+    let transaction = |instructions: &[Instruction], payer: Pubkey, balances: &[(Pubkey, Pubkey, Pubkey, u64, u64)]| {
+        let mut message = Message::new(instructions, Some(&payer));
+        for (account, ..) in balances {
+            message.account_keys.push(*account);
+        }
+        let token_balances = |post: bool| json!(balances.iter().map(|(account, owner, mint, pre_amount, post_amount)| json!({
+            "accountIndex": message.account_keys.iter().position(|key| key == account).unwrap(),
+            "mint": mint.to_string(),
+            "owner": owner.to_string(),
+            "uiTokenAmount": {
+                "uiAmount": null,
+                "decimals": 6,
+                "amount": (if post { post_amount } else { pre_amount }).to_string(),
+                "uiAmountString": "",
+            },
+        })).collect::<Vec<_>>());
+        let meta: UiTransactionStatusMeta = serde_json::from_value(json!({
+            "err": null,
+            "status": { "Ok": null },
+            "fee": 5_000,
+            "preBalances": [],
+            "postBalances": [],
+            "preTokenBalances": token_balances(false),
+            "postTokenBalances": token_balances(true),
+        })).unwrap();
+        let transaction = VersionedTransaction::from(Transaction::new_unsigned(message));
+        EncodedConfirmedTransactionWithStatusMeta {
+            slot: 250_000_000,
+            transaction: EncodedTransactionWithStatusMeta {
+                transaction: EncodedTransaction::Binary(
+                    base64::encode(bincode::serialize(&transaction).unwrap()),
+                    TransactionBinaryEncoding::Base64,
+                ),
+                meta: Some(meta),
+                version: None,
+            },
+            block_time: Some(1_700_000_000),
+        }
+    };
+    let signature = Signature::new_unique();
+    let mints = [tokens[0].mint, tokens[1].mint];
+
+    // A deposit by a user that pays the manager a fee, then a manager's fee claim.
+    let user = Pubkey::new_unique();
+    let deposit = transaction(
+        &[instruction(user, DEPOSIT_ID)],
+        user,
+        &[(manager_account, manager, mints[0], 1_000, 1_500), (Pubkey::new_unique(), user, mints[0], 9_000, 2_000)],
+    );
+    assert_eq!(fee_credits(&deposit, &manager), vec![(mints[0], 500)]);
+    assert_eq!(fee_credits(&deposit, &user), vec![]);
+    assert_eq!(fee_credits(&deposit, &host), vec![]);
+    assert_eq!(
+        transaction_events(&signature, &deposit, &fund, &mints, &[CLAIM_FEE_ID]).unwrap(),
+        vec![TimelineEvent::Instruction {
+            signature,
+            slot: 250_000_000,
+            block_time: Some(1_700_000_000),
+            instruction_id: DEPOSIT_ID,
+            signers: vec![user],
+            by_manager: false,
+        }]
+    );
+
+    let claims = transaction(
+        &[instruction(manager, CLAIM_FEE_ID), instruction(manager, CLAIM_FEE_ID)],
+        manager,
+        &[(manager_account, manager, mints[1], 0, 2_000), (host_account, host, mints[1], 10, 1_000)],
+    );
+    let claim = TimelineEvent::FeeClaim {
+        signature,
+        slot: 250_000_000,
+        block_time: Some(1_700_000_000),
+        recipient: manager,
+        credits: vec![(mints[1], 2_000)],
+    };
+    assert_eq!(
+        transaction_events(&signature, &claims, &fund, &mints, &[CLAIM_FEE_ID]).unwrap(),
+        vec![claim.clone(), claim]
+    );
+    // Without the claim-fee id the same instructions are plain instructions.
+    assert!(transaction_events(&signature, &claims, &fund, &mints, &[]).unwrap()
+        .iter()
+        .all(|event| matches!(event, TimelineEvent::Instruction { instruction_id: CLAIM_FEE_ID, by_manager: true, .. })));
+
+    // A claim crediting only the host is the host's.
+    let host_claim = transaction(&[instruction(host, CLAIM_FEE_ID)], host, &[(host_account, host, mints[1], 10, 1_000)]);
+    assert!(matches!(
+        transaction_events(&signature, &host_claim, &fund, &mints, &[CLAIM_FEE_ID]).unwrap()[..],
+        [TimelineEvent::FeeClaim { recipient, ref credits, .. }] if recipient == host && credits == &vec![(mints[1], 990)]
+    ));
+}
+
+#[test]
+fn test_timeline() {
+    use serde_json::json;
+    use solana_client::rpc_request::RpcRequest;
+    use solana_sdk::instruction::{AccountMeta, Instruction};
+    use solana_sdk::message::Message;
+    use solana_sdk::transaction::{Transaction, VersionedTransaction};
+    use solana_transaction_status::{EncodedTransaction, EncodedTransactionWithStatusMeta, TransactionBinaryEncoding};
+
+    use crate::amms::account_builders::{fixture_accounts, quotable_fund, FixtureToken, FundStateBuilder};
+    use crate::amms::accounts::WEIGHT_MULTIPLIER;
+
+    let tokens = [FixtureToken::new(6, 100_000_000), FixtureToken::new(9, 10_000_000_000)];
+    let mints = [tokens[0].mint, tokens[1].mint];
+    let key = Pubkey::new_unique();
+    // The fund as observed at `slot`, holding `amounts` at `weights`.
+    let observed = |slot: u64, amounts: [u64; 2], weights: [u64; 2]| {
+        let fund_state = FundStateBuilder::new()
+            .with_token(0, amounts[0], weights[0])
+            .with_token(1, amounts[1], weights[1])
+            .with_weight_band(1_000, 5_000);
+        let mut fund = quotable_fund(key, &fund_state, &tokens);
+        let account_map = fixture_accounts(key, &fund_state, &tokens);
+        let slots = fund.get_accounts_to_update().into_iter().map(|account| (account, slot)).collect();
+        fund.update_with_slots(&account_map, &slots).unwrap();
+        fund
+    };
+    let half = WEIGHT_MULTIPLIER / 2;
+    let at_100 = observed(100, [10_000_000_000, 100_000_000_000], [half, half]);
+    // A swap between the first two observations, a reweight and a deposit before the third.
+    let at_300 = observed(300, [10_100_000_000, 99_000_000_000], [half, half]);
+    let at_500 = observed(500, [10_200_000_000, 99_500_000_000], [half * 3 / 5, half * 7 / 5]);
+    let unobserved = quotable_fund(key, &FundStateBuilder::new(), &tokens);
+
+    // Out of order and with an observation lacking a slot, as a cache might return them.
+    let observations = [at_500.clone(), unobserved, at_100.clone(), at_300];
+    let events = observation_events(&observations);
+    assert_eq!(events, vec![
+        TimelineEvent::WeightChange { slot: 500, changes: {
+            let mut changes = vec![(mints[0], half, half * 3 / 5), (mints[1], half, half * 7 / 5)];
+            changes.sort();
+            changes
+        } },
+        TimelineEvent::CompositionChange {
+            slot: 500,
+            deltas: {
+                let mut deltas = vec![(mints[0], 100_000_000), (mints[1], 500_000_000)];
+                deltas.sort();
+                deltas
+            },
+            during_rebalance: false,
+        },
+    ]);
+
+    // One transaction at slot 400 sits between the observed changes once merged.
+    let signer = Pubkey::new_unique();
+    let manager_instruction = Instruction::new_with_bytes(
+        SymmetryTokenSwap::SYMMETRY_PROGRAM_ADDRESS,
+        &9u64.to_le_bytes(),
+        vec![AccountMeta::new_readonly(signer, true), AccountMeta::new(key, false)],
+    );
+    let message = Message::new(&[manager_instruction], Some(&signer));
+    let signature = Signature::new_unique();
+    let transaction = EncodedConfirmedTransactionWithStatusMeta {
+        slot: 400,
+        transaction: EncodedTransactionWithStatusMeta {
+            transaction: EncodedTransaction::Binary(
+                base64::encode(bincode::serialize(&VersionedTransaction::from(Transaction::new_unsigned(message))).unwrap()),
+                TransactionBinaryEncoding::Base64,
+            ),
+            meta: None,
+            version: None,
+        },
+        block_time: None,
+    };
+    let client = RpcClient::new_mock_with_mocks("succeeds".to_string(), [
+        (RpcRequest::GetSignaturesForAddress, json!([{ "signature": signature.to_string(), "slot": 400, "err": null, "memo": null, "blockTime": null }])),
+        (RpcRequest::GetTransaction, serde_json::to_value(&transaction).unwrap()),
+    ].into());
+    let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+    let events = runtime.block_on(timeline(&client, &at_500, 0..=1_000, &observations, &[])).unwrap();
+    assert_eq!(events.iter().map(TimelineEvent::slot).collect::<Vec<_>>(), vec![400, 500, 500]);
+    assert!(matches!(events[0], TimelineEvent::Instruction { instruction_id: 9, .. }));
+}
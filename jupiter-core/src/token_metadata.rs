@@ -0,0 +1,187 @@
+use anyhow::{ensure, Context, Result};
+use serde::Deserialize;
+use solana_sdk::pubkey::Pubkey;
+use std::{collections::HashMap, path::Path, str::FromStr};
+
+/// Entry of a Jupiter token-list JSON file.
+#[derive(Clone, Debug, Deserialize)]
+pub struct TokenInfo {
+    pub address: String,
+    pub symbol: String,
+    pub name: String,
+    #[serde(default)]
+    pub decimals: u8,
+    #[serde(rename = "logoURI", default)]
+    pub logo_uri: Option<String>,
+}
+
+/// Both the flat array (`/strict`, `/all`) and the legacy `{ "tokens": [...] }` shapes are accepted.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TokenListJson {
+    Flat(Vec<TokenInfo>),
+    Wrapped { tokens: Vec<TokenInfo> },
+}
+
+/// `Key::MetadataV1` tag of a Metaplex token metadata account.
+const METAPLEX_METADATA_V1_KEY: u8 = 4;
+
+/// Offline mint -> symbol/name/logo resolver backed by a Jupiter token list.
+#[derive(Clone, Debug, Default)]
+pub struct TokenMetadata {
+    tokens: HashMap<Pubkey, TokenInfo>,
+}
+
+impl TokenMetadata {
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let bytes = std::fs::read(path.as_ref())
+            .with_context(|| format!("failed to read token list {}", path.as_ref().display()))?;
+        Self::from_bytes(&bytes)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let list: TokenListJson = serde_json::from_slice(bytes).context("invalid token list json")?;
+        let tokens = match list {
+            TokenListJson::Flat(tokens) | TokenListJson::Wrapped { tokens } => tokens,
+        };
+        // Entries with a malformed address are skipped rather than failing the whole list
+        let tokens = tokens
+            .into_iter()
+            .filter_map(|token| Some((Pubkey::from_str(&token.address).ok()?, token)))
+            .collect();
+        Ok(Self { tokens })
+    }
+
+    /// Adds the name and symbol from a Metaplex token metadata account (as fetched over RPC),
+    /// returning its mint. The account's `uri` points at off-chain JSON, so no logo is set.
+    pub fn insert_metaplex_account(&mut self, data: &[u8]) -> Result<Pubkey> {
+        ensure!(
+            data.first() == Some(&METAPLEX_METADATA_V1_KEY),
+            "not a Metaplex metadata account"
+        );
+        // key (1) + update_authority (32), then the mint
+        let mint = data.get(33..65).context("metaplex metadata too short")?;
+        let mint = Pubkey::try_from(mint).unwrap();
+        let mut offset = 65;
+        let name = read_metaplex_string(data, &mut offset)?;
+        let symbol = read_metaplex_string(data, &mut offset)?;
+        self.tokens.insert(
+            mint,
+            TokenInfo {
+                address: mint.to_string(),
+                symbol,
+                name,
+                decimals: 0,
+                logo_uri: None,
+            },
+        );
+        Ok(mint)
+    }
+
+    pub fn len(&self) -> usize {
+        self.tokens.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    pub fn get(&self, mint: &Pubkey) -> Option<&TokenInfo> {
+        self.tokens.get(mint)
+    }
+
+    pub fn symbol(&self, mint: &Pubkey) -> Option<&str> {
+        self.get(mint).map(|token| token.symbol.as_str())
+    }
+
+    pub fn name(&self, mint: &Pubkey) -> Option<&str> {
+        self.get(mint).map(|token| token.name.as_str())
+    }
+
+    pub fn logo_uri(&self, mint: &Pubkey) -> Option<&str> {
+        self.get(mint).and_then(|token| token.logo_uri.as_deref())
+    }
+
+    /// Symbol if known, otherwise the base58 mint address.
+    pub fn display(&self, mint: &Pubkey) -> String {
+        self.symbol(mint)
+            .map(String::from)
+            .unwrap_or_else(|| mint.to_string())
+    }
+
+    /// Resolves a fund's mints (e.g. `Amm::get_reserve_mints()`), keeping the input order.
+    pub fn resolve_mints<'a>(&'a self, mints: &[Pubkey]) -> Vec<(Pubkey, Option<&'a TokenInfo>)> {
+        mints.iter().map(|mint| (*mint, self.get(mint))).collect()
+    }
+}
+
+/// Borsh string (u32 length prefix); Metaplex pads names and symbols with NULs.
+fn read_metaplex_string(data: &[u8], offset: &mut usize) -> Result<String> {
+    let len = data
+        .get(*offset..*offset + 4)
+        .context("metaplex metadata too short")?;
+    let len = u32::from_le_bytes(len.try_into().unwrap()) as usize;
+    let bytes = data
+        .get(*offset + 4..*offset + 4 + len)
+        .context("metaplex metadata too short")?;
+    *offset += 4 + len;
+    let value = std::str::from_utf8(bytes).context("invalid utf-8 in metaplex metadata")?;
+    Ok(value.trim_end_matches('\0').to_string())
+}
+
+#[test]
+fn test_token_list_decode() {
+    let usdc = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+    let flat = format!(
+        r#"[{{"address":"{usdc}","symbol":"USDC","name":"USD Coin","decimals":6,"logoURI":"https://example.com/usdc.png"}},
+            {{"address":"not-a-mint","symbol":"BAD","name":"Bad"}}]"#
+    );
+    let metadata = TokenMetadata::from_bytes(flat.as_bytes()).unwrap();
+    let mint = Pubkey::from_str(usdc).unwrap();
+    assert_eq!(metadata.len(), 1);
+    assert_eq!(metadata.symbol(&mint), Some("USDC"));
+    assert_eq!(metadata.get(&mint).unwrap().decimals, 6);
+    assert_eq!(
+        metadata.logo_uri(&mint),
+        Some("https://example.com/usdc.png")
+    );
+
+    let wrapped =
+        format!(r#"{{"tokens":[{{"address":"{usdc}","symbol":"USDC","name":"USD Coin"}}]}}"#);
+    let metadata = TokenMetadata::from_bytes(wrapped.as_bytes()).unwrap();
+    assert_eq!(metadata.name(&mint), Some("USD Coin"));
+    assert_eq!(
+        metadata.display(&Pubkey::default()),
+        Pubkey::default().to_string()
+    );
+}
+
+#[test]
+fn test_metaplex_metadata_decode() {
+    fn padded(value: &str, max: usize) -> Vec<u8> {
+        let mut bytes = (max as u32).to_le_bytes().to_vec();
+        bytes.extend_from_slice(value.as_bytes());
+        bytes.resize(4 + max, 0);
+        bytes
+    }
+
+    // MetadataV1 as laid out on chain: key, update_authority, mint, then the NUL-padded
+    // name (32), symbol (10) and uri (200) strings.
+    let mint = Pubkey::new_unique();
+    let mut data = vec![METAPLEX_METADATA_V1_KEY];
+    data.extend_from_slice(Pubkey::new_unique().as_ref());
+    data.extend_from_slice(mint.as_ref());
+    data.extend(padded("Symmetry Fund", 32));
+    data.extend(padded("SYMF", 10));
+    data.extend(padded("https://example.com/symf.json", 200));
+
+    let mut metadata = TokenMetadata::default();
+    assert_eq!(metadata.insert_metaplex_account(&data).unwrap(), mint);
+    assert_eq!(metadata.name(&mint), Some("Symmetry Fund"));
+    assert_eq!(metadata.symbol(&mint), Some("SYMF"));
+    assert_eq!(metadata.logo_uri(&mint), None);
+
+    assert!(metadata.insert_metaplex_account(&data[..80]).is_err());
+    data[0] = 0;
+    assert!(metadata.insert_metaplex_account(&data).is_err());
+}
@@ -0,0 +1,311 @@
+use anyhow::{Error, Result};
+use jupiter_amm_interface::{QuoteMintToReferrer, SwapParams};
+#[cfg(feature = "rpc")]
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::address_lookup_table_account::AddressLookupTableAccount;
+use solana_sdk::compute_budget::ComputeBudgetInstruction;
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::message::{v0, VersionedMessage};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::signer::signers::Signers;
+use solana_sdk::{system_instruction, system_program};
+use solana_sdk::transaction::VersionedTransaction;
+use serde::{Deserialize, Serialize};
+use std::convert::TryInto;
+
+use crate::amms::fee_accounts::FeeAccount;
+use crate::amms::symmetry_token_swap::SymmetryTokenSwap;
+use crate::constants::WSOL_MINT;
+pub use crate::pda::associated_token_address;
+
+/// Bytes before the address list in an address lookup table account.
+const LOOKUP_TABLE_META_SIZE: usize = 56;
+
+/// Compute units requested for a swap transaction by default. Funds with many oracle
+/// accounts need more than the 200k default.
+pub const DEFAULT_SWAP_COMPUTE_UNIT_LIMIT: u32 = 400_000;
+
+/// Percentile of recent prioritization fees used when none is specified.
+pub const DEFAULT_PRIORITY_FEE_PERCENTILE: u8 = 75;
+
+/// `percentile` (0-100) of the per-slot minimum prioritization fees the node saw recently for
+/// transactions locking all of `writable_accounts`, in micro-lamports per compute unit.
+#[cfg(feature = "rpc")]
+pub fn estimate_compute_unit_price(client: &RpcClient, writable_accounts: &[Pubkey], percentile: u8) -> Result<u64> {
+    let mut fees: Vec<u64> = client
+        .get_recent_prioritization_fees(writable_accounts)?
+        .into_iter()
+        .map(|fee| fee.prioritization_fee)
+        .collect();
+    if fees.is_empty() {
+        return Ok(0);
+    }
+    fees.sort_unstable();
+    let index = (fees.len() - 1) * percentile.min(100) as usize / 100;
+    Ok(fees[index])
+}
+
+/// A swap of `in_amount` of `input_mint` into `output_mint` from `user`'s ATAs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SwapRequest {
+    pub user: Pubkey,
+    pub input_mint: Pubkey,
+    pub output_mint: Pubkey,
+    pub in_amount: u64,
+    pub minimum_amount_out: u64,
+}
+
+/// Associated token program `CreateIdempotent`: creates `owner`'s ATA for `mint` unless it exists.
+pub fn create_associated_token_account_idempotent(payer: &Pubkey, owner: &Pubkey, mint: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: SymmetryTokenSwap::ASSOCIATED_TOKEN_PROGRAM_ADDRESS,
+        accounts: vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(associated_token_address(owner, mint), false),
+            AccountMeta::new_readonly(*owner, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(SymmetryTokenSwap::SPL_TOKEN_PROGRAM_ADDRESS, false),
+        ],
+        data: vec![1],
+    }
+}
+
+/// Funds `owner`'s WSOL ATA with `lamports` of native SOL: creates the ATA unless it exists,
+/// transfers the lamports from `owner` and syncs the token balance.
+pub fn wrap_sol_instructions(payer: &Pubkey, owner: &Pubkey, lamports: u64) -> Result<Vec<Instruction>> {
+    let wsol_account = associated_token_address(owner, &WSOL_MINT);
+    Ok(vec![
+        create_associated_token_account_idempotent(payer, owner, &WSOL_MINT),
+        system_instruction::transfer(owner, &wsol_account, lamports),
+        spl_token::instruction::sync_native(&SymmetryTokenSwap::SPL_TOKEN_PROGRAM_ADDRESS, &wsol_account)?,
+    ])
+}
+
+/// Closes `owner`'s WSOL ATA, returning its whole balance and rent to `owner` as native SOL.
+pub fn unwrap_sol_instruction(owner: &Pubkey) -> Result<Instruction> {
+    Ok(spl_token::instruction::close_account(
+        &SymmetryTokenSwap::SPL_TOKEN_PROGRAM_ADDRESS,
+        &associated_token_address(owner, &WSOL_MINT),
+        owner,
+        owner,
+        &[],
+    )?)
+}
+
+/// Reads the addresses of an address lookup table account.
+pub fn parse_lookup_table(key: Pubkey, account_data: &[u8]) -> Result<AddressLookupTableAccount> {
+    if account_data.len() < LOOKUP_TABLE_META_SIZE || !(account_data.len() - LOOKUP_TABLE_META_SIZE).is_multiple_of(32) {
+        return Err(Error::msg("Invalid address lookup table account"));
+    }
+    let addresses = account_data[LOOKUP_TABLE_META_SIZE..]
+        .chunks_exact(32)
+        .map(|address| Pubkey::new_from_array(address.try_into().unwrap()))
+        .collect();
+    Ok(AddressLookupTableAccount { key, addresses })
+}
+
+/// A swap transaction for a wallet to sign, shaped like the response of Jupiter's swap API
+/// so wallet-adapter frontends can deserialize, sign and send it unchanged.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnsignedSwapTransaction {
+    /// Base64 of the bincode-serialized `VersionedTransaction`, with blank signatures.
+    pub swap_transaction: String,
+    pub last_valid_block_height: u64,
+    /// Accounts that must sign, in signature order.
+    pub signers: Vec<String>,
+}
+
+/// Base64 of the bincode wire format, as accepted by `sendTransaction`.
+pub fn encode_transaction_base64(transaction: &VersionedTransaction) -> Result<String> {
+    Ok(base64::encode(bincode::serialize(transaction)?))
+}
+
+/// Assembles a v0 swap transaction against one fund: compute budget, SOL wrapping,
+/// destination and fee ATA creation, the swap itself and SOL unwrapping.
+pub struct SwapTransactionBuilder<'a> {
+    fund: &'a SymmetryTokenSwap,
+    payer: Pubkey,
+    compute_unit_limit: u32,
+    compute_unit_price: Option<u64>,
+    lookup_tables: Vec<AddressLookupTableAccount>,
+    missing_fee_accounts: Vec<FeeAccount>,
+    native_sol: bool,
+    referrers: QuoteMintToReferrer,
+}
+
+impl<'a> SwapTransactionBuilder<'a> {
+    pub fn new(fund: &'a SymmetryTokenSwap, payer: Pubkey) -> Self {
+        Self {
+            fund,
+            payer,
+            compute_unit_limit: DEFAULT_SWAP_COMPUTE_UNIT_LIMIT,
+            compute_unit_price: None,
+            lookup_tables: Vec::new(),
+            missing_fee_accounts: Vec::new(),
+            native_sol: false,
+            referrers: QuoteMintToReferrer::new(),
+        }
+    }
+
+    pub fn payer(&self) -> Pubkey {
+        self.payer
+    }
+
+    pub fn fund(&self) -> &'a SymmetryTokenSwap {
+        self.fund
+    }
+
+    pub fn with_compute_unit_limit(mut self, units: u32) -> Self {
+        self.compute_unit_limit = units;
+        self
+    }
+
+    /// Priority fee, in micro-lamports per compute unit.
+    pub fn with_compute_unit_price(mut self, micro_lamports: u64) -> Self {
+        self.compute_unit_price = Some(micro_lamports);
+        self
+    }
+
+    /// Sets the compute unit price from recent prioritization fees on the accounts the
+    /// swap writes, so the priority fee tracks congestion on this fund.
+    #[cfg(feature = "rpc")]
+    pub fn with_estimated_compute_unit_price(self, client: &RpcClient, swap: &SwapRequest, percentile: u8) -> Result<Self> {
+        let writable_accounts = self.writable_accounts(swap)?;
+        let micro_lamports = estimate_compute_unit_price(client, &writable_accounts, percentile)?;
+        Ok(self.with_compute_unit_price(micro_lamports))
+    }
+
+    /// Accounts the swap instruction write-locks.
+    pub fn writable_accounts(&self, swap: &SwapRequest) -> Result<Vec<Pubkey>> {
+        Ok(self.swap_instruction(swap)?
+            .accounts
+            .into_iter()
+            .filter(|meta| meta.is_writable)
+            .map(|meta| meta.pubkey)
+            .collect())
+    }
+
+    pub fn with_lookup_table(mut self, lookup_table: AddressLookupTableAccount) -> Self {
+        self.lookup_tables.push(lookup_table);
+        self
+    }
+
+    /// Creates `missing_fee_accounts` ahead of the swap, at the payer's expense. See
+    /// `SymmetryTokenSwap::missing_swap_fee_accounts`.
+    pub fn with_missing_fee_accounts(mut self, missing_fee_accounts: Vec<FeeAccount>) -> Self {
+        self.missing_fee_accounts = missing_fee_accounts;
+        self
+    }
+
+    /// Swaps from and to native SOL instead of WSOL: an input of WSOL is wrapped from the
+    /// user's lamports before the swap, and the user's WSOL ATA is closed after it, which
+    /// also unwraps any WSOL the user held there before.
+    pub fn with_native_sol(mut self) -> Self {
+        self.native_sol = true;
+        self
+    }
+
+    /// Referrer token account for swaps into `mint`. It receives the host fee when the fund's
+    /// `AmmConfig::route_host_fee_to_referrer` is set.
+    pub fn with_referrer_token_account(mut self, mint: Pubkey, token_account: Pubkey) -> Self {
+        self.referrers.insert(mint, token_account);
+        self
+    }
+
+    pub fn instructions(&self, swap: &SwapRequest) -> Result<Vec<Instruction>> {
+        let mut instructions = vec![ComputeBudgetInstruction::set_compute_unit_limit(self.compute_unit_limit)];
+        if let Some(micro_lamports) = self.compute_unit_price {
+            instructions.push(ComputeBudgetInstruction::set_compute_unit_price(micro_lamports));
+        }
+        let wrap_input = self.native_sol && swap.input_mint == WSOL_MINT;
+        if wrap_input {
+            instructions.extend(wrap_sol_instructions(&self.payer, &swap.user, swap.in_amount)?);
+        }
+        instructions.push(create_associated_token_account_idempotent(&self.payer, &swap.user, &swap.output_mint));
+        instructions.extend(self.missing_fee_accounts.iter().map(|account| account.create_instruction(&self.payer)));
+        instructions.push(self.swap_instruction(swap)?);
+        if wrap_input || (self.native_sol && swap.output_mint == WSOL_MINT) {
+            instructions.push(unwrap_sol_instruction(&swap.user)?);
+        }
+        Ok(instructions)
+    }
+
+    fn swap_instruction(&self, swap: &SwapRequest) -> Result<Instruction> {
+        self.fund.build_swap_instruction(
+            &SwapParams {
+                in_amount: swap.in_amount,
+                source_mint: swap.input_mint,
+                destination_mint: swap.output_mint,
+                source_token_account: associated_token_address(&swap.user, &swap.input_mint),
+                destination_token_account: associated_token_address(&swap.user, &swap.output_mint),
+                token_transfer_authority: swap.user,
+                open_order_address: None,
+                quote_mint_to_referrer: Some(&self.referrers),
+                jupiter_program_id: &SymmetryTokenSwap::SYMMETRY_PROGRAM_ADDRESS,
+            },
+            swap.minimum_amount_out,
+        )
+    }
+
+    pub fn message(&self, swap: &SwapRequest, recent_blockhash: Hash) -> Result<VersionedMessage> {
+        let message = v0::Message::try_compile(
+            &self.payer,
+            &self.instructions(swap)?,
+            &self.lookup_tables,
+            recent_blockhash,
+        )?;
+        Ok(VersionedMessage::V0(message))
+    }
+
+    /// Builds and signs the transaction. `signers` must cover the payer and the swap user.
+    pub fn build<S: Signers>(&self, swap: &SwapRequest, recent_blockhash: Hash, signers: &S) -> Result<VersionedTransaction> {
+        Ok(VersionedTransaction::try_new(self.message(swap, recent_blockhash)?, signers)?)
+    }
+
+    /// The transaction with blank signatures, for signing elsewhere.
+    pub fn unsigned(&self, swap: &SwapRequest, recent_blockhash: Hash) -> Result<VersionedTransaction> {
+        let message = self.message(swap, recent_blockhash)?;
+        let num_signers = message.header().num_required_signatures as usize;
+        Ok(VersionedTransaction {
+            signatures: vec![Signature::default(); num_signers],
+            message,
+        })
+    }
+
+    pub fn export_unsigned(
+        &self,
+        swap: &SwapRequest,
+        recent_blockhash: Hash,
+        last_valid_block_height: u64,
+    ) -> Result<UnsignedSwapTransaction> {
+        let transaction = self.unsigned(swap, recent_blockhash)?;
+        let signers = transaction.message.static_account_keys()[..transaction.signatures.len()]
+            .iter()
+            .map(|key| key.to_string())
+            .collect();
+        Ok(UnsignedSwapTransaction {
+            swap_transaction: encode_transaction_base64(&transaction)?,
+            last_valid_block_height,
+            signers,
+        })
+    }
+}
+
+#[test]
+fn test_wrap_sol_instructions() {
+    let payer = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let wsol_account = associated_token_address(&owner, &WSOL_MINT);
+    let instructions = wrap_sol_instructions(&payer, &owner, 1_000_000_000).unwrap();
+    assert_eq!(instructions[0], create_associated_token_account_idempotent(&payer, &owner, &WSOL_MINT));
+    assert_eq!(instructions[1], system_instruction::transfer(&owner, &wsol_account, 1_000_000_000));
+    assert_eq!(instructions[2].accounts[0].pubkey, wsol_account);
+
+    let close = unwrap_sol_instruction(&owner).unwrap();
+    assert_eq!(close.accounts[0].pubkey, wsol_account);
+    assert_eq!(close.accounts[1].pubkey, owner);
+}
@@ -0,0 +1,365 @@
+//! Cross-implementation quote test vectors.
+//!
+//! A vector file is JSON holding raw account bytes, quote params and the quote another
+//! implementation (Symmetry's TypeScript SDK, or the program itself via simulation)
+//! produced for them. `run_vector` replays the accounts through `SymmetryTokenSwap` and
+//! reports where the Rust quote diverges. Pubkeys are base58, amounts decimal strings and
+//! account data base64, as in the `dto` module.
+//!
+//! `generate_vectors` goes the other way: it quotes a matrix of pairs, amounts and modes
+//! from a `RegistrySnapshot`, producing ground truth for other implementations.
+
+use anyhow::{Error, Result};
+use jupiter_amm_interface::{AccountMap, Amm, KeyedAccount, QuoteParams};
+use serde::{Deserialize, Serialize};
+use solana_sdk::account::Account;
+use solana_sdk::pubkey::Pubkey;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::amms::accounts::ONE_USD;
+use crate::amms::liquidity::LiquidityPolicy;
+use crate::amms::symmetry_token_swap::SymmetryTokenSwap;
+use crate::snapshot::RegistrySnapshot;
+
+pub const VECTOR_FORMAT_VERSION: u16 = 1;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VectorFile {
+    pub version: u16,
+    /// Free-form origin of the vectors, e.g. "symmetry-ts-sdk 1.2.0".
+    #[serde(default)]
+    pub generator: String,
+    /// Accounts shared by every vector in the file, added to each vector's own `accounts`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub accounts: Vec<VectorAccount>,
+    pub vectors: Vec<QuoteVector>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VectorAccount {
+    pub pubkey: String,
+    #[serde(default)]
+    pub owner: Option<String>,
+    /// Base64 account data.
+    pub data: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VectorQuoteParams {
+    pub input_mint: String,
+    pub output_mint: String,
+    pub in_amount: String,
+}
+
+/// Quote the generator produced. `error` set means the generator rejected the quote.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExpectedQuote {
+    #[serde(default)]
+    pub out_amount: Option<String>,
+    #[serde(default)]
+    pub fee_amount: Option<String>,
+    #[serde(default)]
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub not_enough_liquidity: Option<bool>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuoteVector {
+    pub name: String,
+    pub fund: String,
+    /// Fund state, token list, curve data and every oracle account of the fund, unless the
+    /// file lists them in its shared `accounts`.
+    #[serde(default)]
+    pub accounts: Vec<VectorAccount>,
+    pub params: VectorQuoteParams,
+    #[serde(default)]
+    pub liquidity_policy: LiquidityPolicy,
+    pub expected: ExpectedQuote,
+    /// Accepted absolute difference of `out_amount` and `fee_amount`, in raw token units.
+    #[serde(default)]
+    pub tolerance: u64,
+}
+
+/// Result of replaying one vector.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VectorOutcome {
+    Match,
+    Mismatch {
+        field: &'static str,
+        expected: String,
+        actual: String,
+    },
+}
+
+impl VectorFile {
+    pub fn load(path: &Path) -> Result<VectorFile> {
+        let file: VectorFile = serde_json::from_slice(&std::fs::read(path)?)?;
+        if file.version != VECTOR_FORMAT_VERSION {
+            return Err(Error::msg(format!("Unsupported vector format version {}", file.version)));
+        }
+        Ok(file)
+    }
+}
+
+fn parse_pubkey(value: &str) -> Result<Pubkey> {
+    Pubkey::from_str(value).map_err(|e| Error::msg(format!("Invalid pubkey {value}: {e}")))
+}
+
+fn within(expected: &str, actual: u64, tolerance: u64) -> Result<bool> {
+    Ok(expected.parse::<u64>()?.abs_diff(actual) <= tolerance)
+}
+
+impl VectorAccount {
+    pub fn from_account(key: &Pubkey, account: &Account) -> VectorAccount {
+        VectorAccount {
+            pubkey: key.to_string(),
+            owner: Some(account.owner.to_string()),
+            data: base64::encode(&account.data),
+        }
+    }
+
+    /// The account, owned by the Symmetry program unless `owner` says otherwise.
+    pub fn to_account(&self) -> Result<(Pubkey, Account)> {
+        let owner = match &self.owner {
+            Some(owner) => parse_pubkey(owner)?,
+            None => SymmetryTokenSwap::SYMMETRY_PROGRAM_ADDRESS,
+        };
+        Ok((parse_pubkey(&self.pubkey)?, Account {
+            lamports: 0,
+            data: base64::decode(&self.data)?,
+            owner,
+            executable: false,
+            rent_epoch: 0,
+        }))
+    }
+}
+
+impl QuoteVector {
+    fn account_map(&self, shared_accounts: &[VectorAccount]) -> Result<AccountMap> {
+        shared_accounts.iter().chain(&self.accounts).map(VectorAccount::to_account).collect()
+    }
+
+    /// Builds the fund from the vector's accounts and quotes its params.
+    pub fn run(&self) -> Result<VectorOutcome> {
+        self.run_with(&[])
+    }
+
+    /// `run()` with the shared accounts of the vector's file.
+    pub fn run_with(&self, shared_accounts: &[VectorAccount]) -> Result<VectorOutcome> {
+        let account_map = self.account_map(shared_accounts)?;
+        let keyed_account = |key: Pubkey| -> Result<KeyedAccount> {
+            Ok(KeyedAccount {
+                key,
+                account: account_map.get(&key)
+                    .ok_or_else(|| Error::msg(format!("Vector is missing account {key}")))?
+                    .clone(),
+                params: None,
+            })
+        };
+        let mut amm = SymmetryTokenSwap::from_keyed_account(
+            &keyed_account(parse_pubkey(&self.fund)?)?,
+            &keyed_account(SymmetryTokenSwap::TOKEN_LIST_ADDRESS)?,
+        )?.with_liquidity_policy(self.liquidity_policy);
+        amm.update(&account_map)?;
+
+        let quote = amm.quote(&QuoteParams {
+            in_amount: self.params.in_amount.parse()?,
+            input_mint: parse_pubkey(&self.params.input_mint)?,
+            output_mint: parse_pubkey(&self.params.output_mint)?,
+        });
+        let quote = match (quote, &self.expected.error) {
+            (Ok(quote), None) => quote,
+            (Err(_), Some(_)) => return Ok(VectorOutcome::Match),
+            (Ok(quote), Some(error)) => return Ok(VectorOutcome::Mismatch {
+                field: "error",
+                expected: error.clone(),
+                actual: format!("quote with out amount {}", quote.out_amount),
+            }),
+            (Err(e), None) => return Ok(VectorOutcome::Mismatch {
+                field: "error",
+                expected: "a quote".to_string(),
+                actual: e.to_string(),
+            }),
+        };
+        if let Some(out_amount) = &self.expected.out_amount {
+            if !within(out_amount, quote.out_amount, self.tolerance)? {
+                return Ok(VectorOutcome::Mismatch {
+                    field: "outAmount",
+                    expected: out_amount.clone(),
+                    actual: quote.out_amount.to_string(),
+                });
+            }
+        }
+        if let Some(fee_amount) = &self.expected.fee_amount {
+            if !within(fee_amount, quote.fee_amount, self.tolerance)? {
+                return Ok(VectorOutcome::Mismatch {
+                    field: "feeAmount",
+                    expected: fee_amount.clone(),
+                    actual: quote.fee_amount.to_string(),
+                });
+            }
+        }
+        if let Some(not_enough_liquidity) = self.expected.not_enough_liquidity {
+            if not_enough_liquidity != quote.not_enough_liquidity {
+                return Ok(VectorOutcome::Mismatch {
+                    field: "notEnoughLiquidity",
+                    expected: not_enough_liquidity.to_string(),
+                    actual: quote.not_enough_liquidity.to_string(),
+                });
+            }
+        }
+        Ok(VectorOutcome::Match)
+    }
+}
+
+/// Runs every `*.json` vector file in `dir`, returning the name and outcome of each vector.
+/// Vectors that can't be replayed (missing accounts, bad data) are errors.
+pub fn run_vectors_dir(dir: &Path) -> Result<Vec<(String, VectorOutcome)>> {
+    let mut paths: Vec<_> = std::fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::io::Result<_>>()?;
+    paths.retain(|path| path.extension().is_some_and(|extension| extension == "json"));
+    paths.sort();
+
+    let mut outcomes = Vec::new();
+    for path in paths {
+        let file = VectorFile::load(&path)?;
+        for vector in file.vectors {
+            let outcome = vector.run_with(&file.accounts)
+                .map_err(|e| Error::msg(format!("{}: {}: {e}", path.display(), vector.name)))?;
+            outcomes.push((vector.name, outcome));
+        }
+    }
+    Ok(outcomes)
+}
+
+/// Quotes generated by `generate_vectors`: every ordered pair of each fund's tokens, at each
+/// amount, under each liquidity policy.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VectorMatrix {
+    /// In amounts as USD values (whole dollars), converted to the input token at its
+    /// average oracle price. One raw unit is always quoted as well.
+    pub usd_amounts: Vec<u64>,
+    pub liquidity_policies: Vec<LiquidityPolicy>,
+}
+
+impl Default for VectorMatrix {
+    fn default() -> Self {
+        Self {
+            usd_amounts: vec![1, 100, 10_000, 1_000_000, 100_000_000],
+            liquidity_policies: vec![LiquidityPolicy::Flag, LiquidityPolicy::Reject],
+        }
+    }
+}
+
+fn expected_quote(fund: &SymmetryTokenSwap, params: &QuoteParams) -> ExpectedQuote {
+    match fund.quote(params) {
+        Ok(quote) => ExpectedQuote {
+            out_amount: Some(quote.out_amount.to_string()),
+            fee_amount: Some(quote.fee_amount.to_string()),
+            error: None,
+            not_enough_liquidity: Some(quote.not_enough_liquidity),
+        },
+        Err(e) => ExpectedQuote {
+            out_amount: None,
+            fee_amount: None,
+            error: Some(e.to_string()),
+            not_enough_liquidity: None,
+        },
+    }
+}
+
+/// Quotes `matrix` against every fund in `snapshot`. The snapshot's accounts go in the
+/// file's shared `accounts`, so vectors only carry their params and expected quote.
+pub fn generate_vectors(snapshot: &RegistrySnapshot, matrix: &VectorMatrix) -> Result<VectorFile> {
+    let mut account_map = snapshot.accounts.clone();
+    account_map.insert(SymmetryTokenSwap::TOKEN_LIST_ADDRESS, snapshot.token_list.clone());
+    account_map.extend(snapshot.funds.iter().cloned());
+
+    let mut vectors = Vec::new();
+    for (key, account) in &snapshot.funds {
+        let mut fund = SymmetryTokenSwap::from_keyed_account(
+            &KeyedAccount { key: *key, account: account.clone(), params: None },
+            &KeyedAccount { key: SymmetryTokenSwap::TOKEN_LIST_ADDRESS, account: snapshot.token_list.clone(), params: None },
+        )?;
+        fund.update(&account_map)
+            .map_err(|e| Error::msg(format!("Fund {key}: {e}")))?;
+
+        let tokens: Vec<_> = fund.fund_state().token_ids()
+            .iter()
+            .map(|&token_id| fund.token_list().list[token_id as usize])
+            .collect();
+        for &liquidity_policy in &matrix.liquidity_policies {
+            let fund = fund.clone().with_liquidity_policy(liquidity_policy);
+            for input in &tokens {
+                for output in tokens.iter().filter(|output| output.token_mint != input.token_mint) {
+                    let mut amounts: Vec<u64> = matrix.usd_amounts.iter()
+                        .map(|usd| SymmetryTokenSwap::usd_value_to_amount(
+                            usd.saturating_mul(ONE_USD),
+                            input.decimals,
+                            input.oracle_price.avg_price,
+                        ))
+                        .collect();
+                    amounts.push(1);
+                    amounts.sort_unstable();
+                    amounts.dedup();
+                    for in_amount in amounts {
+                        let params = QuoteParams {
+                            in_amount,
+                            input_mint: input.token_mint,
+                            output_mint: output.token_mint,
+                        };
+                        vectors.push(QuoteVector {
+                            name: format!("{key}:{}->{}:{in_amount}:{liquidity_policy:?}", input.token_mint, output.token_mint),
+                            fund: key.to_string(),
+                            accounts: Vec::new(),
+                            params: VectorQuoteParams {
+                                input_mint: input.token_mint.to_string(),
+                                output_mint: output.token_mint.to_string(),
+                                in_amount: in_amount.to_string(),
+                            },
+                            liquidity_policy,
+                            expected: expected_quote(&fund, &params),
+                            tolerance: 0,
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    let mut accounts: Vec<_> = account_map.iter()
+        .map(|(key, account)| VectorAccount::from_account(key, account))
+        .collect();
+    accounts.sort_by(|a, b| a.pubkey.cmp(&b.pubkey));
+    Ok(VectorFile {
+        version: VECTOR_FORMAT_VERSION,
+        generator: format!("symmetry-rust-sdk {} (slot {})", env!("CARGO_PKG_VERSION"), snapshot.slot),
+        accounts,
+        vectors,
+    })
+}
+
+/// Replays the vectors in `$SYMMETRY_VECTORS_DIR`, or `tests/vectors` when unset. Nothing
+/// runs if the directory doesn't exist.
+#[test]
+fn test_quote_vectors() {
+    let dir = std::env::var("SYMMETRY_VECTORS_DIR")
+        .map(std::path::PathBuf::from)
+        .unwrap_or_else(|_| Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/vectors"));
+    if !dir.is_dir() {
+        return;
+    }
+    let mismatches: Vec<_> = run_vectors_dir(&dir).unwrap()
+        .into_iter()
+        .filter(|(_, outcome)| *outcome != VectorOutcome::Match)
+        .collect();
+    assert!(mismatches.is_empty(), "{mismatches:#?}");
+}
@@ -0,0 +1,157 @@
+//! Quoting from raw account bytes for browser apps, built with the `wasm` feature (and
+//! without the default `rpc` feature when targeting `wasm32-unknown-unknown`). The caller
+//! fetches the accounts `accountsToUpdate()` names and passes their data in.
+
+use jupiter_amm_interface::{AccountMap, Amm, KeyedAccount, QuoteParams};
+use solana_sdk::{account::Account, clock::Clock, pubkey::Pubkey};
+use std::str::FromStr;
+use wasm_bindgen::prelude::*;
+
+use crate::amms::symmetry_token_swap::SymmetryTokenSwap;
+
+fn parse_pubkey(key: &str) -> Result<Pubkey, JsError> {
+    Pubkey::from_str(key).map_err(|e| JsError::new(&format!("Invalid pubkey {key}: {e}")))
+}
+
+fn js_error(error: anyhow::Error) -> JsError {
+    JsError::new(&error.to_string())
+}
+
+/// A fund and the raw accounts it is quoted from.
+#[wasm_bindgen]
+pub struct WasmFund {
+    fund: SymmetryTokenSwap,
+    accounts: AccountMap,
+}
+
+#[wasm_bindgen]
+impl WasmFund {
+    #[wasm_bindgen(constructor)]
+    pub fn new(fund: &str, fund_state: &[u8], token_list: &[u8]) -> Result<WasmFund, JsError> {
+        let keyed_account = |key: Pubkey, data: &[u8]| KeyedAccount {
+            key,
+            account: Account { data: data.to_vec(), ..Account::default() },
+            params: None,
+        };
+        let fund = SymmetryTokenSwap::from_keyed_account(
+            &keyed_account(parse_pubkey(fund)?, fund_state),
+            &keyed_account(SymmetryTokenSwap::TOKEN_LIST_ADDRESS, token_list),
+        ).map_err(js_error)?;
+        Ok(WasmFund { fund, accounts: AccountMap::new() })
+    }
+
+    /// Base58 addresses of the accounts `update` reads.
+    #[wasm_bindgen(js_name = accountsToUpdate)]
+    pub fn accounts_to_update(&self) -> Box<[JsValue]> {
+        self.fund
+            .get_accounts_to_update()
+            .iter()
+            .map(|key| JsValue::from_str(&key.to_string()))
+            .collect()
+    }
+
+    /// Stores the data of account `key` for the next `update`.
+    #[wasm_bindgen(js_name = setAccount)]
+    pub fn set_account(&mut self, key: &str, data: &[u8]) -> Result<(), JsError> {
+        let account = Account { data: data.to_vec(), ..Account::default() };
+        self.accounts.insert(parse_pubkey(key)?, account);
+        Ok(())
+    }
+
+    /// Applies the stored accounts, judging oracle staleness at `slot` and `unix_timestamp`.
+    pub fn update(&mut self, slot: u64, unix_timestamp: i64) -> Result<(), JsError> {
+        let clock = Clock { slot, unix_timestamp, ..Clock::default() };
+        self.fund.update_with_clock(&self.accounts, clock).map_err(js_error)
+    }
+
+    pub fn quote(&self, input_mint: &str, output_mint: &str, in_amount: u64) -> Result<WasmQuote, JsError> {
+        let quote = self.fund.quote(&QuoteParams {
+            in_amount,
+            input_mint: parse_pubkey(input_mint)?,
+            output_mint: parse_pubkey(output_mint)?,
+        }).map_err(js_error)?;
+        Ok(WasmQuote {
+            in_amount: quote.in_amount,
+            out_amount: quote.out_amount,
+            fee_amount: quote.fee_amount,
+            not_enough_liquidity: quote.not_enough_liquidity,
+        })
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Copy)]
+pub struct WasmQuote {
+    #[wasm_bindgen(js_name = inAmount)]
+    pub in_amount: u64,
+    #[wasm_bindgen(js_name = outAmount)]
+    pub out_amount: u64,
+    /// Fee in the output token.
+    #[wasm_bindgen(js_name = feeAmount)]
+    pub fee_amount: u64,
+    #[wasm_bindgen(js_name = notEnoughLiquidity)]
+    pub not_enough_liquidity: bool,
+}
+
+#[test]
+fn test_wasm_fund_matches_fixture() {
+    use crate::amms::account_builders::{CurveDataBuilder, FundStateBuilder, OracleAccountBuilder, TokenListBuilder};
+    use crate::amms::accounts::{TokenSettings, WEIGHT_MULTIPLIER};
+    use crate::fixture::AmmFixture;
+
+    // `JsValue` and `JsError` only work on wasm32, so only the paths that don't build
+    // them run natively.
+    let usdc = Pubkey::new_unique();
+    let sol = Pubkey::new_unique();
+    let usdc_oracle = Pubkey::new_unique();
+    let sol_oracle = Pubkey::new_unique();
+    let token = |mint: Pubkey, decimals: u8, oracle_account: Pubkey| TokenSettings {
+        token_mint: mint,
+        decimals,
+        oracle_account,
+        oracle_confidence_pct: 10,
+        is_live: 1,
+        lp_on: 1,
+        ..TokenSettings::empty()
+    };
+    let fund = Pubkey::new_unique();
+    let mut account_map = AccountMap::new();
+    account_map.insert(fund, FundStateBuilder::new()
+        .with_token(0, 10_000_000_000, WEIGHT_MULTIPLIER / 2)
+        .with_token(1, 100_000_000_000, WEIGHT_MULTIPLIER / 2)
+        .with_weight_band(1_000, 5_000)
+        .build_account());
+    account_map.insert(SymmetryTokenSwap::TOKEN_LIST_ADDRESS, TokenListBuilder::new()
+        .with_token(token(usdc, 6, usdc_oracle))
+        .with_token(token(sol, 9, sol_oracle))
+        .build_account());
+    account_map.insert(SymmetryTokenSwap::CURVE_DATA_ADDRESS, CurveDataBuilder::new().build_account());
+    account_map.insert(usdc_oracle, OracleAccountBuilder::pyth(100_000_000, 10_000, -8, 1_000).build_account());
+    account_map.insert(sol_oracle, OracleAccountBuilder::pyth(10_000_000_000, 1_000_000, -8, 1_000).build_account());
+    let fixture = AmmFixture::new(&fund, 1_010, &account_map).unwrap();
+
+    let data = |key: &Pubkey| account_map[key].data.as_slice();
+    let mut wasm_fund = WasmFund::new(
+        &fund.to_string(),
+        data(&fund),
+        data(&SymmetryTokenSwap::TOKEN_LIST_ADDRESS),
+    ).unwrap_or_else(|_| panic!("fund failed to load"));
+    for key in wasm_fund.fund.get_accounts_to_update() {
+        if let Some(account) = account_map.get(&key) {
+            wasm_fund.set_account(&key.to_string(), &account.data).unwrap_or_else(|_| panic!("{key} failed to parse"));
+        }
+    }
+    let clock = fixture.clock();
+    wasm_fund.update(clock.slot, clock.unix_timestamp).unwrap_or_else(|_| panic!("update failed"));
+
+    let quote = wasm_fund.quote(&usdc.to_string(), &sol.to_string(), 100_000_000)
+        .unwrap_or_else(|_| panic!("quote failed"));
+    let expected = fixture.amm().unwrap()
+        .quote(&QuoteParams { in_amount: 100_000_000, input_mint: usdc, output_mint: sol })
+        .unwrap();
+    assert_eq!(quote.in_amount, expected.in_amount);
+    assert_eq!(quote.out_amount, expected.out_amount);
+    assert_eq!(quote.fee_amount, expected.fee_amount);
+    assert_eq!(quote.not_enough_liquidity, expected.not_enough_liquidity);
+    assert!(quote.out_amount > 0);
+}
@@ -0,0 +1,72 @@
+//! Writes a JSON matrix of Symmetry quotes (pairs x amounts x liquidity policies) for
+//! validating other implementations of the fund math. Replay it with
+//! `jupiter_core::vectors::run_vectors_dir`.
+
+use anyhow::{Error, Result};
+use clap::Parser;
+use jupiter_core::amms::liquidity::LiquidityPolicy;
+use jupiter_core::snapshot::RegistrySnapshot;
+use jupiter_core::vectors::{generate_vectors, VectorMatrix};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use std::path::PathBuf;
+
+#[derive(Parser)]
+#[command(name = "symmetry-vectors", version, about = "Generate Symmetry quote test vectors")]
+struct Args {
+    /// Registry snapshot holding the fixture accounts.
+    #[arg(long, conflicts_with = "fund")]
+    snapshot: Option<PathBuf>,
+    /// Funds to fetch from `--rpc-url` instead of reading a snapshot.
+    #[arg(long, num_args = 1..)]
+    fund: Vec<Pubkey>,
+    /// RPC endpoint, with `--fund`.
+    #[arg(long, short = 'u', default_value = "https://api.mainnet-beta.solana.com")]
+    rpc_url: String,
+    /// Also write the fetched accounts as a registry snapshot, with `--fund`.
+    #[arg(long, requires = "fund")]
+    save_snapshot: Option<PathBuf>,
+    /// In amounts as whole USD values. Defaults to 1, 100, 10k, 1M and 100M.
+    #[arg(long, value_delimiter = ',')]
+    usd_amounts: Vec<u64>,
+    /// Only quote under `LiquidityPolicy::Flag`.
+    #[arg(long)]
+    flag_only: bool,
+    /// Output file; stdout when omitted.
+    #[arg(long, short)]
+    output: Option<PathBuf>,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    let snapshot = match &args.snapshot {
+        Some(path) => RegistrySnapshot::load(path)?,
+        None if !args.fund.is_empty() => {
+            let client = RpcClient::new_with_commitment(args.rpc_url.clone(), CommitmentConfig::confirmed());
+            let snapshot = RegistrySnapshot::fetch(&client, &args.fund)?;
+            if let Some(path) = &args.save_snapshot {
+                snapshot.save(path)?;
+            }
+            snapshot
+        }
+        None => return Err(Error::msg("Pass --snapshot or at least one --fund")),
+    };
+
+    let mut matrix = VectorMatrix::default();
+    if !args.usd_amounts.is_empty() {
+        matrix.usd_amounts = args.usd_amounts;
+    }
+    if args.flag_only {
+        matrix.liquidity_policies = vec![LiquidityPolicy::Flag];
+    }
+    let file = generate_vectors(&snapshot, &matrix)?;
+    eprintln!("{} vectors over {} funds", file.vectors.len(), snapshot.funds.len());
+
+    let json = serde_json::to_string_pretty(&file)?;
+    match &args.output {
+        Some(path) => std::fs::write(path, json)?,
+        None => println!("{json}"),
+    }
+    Ok(())
+}
@@ -0,0 +1,255 @@
+use anyhow::{Error, Result};
+use clap::Args;
+use jupiter_amm_interface::{Amm, KeyedAccount, QuoteParams};
+use jupiter_core::amms::accounts::{FundState, LP_DISABLED, ONE_USD, WEIGHT_MULTIPLIER};
+use jupiter_core::amms::price_impact::price_impact_pct;
+use jupiter_core::amms::symmetry_token_swap::SymmetryTokenSwap;
+use jupiter_core::client::load_fund;
+use jupiter_core::discovery::{self, find_all_funds, DiscoveryFilter};
+use jupiter_core::parity::check_quote_parity;
+use jupiter_core::registry::FundRegistry;
+use solana_client::nonblocking::rpc_client::RpcClient as AsyncRpcClient;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use std::fmt::Write;
+
+#[derive(Args)]
+pub struct QuoteArgs {
+    fund: Pubkey,
+    input_mint: Pubkey,
+    output_mint: Pubkey,
+    /// Input amount, in the input token's smallest unit.
+    amount: u64,
+}
+
+#[derive(Args)]
+pub struct CheckParityArgs {
+    fund: Pubkey,
+    input_mint: Pubkey,
+    output_mint: Pubkey,
+    /// Input amount, in the input token's smallest unit.
+    amount: u64,
+    /// Wallet holding the input tokens that the swap is simulated from.
+    #[arg(long)]
+    user: Pubkey,
+    /// Accepted difference of the simulated and quoted out amounts, in raw output units.
+    #[arg(long, default_value_t = 0)]
+    tolerance: u64,
+}
+
+#[derive(Args)]
+pub struct ShowFundArgs {
+    fund: Pubkey,
+}
+
+#[derive(Args)]
+pub struct ListFundsArgs {
+    /// Only funds managed by this wallet.
+    #[arg(long)]
+    manager: Option<Pubkey>,
+    /// Only funds hosted by this platform.
+    #[arg(long)]
+    host: Option<Pubkey>,
+    /// Only funds holding this mint. Can be repeated.
+    #[arg(long = "mint")]
+    mints: Vec<Pubkey>,
+}
+
+#[derive(Args)]
+pub struct FundsWithMintArgs {
+    mint: Pubkey,
+}
+
+fn usd(value: u64) -> String {
+    format!("${}.{:02}", value / ONE_USD, value % ONE_USD * 100 / ONE_USD)
+}
+
+fn pct(weight: u64, total: u64) -> String {
+    if total == 0 {
+        return "-".to_string();
+    }
+    format!("{:.2}%", weight as f64 * 100.0 / total as f64)
+}
+
+pub async fn quote(client: &AsyncRpcClient, args: QuoteArgs) -> Result<()> {
+    let amm = load_fund(client, &args.fund).await?;
+    let (quote, fees) = amm.quote_with_fees(&QuoteParams {
+        in_amount: args.amount,
+        input_mint: args.input_mint,
+        output_mint: args.output_mint,
+    })?;
+    println!("In:  {} {}", quote.in_amount, args.input_mint);
+    println!("Out: {} {}", quote.out_amount, args.output_mint);
+    println!("Fee: {} ({}%)", quote.fee_amount, quote.fee_pct);
+    println!(
+        "     symmetry {}, host {}, manager {}, fund {}",
+        fees.symmetry_fee, fees.host_fee, fees.manager_fee, fees.fund_fee
+    );
+    if let Some(fair_amount) = amm.fair_out_amount(&args.input_mint, &args.output_mint, quote.in_amount) {
+        println!("Price impact: {}%", price_impact_pct(fair_amount, quote.out_amount));
+    }
+    if quote.not_enough_liquidity {
+        println!("The fund can only partly fill this amount; the output is capped at its holding");
+    }
+    Ok(())
+}
+
+pub fn check_parity(client: &RpcClient, args: CheckParityArgs) -> Result<()> {
+    let amm = discovery::load_fund(client, &args.fund)?;
+    let report = check_quote_parity(client, &amm, args.user, &QuoteParams {
+        in_amount: args.amount,
+        input_mint: args.input_mint,
+        output_mint: args.output_mint,
+    })?;
+    println!("Quoted:    {}", report.quoted_out_amount);
+    println!("Simulated: {}", report.simulated.out_amount);
+    if let Some(units) = report.simulated.units_consumed {
+        println!("Compute:   {units} units");
+    }
+    if !report.within(args.tolerance) {
+        return Err(Error::msg(format!("Quote differs from the program by {}", report.difference())));
+    }
+    println!("Within tolerance of {}", args.tolerance);
+    Ok(())
+}
+
+pub async fn show_fund(client: &AsyncRpcClient, args: ShowFundArgs) -> Result<()> {
+    let amm = load_fund(client, &args.fund).await?;
+    let fund_state = amm.fund_state();
+    println!("Fund:       {}", amm.key());
+    println!("Manager:    {}", fund_state.manager);
+    println!("Host:       {}", fund_state.host_pubkey);
+    println!("Fund token: {} (supply {})", fund_state.fund_token, fund_state.supply_outstanding);
+    match amm.fund_worth() {
+        Ok(fund_worth) => println!("Worth:      {}", usd(fund_worth)),
+        Err(e) => println!("Worth:      unavailable ({e})"),
+    }
+    if let Ok(nav) = amm.nav_per_share() {
+        println!("NAV/share:  ${}", nav.round_dp(6));
+    }
+    if fund_state.rebalance_in_progress() {
+        println!("Rebalance in progress");
+    }
+    println!();
+    println!("{:<44} {:>20} {:>8} {:>8} {:>6} {:>6}", "Mint", "Amount", "Target", "Weight", "LP", "Oracle");
+    let token_list = amm.token_list();
+    for i in 0..fund_state.num_of_tokens as usize {
+        let settings = &token_list.list[fund_state.current_comp_token[i] as usize];
+        println!(
+            "{:<44} {:>20} {:>8} {:>8} {:>6} {:>6}",
+            settings.token_mint.to_string(),
+            fund_state.current_comp_amount[i],
+            pct(fund_state.target_weight[i], fund_state.weight_sum),
+            pct(fund_state.current_weight[i], WEIGHT_MULTIPLIER),
+            if settings.lp_on == LP_DISABLED { "off" } else { "on" },
+            if settings.oracle_price.oracle_live == 0 { "stale" } else { "live" },
+        );
+    }
+    Ok(())
+}
+
+pub fn list_funds(client: &RpcClient, args: ListFundsArgs) -> Result<()> {
+    let filter = DiscoveryFilter {
+        manager: args.manager,
+        host: args.host,
+        mints: args.mints,
+    };
+    let funds = find_all_funds(client, &filter)?;
+    print!("{}", fund_table(&funds)?);
+    eprintln!("{} funds", funds.len());
+    Ok(())
+}
+
+/// The `list-funds` table: one row per fund with its manager, token count and recorded worth.
+fn fund_table(funds: &[KeyedAccount]) -> Result<String> {
+    let mut table = format!("{:<44} {:<44} {:>6} {:>16}\n", "Fund", "Manager", "Tokens", "Worth");
+    for keyed_account in funds {
+        let fund_state = FundState::load(&keyed_account.account.data)
+            .map_err(|e| Error::msg(format!("Failed to parse fund {}: {e}", keyed_account.key)))?;
+        writeln!(
+            table,
+            "{:<44} {:<44} {:>6} {:>16}",
+            keyed_account.key.to_string(),
+            fund_state.manager.to_string(),
+            fund_state.num_of_tokens,
+            usd(fund_state.fund_worth),
+        )?;
+    }
+    Ok(table)
+}
+
+pub fn funds_with_mint(client: &RpcClient, args: FundsWithMintArgs) -> Result<()> {
+    let filter = DiscoveryFilter {
+        mints: vec![args.mint],
+        ..DiscoveryFilter::default()
+    };
+    let funds = find_all_funds(client, &filter)?;
+    let token_list = KeyedAccount {
+        key: SymmetryTokenSwap::TOKEN_LIST_ADDRESS,
+        account: client.get_account(&SymmetryTokenSwap::TOKEN_LIST_ADDRESS)?,
+        params: None,
+    };
+    let mut registry = FundRegistry::new();
+    for keyed_account in &funds {
+        registry.insert(SymmetryTokenSwap::from_keyed_account_checked(keyed_account, &token_list)?);
+    }
+    let liquidity = registry.funds_with_mint(&args.mint);
+    println!("{:<44} {:>6} {:>20}", "Fund", "Index", "Amount");
+    for fund in &liquidity {
+        println!("{:<44} {:>6} {:>20}", fund.fund.to_string(), fund.composition_index, fund.amount);
+    }
+    eprintln!("{} funds", liquidity.len());
+    Ok(())
+}
+
+#[test]
+fn test_list_funds_table() {
+    use jupiter_core::amms::account_builders::FundStateBuilder;
+    use serde_json::json;
+    use solana_account_decoder::{UiAccount, UiAccountEncoding};
+    use solana_client::rpc_request::RpcRequest;
+    use solana_client::rpc_response::RpcKeyedAccount;
+
+    let manager = Pubkey::new_unique();
+    let funds = [Pubkey::new_unique(), Pubkey::new_unique()];
+    let fund_state = |tokens: u64, fund_worth: u64| {
+        (0..tokens)
+            .fold(FundStateBuilder::new().with_manager(manager), |fund_state, token_id| {
+                fund_state.with_token(token_id, 1_000_000, WEIGHT_MULTIPLIER / tokens)
+            })
+            .with_fund_worth(fund_worth, 0)
+            .build_account()
+    };
+    let accounts = [fund_state(2, 20_000 * ONE_USD), fund_state(3, 1_234 * ONE_USD + ONE_USD / 2)];
+    let program_accounts: Vec<_> = funds.iter().zip(&accounts)
+        .map(|(key, account)| RpcKeyedAccount {
+            pubkey: key.to_string(),
+            account: UiAccount::encode(key, account, UiAccountEncoding::Base64, None, None),
+        })
+        .collect();
+    let client = RpcClient::new_mock_with_mocks(
+        "succeeds",
+        [(RpcRequest::GetProgramAccounts, json!(program_accounts))].into(),
+    );
+
+    let filter = DiscoveryFilter { manager: Some(manager), ..DiscoveryFilter::default() };
+    let listed = find_all_funds(&client, &filter).unwrap();
+    let table = fund_table(&listed).unwrap();
+    let rows: Vec<_> = table.lines().collect();
+    assert_eq!(rows.len(), 3);
+    assert_eq!(rows[0].split_whitespace().collect::<Vec<_>>(), ["Fund", "Manager", "Tokens", "Worth"]);
+    assert_eq!(
+        rows[1].split_whitespace().collect::<Vec<_>>(),
+        [funds[0].to_string(), manager.to_string(), "2".to_string(), "$20000.00".to_string()],
+    );
+    assert_eq!(
+        rows[2].split_whitespace().collect::<Vec<_>>(),
+        [funds[1].to_string(), manager.to_string(), "3".to_string(), "$1234.50".to_string()],
+    );
+
+    let broken = KeyedAccount { key: funds[0], account: Default::default(), params: None };
+    assert_eq!(
+        fund_table(&[broken]).unwrap_err().to_string().split(':').next().unwrap(),
+        format!("Failed to parse fund {}", funds[0]),
+    );
+}
@@ -0,0 +1,53 @@
+mod inspect;
+mod swap;
+
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+use solana_client::nonblocking::rpc_client::RpcClient as AsyncRpcClient;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+
+#[derive(Parser)]
+#[command(name = "symmetry", version, about = "Command line tools for Symmetry funds")]
+struct Cli {
+    /// RPC endpoint.
+    #[arg(long, short = 'u', global = true, default_value = "https://api.mainnet-beta.solana.com")]
+    rpc_url: String,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Quote, sign, send and confirm a swap against a fund.
+    Swap(swap::SwapArgs),
+    /// Quote a swap against a fund's current accounts.
+    Quote(inspect::QuoteArgs),
+    /// Quote a swap and compare it with a simulation of the same swap on chain.
+    CheckParity(inspect::CheckParityArgs),
+    /// Print a fund's state and composition.
+    ShowFund(inspect::ShowFundArgs),
+    /// List Symmetry funds, optionally filtered by manager, host or held mints.
+    ListFunds(inspect::ListFundsArgs),
+    /// List funds that swap a mint, with LP enabled for it, by the amount of it they hold.
+    FundsWithMint(inspect::FundsWithMintArgs),
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let commitment = CommitmentConfig::confirmed();
+    match cli.command {
+        Command::Swap(args) => swap::run(&RpcClient::new_with_commitment(cli.rpc_url, commitment), args),
+        Command::CheckParity(args) => inspect::check_parity(&RpcClient::new_with_commitment(cli.rpc_url, commitment), args),
+        Command::ListFunds(args) => inspect::list_funds(&RpcClient::new_with_commitment(cli.rpc_url, commitment), args),
+        Command::FundsWithMint(args) => inspect::funds_with_mint(&RpcClient::new_with_commitment(cli.rpc_url, commitment), args),
+        Command::Quote(args) => {
+            let client = AsyncRpcClient::new_with_commitment(cli.rpc_url, commitment);
+            tokio::runtime::Runtime::new()?.block_on(inspect::quote(&client, args))
+        }
+        Command::ShowFund(args) => {
+            let client = AsyncRpcClient::new_with_commitment(cli.rpc_url, commitment);
+            tokio::runtime::Runtime::new()?.block_on(inspect::show_fund(&client, args))
+        }
+    }
+}
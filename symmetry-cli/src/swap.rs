@@ -0,0 +1,180 @@
+use anyhow::{Error, Result};
+use clap::{Args, ValueEnum};
+use jupiter_amm_interface::{Amm, QuoteParams};
+use jupiter_core::amms::accounts::BPS_DIVIDER;
+use jupiter_core::amms::symmetry_token_swap::SymmetryTokenSwap;
+use jupiter_core::constants::WSOL_MINT;
+use jupiter_core::discovery::load_fund;
+use jupiter_core::fills::decode_fills;
+use jupiter_core::transaction::{
+    associated_token_address, parse_lookup_table, SwapRequest, SwapTransactionBuilder,
+    DEFAULT_PRIORITY_FEE_PERCENTILE, DEFAULT_SWAP_COMPUTE_UNIT_LIMIT,
+};
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::RpcTransactionConfig;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{read_keypair_file, Signer};
+use solana_transaction_status::UiTransactionEncoding;
+use std::path::PathBuf;
+
+#[derive(Args)]
+pub struct SwapArgs {
+    /// Keypair file that pays for and signs the swap.
+    #[arg(long, short = 'k', required_unless_present = "unsigned")]
+    keypair: Option<PathBuf>,
+    /// Print the transaction unsigned, as wallet-adapter JSON, instead of sending it.
+    #[arg(long, requires = "user")]
+    unsigned: bool,
+    /// Output format of `--unsigned`.
+    #[arg(long, value_enum, default_value_t = UnsignedFormat::Wallet, requires = "unsigned")]
+    format: UnsignedFormat,
+    /// Wallet that will sign an `--unsigned` transaction.
+    #[arg(long, conflicts_with = "keypair")]
+    user: Option<Pubkey>,
+    #[arg(long)]
+    fund: Pubkey,
+    #[arg(long)]
+    input_mint: Pubkey,
+    #[arg(long)]
+    output_mint: Pubkey,
+    /// Input amount, in the input token's smallest unit.
+    #[arg(long)]
+    amount: u64,
+    /// Accepted shortfall of the output from the quote.
+    #[arg(long, default_value_t = 50)]
+    slippage_bps: u64,
+    #[arg(long, default_value_t = DEFAULT_SWAP_COMPUTE_UNIT_LIMIT)]
+    compute_unit_limit: u32,
+    /// Priority fee, in micro-lamports per compute unit. Estimated from recent fees on the
+    /// fund's accounts when not set.
+    #[arg(long)]
+    compute_unit_price: Option<u64>,
+    /// Percentile of recent prioritization fees used for the estimate.
+    #[arg(long, default_value_t = DEFAULT_PRIORITY_FEE_PERCENTILE)]
+    priority_fee_percentile: u8,
+    /// Address lookup table to compile the transaction against. Can be repeated.
+    #[arg(long = "lookup-table")]
+    lookup_tables: Vec<Pubkey>,
+    /// Pay in or receive native SOL when either mint is WSOL, wrapping and unwrapping it in
+    /// the swap transaction.
+    #[arg(long)]
+    native_sol: bool,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum UnsignedFormat {
+    /// Base64 v0 transaction as wallet-adapter JSON.
+    Wallet,
+    /// Base58 legacy message, for `solana` CLI offline signing and multisig import.
+    Base58,
+    /// Instruction list as JSON, for multisig and governance proposals.
+    Instructions,
+}
+
+pub fn run(client: &RpcClient, args: SwapArgs) -> Result<()> {
+    let keypair = match &args.keypair {
+        Some(path) => Some(read_keypair_file(path)
+            .map_err(|e| Error::msg(format!("Failed to read keypair {}: {e}", path.display())))?),
+        None => None,
+    };
+    let user = match (&keypair, args.user) {
+        (Some(keypair), _) => keypair.pubkey(),
+        (None, Some(user)) => user,
+        (None, None) => return Err(Error::msg("Either --keypair or --user is required")),
+    };
+    if args.slippage_bps > BPS_DIVIDER {
+        return Err(Error::msg("Slippage can't exceed 10000 bps"));
+    }
+
+    let amm = load_fund(client, &args.fund)?;
+    let quote = amm.quote(&QuoteParams {
+        in_amount: args.amount,
+        input_mint: args.input_mint,
+        output_mint: args.output_mint,
+    })?;
+    let minimum_amount_out = SymmetryTokenSwap::mul_div(quote.out_amount, BPS_DIVIDER - args.slippage_bps, BPS_DIVIDER);
+    eprintln!("Quote: {} -> {} (min {}), fee {} ({}%)", quote.in_amount, quote.out_amount, minimum_amount_out, quote.fee_amount, quote.fee_pct);
+    if quote.not_enough_liquidity {
+        eprintln!("Warning: the fund can only partly fill this amount; the output is capped at its holding");
+    }
+
+    let balance = if args.native_sol && args.input_mint == WSOL_MINT {
+        client.get_balance(&user)?
+    } else {
+        let source = associated_token_address(&user, &args.input_mint);
+        client.get_token_account_balance(&source)?.amount.parse()?
+    };
+    if balance < args.amount {
+        return Err(Error::msg(format!("Insufficient input balance: {balance} < {}", args.amount)));
+    }
+
+    let swap = SwapRequest {
+        user,
+        input_mint: args.input_mint,
+        output_mint: args.output_mint,
+        in_amount: args.amount,
+        minimum_amount_out,
+    };
+    let mut builder = SwapTransactionBuilder::new(&amm, user).with_compute_unit_limit(args.compute_unit_limit);
+    builder = match args.compute_unit_price {
+        Some(micro_lamports) => builder.with_compute_unit_price(micro_lamports),
+        None => builder.with_estimated_compute_unit_price(client, &swap, args.priority_fee_percentile)?,
+    };
+    if args.native_sol {
+        builder = builder.with_native_sol();
+    }
+    for (key, account) in args.lookup_tables.iter().zip(client.get_multiple_accounts(&args.lookup_tables)?) {
+        let account = account.ok_or_else(|| Error::msg(format!("Lookup table {key} not found")))?;
+        builder = builder.with_lookup_table(parse_lookup_table(*key, &account.data)?);
+    }
+    let (blockhash, last_valid_block_height) = client.get_latest_blockhash_with_commitment(client.commitment())?;
+    let keypair = match keypair {
+        Some(keypair) if !args.unsigned => keypair,
+        _ => {
+            match args.format {
+                UnsignedFormat::Wallet => {
+                    let unsigned = builder.export_unsigned(&swap, blockhash, last_valid_block_height)?;
+                    println!("{}", serde_json::to_string_pretty(&unsigned)?);
+                }
+                UnsignedFormat::Base58 => println!("{}", builder.export_message_base58(&swap, blockhash)?),
+                UnsignedFormat::Instructions => {
+                    println!("{}", serde_json::to_string_pretty(&builder.export_instructions(&swap)?)?);
+                }
+            }
+            return Ok(());
+        }
+    };
+    let transaction = builder.build(&swap, blockhash, &[&keypair])?;
+
+    let simulation = client.simulate_transaction(&transaction)?.value;
+    if let Some(err) = simulation.err {
+        for log in simulation.logs.unwrap_or_default() {
+            eprintln!("  {log}");
+        }
+        return Err(Error::msg(format!("Preflight simulation failed: {err}")));
+    }
+
+    let signature = client.send_and_confirm_transaction(&transaction)?;
+    println!("Confirmed: {signature}");
+
+    let confirmed = client.get_transaction_with_config(&signature, RpcTransactionConfig {
+        encoding: Some(UiTransactionEncoding::Base64),
+        commitment: Some(CommitmentConfig::confirmed()),
+        max_supported_transaction_version: Some(0),
+    })?;
+    let token_list = amm.token_list();
+    let num_tokens = (token_list.num_tokens as usize).min(token_list.list.len());
+    let mints: Vec<Pubkey> = token_list.list[..num_tokens].iter().map(|token| token.token_mint).collect();
+    for fill in decode_fills(&signature, &confirmed, &args.fund, &mints)? {
+        println!(
+            "Fill: {} {} -> {} {} at slot {}",
+            fill.args.amount,
+            fill.from_mint.map(|mint| mint.to_string()).unwrap_or_default(),
+            fill.out_amount.map(|amount| amount.to_string()).unwrap_or_else(|| "?".to_string()),
+            fill.to_mint.map(|mint| mint.to_string()).unwrap_or_default(),
+            fill.slot,
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,19 @@
+//! Stable import path for the Symmetry SDK: `use symmetry_sdk::prelude::*;`.
+//!
+//! The workspace's only library crate is `jupiter-core`, re-exported whole as
+//! `symmetry_sdk::jupiter_core`. `symmetry-cli` builds the `symmetry` and `symmetry-vectors`
+//! binaries and has no library items to re-export. Features forward to `jupiter-core`.
+
+pub use jupiter_core;
+
+pub mod prelude {
+    pub use jupiter_core::prelude::*;
+}
+
+#[test]
+fn test_prelude_errors_downcast() {
+    use prelude::*;
+
+    let error: anyhow::Error = SymmetryError::FundLpDisabled.into();
+    assert_eq!(error.downcast_ref::<SymmetryError>(), Some(&SymmetryError::FundLpDisabled));
+}